@@ -1,9 +1,10 @@
 use std::error::Error;
 use std::io::{self, Stdout};
-use std::time::Duration;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 use crossterm::event::{
-    self, DisableMouseCapture, EnableMouseCapture, Event, KeyEventKind, MouseEventKind,
+    self, DisableMouseCapture, EnableMouseCapture, Event, KeyEventKind, MouseButton, MouseEventKind,
 };
 use crossterm::terminal::{
     EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
@@ -19,7 +20,18 @@ use crate::ui::ScrollTarget;
 
 type AppResult<T> = Result<T, Box<dyn Error>>;
 
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(1);
+const WALK_STEP_INTERVAL: Duration = Duration::from_millis(120);
+const ANIMATION_FRAME_INTERVAL: Duration = Duration::from_millis(120);
+
 pub fn run() -> AppResult<()> {
+    if let Some(path) = config_path_from_args(std::env::args().skip(1))? {
+        Game::use_config_path(path);
+    }
+    for diagnostic in Game::config_diagnostics() {
+        eprintln!("config: {diagnostic}");
+    }
+
     let mut terminal = init_terminal()?;
     let run_result = event_loop(&mut terminal);
     let cleanup_result = restore_terminal(&mut terminal);
@@ -37,9 +49,26 @@ pub fn run() -> AppResult<()> {
 
 fn event_loop(terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> AppResult<()> {
     let mut game = Game::new();
+    game.animations = true;
+    game.begin_session();
     let tick_rate = Duration::from_millis(120);
+    let mut last_config_check = Instant::now();
+    let mut last_walk_step = Instant::now();
+    let mut last_frame = Instant::now();
 
     while !game.should_quit {
+        if last_config_check.elapsed() >= CONFIG_POLL_INTERVAL {
+            game.reload_config();
+            last_config_check = Instant::now();
+        }
+        if last_walk_step.elapsed() >= WALK_STEP_INTERVAL {
+            game.step_auto_walk();
+            last_walk_step = Instant::now();
+        }
+        if last_frame.elapsed() >= ANIMATION_FRAME_INTERVAL {
+            game.step_animations();
+            last_frame = Instant::now();
+        }
         terminal.draw(|frame| ui::render(frame, &game))?;
         if event::poll(tick_rate)? {
             let evt = event::read()?;
@@ -48,17 +77,26 @@ fn event_loop(terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> AppResult<()
                     game.handle_key(key.code);
                 }
                 Event::Mouse(mouse) => {
+                    let size = terminal.size()?;
+                    let area = Rect::new(0, 0, size.width, size.height);
+                    game.mouse = Some((mouse.column, mouse.row));
                     let delta = match mouse.kind {
                         MouseEventKind::ScrollUp => Some(-1),
                         MouseEventKind::ScrollDown => Some(1),
+                        MouseEventKind::Down(MouseButton::Left) => {
+                            if let Some(target) =
+                                ui::pointer_target_at(area, &game, mouse.column, mouse.row)
+                            {
+                                game.click(target);
+                            }
+                            None
+                        }
                         _ => None,
                     };
                     let Some(delta) = delta else {
                         continue;
                     };
-                    let size = terminal.size()?;
-                    let area = Rect::new(0, 0, size.width, size.height);
-                    match ui::scroll_target_at(area, mouse.column, mouse.row) {
+                    match ui::scroll_target_at(area, &game, mouse.column, mouse.row) {
                         Some(ScrollTarget::Hero) => shift_scroll(&mut game.hero_scroll, delta),
                         Some(ScrollTarget::BattleLog) => shift_scroll(&mut game.log_scroll, delta),
                         Some(ScrollTarget::Controls) => {
@@ -71,9 +109,26 @@ fn event_loop(terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> AppResult<()
             }
         }
     }
+    game.end_session();
     Ok(())
 }
 
+/// Reads `--config <file>` (or `--config=<file>`) from the command line.
+fn config_path_from_args(mut args: impl Iterator<Item = String>) -> AppResult<Option<PathBuf>> {
+    let mut path = None;
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            let value = args.next().ok_or("--config needs a file path")?;
+            path = Some(PathBuf::from(value));
+        } else if let Some(value) = arg.strip_prefix("--config=") {
+            path = Some(PathBuf::from(value));
+        } else {
+            return Err(format!("unknown argument: {arg}").into());
+        }
+    }
+    Ok(path)
+}
+
 fn init_terminal() -> AppResult<Terminal<CrosstermBackend<Stdout>>> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
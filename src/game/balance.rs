@@ -1,4 +1,6 @@
-use crate::game::model::EnemyStyle;
+use crate::game::model::{
+    AiKind, BossId, CompanionKind, EnemyStyle, HeroClass, Material, ProfileField, Unlock,
+};
 
 pub struct EnemyTemplate {
     pub name_key: &'static str,
@@ -8,9 +10,13 @@ pub struct EnemyTemplate {
     pub base_def: i32,
     pub base_exp: i32,
     pub base_gold: i32,
+    /// Only spawns in random encounters at night.
+    pub nocturnal: bool,
+    pub ai: AiKind,
+    pub loot: Option<Material>,
 }
 
-pub const NORMAL_ENEMIES: [EnemyTemplate; 5] = [
+pub const NORMAL_ENEMIES: [EnemyTemplate; 7] = [
     EnemyTemplate {
         name_key: "enemy.slime",
         style: EnemyStyle::Skirmisher,
@@ -19,6 +25,9 @@ pub const NORMAL_ENEMIES: [EnemyTemplate; 5] = [
         base_def: 1,
         base_exp: 8,
         base_gold: 6,
+        nocturnal: false,
+        ai: AiKind::Aggressive,
+        loot: Some(Material::Herb),
     },
     EnemyTemplate {
         name_key: "enemy.goblin",
@@ -28,6 +37,9 @@ pub const NORMAL_ENEMIES: [EnemyTemplate; 5] = [
         base_def: 2,
         base_exp: 11,
         base_gold: 8,
+        nocturnal: false,
+        ai: AiKind::Coward,
+        loot: Some(Material::Ore),
     },
     EnemyTemplate {
         name_key: "enemy.wolf",
@@ -37,6 +49,9 @@ pub const NORMAL_ENEMIES: [EnemyTemplate; 5] = [
         base_def: 2,
         base_exp: 13,
         base_gold: 11,
+        nocturnal: false,
+        ai: AiKind::Aggressive,
+        loot: Some(Material::Hide),
     },
     EnemyTemplate {
         name_key: "enemy.skeleton",
@@ -46,6 +61,9 @@ pub const NORMAL_ENEMIES: [EnemyTemplate; 5] = [
         base_def: 3,
         base_exp: 16,
         base_gold: 14,
+        nocturnal: true,
+        ai: AiKind::Guardian,
+        loot: Some(Material::Ore),
     },
     EnemyTemplate {
         name_key: "enemy.orc_brute",
@@ -55,6 +73,33 @@ pub const NORMAL_ENEMIES: [EnemyTemplate; 5] = [
         base_def: 4,
         base_exp: 20,
         base_gold: 18,
+        nocturnal: false,
+        ai: AiKind::ManaBurner,
+        loot: Some(Material::Hide),
+    },
+    EnemyTemplate {
+        name_key: "enemy.ghoul",
+        style: EnemyStyle::Undead,
+        base_hp: 30,
+        base_atk: 11,
+        base_def: 2,
+        base_exp: 18,
+        base_gold: 12,
+        nocturnal: true,
+        ai: AiKind::CautiousHealer,
+        loot: Some(Material::Herb),
+    },
+    EnemyTemplate {
+        name_key: "enemy.wraith",
+        style: EnemyStyle::Undead,
+        base_hp: 22,
+        base_atk: 13,
+        base_def: 4,
+        base_exp: 21,
+        base_gold: 16,
+        nocturnal: true,
+        ai: AiKind::Coward,
+        loot: None,
     },
 ];
 
@@ -64,42 +109,290 @@ pub const NORMAL_ENEMY_DEF_PER_LEVEL: i32 = 1;
 pub const NORMAL_ENEMY_EXP_PER_LEVEL: i32 = 3;
 pub const NORMAL_ENEMY_GOLD_PER_LEVEL: i32 = 3;
 
-pub const BOSS_NAME_KEY: &str = "enemy.ancient_dragon";
-pub const BOSS_BASE_HP: i32 = 84;
-pub const BOSS_BASE_ATK: i32 = 16;
-pub const BOSS_BASE_DEF: i32 = 7;
-pub const BOSS_BASE_EXP: i32 = 55;
-pub const BOSS_BASE_GOLD: i32 = 90;
+pub const EXPLORE_SIGHT_RADIUS: usize = 3;
+
+pub const TURNS_PER_HOUR: u32 = 4;
+pub const CLOCK_START_HOUR: u32 = 8;
+pub const DAWN_HOUR: u32 = 5;
+pub const DAY_HOUR: u32 = 8;
+pub const DUSK_HOUR: u32 = 18;
+pub const NIGHT_HOUR: u32 = 21;
+pub const NIGHT_ENCOUNTER_RATE_BONUS: i32 = 6;
+
+pub const SHOP_POTION_STOCK: i32 = 6;
+pub const SHOP_ETHER_STOCK: i32 = 4;
+
+pub const BOSS_SITE_MIN_TOWN_DISTANCE: usize = 10;
+
+pub const RESOURCE_NODE_COUNT: usize = 8;
+pub const RESOURCE_NODE_MAX_AMOUNT: i32 = 3;
+pub const MATERIAL_DROP_PERCENT: i32 = 55;
+
+pub const HALL_OF_FAME_SIZE: usize = 10;
+pub const WEALTHY_GOLD: i32 = 500;
+pub const VETERAN_LEVEL: i32 = 10;
+
+/// Step intervals the autosave setting cycles through; 0 is off.
+pub const AUTOSAVE_MOVE_CHOICES: [u32; 4] = [0, 25, 50, 100];
+
+/// Run score needed per legacy shard.
+pub const SCORE_PER_SHARD: i32 = 100;
+/// Extra shards, in percent, for each active difficulty modifier.
+pub const MODIFIER_SHARD_BONUS_PERCENT: u32 = 25;
+pub const ELITE_SPAWN_PERCENT: i32 = 20;
+pub const ELITE_HP_PERCENT: i32 = 150;
+pub const ELITE_REWARD_MULTIPLIER: i32 = 2;
+pub const PROVISIONS_POTIONS: i32 = 2;
+pub const PROVISIONS_ETHERS: i32 = 1;
+pub const INHERITANCE_GOLD: i32 = 80;
+pub const HARDENED_MAX_HP: i32 = 10;
+
+pub const DAILY_MODIFIER_COUNT: usize = 2;
+pub const DOUBLE_ENCOUNTER_MULTIPLIER: i32 = 2;
+/// Glass cannon: foes fall faster but hit far harder.
+pub const GLASS_CANNON_ENEMY_HP_SCALE: f32 = 0.7;
+pub const GLASS_CANNON_ENEMY_ATK_SCALE: f32 = 1.4;
+
+/// Extra enemy scaling per New Game+ cycle, on top of the difficulty profile.
+pub const NG_PLUS_HP_SCALE_PER_TIER: f32 = 0.5;
+pub const NG_PLUS_ATK_SCALE_PER_TIER: f32 = 0.25;
+pub const NG_PLUS_DEF_SCALE_PER_TIER: f32 = 0.2;
+pub const NG_PLUS_REWARD_SCALE_PER_TIER: f32 = 0.5;
+
+pub fn unlock_cost(unlock: Unlock) -> u32 {
+    match unlock {
+        Unlock::Provisions => 3,
+        Unlock::Inheritance => 4,
+        Unlock::Hardened => 6,
+        Unlock::EliteFoes => 5,
+        Unlock::Bloodlust => 4,
+        Unlock::Famine => 4,
+    }
+}
+
+/// Limits of a custom difficulty slider.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FieldBounds {
+    pub min: f32,
+    pub max: f32,
+    pub step: f32,
+}
+
+pub fn profile_field_bounds(field: ProfileField) -> FieldBounds {
+    let (min, max, step) = match field {
+        ProfileField::EncounterRate => (0.0, 50.0, 1.0),
+        ProfileField::EventRate => (0.0, 50.0, 1.0),
+        ProfileField::EnemyHp
+        | ProfileField::EnemyAtk
+        | ProfileField::EnemyDef
+        | ProfileField::EnemyReward => (0.5, 2.0, 0.05),
+        ProfileField::SkillRate => (0.0, 80.0, 2.0),
+        ProfileField::RunBonus => (-50.0, 50.0, 5.0),
+    };
+    FieldBounds { min, max, step }
+}
+
+/// What a difficulty file may set a field to: wider than the sliders, as
+/// far as the game still plays.
+pub fn profile_field_limits(field: ProfileField) -> (f32, f32) {
+    match field {
+        ProfileField::EncounterRate | ProfileField::EventRate | ProfileField::SkillRate => {
+            (0.0, 100.0)
+        }
+        ProfileField::EnemyHp
+        | ProfileField::EnemyAtk
+        | ProfileField::EnemyDef
+        | ProfileField::EnemyReward => (0.1, 10.0),
+        ProfileField::RunBonus => (-100.0, 100.0),
+    }
+}
+
+pub const ROAMING_MONSTER_COUNT: usize = 6;
+pub const ROAMING_CHASE_RANGE: usize = 5;
+pub const ROAMING_FLEE_HP_PERCENT: i32 = 40;
+pub const ROAMING_IDLE_PERCENT: i32 = 35;
+pub const ROAMING_ESCAPE_STUN_TURNS: i32 = 3;
+
+pub struct BossTemplate {
+    pub style: EnemyStyle,
+    pub base_hp: i32,
+    pub base_atk: i32,
+    pub base_def: i32,
+    pub base_exp: i32,
+    pub base_gold: i32,
+}
+
+pub fn boss_template(boss: BossId) -> BossTemplate {
+    match boss {
+        BossId::GoblinWarlord => BossTemplate {
+            style: EnemyStyle::Warlord,
+            base_hp: 50,
+            base_atk: 12,
+            base_def: 4,
+            base_exp: 30,
+            base_gold: 45,
+        },
+        BossId::StoneGolem => BossTemplate {
+            style: EnemyStyle::Golem,
+            base_hp: 62,
+            base_atk: 11,
+            base_def: 7,
+            base_exp: 34,
+            base_gold: 50,
+        },
+        BossId::Lich => BossTemplate {
+            style: EnemyStyle::Lich,
+            base_hp: 54,
+            base_atk: 14,
+            base_def: 5,
+            base_exp: 38,
+            base_gold: 55,
+        },
+        BossId::AncientDragon => BossTemplate {
+            style: EnemyStyle::Boss,
+            base_hp: 84,
+            base_atk: 16,
+            base_def: 7,
+            base_exp: 55,
+            base_gold: 90,
+        },
+    }
+}
+
 pub const BOSS_HP_PER_LEVEL: i32 = 9;
 pub const BOSS_ATK_PER_LEVEL: i32 = 2;
 pub const BOSS_DEF_PER_LEVEL: i32 = 1;
 pub const BOSS_EXP_PER_LEVEL: i32 = 8;
 pub const BOSS_GOLD_PER_LEVEL: i32 = 10;
 
+pub struct CompanionTemplate {
+    pub hp: i32,
+    pub mp: i32,
+    pub atk: i32,
+    pub def: i32,
+    pub hp_per_level: i32,
+    pub mp_per_level: i32,
+    pub atk_per_level: i32,
+    pub def_per_level: i32,
+    pub hire_cost: i32,
+}
+
+pub fn companion_template(kind: CompanionKind) -> CompanionTemplate {
+    match kind {
+        CompanionKind::Knight => CompanionTemplate {
+            hp: 36,
+            mp: 6,
+            atk: 8,
+            def: 5,
+            hp_per_level: 7,
+            mp_per_level: 1,
+            atk_per_level: 1,
+            def_per_level: 2,
+            hire_cost: 60,
+        },
+        CompanionKind::Cleric => CompanionTemplate {
+            hp: 26,
+            mp: 16,
+            atk: 5,
+            def: 3,
+            hp_per_level: 4,
+            mp_per_level: 3,
+            atk_per_level: 1,
+            def_per_level: 1,
+            hire_cost: 50,
+        },
+        CompanionKind::Mage => CompanionTemplate {
+            hp: 22,
+            mp: 18,
+            atk: 5,
+            def: 2,
+            hp_per_level: 3,
+            mp_per_level: 3,
+            atk_per_level: 2,
+            def_per_level: 1,
+            hire_cost: 0,
+        },
+        CompanionKind::Archer => CompanionTemplate {
+            hp: 26,
+            mp: 8,
+            atk: 9,
+            def: 2,
+            hp_per_level: 5,
+            mp_per_level: 1,
+            atk_per_level: 2,
+            def_per_level: 1,
+            hire_cost: 0,
+        },
+    }
+}
+
+/// Companions for hire at the town tavern, offered in this order.
+pub const TAVERN_ROSTER: [CompanionKind; 2] = [CompanionKind::Knight, CompanionKind::Cleric];
+pub const CLERIC_HEAL_BASE: i32 = 10;
+pub const CLERIC_HEAL_PER_LEVEL: i32 = 2;
+
 pub const NEXT_EXP_BASE_INCREASE: i32 = 12;
 pub const NEXT_EXP_LEVEL_MULTIPLIER: i32 = 6;
-pub const LEVEL_UP_HP_INCREASE: i32 = 6;
-pub const LEVEL_UP_MP_INCREASE: i32 = 2;
-pub const LEVEL_UP_ATK_INCREASE: i32 = 2;
-pub const LEVEL_UP_DEF_INCREASE: i32 = 1;
-
-pub const EVENT_WEIGHT_GOLD_CACHE: i32 = 30;
-pub const EVENT_WEIGHT_POTION_STASH: i32 = 22;
-pub const EVENT_WEIGHT_ETHER_STASH: i32 = 18;
-pub const EVENT_WEIGHT_CAMPFIRE: i32 = 16;
-pub const EVENT_WEIGHT_SPIKE_TRAP: i32 = 14;
-
-pub const EVENT_GOLD_MIN: i32 = 7;
-pub const EVENT_GOLD_MAX: i32 = 16;
-pub const EVENT_GOLD_PER_LEVEL: i32 = 2;
-
-pub const EVENT_POTION_MIN: i32 = 1;
-pub const EVENT_POTION_MAX: i32 = 2;
-pub const EVENT_ETHER_MIN: i32 = 1;
-pub const EVENT_ETHER_MAX: i32 = 2;
-
-pub const EVENT_CAMPFIRE_HEAL_BASE: i32 = 8;
-pub const EVENT_CAMPFIRE_HEAL_PER_LEVEL: i32 = 2;
-
-pub const EVENT_TRAP_DAMAGE_MIN: i32 = 4;
-pub const EVENT_TRAP_DAMAGE_MAX: i32 = 10;
+
+/// Starting stats and per-level growth for each hero class.
+pub struct ClassTemplate {
+    pub hp: i32,
+    pub mp: i32,
+    pub atk: i32,
+    pub def: i32,
+    pub hp_growth: i32,
+    pub mp_growth: i32,
+    pub atk_growth: i32,
+    pub def_growth: i32,
+}
+
+pub fn class_template(class: HeroClass) -> ClassTemplate {
+    match class {
+        HeroClass::Warrior => ClassTemplate {
+            hp: 40,
+            mp: 12,
+            atk: 10,
+            def: 4,
+            hp_growth: 6,
+            mp_growth: 2,
+            atk_growth: 2,
+            def_growth: 1,
+        },
+        HeroClass::Mage => ClassTemplate {
+            hp: 30,
+            mp: 22,
+            atk: 7,
+            def: 3,
+            hp_growth: 4,
+            mp_growth: 4,
+            atk_growth: 1,
+            def_growth: 1,
+        },
+        HeroClass::Rogue => ClassTemplate {
+            hp: 34,
+            mp: 14,
+            atk: 11,
+            def: 3,
+            hp_growth: 5,
+            mp_growth: 2,
+            atk_growth: 2,
+            def_growth: 1,
+        },
+    }
+}
+
+/// Free points granted on each level-up, and what one point buys.
+pub const STAT_POINTS_PER_LEVEL: i32 = 2;
+pub const STAT_POINT_HP: i32 = 5;
+pub const STAT_POINT_MP: i32 = 3;
+pub const STAT_POINT_ATK: i32 = 1;
+pub const STAT_POINT_DEF: i32 = 1;
+
+pub const MAGE_HEAL_BASE: i32 = 12;
+pub const MAGE_HEAL_PER_LEVEL: i32 = 3;
+pub const BACKSTAB_CRIT_PERCENT: i32 = 35;
+pub const STEAL_GOLD_BASE: i32 = 6;
+pub const STEAL_GOLD_PER_LEVEL: i32 = 2;
+
+pub const EVENT_REGION_TOWN_RADIUS: usize = 6;
+pub const EVENT_REGION_LAIR_RADIUS: usize = 8;
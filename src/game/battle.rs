@@ -1,15 +1,23 @@
-use crossterm::event::KeyCode;
 use rand::Rng;
 use rust_i18n::t;
 
+use crate::game::balance::{
+    BACKSTAB_CRIT_PERCENT, CLERIC_HEAL_BASE, CLERIC_HEAL_PER_LEVEL, MAGE_HEAL_BASE,
+    MAGE_HEAL_PER_LEVEL, STEAL_GOLD_BASE, STEAL_GOLD_PER_LEVEL,
+};
+use crate::game::boss_ai;
 use crate::game::combat;
 use crate::game::config::DifficultyProfile;
-use crate::game::model::{Battle, Enemy, EnemyStyle, Player};
+use crate::game::enemy_ai::{self, AiView, EnemyIntent};
+use crate::game::model::{
+    Battle, Command, Companion, CompanionKind, CompanionOrder, Enemy, EnemyStyle, HeroSkill, Player,
+};
+use crate::game::party;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum BattleAction {
     Attack,
-    FireSlash,
+    Skill(HeroSkill),
     Defend,
     Potion,
     Ether,
@@ -21,6 +29,7 @@ pub const ACTION_COUNT: usize = 6;
 pub enum BattleOutcome {
     Continue,
     Escaped,
+    EnemyFled,
     EnemyDefeated(Enemy),
     PlayerDefeated,
 }
@@ -30,22 +39,23 @@ pub struct TurnResult {
     pub logs: Vec<String>,
 }
 
-pub fn action_from_key(code: KeyCode) -> Option<BattleAction> {
-    match code {
-        KeyCode::Char('1') => Some(BattleAction::Attack),
-        KeyCode::Char('2') => Some(BattleAction::FireSlash),
-        KeyCode::Char('3') => Some(BattleAction::Defend),
-        KeyCode::Char('4') => Some(BattleAction::Potion),
-        KeyCode::Char('5') => Some(BattleAction::Ether),
-        KeyCode::Char('6') => Some(BattleAction::Run),
+/// `skill` fills the skill slot; the caller decides which class skill that is.
+pub fn action_from_command(command: Command, skill: HeroSkill) -> Option<BattleAction> {
+    match command {
+        Command::Attack => Some(BattleAction::Attack),
+        Command::Skill => Some(BattleAction::Skill(skill)),
+        Command::Defend => Some(BattleAction::Defend),
+        Command::Potion => Some(BattleAction::Potion),
+        Command::Ether => Some(BattleAction::Ether),
+        Command::Run => Some(BattleAction::Run),
         _ => None,
     }
 }
 
-pub fn action_from_index(index: usize) -> BattleAction {
+pub fn action_from_index(index: usize, skill: HeroSkill) -> BattleAction {
     match index % ACTION_COUNT {
         0 => BattleAction::Attack,
-        1 => BattleAction::FireSlash,
+        1 => BattleAction::Skill(skill),
         2 => BattleAction::Defend,
         3 => BattleAction::Potion,
         4 => BattleAction::Ether,
@@ -56,7 +66,7 @@ pub fn action_from_index(index: usize) -> BattleAction {
 pub fn action_index(action: BattleAction) -> usize {
     match action {
         BattleAction::Attack => 0,
-        BattleAction::FireSlash => 1,
+        BattleAction::Skill(_) => 1,
         BattleAction::Defend => 2,
         BattleAction::Potion => 3,
         BattleAction::Ether => 4,
@@ -64,45 +74,47 @@ pub fn action_index(action: BattleAction) -> usize {
     }
 }
 
-pub fn resolve_turn<R: Rng>(
+/// One full round: the hero acts, then each companion follows its order in
+/// formation order, then the enemy side answers.
+pub fn resolve_round<R: Rng>(
     action: BattleAction,
+    orders: &[CompanionOrder],
     battle: &mut Battle,
     player: &mut Player,
+    companions: &mut [Companion],
     rng: &mut R,
     difficulty: &DifficultyProfile,
 ) -> TurnResult {
     let mut logs = Vec::new();
     let mut player_acted = false;
+    let script = boss_ai::script_for(battle.enemy.style);
+    let foes_before = battle.foe_hp();
 
     match action {
         BattleAction::Attack => {
-            let dmg = combat::random_damage(rng, player.total_atk(), battle.enemy.def, 3);
-            battle.enemy.hp -= dmg;
-            logs.push(
-                t!(
-                    "log.battle.player_slash",
-                    enemy = battle.enemy.name.as_str(),
-                    dmg = dmg
-                )
-                .to_string(),
+            strike_front(
+                battle,
+                player.total_atk(),
+                3,
+                rng,
+                &mut logs,
+                |enemy, dmg| t!("log.battle.player_slash", enemy = enemy, dmg = dmg).to_string(),
             );
             player_acted = true;
         }
-        BattleAction::FireSlash => {
-            if player.mp < 4 {
-                logs.push(t!("log.battle.not_enough_mp_fire_slash").to_string());
-            } else {
-                player.mp -= 4;
-                let dmg = combat::random_damage(rng, player.total_atk() + 6, battle.enemy.def, 5);
-                battle.enemy.hp -= dmg;
+        BattleAction::Skill(skill) => {
+            if player.mp < skill.mp_cost() {
                 logs.push(
                     t!(
-                        "log.battle.fire_slash",
-                        enemy = battle.enemy.name.as_str(),
-                        dmg = dmg
+                        "log.battle.not_enough_mp_skill",
+                        skill = t!(skill.name_key()),
+                        mp = skill.mp_cost()
                     )
                     .to_string(),
                 );
+            } else {
+                player.mp -= skill.mp_cost();
+                use_skill(skill, battle, player, rng, &mut logs);
                 player_acted = true;
             }
         }
@@ -136,16 +148,37 @@ pub fn resolve_turn<R: Rng>(
         }
     }
 
+    if player_acted {
+        resolve_companions(orders, battle, player, companions, rng, &mut logs);
+    }
+    battle.damage_dealt += (foes_before - battle.foe_hp()).max(0);
     if battle.enemy.hp <= 0 {
         return TurnResult {
             outcome: BattleOutcome::EnemyDefeated(battle.enemy.clone()),
             logs,
         };
     }
+    if let Some(script) = script {
+        boss_ai::update_phase(script, battle, &mut logs);
+    }
 
     if player_acted {
-        resolve_enemy_action(battle, player, rng, difficulty, &mut logs);
+        let hero_before = player.hp;
+        match script {
+            Some(script) => boss_ai::take_turn(script, battle, player, rng, difficulty, &mut logs),
+            None => {
+                if resolve_enemy_action(battle, player, companions, rng, difficulty, &mut logs) {
+                    return TurnResult {
+                        outcome: BattleOutcome::EnemyFled,
+                        logs,
+                    };
+                }
+            }
+        }
+        resolve_adds(battle, player, companions, rng, &mut logs);
+        battle.damage_taken += (hero_before - player.hp.max(0)).max(0);
         battle.defending = false;
+        battle.companions_defending.clear();
 
         if player.hp <= 0 {
             player.hp = 0;
@@ -162,18 +195,68 @@ pub fn resolve_turn<R: Rng>(
     }
 }
 
+/// Free opening hit when the player catches a roaming monster from behind.
+pub fn preemptive_strike<R: Rng>(battle: &mut Battle, player: &Player, rng: &mut R) -> TurnResult {
+    let dmg = combat::random_damage(rng, player.total_atk(), battle.enemy.def, 3);
+    battle.enemy.hp -= dmg;
+    battle.damage_dealt += dmg;
+    let logs = vec![
+        t!(
+            "log.battle.preemptive_strike",
+            enemy = battle.enemy.name.as_str(),
+            dmg = dmg
+        )
+        .to_string(),
+    ];
+    let outcome = if battle.enemy.hp <= 0 {
+        BattleOutcome::EnemyDefeated(battle.enemy.clone())
+    } else {
+        BattleOutcome::Continue
+    };
+    TurnResult { outcome, logs }
+}
+
+/// Lets the enemy's AI pick an intent and carries it out. Returns true when the enemy fled.
 fn resolve_enemy_action<R: Rng>(
     battle: &mut Battle,
     player: &mut Player,
+    companions: &mut [Companion],
     rng: &mut R,
     difficulty: &DifficultyProfile,
     logs: &mut Vec<String>,
-) {
-    let special_triggered = rng.random_range(0..100)
-        < DifficultyProfile::clamp_rate(difficulty.enemy_skill_rate_percent);
+) -> bool {
+    let guarding = battle.enemy_guarding;
+    battle.enemy_guarding = false;
+    let view = AiView {
+        enemy: &battle.enemy,
+        player,
+        skill_rate: DifficultyProfile::clamp_rate(difficulty.enemy_skill_rate_percent),
+        guarding,
+    };
+    let intent = enemy_ai::behavior(battle.enemy.ai).choose(&view, rng);
 
-    match (battle.enemy.style, special_triggered) {
-        (EnemyStyle::Brute, true) => {
+    match (intent, battle.enemy.style) {
+        (EnemyIntent::Flee, _) => {
+            logs.push(t!("log.battle.enemy_fled", enemy = battle.enemy.name.as_str()).to_string());
+            return true;
+        }
+        (EnemyIntent::Guard, _) => {
+            battle.enemy_guarding = true;
+            logs.push(t!("log.battle.enemy_guard", enemy = battle.enemy.name.as_str()).to_string());
+        }
+        (EnemyIntent::Heal, _) => {
+            let heal = (battle.enemy.max_hp / 4).max(1);
+            battle.enemy.hp = (battle.enemy.hp + heal).min(battle.enemy.max_hp);
+            logs.push(
+                t!(
+                    "log.battle.enemy_heal",
+                    enemy = battle.enemy.name.as_str(),
+                    heal = heal
+                )
+                .to_string(),
+            );
+        }
+        (EnemyIntent::Skill, EnemyStyle::Brute) => {
             let raw = combat::random_damage(rng, battle.enemy.atk + 4, player.total_def(), 3);
             let dealt = apply_defense_guard(raw, battle.defending);
             player.hp -= dealt;
@@ -186,7 +269,7 @@ fn resolve_enemy_action<R: Rng>(
                 .to_string(),
             );
         }
-        (EnemyStyle::Caster, true) => {
+        (EnemyIntent::Skill, EnemyStyle::Caster) => {
             let raw = combat::random_damage(rng, battle.enemy.atk + 1, player.total_def(), 2);
             let dealt = apply_defense_guard(raw, battle.defending);
             player.hp -= dealt;
@@ -202,7 +285,7 @@ fn resolve_enemy_action<R: Rng>(
                 .to_string(),
             );
         }
-        (EnemyStyle::Predator, true) => {
+        (EnemyIntent::Skill, EnemyStyle::Predator) => {
             let first = combat::random_damage(rng, battle.enemy.atk + 1, player.total_def(), 2);
             let second = combat::random_damage(rng, battle.enemy.atk, player.total_def(), 1);
             let total = apply_defense_guard(first + second, battle.defending);
@@ -216,7 +299,7 @@ fn resolve_enemy_action<R: Rng>(
                 .to_string(),
             );
         }
-        (EnemyStyle::Undead, true) => {
+        (EnemyIntent::Skill, EnemyStyle::Undead) => {
             let raw = combat::random_damage(rng, battle.enemy.atk + 2, player.total_def(), 2);
             let dealt = apply_defense_guard(raw, battle.defending);
             player.hp -= dealt;
@@ -232,51 +315,243 @@ fn resolve_enemy_action<R: Rng>(
                 .to_string(),
             );
         }
-        (EnemyStyle::Boss, true) => {
-            let breath = rng.random_range(0..100) < 60;
-            if breath {
-                let raw = combat::random_damage(rng, battle.enemy.atk + 6, player.total_def(), 4);
-                let dealt = apply_defense_guard(raw, battle.defending);
-                player.hp -= dealt;
-                logs.push(
-                    t!(
-                        "log.battle.enemy_skill_flame_breath",
-                        enemy = battle.enemy.name.as_str(),
-                        dmg = dealt
-                    )
-                    .to_string(),
-                );
-            } else {
-                let raw = combat::random_damage(rng, battle.enemy.atk + 3, player.total_def(), 2);
-                let dealt = apply_defense_guard(raw, battle.defending);
-                player.hp -= dealt;
-                logs.push(
+        _ => {
+            let (name, atk) = (battle.enemy.name.clone(), battle.enemy.atk);
+            plain_hit(&name, atk, battle, player, companions, rng, logs);
+        }
+    }
+    false
+}
+
+/// Summoned minions each make a plain attack after the boss acts.
+fn resolve_adds<R: Rng>(
+    battle: &mut Battle,
+    player: &mut Player,
+    companions: &mut [Companion],
+    rng: &mut R,
+    logs: &mut Vec<String>,
+) {
+    let attackers: Vec<(String, i32)> = battle
+        .adds
+        .iter()
+        .map(|add| (add.name.clone(), add.atk))
+        .collect();
+    for (name, atk) in attackers {
+        plain_hit(&name, atk, battle, player, companions, rng, logs);
+    }
+}
+
+/// A plain enemy attack aimed at someone in the party, weighted toward the front.
+fn plain_hit<R: Rng>(
+    attacker: &str,
+    atk: i32,
+    battle: &Battle,
+    player: &mut Player,
+    companions: &mut [Companion],
+    rng: &mut R,
+    logs: &mut Vec<String>,
+) {
+    let Some(slot) = party::pick_target(companions, rng) else {
+        let raw = combat::random_damage(rng, atk, player.total_def(), 2);
+        let dealt = apply_defense_guard(raw, battle.defending);
+        player.hp -= dealt;
+        logs.push(t!("log.battle.enemy_hit", enemy = attacker, dmg = dealt).to_string());
+        return;
+    };
+    let member = &mut companions[slot];
+    let raw = combat::random_damage(rng, atk, member.total_def(), 2);
+    let dealt = apply_defense_guard(raw, battle.companions_defending.contains(&slot));
+    member.hp = (member.hp - dealt).max(0);
+    let name = t!(member.kind.name_key());
+    logs.push(
+        t!(
+            "log.party.hit",
+            enemy = attacker,
+            name = name.as_ref(),
+            dmg = dealt
+        )
+        .to_string(),
+    );
+    if member.is_down() {
+        logs.push(t!("log.party.knocked_out", name = name.as_ref()).to_string());
+    }
+}
+
+/// Plain attacks land on the first add, if any, before the enemy itself.
+fn strike_front<R: Rng>(
+    battle: &mut Battle,
+    atk: i32,
+    variance: i32,
+    rng: &mut R,
+    logs: &mut Vec<String>,
+    message: impl Fn(&str, i32) -> String,
+) {
+    let guarded = battle.adds.is_empty() && battle.enemy_guarding;
+    let target = battle.adds.first_mut().unwrap_or(&mut battle.enemy);
+    let raw = combat::random_damage(rng, atk, target.def, variance);
+    let dmg = apply_defense_guard(raw, guarded);
+    target.hp -= dmg;
+    logs.push(message(target.name.as_str(), dmg));
+    if let Some(add) = battle.adds.first()
+        && add.hp <= 0
+    {
+        logs.push(t!("log.battle.add_defeated", enemy = add.name.as_str()).to_string());
+        battle.adds.remove(0);
+    }
+}
+
+fn resolve_companions<R: Rng>(
+    orders: &[CompanionOrder],
+    battle: &mut Battle,
+    player: &mut Player,
+    companions: &mut [Companion],
+    rng: &mut R,
+    logs: &mut Vec<String>,
+) {
+    for (slot, order) in orders.iter().enumerate() {
+        if battle.enemy.hp <= 0 {
+            return;
+        }
+        let Some(member) = companions.get(slot) else {
+            return;
+        };
+        if member.is_down() {
+            continue;
+        }
+        let kind = member.kind;
+        let name = t!(kind.name_key()).to_string();
+        match order {
+            CompanionOrder::Defend => {
+                battle.companions_defending.push(slot);
+                logs.push(t!("log.party.brace", name = name.as_str()).to_string());
+            }
+            CompanionOrder::Skill if member.mp >= kind.skill_mp() => {
+                companions[slot].mp -= kind.skill_mp();
+                companion_skill(slot, &name, battle, player, companions, rng, logs);
+            }
+            CompanionOrder::Skill | CompanionOrder::Attack => {
+                if *order == CompanionOrder::Skill {
+                    logs.push(t!("log.party.no_mp", name = name.as_str()).to_string());
+                }
+                let atk = member.total_atk();
+                strike_front(battle, atk, 3, rng, logs, |enemy, dmg| {
                     t!(
-                        "log.battle.enemy_skill_tail_sweep",
-                        enemy = battle.enemy.name.as_str(),
-                        dmg = dealt
+                        "log.party.attack",
+                        name = name.as_str(),
+                        enemy = enemy,
+                        dmg = dmg
                     )
-                    .to_string(),
-                );
+                    .to_string()
+                });
             }
         }
-        _ => {
-            let raw = combat::random_damage(rng, battle.enemy.atk, player.total_def(), 2);
-            let dealt = apply_defense_guard(raw, battle.defending);
-            player.hp -= dealt;
+    }
+}
+
+fn companion_skill<R: Rng>(
+    slot: usize,
+    name: &str,
+    battle: &mut Battle,
+    player: &mut Player,
+    companions: &mut [Companion],
+    rng: &mut R,
+    logs: &mut Vec<String>,
+) {
+    let member = &companions[slot];
+    match member.kind {
+        CompanionKind::Knight => {
+            battle.defending = true;
+            battle.companions_defending = (0..companions.len()).collect();
+            logs.push(t!("log.party.skill.shield_wall", name = name).to_string());
+        }
+        CompanionKind::Cleric => {
+            let heal = CLERIC_HEAL_BASE + member.level * CLERIC_HEAL_PER_LEVEL;
+            let hero_ratio = player.hp * 100 / player.max_hp.max(1);
+            let weakest = companions
+                .iter()
+                .enumerate()
+                .filter(|(_, m)| !m.is_down())
+                .min_by_key(|(_, m)| m.hp * 100 / m.max_hp.max(1))
+                .filter(|(_, m)| m.hp * 100 / m.max_hp.max(1) < hero_ratio)
+                .map(|(idx, _)| idx);
+            let target = match weakest {
+                Some(idx) => {
+                    let ally = &mut companions[idx];
+                    ally.hp = (ally.hp + heal).min(ally.max_hp);
+                    t!(ally.kind.name_key()).to_string()
+                }
+                None => {
+                    player.hp = (player.hp + heal).min(player.max_hp);
+                    t!("ui.party.hero").to_string()
+                }
+            };
+            logs.push(
+                t!(
+                    "log.party.skill.heal",
+                    name = name,
+                    target = target,
+                    heal = heal
+                )
+                .to_string(),
+            );
+        }
+        CompanionKind::Mage => {
+            let raw = combat::random_damage(rng, member.total_atk() + 8, battle.enemy.def / 2, 3);
+            let dmg = apply_defense_guard(raw, battle.enemy_guarding);
+            battle.enemy.hp -= dmg;
             logs.push(
                 t!(
-                    "log.battle.enemy_hit",
+                    "log.party.skill.firebolt",
+                    name = name,
                     enemy = battle.enemy.name.as_str(),
-                    dmg = dealt
+                    dmg = dmg
                 )
                 .to_string(),
             );
         }
+        CompanionKind::Archer => {
+            let atk = member.total_atk();
+            for _ in 0..2 {
+                strike_front(battle, atk, 2, rng, logs, |enemy, dmg| {
+                    t!(
+                        "log.party.skill.double_shot",
+                        name = name,
+                        enemy = enemy,
+                        dmg = dmg
+                    )
+                    .to_string()
+                });
+            }
+        }
+    }
+}
+
+/// Locale key of the warning shown over the enemy when its next move is
+/// telegraphed: a boss charging up, or a regular enemy's AI tell.
+pub fn enemy_tell(battle: &Battle, player: &Player) -> Option<&'static str> {
+    if battle.script.charging.is_some() {
+        return Some("ui.battle_scene.tell.charging");
     }
+    if boss_ai::script_for(battle.enemy.style).is_some() {
+        return None;
+    }
+    let view = AiView {
+        enemy: &battle.enemy,
+        player,
+        skill_rate: 0,
+        guarding: battle.enemy_guarding,
+    };
+    let intent = enemy_ai::behavior(battle.enemy.ai).tell(&view)?;
+    Some(match intent {
+        EnemyIntent::Attack => "ui.battle_scene.tell.attack",
+        EnemyIntent::Skill => "ui.battle_scene.tell.skill",
+        EnemyIntent::Heal => "ui.battle_scene.tell.heal",
+        EnemyIntent::Guard => "ui.battle_scene.tell.guard",
+        EnemyIntent::Flee => "ui.battle_scene.tell.flee",
+    })
 }
 
-fn apply_defense_guard(damage: i32, defending: bool) -> i32 {
+pub fn apply_defense_guard(damage: i32, defending: bool) -> i32 {
     if defending {
         (damage / 2).max(1)
     } else {
@@ -284,6 +559,86 @@ fn apply_defense_guard(damage: i32, defending: bool) -> i32 {
     }
 }
 
+/// Class skills aim at the enemy itself, past any adds.
+fn use_skill<R: Rng>(
+    skill: HeroSkill,
+    battle: &mut Battle,
+    player: &mut Player,
+    rng: &mut R,
+    logs: &mut Vec<String>,
+) {
+    let enemy = battle.enemy.name.clone();
+    let def = battle.enemy.def;
+    match skill {
+        HeroSkill::FireSlash => {
+            let dmg = skill_hit(battle, player.total_atk() + 6, def, 5, rng);
+            logs.push(t!("log.battle.fire_slash", enemy = enemy.as_str(), dmg = dmg).to_string());
+        }
+        HeroSkill::PowerStrike => {
+            let dmg = skill_hit(battle, player.total_atk() * 2, def / 2, 4, rng);
+            logs.push(
+                t!(
+                    "log.battle.skill.power_strike",
+                    enemy = enemy.as_str(),
+                    dmg = dmg
+                )
+                .to_string(),
+            );
+        }
+        HeroSkill::Fireball => {
+            let dmg = skill_hit(battle, player.total_atk() + 8 + player.level, 0, 4, rng);
+            logs.push(
+                t!(
+                    "log.battle.skill.fireball",
+                    enemy = enemy.as_str(),
+                    dmg = dmg
+                )
+                .to_string(),
+            );
+        }
+        HeroSkill::Heal => {
+            let before = player.hp;
+            player.hp = (player.hp + MAGE_HEAL_BASE + player.level * MAGE_HEAL_PER_LEVEL)
+                .min(player.max_hp);
+            logs.push(t!("log.battle.skill.heal", before = before, after = player.hp).to_string());
+        }
+        HeroSkill::Backstab => {
+            let crit = rng.random_range(0..100) < BACKSTAB_CRIT_PERCENT;
+            let atk = player.total_atk() + 4;
+            let dmg = skill_hit(battle, if crit { atk * 2 } else { atk }, def, 3, rng);
+            let key = if crit {
+                "log.battle.skill.backstab_crit"
+            } else {
+                "log.battle.skill.backstab"
+            };
+            logs.push(t!(key, enemy = enemy.as_str(), dmg = dmg).to_string());
+        }
+        HeroSkill::Steal => {
+            if battle.enemy.is_boss {
+                logs.push(t!("log.battle.skill.steal_failed", enemy = enemy.as_str()).to_string());
+            } else {
+                let gold = STEAL_GOLD_BASE + player.level * STEAL_GOLD_PER_LEVEL;
+                player.gold += gold;
+                logs.push(
+                    t!(
+                        "log.battle.skill.steal",
+                        enemy = enemy.as_str(),
+                        gold = gold
+                    )
+                    .to_string(),
+                );
+            }
+        }
+    }
+}
+
+fn skill_hit<R: Rng>(battle: &mut Battle, atk: i32, def: i32, variance: i32, rng: &mut R) -> i32 {
+    let raw = combat::random_damage(rng, atk, def, variance);
+    let dmg = apply_defense_guard(raw, battle.enemy_guarding);
+    battle.enemy.hp -= dmg;
+    dmg
+}
+
 fn use_potion(player: &mut Player, logs: &mut Vec<String>) -> bool {
     if player.bag.potion <= 0 {
         logs.push(t!("log.item.no_potion").to_string());
@@ -321,14 +676,31 @@ mod tests {
     use rand::SeedableRng;
     use rand::rngs::StdRng;
 
-    use super::{BattleAction, BattleOutcome, action_from_key, resolve_turn};
-    use crate::game::config::profile_for;
-    use crate::game::model::{Battle, Difficulty, Enemy, EnemyStyle, Player};
-    use crossterm::event::KeyCode;
+    use super::{
+        BattleAction, BattleOutcome, TurnResult, action_from_command, enemy_tell,
+        preemptive_strike, resolve_round,
+    };
+    use crate::game::config::{DifficultyProfile, profile_for};
+    use crate::game::model::{
+        AiKind, Battle, Command, CompanionKind, CompanionOrder, Difficulty, Enemy, EnemyStyle,
+        HeroSkill, Player,
+    };
+    use crate::game::party::new_companion;
+
+    fn resolve_turn(
+        action: BattleAction,
+        battle: &mut Battle,
+        player: &mut Player,
+        rng: &mut StdRng,
+        difficulty: &DifficultyProfile,
+    ) -> TurnResult {
+        resolve_round(action, &[], battle, player, &mut [], rng, difficulty)
+    }
 
     fn sample_enemy() -> Enemy {
         Enemy {
             name: "Test Enemy".to_string(),
+            name_key: String::new(),
             hp: 20,
             max_hp: 20,
             atk: 8,
@@ -337,17 +709,26 @@ mod tests {
             gold_reward: 1,
             is_boss: false,
             style: EnemyStyle::Skirmisher,
+            ai: AiKind::Aggressive,
+            loot: None,
+            art: String::new(),
         }
     }
 
     #[test]
-    fn key_to_action_mapping_is_correct() {
+    fn command_to_action_mapping_is_correct() {
         assert_eq!(
-            action_from_key(KeyCode::Char('1')),
+            action_from_command(Command::Attack, HeroSkill::FireSlash),
             Some(BattleAction::Attack)
         );
-        assert_eq!(action_from_key(KeyCode::Char('6')), Some(BattleAction::Run));
-        assert_eq!(action_from_key(KeyCode::Char('x')), None);
+        assert_eq!(
+            action_from_command(Command::Run, HeroSkill::FireSlash),
+            Some(BattleAction::Run)
+        );
+        assert_eq!(
+            action_from_command(Command::MenuUp, HeroSkill::FireSlash),
+            None
+        );
     }
 
     #[test]
@@ -356,14 +737,11 @@ mod tests {
         let mut player = Player::new();
         player.hp = 30;
         player.mp = 0;
-        let mut battle = Battle {
-            enemy: sample_enemy(),
-            defending: false,
-        };
+        let mut battle = Battle::new(sample_enemy());
         let profile = profile_for(Difficulty::Normal);
 
         let result = resolve_turn(
-            BattleAction::FireSlash,
+            BattleAction::Skill(HeroSkill::FireSlash),
             &mut battle,
             &mut player,
             &mut rng,
@@ -374,19 +752,41 @@ mod tests {
         assert_eq!(battle.enemy.hp, 20);
     }
 
+    #[test]
+    fn rounds_tally_damage_dealt_and_taken() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut player = Player::new();
+        let mut battle = Battle::new(Enemy {
+            hp: 500,
+            max_hp: 500,
+            ..sample_enemy()
+        });
+        let profile = profile_for(Difficulty::Normal);
+
+        for _ in 0..3 {
+            resolve_turn(
+                BattleAction::Attack,
+                &mut battle,
+                &mut player,
+                &mut rng,
+                &profile,
+            );
+        }
+        assert_eq!(battle.damage_dealt, 500 - battle.enemy.hp);
+        assert_eq!(battle.damage_taken, player.max_hp - player.hp);
+        assert!(battle.damage_taken > 0);
+    }
+
     #[test]
     fn attack_can_finish_enemy_before_counterattack() {
         let mut rng = StdRng::seed_from_u64(9);
         let mut player = Player::new();
         player.base_atk = 99;
-        let mut battle = Battle {
-            enemy: Enemy {
-                hp: 3,
-                max_hp: 3,
-                ..sample_enemy()
-            },
-            defending: false,
-        };
+        let mut battle = Battle::new(Enemy {
+            hp: 3,
+            max_hp: 3,
+            ..sample_enemy()
+        });
         let profile = profile_for(Difficulty::Normal);
 
         let result = resolve_turn(
@@ -406,17 +806,14 @@ mod tests {
         player.hp = 1;
         player.base_atk = 1;
         player.base_def = 0;
-        let mut battle = Battle {
-            enemy: Enemy {
-                hp: 999,
-                max_hp: 999,
-                atk: 10,
-                def: 50,
-                style: EnemyStyle::Brute,
-                ..sample_enemy()
-            },
-            defending: false,
-        };
+        let mut battle = Battle::new(Enemy {
+            hp: 999,
+            max_hp: 999,
+            atk: 10,
+            def: 50,
+            style: EnemyStyle::Brute,
+            ..sample_enemy()
+        });
         let profile = profile_for(Difficulty::Normal);
 
         let result = resolve_turn(
@@ -429,4 +826,168 @@ mod tests {
         assert!(matches!(result.outcome, BattleOutcome::PlayerDefeated));
         assert_eq!(player.hp, 0);
     }
+
+    #[test]
+    fn preemptive_strike_damages_without_counterattack() {
+        rust_i18n::set_locale("en");
+        let mut rng = StdRng::seed_from_u64(5);
+        let player = Player::new();
+        let mut battle = Battle::new(sample_enemy());
+
+        let result = preemptive_strike(&mut battle, &player, &mut rng);
+        assert!(matches!(result.outcome, BattleOutcome::Continue));
+        assert!(battle.enemy.hp < 20);
+        assert_eq!(result.logs.len(), 1);
+    }
+
+    #[test]
+    fn tells_come_from_the_ai_or_a_charging_boss() {
+        let player = Player::new();
+        let mut battle = Battle::new(Enemy {
+            hp: 5,
+            ai: AiKind::Coward,
+            ..sample_enemy()
+        });
+        assert_eq!(
+            enemy_tell(&battle, &player),
+            Some("ui.battle_scene.tell.flee")
+        );
+        battle.enemy.hp = 20;
+        assert_eq!(enemy_tell(&battle, &player), None);
+
+        battle.enemy.style = EnemyStyle::Warlord;
+        battle.enemy.hp = 5;
+        assert_eq!(enemy_tell(&battle, &player), None);
+        battle.script.charging = Some((0, 0));
+        assert_eq!(
+            enemy_tell(&battle, &player),
+            Some("ui.battle_scene.tell.charging")
+        );
+    }
+
+    #[test]
+    fn boss_announces_second_phase_when_hp_crosses_threshold() {
+        rust_i18n::set_locale("en");
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut player = Player::new();
+        player.hp = 500;
+        player.max_hp = 500;
+        player.base_atk = 30;
+        let mut battle = Battle::new(Enemy {
+            name: "Warlord".to_string(),
+            name_key: String::new(),
+            hp: 60,
+            max_hp: 100,
+            is_boss: true,
+            style: EnemyStyle::Warlord,
+            ..sample_enemy()
+        });
+        let profile = profile_for(Difficulty::Normal);
+
+        let result = resolve_turn(
+            BattleAction::Attack,
+            &mut battle,
+            &mut player,
+            &mut rng,
+            &profile,
+        );
+        assert!(matches!(result.outcome, BattleOutcome::Continue));
+        assert!(battle.enemy.hp <= 50);
+        assert!(
+            result
+                .logs
+                .iter()
+                .any(|line| line.contains("Warlord") && line.contains("frenzy"))
+        );
+    }
+
+    #[test]
+    fn cowardly_enemy_at_low_hp_can_flee_the_battle() {
+        rust_i18n::set_locale("en");
+        let profile = profile_for(Difficulty::Normal);
+        let fled = (0..20).any(|seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut player = Player::new();
+            player.base_atk = 1;
+            let mut battle = Battle::new(Enemy {
+                hp: 10,
+                max_hp: 100,
+                def: 50,
+                ai: AiKind::Coward,
+                loot: None,
+                art: String::new(),
+                ..sample_enemy()
+            });
+            let result = resolve_turn(
+                BattleAction::Defend,
+                &mut battle,
+                &mut player,
+                &mut rng,
+                &profile,
+            );
+            matches!(result.outcome, BattleOutcome::EnemyFled)
+        });
+        assert!(fled);
+    }
+
+    #[test]
+    fn guarding_enemy_halves_the_next_hit() {
+        let mut player = Player::new();
+        player.base_atk = 40;
+        let profile = profile_for(Difficulty::Normal);
+        let mut open = Battle::new(Enemy {
+            hp: 500,
+            max_hp: 500,
+            ..sample_enemy()
+        });
+        let mut guarded = open.clone();
+        guarded.enemy_guarding = true;
+
+        resolve_turn(
+            BattleAction::Attack,
+            &mut open,
+            &mut player.clone(),
+            &mut StdRng::seed_from_u64(6),
+            &profile,
+        );
+        resolve_turn(
+            BattleAction::Attack,
+            &mut guarded,
+            &mut player,
+            &mut StdRng::seed_from_u64(6),
+            &profile,
+        );
+        assert!(500 - guarded.enemy.hp < 500 - open.enemy.hp);
+    }
+
+    #[test]
+    fn companions_follow_their_orders_after_the_hero() {
+        rust_i18n::set_locale("en");
+        let profile = profile_for(Difficulty::Normal);
+        let mut enemy = sample_enemy();
+        enemy.hp = 200;
+        enemy.max_hp = 200;
+        let mut battle = Battle::new(enemy);
+        let mut player = Player::new();
+        let mut party = vec![
+            new_companion(CompanionKind::Knight, 1),
+            new_companion(CompanionKind::Archer, 1),
+        ];
+        let mut rng = StdRng::seed_from_u64(5);
+
+        let result = resolve_round(
+            BattleAction::Defend,
+            &[CompanionOrder::Skill, CompanionOrder::Attack],
+            &mut battle,
+            &mut player,
+            &mut party,
+            &mut rng,
+            &profile,
+        );
+
+        assert!(matches!(result.outcome, BattleOutcome::Continue));
+        assert!(battle.enemy.hp < 200);
+        assert!(party[0].mp < party[0].max_mp);
+        assert!(battle.companions_defending.is_empty());
+    }
 }
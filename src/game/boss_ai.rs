@@ -0,0 +1,517 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use rand::Rng;
+use rust_i18n::t;
+use serde::Deserialize;
+
+use crate::game::battle::apply_defense_guard;
+use crate::game::combat;
+use crate::game::config::DifficultyProfile;
+use crate::game::model::{AiKind, Battle, Enemy, EnemyStyle, Player};
+
+const BOSS_DATA: &str = include_str!("../../config/bosses.toml");
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct BossScript {
+    pub phases: Vec<BossPhase>,
+    #[serde(default)]
+    pub enrage: Option<Enrage>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct BossPhase {
+    pub hp_percent: i32,
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub skill_rate_bonus: i32,
+    pub moves: Vec<BossMove>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct BossMove {
+    pub weight: i32,
+    pub text: String,
+    #[serde(default)]
+    pub telegraph: Option<String>,
+    #[serde(default)]
+    pub effects: Vec<MoveEffect>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MoveEffect {
+    Strike {
+        power: i32,
+        variance: i32,
+        #[serde(default = "one")]
+        hits: i32,
+    },
+    BuffAtk(i32),
+    BuffDef(i32),
+    BurnMp(i32),
+    /// Heals the boss by this percent of the damage dealt so far.
+    Drain(i32),
+    Summon(SummonDef),
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct SummonDef {
+    pub name: String,
+    pub hp: i32,
+    pub atk: i32,
+    pub def: i32,
+    pub max: usize,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Enrage {
+    pub turn: u32,
+    pub text: String,
+    pub atk: i32,
+    #[serde(default)]
+    pub skill_rate_bonus: i32,
+}
+
+fn one() -> i32 {
+    1
+}
+
+fn book() -> &'static HashMap<String, BossScript> {
+    static BOOK: OnceLock<HashMap<String, BossScript>> = OnceLock::new();
+    BOOK.get_or_init(|| toml::from_str(BOSS_DATA).expect("embedded boss data is valid"))
+}
+
+pub fn script_for(style: EnemyStyle) -> Option<&'static BossScript> {
+    let id = match style {
+        EnemyStyle::Warlord => "warlord",
+        EnemyStyle::Golem => "golem",
+        EnemyStyle::Lich => "lich",
+        EnemyStyle::Boss => "dragon",
+        _ => return None,
+    };
+    book().get(id)
+}
+
+/// Moves the boss into the deepest phase its HP qualifies for, announcing it.
+pub fn update_phase(script: &BossScript, battle: &mut Battle, logs: &mut Vec<String>) {
+    let hp_percent = battle.enemy.hp * 100 / battle.enemy.max_hp.max(1);
+    let Some(phase) = script
+        .phases
+        .iter()
+        .rposition(|phase| hp_percent <= phase.hp_percent)
+    else {
+        return;
+    };
+    if phase <= battle.script.phase {
+        return;
+    }
+    battle.script.phase = phase;
+    if let Some(text) = &script.phases[phase].text {
+        logs.push(t!(text.as_str(), enemy = battle.enemy.name.as_str()).to_string());
+    }
+}
+
+pub fn take_turn<R: Rng>(
+    script: &BossScript,
+    battle: &mut Battle,
+    player: &mut Player,
+    rng: &mut R,
+    difficulty: &DifficultyProfile,
+    logs: &mut Vec<String>,
+) {
+    battle.script.turns += 1;
+    if let Some(enrage) = &script.enrage
+        && !battle.script.enraged
+        && battle.script.turns >= enrage.turn
+    {
+        battle.script.enraged = true;
+        battle.enemy.atk += enrage.atk;
+        logs.push(t!(enrage.text.as_str(), enemy = battle.enemy.name.as_str()).to_string());
+    }
+
+    if let Some((phase, index)) = battle.script.charging.take()
+        && let Some(mv) = script.phases.get(phase).and_then(|p| p.moves.get(index))
+    {
+        perform_move(mv, true, battle, player, rng, logs);
+        return;
+    }
+
+    let Some(phase) = script.phases.get(battle.script.phase) else {
+        basic_attack(battle, player, rng, logs);
+        return;
+    };
+    let enrage_bonus = match &script.enrage {
+        Some(enrage) if battle.script.enraged => enrage.skill_rate_bonus,
+        _ => 0,
+    };
+    let rate = DifficultyProfile::clamp_rate(
+        difficulty.enemy_skill_rate_percent + phase.skill_rate_bonus + enrage_bonus,
+    );
+    let picked = if rng.random_range(0..100) < rate {
+        pick_move(phase, battle, rng)
+    } else {
+        None
+    };
+    let Some(index) = picked else {
+        basic_attack(battle, player, rng, logs);
+        return;
+    };
+
+    let mv = &phase.moves[index];
+    if let Some(telegraph) = &mv.telegraph {
+        battle.script.charging = Some((battle.script.phase, index));
+        logs.push(t!(telegraph.as_str(), enemy = battle.enemy.name.as_str()).to_string());
+    } else {
+        perform_move(mv, false, battle, player, rng, logs);
+    }
+}
+
+fn pick_move<R: Rng>(phase: &BossPhase, battle: &Battle, rng: &mut R) -> Option<usize> {
+    let options: Vec<(usize, i32)> = phase
+        .moves
+        .iter()
+        .enumerate()
+        .filter(|(_, mv)| is_available(mv, battle))
+        .map(|(index, mv)| (index, mv.weight.max(0)))
+        .collect();
+    let total: i32 = options.iter().map(|(_, weight)| weight).sum();
+    if total <= 0 {
+        return None;
+    }
+    let mut roll = rng.random_range(0..total);
+    for (index, weight) in options {
+        if roll < weight {
+            return Some(index);
+        }
+        roll -= weight;
+    }
+    None
+}
+
+/// Summons are skipped once the boss already has its full retinue.
+fn is_available(mv: &BossMove, battle: &Battle) -> bool {
+    mv.effects.iter().all(|effect| match effect {
+        MoveEffect::Summon(summon) => battle.adds.len() < summon.max,
+        _ => true,
+    })
+}
+
+fn perform_move<R: Rng>(
+    mv: &BossMove,
+    telegraphed: bool,
+    battle: &mut Battle,
+    player: &mut Player,
+    rng: &mut R,
+    logs: &mut Vec<String>,
+) {
+    let mut dealt = 0;
+    let mut burned = 0;
+    let mut healed = 0;
+    let mut summoned = String::new();
+    for effect in &mv.effects {
+        match effect {
+            MoveEffect::Strike {
+                power,
+                variance,
+                hits,
+            } => {
+                let raw: i32 = (0..(*hits).max(1))
+                    .map(|_| {
+                        combat::random_damage(
+                            rng,
+                            battle.enemy.atk + power,
+                            player.total_def(),
+                            *variance,
+                        )
+                    })
+                    .sum();
+                let damage = if telegraphed && battle.defending {
+                    (raw / 4).max(1)
+                } else {
+                    apply_defense_guard(raw, battle.defending)
+                };
+                player.hp -= damage;
+                dealt += damage;
+            }
+            MoveEffect::BuffAtk(amount) => battle.enemy.atk += amount,
+            MoveEffect::BuffDef(amount) => battle.enemy.def += amount,
+            MoveEffect::BurnMp(amount) => {
+                let burn = (*amount).min(player.mp);
+                player.mp -= burn;
+                burned += burn;
+            }
+            MoveEffect::Drain(percent) if dealt > 0 => {
+                let heal = (dealt * percent / 100).max(1);
+                battle.enemy.hp = (battle.enemy.hp + heal).min(battle.enemy.max_hp);
+                healed += heal;
+            }
+            MoveEffect::Drain(_) => {}
+            MoveEffect::Summon(summon) => {
+                summoned = t!(summon.name.as_str()).to_string();
+                battle.adds.push(Enemy {
+                    name: summoned.clone(),
+                    name_key: summon.name.clone(),
+                    hp: summon.hp,
+                    max_hp: summon.hp,
+                    atk: summon.atk,
+                    def: summon.def,
+                    exp_reward: 0,
+                    gold_reward: 0,
+                    is_boss: false,
+                    style: EnemyStyle::Skirmisher,
+                    ai: AiKind::Aggressive,
+                    loot: None,
+                    art: String::new(),
+                });
+            }
+        }
+    }
+    logs.push(
+        t!(
+            mv.text.as_str(),
+            enemy = battle.enemy.name.as_str(),
+            dmg = dealt,
+            mp = burned,
+            heal = healed,
+            add = summoned
+        )
+        .to_string(),
+    );
+    if telegraphed && battle.defending {
+        logs.push(t!("log.battle.boss.guarded").to_string());
+    }
+}
+
+fn basic_attack<R: Rng>(
+    battle: &mut Battle,
+    player: &mut Player,
+    rng: &mut R,
+    logs: &mut Vec<String>,
+) {
+    let raw = combat::random_damage(rng, battle.enemy.atk, player.total_def(), 2);
+    let dealt = apply_defense_guard(raw, battle.defending);
+    player.hp -= dealt;
+    logs.push(
+        t!(
+            "log.battle.enemy_hit",
+            enemy = battle.enemy.name.as_str(),
+            dmg = dealt
+        )
+        .to_string(),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use rust_i18n::t;
+
+    use super::{BossMove, MoveEffect, book, perform_move, script_for, take_turn, update_phase};
+    use crate::game::config::profile_for;
+    use crate::game::model::{AiKind, Battle, Difficulty, Enemy, EnemyStyle, Player};
+
+    fn boss(style: EnemyStyle, hp: i32) -> Battle {
+        Battle::new(Enemy {
+            name: "Boss".to_string(),
+            name_key: String::new(),
+            hp,
+            max_hp: 100,
+            atk: 12,
+            def: 4,
+            exp_reward: 1,
+            gold_reward: 1,
+            is_boss: true,
+            style,
+            ai: AiKind::Aggressive,
+            loot: None,
+            art: String::new(),
+        })
+    }
+
+    #[test]
+    fn drain_heals_only_off_damage_dealt() {
+        rust_i18n::set_locale("en");
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut player = Player::new();
+        let mut battle = boss(EnemyStyle::Lich, 50);
+        let drain = |effects| BossMove {
+            weight: 1,
+            text: "log.battle.boss.soul_drain".to_string(),
+            telegraph: None,
+            effects,
+        };
+
+        let mut logs = Vec::new();
+        let dry = drain(vec![MoveEffect::Drain(50)]);
+        perform_move(&dry, false, &mut battle, &mut player, &mut rng, &mut logs);
+        assert_eq!(battle.enemy.hp, 50);
+
+        let strike = MoveEffect::Strike {
+            power: 2,
+            variance: 0,
+            hits: 1,
+        };
+        let soul_drain = drain(vec![strike, MoveEffect::Drain(50)]);
+        perform_move(
+            &soul_drain,
+            false,
+            &mut battle,
+            &mut player,
+            &mut rng,
+            &mut logs,
+        );
+        assert!(battle.enemy.hp > 50);
+    }
+
+    #[test]
+    fn embedded_scripts_have_phases_and_locale_keys() {
+        rust_i18n::set_locale("en");
+        for (id, script) in book() {
+            assert!(!script.phases.is_empty(), "{id}");
+            for phase in &script.phases {
+                assert!(!phase.moves.is_empty(), "{id}");
+                if let Some(text) = &phase.text {
+                    assert_ne!(t!(text.as_str()), *text);
+                }
+                for mv in &phase.moves {
+                    assert_ne!(t!(mv.text.as_str()), mv.text);
+                    if let Some(telegraph) = &mv.telegraph {
+                        assert_ne!(t!(telegraph.as_str()), *telegraph);
+                    }
+                }
+            }
+        }
+        for style in [
+            EnemyStyle::Warlord,
+            EnemyStyle::Golem,
+            EnemyStyle::Lich,
+            EnemyStyle::Boss,
+        ] {
+            assert!(script_for(style).is_some());
+        }
+        assert!(script_for(EnemyStyle::Brute).is_none());
+    }
+
+    #[test]
+    fn phase_only_advances_once_hp_drops() {
+        let script = script_for(EnemyStyle::Golem).unwrap();
+        let mut battle = boss(EnemyStyle::Golem, 80);
+        let mut logs = Vec::new();
+
+        update_phase(script, &mut battle, &mut logs);
+        assert_eq!(battle.script.phase, 0);
+        assert!(logs.is_empty());
+
+        battle.enemy.hp = 40;
+        update_phase(script, &mut battle, &mut logs);
+        assert_eq!(battle.script.phase, 1);
+        assert_eq!(logs.len(), 1);
+
+        battle.enemy.hp = 90;
+        update_phase(script, &mut battle, &mut logs);
+        assert_eq!(battle.script.phase, 1);
+    }
+
+    #[test]
+    fn defending_blunts_a_telegraphed_attack() {
+        let script = script_for(EnemyStyle::Boss).unwrap();
+        let profile = profile_for(Difficulty::Normal);
+        let mut rng = StdRng::seed_from_u64(4);
+        let mut player = Player::new();
+        player.hp = 500;
+        player.max_hp = 500;
+
+        let mut open = boss(EnemyStyle::Boss, 40);
+        open.script.phase = 1;
+        open.script.charging = Some((1, 0));
+        take_turn(
+            script,
+            &mut open,
+            &mut player,
+            &mut rng,
+            &profile,
+            &mut Vec::new(),
+        );
+        let open_damage = 500 - player.hp;
+
+        player.hp = 500;
+        let mut guarded = boss(EnemyStyle::Boss, 40);
+        guarded.script.phase = 1;
+        guarded.script.charging = Some((1, 0));
+        guarded.defending = true;
+        let mut logs = Vec::new();
+        take_turn(
+            script,
+            &mut guarded,
+            &mut player,
+            &mut rng,
+            &profile,
+            &mut logs,
+        );
+        let guarded_damage = 500 - player.hp;
+
+        assert!(open.script.charging.is_none());
+        assert!(guarded_damage * 2 < open_damage);
+        assert_eq!(logs.len(), 2);
+    }
+
+    #[test]
+    fn boss_enrages_after_its_turn_limit() {
+        let script = script_for(EnemyStyle::Warlord).unwrap();
+        let enrage_turn = script.enrage.as_ref().unwrap().turn;
+        let profile = profile_for(Difficulty::Normal);
+        let mut rng = StdRng::seed_from_u64(8);
+        let mut player = Player::new();
+        player.hp = 9999;
+        player.max_hp = 9999;
+        let mut battle = boss(EnemyStyle::Warlord, 100);
+
+        for _ in 1..enrage_turn {
+            take_turn(
+                script,
+                &mut battle,
+                &mut player,
+                &mut rng,
+                &profile,
+                &mut Vec::new(),
+            );
+        }
+        assert!(!battle.script.enraged);
+        take_turn(
+            script,
+            &mut battle,
+            &mut player,
+            &mut rng,
+            &profile,
+            &mut Vec::new(),
+        );
+        assert!(battle.script.enraged);
+    }
+
+    #[test]
+    fn summons_stop_at_the_retinue_cap() {
+        let script = script_for(EnemyStyle::Warlord).unwrap();
+        let mut profile = profile_for(Difficulty::Normal);
+        profile.enemy_skill_rate_percent = 100;
+        let mut rng = StdRng::seed_from_u64(2);
+        let mut player = Player::new();
+        player.hp = 9999;
+        player.max_hp = 9999;
+        let mut battle = boss(EnemyStyle::Warlord, 100);
+
+        for _ in 0..10 {
+            take_turn(
+                script,
+                &mut battle,
+                &mut player,
+                &mut rng,
+                &profile,
+                &mut Vec::new(),
+            );
+        }
+        assert_eq!(battle.adds.len(), 2);
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::BTreeSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+
+use crate::game::balance::{
+    DAILY_MODIFIER_COUNT, DOUBLE_ENCOUNTER_MULTIPLIER, GLASS_CANNON_ENEMY_ATK_SCALE,
+    GLASS_CANNON_ENEMY_HP_SCALE,
+};
+use crate::game::config::DifficultyProfile;
+use crate::game::model::RunModifier;
+
+const DAILY_SALT: u64 = 0x2545_F491_4F6C_DD1D;
+
+/// Days since the Unix epoch, in UTC.
+pub fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() / 86_400)
+        .unwrap_or(0)
+}
+
+/// The map seed everyone shares on the given day.
+pub fn daily_seed(day: u64) -> u64 {
+    let mut z = day.wrapping_add(DAILY_SALT);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// The challenge rules drawn for the given day.
+pub fn daily_modifiers(day: u64) -> BTreeSet<RunModifier> {
+    let mut rng = StdRng::seed_from_u64(daily_seed(day));
+    let mut pool = RunModifier::ALL.to_vec();
+    pool.shuffle(&mut rng);
+    pool.into_iter().take(DAILY_MODIFIER_COUNT).collect()
+}
+
+/// Formats a day number as an ISO date (`YYYY-MM-DD`).
+pub fn date_label(day: u64) -> String {
+    // Civil-from-days conversion for the proleptic Gregorian calendar.
+    let z = day as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    format!("{y:04}-{m:02}-{d:02}")
+}
+
+/// Folds the run's modifiers into the difficulty profile.
+pub fn adjust_profile(
+    mut profile: DifficultyProfile,
+    modifiers: &BTreeSet<RunModifier>,
+) -> DifficultyProfile {
+    if modifiers.contains(&RunModifier::DoubleEncounters) {
+        profile.random_encounter_rate_percent = DifficultyProfile::clamp_rate(
+            profile.random_encounter_rate_percent * DOUBLE_ENCOUNTER_MULTIPLIER,
+        );
+    }
+    if modifiers.contains(&RunModifier::GlassCannon) {
+        profile.enemy_hp_scale *= GLASS_CANNON_ENEMY_HP_SCALE;
+        profile.enemy_atk_scale *= GLASS_CANNON_ENEMY_ATK_SCALE;
+    }
+    profile
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::{adjust_profile, daily_modifiers, daily_seed, date_label};
+    use crate::game::balance::DAILY_MODIFIER_COUNT;
+    use crate::game::config::profile_for;
+    use crate::game::model::{Difficulty, RunModifier};
+
+    #[test]
+    fn daily_runs_are_shared_per_day() {
+        assert_eq!(daily_seed(20_000), daily_seed(20_000));
+        assert_ne!(daily_seed(20_000), daily_seed(20_001));
+        assert_eq!(daily_modifiers(20_000), daily_modifiers(20_000));
+        assert_eq!(daily_modifiers(20_000).len(), DAILY_MODIFIER_COUNT);
+    }
+
+    #[test]
+    fn day_numbers_format_as_dates() {
+        assert_eq!(date_label(0), "1970-01-01");
+        assert_eq!(date_label(11_016), "2000-02-29");
+        assert_eq!(date_label(20_744), "2026-10-18");
+    }
+
+    #[test]
+    fn modifiers_reshape_the_difficulty_profile() {
+        let normal = profile_for(Difficulty::Normal);
+        let unchanged = adjust_profile(normal.clone(), &BTreeSet::new());
+        assert_eq!(
+            unchanged.random_encounter_rate_percent,
+            normal.random_encounter_rate_percent
+        );
+
+        let modifiers = BTreeSet::from([RunModifier::DoubleEncounters, RunModifier::GlassCannon]);
+        let adjusted = adjust_profile(normal.clone(), &modifiers);
+        assert_eq!(
+            adjusted.random_encounter_rate_percent,
+            normal.random_encounter_rate_percent * 2
+        );
+        assert!(adjusted.enemy_hp_scale < normal.enemy_hp_scale);
+        assert!(adjusted.enemy_atk_scale > normal.enemy_atk_scale);
+    }
+}
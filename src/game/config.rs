@@ -1,13 +1,25 @@
+use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, OnceLock, PoisonError};
+use std::time::SystemTime;
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use crate::game::model::Difficulty;
+use crate::game::balance::{
+    NG_PLUS_ATK_SCALE_PER_TIER, NG_PLUS_DEF_SCALE_PER_TIER, NG_PLUS_HP_SCALE_PER_TIER,
+    NG_PLUS_REWARD_SCALE_PER_TIER, profile_field_bounds, profile_field_limits,
+};
+use crate::game::event;
+use crate::game::model::{Difficulty, ProfileField};
 
 const DEFAULT_DIFFICULTY_CONFIG_PATH: &str = "config/difficulty.toml";
+const EMBEDDED_DIFFICULTY_CONFIG: &str = include_str!("../../config/difficulty.toml");
+const DIFFICULTY_FILE_NAME: &str = "difficulty.toml";
+const APP_CONFIG_DIR: &str = "rpg-tui";
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct DifficultyProfile {
     pub random_encounter_rate_percent: i32,
     pub world_event_rate_percent: i32,
@@ -17,6 +29,9 @@ pub struct DifficultyProfile {
     pub enemy_reward_scale: f32,
     pub enemy_skill_rate_percent: i32,
     pub run_chance_bonus_percent: i32,
+    /// Per-event weight overrides keyed by event id from `config/events.toml`.
+    #[serde(default)]
+    pub event_weights: HashMap<String, i32>,
 }
 
 impl DifficultyProfile {
@@ -27,9 +42,138 @@ impl DifficultyProfile {
     pub fn clamp_rate(value: i32) -> i32 {
         value.clamp(0, 100)
     }
+
+    /// Raises enemy stats and rewards for the given New Game+ cycle.
+    pub fn for_ng_plus(mut self, tier: u32) -> Self {
+        let tier = tier as f32;
+        self.enemy_hp_scale *= 1.0 + NG_PLUS_HP_SCALE_PER_TIER * tier;
+        self.enemy_atk_scale *= 1.0 + NG_PLUS_ATK_SCALE_PER_TIER * tier;
+        self.enemy_def_scale *= 1.0 + NG_PLUS_DEF_SCALE_PER_TIER * tier;
+        self.enemy_reward_scale *= 1.0 + NG_PLUS_REWARD_SCALE_PER_TIER * tier;
+        self
+    }
+
+    pub fn value(&self, field: ProfileField) -> f32 {
+        match field {
+            ProfileField::EncounterRate => self.random_encounter_rate_percent as f32,
+            ProfileField::EventRate => self.world_event_rate_percent as f32,
+            ProfileField::EnemyHp => self.enemy_hp_scale,
+            ProfileField::EnemyAtk => self.enemy_atk_scale,
+            ProfileField::EnemyDef => self.enemy_def_scale,
+            ProfileField::EnemyReward => self.enemy_reward_scale,
+            ProfileField::SkillRate => self.enemy_skill_rate_percent as f32,
+            ProfileField::RunBonus => self.run_chance_bonus_percent as f32,
+        }
+    }
+
+    fn set(&mut self, field: ProfileField, value: f32) {
+        // Scales keep two decimals so repeated nudges do not drift.
+        let scale = (value * 100.0).round() / 100.0;
+        let percent = value.round() as i32;
+        match field {
+            ProfileField::EncounterRate => self.random_encounter_rate_percent = percent,
+            ProfileField::EventRate => self.world_event_rate_percent = percent,
+            ProfileField::EnemyHp => self.enemy_hp_scale = scale,
+            ProfileField::EnemyAtk => self.enemy_atk_scale = scale,
+            ProfileField::EnemyDef => self.enemy_def_scale = scale,
+            ProfileField::EnemyReward => self.enemy_reward_scale = scale,
+            ProfileField::SkillRate => self.enemy_skill_rate_percent = percent,
+            ProfileField::RunBonus => self.run_chance_bonus_percent = percent,
+        }
+    }
+
+    /// The profile with every field pulled back within its Settings slider.
+    pub fn within_sliders(mut self) -> Self {
+        for field in ProfileField::ALL {
+            let bounds = profile_field_bounds(field);
+            self.set(field, self.value(field).clamp(bounds.min, bounds.max));
+        }
+        self
+    }
+
+    /// Moves a slider by `steps` increments without leaving its bounds.
+    pub fn nudge(&mut self, field: ProfileField, steps: i32) {
+        let bounds = profile_field_bounds(field);
+        let value = self.value(field) + bounds.step * steps as f32;
+        self.set(field, value.clamp(bounds.min, bounds.max));
+    }
+
+    /// How far along its slider the field sits, from 0.0 to 1.0.
+    pub fn fraction(&self, field: ProfileField) -> f32 {
+        let bounds = profile_field_bounds(field);
+        ((self.value(field) - bounds.min) / (bounds.max - bounds.min)).clamp(0.0, 1.0)
+    }
+
+    pub fn value_text(&self, field: ProfileField) -> String {
+        let value = self.value(field);
+        match field {
+            _ if field.is_scale() => format!("x{value:.2}"),
+            ProfileField::RunBonus => format!("{value:+}%"),
+            _ => format!("{value}%"),
+        }
+    }
+
+    /// Rejects profiles with a field the game cannot play, a negative event
+    /// weight or a weight for an event that does not exist, naming the
+    /// offending key as it is spelled in the config and save files.
+    pub fn validate(&self) -> Result<(), String> {
+        self.check_fields(profile_field_limits)?;
+        let mut weights: Vec<_> = self.event_weights.iter().collect();
+        weights.sort();
+        for (id, weight) in weights {
+            if !event::is_known(id) {
+                return Err(format!("event_weights.{id}: no event has this id"));
+            }
+            if *weight < 0 {
+                return Err(format!("event_weights.{id} = {weight} is negative"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Like [`Self::validate`], but a custom profile must also sit within its
+    /// Settings sliders.
+    pub fn validate_custom(&self) -> Result<(), String> {
+        self.validate()?;
+        self.check_fields(|field| {
+            let bounds = profile_field_bounds(field);
+            (bounds.min, bounds.max)
+        })
+    }
+
+    fn check_fields(&self, limits: impl Fn(ProfileField) -> (f32, f32)) -> Result<(), String> {
+        for field in ProfileField::ALL {
+            let (min, max) = limits(field);
+            let value = self.value(field);
+            if !(min..=max).contains(&value) {
+                return Err(format!(
+                    "{} = {} is outside {}..={}",
+                    field_key(field),
+                    value,
+                    min,
+                    max
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn field_key(field: ProfileField) -> &'static str {
+    match field {
+        ProfileField::EncounterRate => "random_encounter_rate_percent",
+        ProfileField::EventRate => "world_event_rate_percent",
+        ProfileField::EnemyHp => "enemy_hp_scale",
+        ProfileField::EnemyAtk => "enemy_atk_scale",
+        ProfileField::EnemyDef => "enemy_def_scale",
+        ProfileField::EnemyReward => "enemy_reward_scale",
+        ProfileField::SkillRate => "enemy_skill_rate_percent",
+        ProfileField::RunBonus => "run_chance_bonus_percent",
+    }
 }
 
 #[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
 struct DifficultyProfiles {
     easy: DifficultyProfile,
     normal: DifficultyProfile,
@@ -37,58 +181,367 @@ struct DifficultyProfiles {
 }
 
 impl DifficultyProfiles {
-    fn defaults() -> Self {
-        Self {
-            easy: DifficultyProfile {
-                random_encounter_rate_percent: 12,
-                world_event_rate_percent: 18,
-                enemy_hp_scale: 0.86,
-                enemy_atk_scale: 0.85,
-                enemy_def_scale: 0.9,
-                enemy_reward_scale: 0.95,
-                enemy_skill_rate_percent: 16,
-                run_chance_bonus_percent: 18,
-            },
-            normal: DifficultyProfile {
-                random_encounter_rate_percent: 16,
-                world_event_rate_percent: 14,
-                enemy_hp_scale: 1.0,
-                enemy_atk_scale: 1.0,
-                enemy_def_scale: 1.0,
-                enemy_reward_scale: 1.0,
-                enemy_skill_rate_percent: 26,
-                run_chance_bonus_percent: 0,
-            },
-            hard: DifficultyProfile {
-                random_encounter_rate_percent: 21,
-                world_event_rate_percent: 11,
-                enemy_hp_scale: 1.22,
-                enemy_atk_scale: 1.18,
-                enemy_def_scale: 1.15,
-                enemy_reward_scale: 1.12,
-                enemy_skill_rate_percent: 40,
-                run_chance_bonus_percent: -10,
-            },
-        }
+    fn embedded() -> Self {
+        toml::from_str(EMBEDDED_DIFFICULTY_CONFIG).expect("embedded difficulty config is valid")
     }
 
+    /// Custom difficulty starts from the normal profile; the player's edits
+    /// live in the preferences file.
     fn profile(&self, difficulty: Difficulty) -> DifficultyProfile {
         match difficulty {
             Difficulty::Easy => self.easy.clone(),
-            Difficulty::Normal => self.normal.clone(),
+            Difficulty::Normal | Difficulty::Custom => self.normal.clone(),
             Difficulty::Hard => self.hard.clone(),
         }
     }
+
+    fn validate(&self) -> Vec<String> {
+        [
+            ("easy", &self.easy),
+            ("normal", &self.normal),
+            ("hard", &self.hard),
+        ]
+        .into_iter()
+        .filter_map(|(name, profile)| {
+            profile
+                .validate()
+                .err()
+                .map(|error| format!("[{name}] {error}"))
+        })
+        .collect()
+    }
 }
 
-fn load_profiles(path: &Path) -> DifficultyProfiles {
-    let Ok(content) = fs::read_to_string(path) else {
-        return DifficultyProfiles::defaults();
-    };
-    toml::from_str(&content).unwrap_or_else(|_| DifficultyProfiles::defaults())
+/// Candidate locations for the difficulty file, most specific first: the
+/// `--config` flag, the XDG config directory, the working directory and the
+/// directory of the executable. The embedded copy is used when none exists.
+pub fn search_paths(cli_path: Option<&Path>) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = cli_path.map(Path::to_path_buf).into_iter().collect();
+    let xdg_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")));
+    if let Some(xdg_home) = xdg_home {
+        paths.push(xdg_home.join(APP_CONFIG_DIR).join(DIFFICULTY_FILE_NAME));
+    }
+    paths.push(PathBuf::from(DEFAULT_DIFFICULTY_CONFIG_PATH));
+    if let Ok(exe) = std::env::current_exe()
+        && let Some(dir) = exe.parent()
+    {
+        paths.push(dir.join(DEFAULT_DIFFICULTY_CONFIG_PATH));
+    }
+    paths
+}
+
+/// Which file the profiles came from and when it was last written, so hot
+/// reload can tell an edit or a newly created file apart from no change.
+#[derive(Clone, Debug, PartialEq)]
+struct Fingerprint {
+    path: Option<PathBuf>,
+    modified: Option<SystemTime>,
+}
+
+impl Fingerprint {
+    fn of(paths: &[PathBuf]) -> Self {
+        let path = paths.iter().find(|path| path.is_file()).cloned();
+        let modified = path
+            .as_ref()
+            .and_then(|path| fs::metadata(path).and_then(|meta| meta.modified()).ok());
+        Self { path, modified }
+    }
+
+    fn source(&self) -> String {
+        self.path.as_ref().map_or_else(
+            || "built-in defaults".to_string(),
+            |path| path.display().to_string(),
+        )
+    }
+}
+
+/// The result of a hot reload: where the values now come from and anything
+/// wrong with the file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReloadReport {
+    pub source: String,
+    pub errors: Vec<String>,
+}
+
+struct ActiveConfig {
+    paths: Vec<PathBuf>,
+    profiles: DifficultyProfiles,
+    fingerprint: Fingerprint,
+    diagnostics: Vec<String>,
+}
+
+impl ActiveConfig {
+    /// Loads the first difficulty file found; a broken file is reported and
+    /// replaced by the embedded profiles.
+    fn load(paths: Vec<PathBuf>) -> Self {
+        let fingerprint = Fingerprint::of(&paths);
+        let mut diagnostics = Vec::new();
+        let profiles = match &fingerprint.path {
+            Some(path) => read_profiles(path).unwrap_or_else(|errors| {
+                diagnostics.extend(errors);
+                diagnostics.push("using the built-in difficulty profiles".to_string());
+                DifficultyProfiles::embedded()
+            }),
+            None => DifficultyProfiles::embedded(),
+        };
+        Self {
+            paths,
+            profiles,
+            fingerprint,
+            diagnostics,
+        }
+    }
+
+    /// Re-reads the profiles when another file or a newer revision is on
+    /// disk. A broken edit keeps the values already in play.
+    fn reload_if_changed(&mut self) -> Option<ReloadReport> {
+        let fingerprint = Fingerprint::of(&self.paths);
+        if fingerprint == self.fingerprint {
+            return None;
+        }
+        let errors = match &fingerprint.path {
+            Some(path) => match read_profiles(path) {
+                Ok(profiles) => {
+                    self.profiles = profiles;
+                    Vec::new()
+                }
+                Err(errors) => errors,
+            },
+            None => {
+                self.profiles = DifficultyProfiles::embedded();
+                Vec::new()
+            }
+        };
+        let source = fingerprint.source();
+        self.fingerprint = fingerprint;
+        Some(ReloadReport { source, errors })
+    }
+}
+
+fn read_profiles(path: &Path) -> Result<DifficultyProfiles, Vec<String>> {
+    let label = path.display();
+    let content = fs::read_to_string(path).map_err(|err| vec![format!("{label}: {err}")])?;
+    parse_profiles(&content).map_err(|errors| {
+        errors
+            .into_iter()
+            .map(|error| format!("{label}: {error}"))
+            .collect()
+    })
+}
+
+fn parse_profiles(content: &str) -> Result<DifficultyProfiles, Vec<String>> {
+    let profiles: DifficultyProfiles = toml::from_str(content).map_err(|err| {
+        let message = err.message().trim().to_string();
+        match err.span() {
+            Some(span) => {
+                let line = content[..span.start].lines().count().max(1);
+                vec![format!("line {line}: {message}")]
+            }
+            None => vec![message],
+        }
+    })?;
+    let errors = profiles.validate();
+    if errors.is_empty() {
+        Ok(profiles)
+    } else {
+        Err(errors)
+    }
+}
+
+static CLI_CONFIG_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+fn active() -> MutexGuard<'static, ActiveConfig> {
+    static ACTIVE: OnceLock<Mutex<ActiveConfig>> = OnceLock::new();
+    ACTIVE
+        .get_or_init(|| {
+            let cli_path = CLI_CONFIG_PATH.get();
+            let mut config = ActiveConfig::load(search_paths(cli_path.map(PathBuf::as_path)));
+            if let Some(path) = cli_path
+                && !path.is_file()
+            {
+                config
+                    .diagnostics
+                    .insert(0, format!("{}: config file not found", path.display()));
+            }
+            Mutex::new(config)
+        })
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Points the search at a file given on the command line. Only takes effect
+/// before the first profile is read.
+pub fn set_cli_path(path: PathBuf) {
+    let _ = CLI_CONFIG_PATH.set(path);
+}
+
+/// Problems found while loading the difficulty file at startup.
+pub fn diagnostics() -> Vec<String> {
+    active().diagnostics.clone()
+}
+
+pub fn reload_if_changed() -> Option<ReloadReport> {
+    active().reload_if_changed()
 }
 
 pub fn profile_for(difficulty: Difficulty) -> DifficultyProfile {
-    let profiles = load_profiles(Path::new(DEFAULT_DIFFICULTY_CONFIG_PATH));
-    profiles.profile(difficulty)
+    active().profiles.profile(difficulty)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, SystemTime};
+
+    use super::{
+        ActiveConfig, DEFAULT_DIFFICULTY_CONFIG_PATH, DifficultyProfiles,
+        EMBEDDED_DIFFICULTY_CONFIG, parse_profiles, search_paths,
+    };
+    use crate::game::model::{Difficulty, ProfileField};
+
+    fn write_config(path: &Path, content: &str, age_secs: u64) {
+        fs::write(path, content).expect("write config");
+        let modified = SystemTime::now() - Duration::from_secs(age_secs);
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .and_then(|file| file.set_modified(modified))
+            .expect("set mtime");
+    }
+
+    #[test]
+    fn bundled_difficulty_file_parses_with_event_weights() {
+        let content = std::fs::read_to_string(Path::new(DEFAULT_DIFFICULTY_CONFIG_PATH))
+            .expect("bundled difficulty config exists");
+        let profiles: DifficultyProfiles = toml::from_str(&content).expect("config parses");
+        assert_eq!(profiles.hard.event_weights.get("spike_trap"), Some(&20));
+        assert!(profiles.normal.event_weights.is_empty());
+        assert_eq!(
+            profiles.easy.event_weights,
+            DifficultyProfiles::embedded().easy.event_weights
+        );
+    }
+
+    #[test]
+    fn sliders_stay_in_bounds_and_presets_validate() {
+        let defaults = DifficultyProfiles::embedded();
+        for profile in [&defaults.easy, &defaults.normal, &defaults.hard] {
+            assert_eq!(profile.validate(), Ok(()));
+        }
+
+        let mut custom = defaults.normal.clone();
+        custom.nudge(ProfileField::EnemyHp, 3);
+        assert_eq!(custom.enemy_hp_scale, 1.15);
+        assert_eq!(custom.value_text(ProfileField::EnemyHp), "x1.15");
+        custom.nudge(ProfileField::EncounterRate, -100);
+        assert_eq!(custom.random_encounter_rate_percent, 0);
+        assert_eq!(custom.fraction(ProfileField::EncounterRate), 0.0);
+        custom.nudge(ProfileField::RunBonus, 2);
+        assert_eq!(custom.value_text(ProfileField::RunBonus), "+10%");
+        assert_eq!(custom.validate(), Ok(()));
+
+        custom.enemy_atk_scale = 9.0;
+        assert_eq!(custom.validate(), Ok(()));
+        let error = custom.validate_custom().unwrap_err();
+        assert!(error.starts_with("enemy_atk_scale"), "{error}");
+    }
+
+    #[test]
+    fn broken_files_are_reported_with_lines_and_ranges() {
+        let typo =
+            EMBEDDED_DIFFICULTY_CONFIG.replace("enemy_def_scale = 1.15", "enemy_def_scal = 1.15");
+        let errors = parse_profiles(&typo).map(|_| ()).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(
+            errors[0].starts_with("line ") && errors[0].contains("enemy_def_scal"),
+            "{errors:?}"
+        );
+
+        let negative = EMBEDDED_DIFFICULTY_CONFIG
+            .replace("enemy_hp_scale = 0.86", "enemy_hp_scale = -0.86")
+            .replace("spike_trap = 20", "spike_trap = -20");
+        let errors = parse_profiles(&negative).map(|_| ()).unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                "[easy] enemy_hp_scale = -0.86 is outside 0.1..=10".to_string(),
+                "[hard] event_weights.spike_trap = -20 is negative".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn files_may_leave_the_sliders_but_not_name_unknown_events() {
+        let steep =
+            EMBEDDED_DIFFICULTY_CONFIG.replace("enemy_hp_scale = 0.86", "enemy_hp_scale = 3.5");
+        let profiles = parse_profiles(&steep).expect("steep profiles are playable");
+        let custom = profiles.easy.within_sliders();
+        assert_eq!(custom.enemy_hp_scale, 2.0);
+        assert_eq!(custom.validate_custom(), Ok(()));
+
+        let typo = EMBEDDED_DIFFICULTY_CONFIG.replace("spike_trap = 20", "spike_trp = 20");
+        let errors = parse_profiles(&typo).map(|_| ()).unwrap_err();
+        assert_eq!(
+            errors,
+            vec!["[hard] event_weights.spike_trp: no event has this id".to_string()]
+        );
+    }
+
+    #[test]
+    fn the_command_line_file_is_searched_first() {
+        let paths = search_paths(Some(Path::new("custom/difficulty.toml")));
+        assert_eq!(paths[0], PathBuf::from("custom/difficulty.toml"));
+        assert!(paths.contains(&PathBuf::from(DEFAULT_DIFFICULTY_CONFIG_PATH)));
+        assert!(!search_paths(None).contains(&PathBuf::from("custom/difficulty.toml")));
+    }
+
+    #[test]
+    fn edits_on_disk_are_hot_reloaded_and_broken_edits_are_kept_out() {
+        let path =
+            std::env::temp_dir().join(format!("rpg_tui_difficulty_{}.toml", rand::random::<u64>()));
+        write_config(&path, EMBEDDED_DIFFICULTY_CONFIG, 30);
+        let mut config = ActiveConfig::load(vec![path.clone()]);
+        assert!(config.diagnostics.is_empty());
+        assert_eq!(config.reload_if_changed(), None);
+
+        let harder = EMBEDDED_DIFFICULTY_CONFIG.replace(
+            "random_encounter_rate_percent = 16",
+            "random_encounter_rate_percent = 30",
+        );
+        write_config(&path, &harder, 20);
+        let report = config.reload_if_changed().expect("edit is picked up");
+        assert!(report.errors.is_empty());
+        assert_eq!(
+            config
+                .profiles
+                .profile(Difficulty::Normal)
+                .random_encounter_rate_percent,
+            30
+        );
+
+        write_config(&path, "[easy\n", 10);
+        let report = config.reload_if_changed().expect("edit is picked up");
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(
+            config
+                .profiles
+                .profile(Difficulty::Normal)
+                .random_encounter_rate_percent,
+            30
+        );
+
+        fs::remove_file(&path).expect("remove config");
+        let report = config.reload_if_changed().expect("removal is picked up");
+        assert_eq!(report.source, "built-in defaults");
+        assert_eq!(
+            config
+                .profiles
+                .profile(Difficulty::Normal)
+                .random_encounter_rate_percent,
+            16
+        );
+    }
 }
@@ -0,0 +1,229 @@
+use std::sync::OnceLock;
+
+use rand::Rng;
+use rust_i18n::t;
+use serde::Deserialize;
+
+use crate::game::balance::MATERIAL_DROP_PERCENT;
+use crate::game::model::{Enemy, Material, Player, Recipe, RecipeOutput, WorldObjects};
+
+const RECIPE_DATA: &str = include_str!("../../config/recipes.toml");
+
+#[derive(Deserialize)]
+struct RecipeBook {
+    recipe: Vec<Recipe>,
+}
+
+/// Workshop recipes in menu order.
+pub fn recipes() -> &'static [Recipe] {
+    static BOOK: OnceLock<RecipeBook> = OnceLock::new();
+    &BOOK
+        .get_or_init(|| toml::from_str(RECIPE_DATA).expect("embedded recipe data is valid"))
+        .recipe
+}
+
+pub fn can_craft(player: &Player, recipe: &Recipe) -> bool {
+    player.bag.materials.covers(&recipe.cost) && player.gold >= recipe.gold
+}
+
+/// Crafts the recipe at `index`, spending materials and the workshop fee only
+/// when the result can actually be made. Gear upgrades follow the smith's
+/// New Game+ limits.
+pub fn craft(player: &mut Player, index: usize, ng_tier: u32) -> String {
+    let Some(recipe) = recipes().get(index) else {
+        return t!("log.workshop.unknown").to_string();
+    };
+    let name = t!(recipe.name.as_str());
+    if !player.bag.materials.covers(&recipe.cost) {
+        return t!("log.workshop.missing_materials", name = name).to_string();
+    }
+    if player.gold < recipe.gold {
+        return t!("log.workshop.need_gold", name = name, cost = recipe.gold).to_string();
+    }
+
+    let message = match recipe.output {
+        RecipeOutput::Potion(count) => {
+            player.bag.potion += count;
+            t!("log.workshop.crafted", name = name, count = count).to_string()
+        }
+        RecipeOutput::Ether(count) => {
+            player.bag.ether += count;
+            t!("log.workshop.crafted", name = name, count = count).to_string()
+        }
+        RecipeOutput::WeaponUpgrade => {
+            let Some(next) = player.equipment.weapon.next() else {
+                return t!("log.town.weapon_max").to_string();
+            };
+            if next.ng_tier() > ng_tier {
+                return ng_locked(next.i18n_key(), next.ng_tier());
+            }
+            player.equipment.weapon = next;
+            t!("log.town.weapon_upgraded", weapon = t!(next.i18n_key())).to_string()
+        }
+        RecipeOutput::ArmorUpgrade => {
+            let Some(next) = player.equipment.armor.next() else {
+                return t!("log.town.armor_max").to_string();
+            };
+            if next.ng_tier() > ng_tier {
+                return ng_locked(next.i18n_key(), next.ng_tier());
+            }
+            player.equipment.armor = next;
+            t!("log.town.armor_upgraded", armor = t!(next.i18n_key())).to_string()
+        }
+    };
+    player.bag.materials.spend(&recipe.cost);
+    player.gold -= recipe.gold;
+    message
+}
+
+fn ng_locked(item_key: &str, tier: u32) -> String {
+    t!("log.town.ng_locked", item = t!(item_key), tier = tier).to_string()
+}
+
+/// Rolls the defeated enemy's material drop.
+pub fn roll_loot(enemy: &Enemy, rng: &mut impl Rng) -> Option<(Material, i32)> {
+    let material = enemy.loot?;
+    if rng.random_range(0..100) >= MATERIAL_DROP_PERCENT {
+        return None;
+    }
+    Some((material, rng.random_range(1..=2)))
+}
+
+/// Harvests the resource node at the given tile into the hero's bag.
+pub fn gather(world: &mut WorldObjects, player: &mut Player, x: usize, y: usize) -> Option<String> {
+    let node = world.resource_node_at_mut(x, y)?;
+    node.gathered = true;
+    player.bag.materials.add(node.material, node.amount);
+    Some(
+        t!(
+            "log.world.gathered",
+            material = t!(node.material.name_key()),
+            count = node.amount
+        )
+        .to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use rust_i18n::t;
+
+    use super::{can_craft, craft, gather, recipes, roll_loot};
+    use crate::game::model::{
+        AiKind, Enemy, EnemyStyle, Material, Player, Position, RecipeOutput, ResourceNode,
+        WeaponTier, WorldObjects,
+    };
+
+    fn recipe_index(output: RecipeOutput) -> usize {
+        recipes()
+            .iter()
+            .position(|recipe| recipe.output == output)
+            .unwrap()
+    }
+
+    #[test]
+    fn embedded_recipes_have_costs_and_locale_keys() {
+        rust_i18n::set_locale("en");
+        assert!(!recipes().is_empty());
+        for recipe in recipes() {
+            assert_ne!(t!(recipe.name.as_str()), recipe.name, "{}", recipe.id);
+            assert!(
+                Material::ALL
+                    .into_iter()
+                    .any(|material| recipe.cost.count(material) > 0),
+                "{} costs nothing",
+                recipe.id
+            );
+        }
+    }
+
+    #[test]
+    fn crafting_spends_materials_and_fills_the_bag() {
+        rust_i18n::set_locale("en");
+        let mut player = Player::new();
+        let index = recipe_index(RecipeOutput::Potion(1));
+        let cost = recipes()[index].cost;
+        assert!(!can_craft(&player, &recipes()[index]));
+
+        let potions = player.bag.potion;
+        craft(&mut player, index, 0);
+        assert_eq!(player.bag.potion, potions);
+
+        player.bag.materials = cost;
+        assert!(can_craft(&player, &recipes()[index]));
+        craft(&mut player, index, 0);
+        assert_eq!(player.bag.potion, potions + 1);
+        assert_eq!(player.bag.materials, Default::default());
+    }
+
+    #[test]
+    fn gear_recipes_raise_one_tier_and_keep_materials_at_max() {
+        rust_i18n::set_locale("en");
+        let mut player = Player::new();
+        player.gold = 100;
+        let weapon = recipe_index(RecipeOutput::WeaponUpgrade);
+        player.bag.materials = recipes()[weapon].cost;
+        craft(&mut player, weapon, 0);
+        assert_eq!(player.equipment.weapon, WeaponTier::BronzeSword);
+        assert_eq!(player.gold, 100 - recipes()[weapon].gold);
+
+        let armor = recipe_index(RecipeOutput::ArmorUpgrade);
+        while let Some(next) = player.equipment.armor.next() {
+            player.equipment.armor = next;
+        }
+        player.bag.materials = recipes()[armor].cost;
+        craft(&mut player, armor, 0);
+        assert_eq!(player.bag.materials, recipes()[armor].cost);
+    }
+
+    #[test]
+    fn loot_only_drops_from_enemies_that_carry_it() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut enemy = Enemy {
+            name: "Wolf".to_string(),
+            name_key: "enemy.wolf".to_string(),
+            hp: 0,
+            max_hp: 10,
+            atk: 1,
+            def: 0,
+            exp_reward: 1,
+            gold_reward: 1,
+            is_boss: false,
+            style: EnemyStyle::Predator,
+            ai: AiKind::Aggressive,
+            loot: None,
+            art: String::new(),
+        };
+        assert!((0..50).all(|_| roll_loot(&enemy, &mut rng).is_none()));
+
+        enemy.loot = Some(Material::Hide);
+        let drops: Vec<_> = (0..50)
+            .filter_map(|_| roll_loot(&enemy, &mut rng))
+            .collect();
+        assert!(!drops.is_empty());
+        assert!(
+            drops
+                .iter()
+                .all(|(material, count)| *material == Material::Hide && (1..=2).contains(count))
+        );
+    }
+
+    #[test]
+    fn resource_nodes_are_gathered_once() {
+        rust_i18n::set_locale("en");
+        let mut player = Player::new();
+        let mut world = WorldObjects::new(Vec::new(), Vec::new());
+        world.resource_nodes.push(ResourceNode {
+            position: Position { x: 4, y: 5 },
+            material: Material::Ore,
+            amount: 2,
+            gathered: false,
+        });
+
+        assert!(gather(&mut world, &mut player, 4, 5).is_some());
+        assert!(gather(&mut world, &mut player, 4, 5).is_none());
+        assert_eq!(player.bag.materials.ore, 2);
+    }
+}
@@ -0,0 +1,249 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+
+use crate::game::model::{CompanionKind, DialogueState, Player, QuestState};
+
+const DIALOGUE_DATA: &str = include_str!("../../config/dialogue.toml");
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct DialogueScript {
+    pub speaker: String,
+    pub start: String,
+    pub nodes: HashMap<String, DialogueNode>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct DialogueNode {
+    pub text: String,
+    #[serde(default)]
+    pub choices: Vec<DialogueChoice>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct DialogueChoice {
+    pub text: String,
+    #[serde(default)]
+    pub next: Option<String>,
+    #[serde(default)]
+    pub when: DialogueCondition,
+    #[serde(default)]
+    pub effects: Vec<DialogueEffect>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DialogueCondition {
+    pub min_level: Option<i32>,
+    pub max_level: Option<i32>,
+    pub min_gold: Option<i32>,
+    pub quest: Option<QuestStage>,
+    pub flag: Option<String>,
+    pub not_flag: Option<String>,
+    pub first_meeting: Option<bool>,
+    pub party_room: Option<bool>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuestStage {
+    NotAccepted,
+    InProgress,
+    Completed,
+    Rewarded,
+}
+
+impl QuestStage {
+    pub fn of(quest: &QuestState) -> Self {
+        if !quest.accepted {
+            Self::NotAccepted
+        } else if quest.rewarded {
+            Self::Rewarded
+        } else if quest.completed {
+            Self::Completed
+        } else {
+            Self::InProgress
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DialogueEffect {
+    GiveGold(i32),
+    TakeGold(i32),
+    GiveItem { item: ItemKind, count: i32 },
+    StartQuest,
+    OpenShop,
+    RevealArea { target: RevealTarget, radius: usize },
+    SetFlag(String),
+    NpcGift,
+    Recruit(CompanionKind),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemKind {
+    Potion,
+    Ether,
+}
+
+impl ItemKind {
+    pub fn i18n_key(self) -> &'static str {
+        match self {
+            Self::Potion => "item.consumable.potion",
+            Self::Ether => "item.consumable.ether",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RevealTarget {
+    Lair,
+    NearestChest,
+}
+
+pub struct DialogueContext<'a> {
+    pub player: &'a Player,
+    pub quest: &'a QuestState,
+    pub flags: &'a HashSet<String>,
+    pub first_meeting: bool,
+    pub party_room: bool,
+}
+
+impl DialogueCondition {
+    pub fn is_met(&self, ctx: &DialogueContext) -> bool {
+        self.min_level.is_none_or(|level| ctx.player.level >= level)
+            && self.max_level.is_none_or(|level| ctx.player.level <= level)
+            && self.min_gold.is_none_or(|gold| ctx.player.gold >= gold)
+            && self
+                .quest
+                .is_none_or(|stage| QuestStage::of(ctx.quest) == stage)
+            && self
+                .flag
+                .as_ref()
+                .is_none_or(|flag| ctx.flags.contains(flag))
+            && self
+                .not_flag
+                .as_ref()
+                .is_none_or(|flag| !ctx.flags.contains(flag))
+            && self
+                .first_meeting
+                .is_none_or(|first| first == ctx.first_meeting)
+            && self.party_room.is_none_or(|room| room == ctx.party_room)
+    }
+}
+
+fn book() -> &'static HashMap<String, DialogueScript> {
+    static BOOK: OnceLock<HashMap<String, DialogueScript>> = OnceLock::new();
+    BOOK.get_or_init(|| toml::from_str(DIALOGUE_DATA).expect("embedded dialogue data is valid"))
+}
+
+pub fn script(id: &str) -> Option<&'static DialogueScript> {
+    book().get(id)
+}
+
+pub fn start(id: &str) -> Option<DialogueState> {
+    let script = script(id)?;
+    Some(DialogueState {
+        script: id.to_string(),
+        node: script.start.clone(),
+        npc: None,
+    })
+}
+
+pub fn current_node(state: &DialogueState) -> Option<&'static DialogueNode> {
+    script(&state.script)?.nodes.get(&state.node)
+}
+
+pub fn visible_choices<'a>(
+    node: &'a DialogueNode,
+    ctx: &DialogueContext,
+) -> Vec<&'a DialogueChoice> {
+    node.choices
+        .iter()
+        .filter(|choice| choice.when.is_met(ctx))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use rust_i18n::t;
+
+    use super::{DialogueContext, DialogueEffect, QuestStage, book, start, visible_choices};
+    use crate::game::model::{Player, QuestState};
+
+    #[test]
+    fn embedded_scripts_reference_existing_nodes_and_locale_keys() {
+        rust_i18n::set_locale("en");
+        for (id, script) in book() {
+            assert!(script.nodes.contains_key(&script.start), "{id} start");
+            assert_ne!(t!(script.speaker.as_str()), script.speaker);
+            for (name, node) in &script.nodes {
+                assert_ne!(t!(node.text.as_str()), node.text, "{id}.{name}");
+                for choice in &node.choices {
+                    assert_ne!(t!(choice.text.as_str()), choice.text);
+                    if let Some(next) = &choice.next {
+                        assert!(script.nodes.contains_key(next), "{id}.{name} -> {next}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn choices_are_filtered_by_conditions() {
+        let mut player = Player::new();
+        let quest = QuestState::new();
+        let flags = HashSet::new();
+        let state = start("scout").expect("scout dialogue exists");
+        let node = super::current_node(&state).expect("start node exists");
+
+        player.gold = 0;
+        let ctx = DialogueContext {
+            player: &player,
+            quest: &quest,
+            flags: &flags,
+            first_meeting: false,
+            party_room: true,
+        };
+        let poor = visible_choices(node, &ctx);
+        assert!(poor.iter().all(|choice| choice.when.min_gold.is_none()));
+        assert!(
+            poor.iter()
+                .any(|choice| choice.effects.contains(&DialogueEffect::StartQuest))
+        );
+        assert!(
+            !poor
+                .iter()
+                .any(|choice| choice.effects.contains(&DialogueEffect::NpcGift))
+        );
+
+        player.gold = 50;
+        let ctx = DialogueContext {
+            player: &player,
+            quest: &quest,
+            flags: &flags,
+            first_meeting: true,
+            party_room: true,
+        };
+        let rich = visible_choices(node, &ctx);
+        assert!(rich.len() > poor.len());
+    }
+
+    #[test]
+    fn quest_stage_follows_quest_state() {
+        let mut quest = QuestState::new();
+        assert_eq!(QuestStage::of(&quest), QuestStage::NotAccepted);
+        quest.accepted = true;
+        assert_eq!(QuestStage::of(&quest), QuestStage::InProgress);
+        quest.completed = true;
+        assert_eq!(QuestStage::of(&quest), QuestStage::Completed);
+        quest.rewarded = true;
+        assert_eq!(QuestStage::of(&quest), QuestStage::Rewarded);
+    }
+}
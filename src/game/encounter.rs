@@ -2,59 +2,94 @@ use rand::Rng;
 use rust_i18n::t;
 
 use crate::game::balance::{
-    BOSS_ATK_PER_LEVEL, BOSS_BASE_ATK, BOSS_BASE_DEF, BOSS_BASE_EXP, BOSS_BASE_GOLD, BOSS_BASE_HP,
-    BOSS_DEF_PER_LEVEL, BOSS_EXP_PER_LEVEL, BOSS_GOLD_PER_LEVEL, BOSS_HP_PER_LEVEL, BOSS_NAME_KEY,
-    NORMAL_ENEMIES, NORMAL_ENEMY_ATK_PER_LEVEL, NORMAL_ENEMY_DEF_PER_LEVEL,
+    BOSS_ATK_PER_LEVEL, BOSS_DEF_PER_LEVEL, BOSS_EXP_PER_LEVEL, BOSS_GOLD_PER_LEVEL,
+    BOSS_HP_PER_LEVEL, NORMAL_ENEMIES, NORMAL_ENEMY_ATK_PER_LEVEL, NORMAL_ENEMY_DEF_PER_LEVEL,
     NORMAL_ENEMY_EXP_PER_LEVEL, NORMAL_ENEMY_GOLD_PER_LEVEL, NORMAL_ENEMY_HP_PER_LEVEL,
+    boss_template,
 };
 use crate::game::config::DifficultyProfile;
-use crate::game::model::{Enemy, EnemyStyle};
+use crate::game::model::{AiKind, BossId, Enemy};
 
 pub fn generate_enemy(
     rng: &mut impl Rng,
     player_level: i32,
     boss: bool,
+    night: bool,
     difficulty: &DifficultyProfile,
 ) -> Enemy {
     if boss {
-        let hp = difficulty.scale_stat(
-            BOSS_BASE_HP + player_level * BOSS_HP_PER_LEVEL,
-            difficulty.enemy_hp_scale,
-        );
-        return Enemy {
-            name: t!(BOSS_NAME_KEY).to_string(),
-            hp,
-            max_hp: hp,
-            atk: difficulty.scale_stat(
-                BOSS_BASE_ATK + player_level * BOSS_ATK_PER_LEVEL,
-                difficulty.enemy_atk_scale,
-            ),
-            def: difficulty.scale_stat(
-                BOSS_BASE_DEF + player_level * BOSS_DEF_PER_LEVEL,
-                difficulty.enemy_def_scale,
-            ),
-            exp_reward: difficulty.scale_stat(
-                BOSS_BASE_EXP + player_level * BOSS_EXP_PER_LEVEL,
-                difficulty.enemy_reward_scale,
-            ),
-            gold_reward: difficulty.scale_stat(
-                BOSS_BASE_GOLD + player_level * BOSS_GOLD_PER_LEVEL,
-                difficulty.enemy_reward_scale,
-            ),
-            is_boss: true,
-            style: EnemyStyle::Boss,
-        };
+        return generate_boss(BossId::AncientDragon, player_level, difficulty);
     }
 
+    let pool = spawn_pool(night);
+    let idx = pool[rng.random_range(0..pool.len())];
+    generate_from_template(idx, player_level, difficulty)
+}
+
+pub fn generate_boss(boss: BossId, player_level: i32, difficulty: &DifficultyProfile) -> Enemy {
+    let template = boss_template(boss);
+    let hp = difficulty.scale_stat(
+        template.base_hp + player_level * BOSS_HP_PER_LEVEL,
+        difficulty.enemy_hp_scale,
+    );
+    Enemy {
+        name: t!(boss.name_key()).to_string(),
+        name_key: boss.name_key().to_string(),
+        hp,
+        max_hp: hp,
+        atk: difficulty.scale_stat(
+            template.base_atk + player_level * BOSS_ATK_PER_LEVEL,
+            difficulty.enemy_atk_scale,
+        ),
+        def: difficulty.scale_stat(
+            template.base_def + player_level * BOSS_DEF_PER_LEVEL,
+            difficulty.enemy_def_scale,
+        ),
+        exp_reward: difficulty.scale_stat(
+            template.base_exp + player_level * BOSS_EXP_PER_LEVEL,
+            difficulty.enemy_reward_scale,
+        ),
+        gold_reward: difficulty.scale_stat(
+            template.base_gold + player_level * BOSS_GOLD_PER_LEVEL,
+            difficulty.enemy_reward_scale,
+        ),
+        is_boss: true,
+        style: template.style,
+        ai: AiKind::Aggressive,
+        loot: None,
+        art: art_key(boss.name_key()),
+    }
+}
+
+/// The art key of an enemy, named after it: `enemy.slime` draws `slime`.
+fn art_key(name_key: &str) -> String {
+    name_key.trim_start_matches("enemy.").to_string()
+}
+
+/// Template indices that can appear at the given time; nocturnal enemies join only at night.
+pub fn spawn_pool(night: bool) -> Vec<usize> {
+    NORMAL_ENEMIES
+        .iter()
+        .enumerate()
+        .filter(|(_, template)| night || !template.nocturnal)
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+pub fn generate_from_template(
+    template_index: usize,
+    player_level: i32,
+    difficulty: &DifficultyProfile,
+) -> Enemy {
     let scale = player_level - 1;
-    let idx = rng.random_range(0..NORMAL_ENEMIES.len());
-    let template = &NORMAL_ENEMIES[idx];
+    let template = &NORMAL_ENEMIES[template_index % NORMAL_ENEMIES.len()];
     let hp = difficulty.scale_stat(
         template.base_hp + scale * NORMAL_ENEMY_HP_PER_LEVEL,
         difficulty.enemy_hp_scale,
     );
     Enemy {
         name: t!(template.name_key).to_string(),
+        name_key: template.name_key.to_string(),
         hp,
         max_hp: hp,
         atk: difficulty.scale_stat(
@@ -75,24 +110,29 @@ pub fn generate_enemy(
         ),
         is_boss: false,
         style: template.style,
+        ai: template.ai,
+        loot: template.loot,
+        art: art_key(template.name_key),
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashSet;
+
     use rand::SeedableRng;
     use rand::rngs::StdRng;
 
-    use super::generate_enemy;
+    use super::{generate_boss, generate_enemy, spawn_pool};
     use crate::game::config::profile_for;
-    use crate::game::model::Difficulty;
+    use crate::game::model::{BossId, Difficulty, EnemyStyle};
 
     #[test]
     fn boss_generation_uses_boss_identity() {
         rust_i18n::set_locale("en");
         let mut rng = StdRng::seed_from_u64(1);
         let profile = profile_for(Difficulty::Normal);
-        let enemy = generate_enemy(&mut rng, 3, true, &profile);
+        let enemy = generate_enemy(&mut rng, 3, true, false, &profile);
         assert!(enemy.is_boss);
         assert!(!enemy.name.is_empty());
         assert!(enemy.hp > 0);
@@ -104,8 +144,8 @@ mod tests {
         let mut rng1 = StdRng::seed_from_u64(9);
         let mut rng2 = StdRng::seed_from_u64(9);
         let profile = profile_for(Difficulty::Normal);
-        let e1 = generate_enemy(&mut rng1, 1, false, &profile);
-        let e2 = generate_enemy(&mut rng2, 3, false, &profile);
+        let e1 = generate_enemy(&mut rng1, 1, false, false, &profile);
+        let e2 = generate_enemy(&mut rng2, 3, false, false, &profile);
 
         assert!(!e1.is_boss);
         assert!(!e2.is_boss);
@@ -114,4 +154,33 @@ mod tests {
         assert!(e2.atk > e1.atk);
         assert!(e2.exp_reward > e1.exp_reward);
     }
+
+    #[test]
+    fn undead_only_spawn_at_night() {
+        rust_i18n::set_locale("en");
+        let profile = profile_for(Difficulty::Normal);
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..50 {
+            let enemy = generate_enemy(&mut rng, 1, false, false, &profile);
+            assert_ne!(enemy.style, EnemyStyle::Undead);
+        }
+        assert!(spawn_pool(true).len() > spawn_pool(false).len());
+        assert!((0..50).any(|_| {
+            generate_enemy(&mut rng, 1, false, true, &profile).style == EnemyStyle::Undead
+        }));
+    }
+
+    #[test]
+    fn each_boss_has_its_own_style() {
+        rust_i18n::set_locale("en");
+        let profile = profile_for(Difficulty::Normal);
+        let styles: HashSet<EnemyStyle> = BossId::SEAL_GUARDIANS
+            .iter()
+            .chain([BossId::AncientDragon].iter())
+            .map(|boss| generate_boss(*boss, 2, &profile))
+            .inspect(|enemy| assert!(enemy.is_boss))
+            .map(|enemy| enemy.style)
+            .collect();
+        assert_eq!(styles.len(), 4);
+    }
 }
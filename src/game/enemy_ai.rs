@@ -0,0 +1,307 @@
+use rand::{Rng, RngCore};
+
+use crate::game::model::{AiKind, Enemy, HeroSkill, Player};
+
+/// What an enemy decides to do on its turn. `battle` carries the intent out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EnemyIntent {
+    Attack,
+    /// The style skill: heavy smash, mana burn, pounce or drain.
+    Skill,
+    Heal,
+    Guard,
+    Flee,
+}
+
+/// Everything an enemy may look at when choosing its move.
+pub struct AiView<'a> {
+    pub enemy: &'a Enemy,
+    pub player: &'a Player,
+    /// Difficulty skill rate, already clamped to 0..=100.
+    pub skill_rate: i32,
+    pub guarding: bool,
+}
+
+pub trait EnemyAi {
+    fn choose(&self, view: &AiView, rng: &mut dyn RngCore) -> EnemyIntent;
+
+    /// The move this enemy visibly leans towards right now, shown to the
+    /// player as a warning. Most enemies give nothing away.
+    fn tell(&self, _view: &AiView) -> Option<EnemyIntent> {
+        None
+    }
+}
+
+/// Rolls the skill rate every turn and otherwise attacks.
+pub struct Aggressive;
+
+/// Patches itself up when badly hurt.
+pub struct CautiousHealer;
+
+/// Tries to run once its HP gets low.
+pub struct Coward;
+
+/// Goes after players whose MP is nearly spent.
+pub struct ManaBurner;
+
+/// Raises its guard between attacks.
+pub struct Guardian;
+
+impl EnemyAi for Aggressive {
+    fn choose(&self, view: &AiView, rng: &mut dyn RngCore) -> EnemyIntent {
+        skill_or_attack(view.skill_rate, rng)
+    }
+}
+
+impl EnemyAi for CautiousHealer {
+    fn choose(&self, view: &AiView, rng: &mut dyn RngCore) -> EnemyIntent {
+        if hp_percent(view.enemy) <= 40 && rng.random_range(0..100) < 60 {
+            return EnemyIntent::Heal;
+        }
+        skill_or_attack(view.skill_rate, rng)
+    }
+
+    fn tell(&self, view: &AiView) -> Option<EnemyIntent> {
+        (hp_percent(view.enemy) <= 40).then_some(EnemyIntent::Heal)
+    }
+}
+
+impl EnemyAi for Coward {
+    fn choose(&self, view: &AiView, rng: &mut dyn RngCore) -> EnemyIntent {
+        if hp_percent(view.enemy) <= 25 && rng.random_range(0..100) < 50 {
+            return EnemyIntent::Flee;
+        }
+        skill_or_attack(view.skill_rate, rng)
+    }
+
+    fn tell(&self, view: &AiView) -> Option<EnemyIntent> {
+        (hp_percent(view.enemy) <= 25).then_some(EnemyIntent::Flee)
+    }
+}
+
+impl EnemyAi for ManaBurner {
+    fn choose(&self, view: &AiView, rng: &mut dyn RngCore) -> EnemyIntent {
+        let rate = if mp_nearly_spent(view.player) {
+            (view.skill_rate + 40).min(100)
+        } else {
+            view.skill_rate
+        };
+        skill_or_attack(rate, rng)
+    }
+
+    fn tell(&self, view: &AiView) -> Option<EnemyIntent> {
+        mp_nearly_spent(view.player).then_some(EnemyIntent::Skill)
+    }
+}
+
+impl EnemyAi for Guardian {
+    fn choose(&self, view: &AiView, rng: &mut dyn RngCore) -> EnemyIntent {
+        if !view.guarding && rng.random_range(0..100) < 25 {
+            return EnemyIntent::Guard;
+        }
+        skill_or_attack(view.skill_rate, rng)
+    }
+}
+
+pub fn behavior(kind: AiKind) -> &'static dyn EnemyAi {
+    match kind {
+        AiKind::Aggressive => &Aggressive,
+        AiKind::CautiousHealer => &CautiousHealer,
+        AiKind::Coward => &Coward,
+        AiKind::ManaBurner => &ManaBurner,
+        AiKind::Guardian => &Guardian,
+    }
+}
+
+fn skill_or_attack(rate: i32, rng: &mut dyn RngCore) -> EnemyIntent {
+    if rng.random_range(0..100) < rate {
+        EnemyIntent::Skill
+    } else {
+        EnemyIntent::Attack
+    }
+}
+
+/// Mana burners press harder once the player has MP for no more than two
+/// casts of their cheapest skill.
+fn mp_nearly_spent(player: &Player) -> bool {
+    let cheapest = player.skills().into_iter().map(HeroSkill::mp_cost).min();
+    cheapest.is_some_and(|cost| player.mp > 0 && player.mp <= cost * 2)
+}
+
+fn hp_percent(enemy: &Enemy) -> i32 {
+    enemy.hp * 100 / enemy.max_hp.max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use super::{AiView, EnemyAi, EnemyIntent, behavior};
+    use crate::game::model::{AiKind, Enemy, EnemyStyle, HeroClass, Player};
+
+    fn enemy(hp: i32) -> Enemy {
+        Enemy {
+            name: "Test Enemy".to_string(),
+            name_key: String::new(),
+            hp,
+            max_hp: 100,
+            atk: 8,
+            def: 2,
+            exp_reward: 1,
+            gold_reward: 1,
+            is_boss: false,
+            style: EnemyStyle::Skirmisher,
+            ai: AiKind::Aggressive,
+            loot: None,
+            art: String::new(),
+        }
+    }
+
+    fn intents(ai: &dyn EnemyAi, view: &AiView, seed: u64) -> Vec<EnemyIntent> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..200).map(|_| ai.choose(view, &mut rng)).collect()
+    }
+
+    #[test]
+    fn choices_are_deterministic_for_a_seed() {
+        let enemy = enemy(30);
+        let player = Player::new();
+        for kind in [
+            AiKind::Aggressive,
+            AiKind::CautiousHealer,
+            AiKind::Coward,
+            AiKind::ManaBurner,
+            AiKind::Guardian,
+        ] {
+            let view = AiView {
+                enemy: &enemy,
+                player: &player,
+                skill_rate: 30,
+                guarding: false,
+            };
+            assert_eq!(
+                intents(behavior(kind), &view, 21),
+                intents(behavior(kind), &view, 21)
+            );
+        }
+    }
+
+    #[test]
+    fn healer_only_heals_when_hurt() {
+        let player = Player::new();
+        let healthy = enemy(90);
+        let hurt = enemy(20);
+        let ai = behavior(AiKind::CautiousHealer);
+        let view = |enemy| AiView {
+            enemy,
+            player: &player,
+            skill_rate: 30,
+            guarding: false,
+        };
+
+        assert!(!intents(ai, &view(&healthy), 1).contains(&EnemyIntent::Heal));
+        assert!(intents(ai, &view(&hurt), 1).contains(&EnemyIntent::Heal));
+    }
+
+    #[test]
+    fn coward_flees_only_at_low_hp() {
+        let player = Player::new();
+        let healthy = enemy(80);
+        let hurt = enemy(10);
+        let ai = behavior(AiKind::Coward);
+        let view = |enemy| AiView {
+            enemy,
+            player: &player,
+            skill_rate: 30,
+            guarding: false,
+        };
+
+        assert!(!intents(ai, &view(&healthy), 2).contains(&EnemyIntent::Flee));
+        assert!(intents(ai, &view(&hurt), 2).contains(&EnemyIntent::Flee));
+    }
+
+    #[test]
+    fn mana_burner_presses_players_low_on_mp() {
+        let enemy = enemy(100);
+        let mut full = Player::new();
+        full.mp = 40;
+        full.max_mp = 40;
+        let mut low = Player::new();
+        low.mp = 5;
+        let ai = behavior(AiKind::ManaBurner);
+        let skills = |player| {
+            let view = AiView {
+                enemy: &enemy,
+                player,
+                skill_rate: 20,
+                guarding: false,
+            };
+            intents(ai, &view, 3)
+                .into_iter()
+                .filter(|intent| *intent == EnemyIntent::Skill)
+                .count()
+        };
+
+        assert!(skills(&low) > skills(&full));
+
+        // The threshold follows the hero's own skills: 5 MP is two Backstabs'
+        // worth for a rogue, but not yet low enough for a level 3 one with Steal.
+        let tell = |player: &Player| {
+            ai.tell(&AiView {
+                enemy: &enemy,
+                player,
+                skill_rate: 20,
+                guarding: false,
+            })
+        };
+        let mut rogue = Player::with_class(HeroClass::Rogue);
+        rogue.mp = 5;
+        assert_eq!(tell(&rogue), Some(EnemyIntent::Skill));
+        rogue.level = 3;
+        assert_eq!(tell(&rogue), None);
+    }
+
+    #[test]
+    fn guardian_never_guards_twice_in_a_row() {
+        let enemy = enemy(100);
+        let player = Player::new();
+        let ai = behavior(AiKind::Guardian);
+        let guarding = AiView {
+            enemy: &enemy,
+            player: &player,
+            skill_rate: 30,
+            guarding: true,
+        };
+        let open = AiView {
+            guarding: false,
+            ..guarding
+        };
+
+        assert!(!intents(ai, &guarding, 4).contains(&EnemyIntent::Guard));
+        assert!(intents(ai, &open, 4).contains(&EnemyIntent::Guard));
+    }
+
+    #[test]
+    fn tells_warn_only_when_the_behaviour_kicks_in() {
+        let player = Player::new();
+        let view = |enemy| AiView {
+            enemy,
+            player: &player,
+            skill_rate: 30,
+            guarding: false,
+        };
+        let (healthy, hurt) = (enemy(90), enemy(20));
+
+        assert_eq!(behavior(AiKind::CautiousHealer).tell(&view(&healthy)), None);
+        assert_eq!(
+            behavior(AiKind::CautiousHealer).tell(&view(&hurt)),
+            Some(EnemyIntent::Heal)
+        );
+        assert_eq!(
+            behavior(AiKind::Coward).tell(&view(&hurt)),
+            Some(EnemyIntent::Flee)
+        );
+        assert_eq!(behavior(AiKind::Aggressive).tell(&view(&hurt)), None);
+    }
+}
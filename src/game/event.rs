@@ -1,168 +1,399 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::OnceLock;
+
 use rand::Rng;
 use rust_i18n::t;
+use serde::Deserialize;
+
+use crate::game::balance::{EVENT_REGION_LAIR_RADIUS, EVENT_REGION_TOWN_RADIUS};
+use crate::game::config::DifficultyProfile;
+use crate::game::model::{EventMessage, EventState, Player, Position, Tile};
+
+const EVENT_DATA: &str = include_str!("../../config/events.toml");
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct EventDef {
+    pub weight: i32,
+    #[serde(default)]
+    pub when: EventCondition,
+    pub start: String,
+    pub steps: HashMap<String, EventStep>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EventCondition {
+    pub min_level: Option<i32>,
+    pub max_level: Option<i32>,
+    pub regions: Vec<Region>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct EventStep {
+    pub text: String,
+    #[serde(default)]
+    pub fatal_text: Option<String>,
+    #[serde(default)]
+    pub effects: Vec<EventEffect>,
+    #[serde(default)]
+    pub choices: Vec<EventChoice>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct EventChoice {
+    pub text: String,
+    #[serde(default)]
+    pub min_gold: Option<i32>,
+    #[serde(default)]
+    pub next: Option<String>,
+    #[serde(default)]
+    pub roll: Vec<EventBranch>,
+}
 
-use crate::game::balance::{
-    EVENT_CAMPFIRE_HEAL_BASE, EVENT_CAMPFIRE_HEAL_PER_LEVEL, EVENT_ETHER_MAX, EVENT_ETHER_MIN,
-    EVENT_GOLD_MAX, EVENT_GOLD_MIN, EVENT_GOLD_PER_LEVEL, EVENT_POTION_MAX, EVENT_POTION_MIN,
-    EVENT_TRAP_DAMAGE_MAX, EVENT_TRAP_DAMAGE_MIN, EVENT_WEIGHT_CAMPFIRE, EVENT_WEIGHT_ETHER_STASH,
-    EVENT_WEIGHT_GOLD_CACHE, EVENT_WEIGHT_POTION_STASH, EVENT_WEIGHT_SPIKE_TRAP,
-};
-use crate::game::model::Player;
-
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-enum WorldEventKind {
-    GoldCache,
-    PotionStash,
-    EtherStash,
-    Campfire,
-    SpikeTrap,
-}
-
-pub struct WorldEventResult {
-    pub message: String,
+#[derive(Clone, Debug, Deserialize)]
+pub struct EventBranch {
+    pub weight: i32,
+    pub next: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventEffect {
+    Gold {
+        min: i32,
+        max: i32,
+        #[serde(default)]
+        per_level: i32,
+    },
+    Potion {
+        min: i32,
+        max: i32,
+    },
+    Ether {
+        min: i32,
+        max: i32,
+    },
+    Heal {
+        base: i32,
+        #[serde(default)]
+        per_level: i32,
+    },
+    Damage {
+        min: i32,
+        max: i32,
+    },
+    PayGold(i32),
+    Battle,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Region {
+    NearTown,
+    Wilds,
+    NearLair,
+}
+
+/// What entering an event step did; `state.message` holds what it said.
+pub struct StepOutcome {
+    pub state: EventState,
     pub player_dead: bool,
+    pub start_battle: bool,
+    pub finished: bool,
+    /// Gold the step charged the hero.
+    pub gold_paid: i32,
+}
+
+#[derive(Default)]
+struct Tally {
+    gold: i32,
+    count: i32,
+    cost: i32,
+    dmg: i32,
+    heal: Option<(i32, i32)>,
+}
+
+impl EventCondition {
+    fn is_met(&self, player: &Player, region: Region) -> bool {
+        self.min_level.is_none_or(|level| player.level >= level)
+            && self.max_level.is_none_or(|level| player.level <= level)
+            && (self.regions.is_empty() || self.regions.contains(&region))
+    }
+}
+
+impl EventChoice {
+    fn is_available(&self, player: &Player) -> bool {
+        self.min_gold.is_none_or(|gold| player.gold >= gold)
+    }
+}
+
+fn book() -> &'static BTreeMap<String, EventDef> {
+    static BOOK: OnceLock<BTreeMap<String, EventDef>> = OnceLock::new();
+    BOOK.get_or_init(|| toml::from_str(EVENT_DATA).expect("embedded event data is valid"))
+}
+
+/// Whether `id` names an event of `config/events.toml`.
+pub fn is_known(id: &str) -> bool {
+    book().contains_key(id)
+}
+
+pub fn current_step(state: &EventState) -> Option<&'static EventStep> {
+    book().get(&state.event)?.steps.get(&state.step)
+}
+
+pub fn visible_choices<'a>(step: &'a EventStep, player: &Player) -> Vec<&'a EventChoice> {
+    step.choices
+        .iter()
+        .filter(|choice| choice.is_available(player))
+        .collect()
+}
+
+pub fn region_at(map: &[Vec<Tile>], pos: Position) -> Region {
+    let nearest = |target: Tile| {
+        map.iter()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(move |(_, tile)| **tile == target)
+                    .map(move |(x, _)| x.abs_diff(pos.x) + y.abs_diff(pos.y))
+            })
+            .min()
+            .unwrap_or(usize::MAX)
+    };
+    if nearest(Tile::Town) <= EVENT_REGION_TOWN_RADIUS {
+        Region::NearTown
+    } else if nearest(Tile::Lair) <= EVENT_REGION_LAIR_RADIUS {
+        Region::NearLair
+    } else {
+        Region::Wilds
+    }
 }
 
 pub fn maybe_trigger_event(
     rng: &mut impl Rng,
     player: &mut Player,
-    event_rate_percent: i32,
-) -> Option<WorldEventResult> {
-    if rng.random_range(0..100) >= event_rate_percent.clamp(0, 100) {
+    region: Region,
+    profile: &DifficultyProfile,
+) -> Option<StepOutcome> {
+    if rng.random_range(0..100) >= DifficultyProfile::clamp_rate(profile.world_event_rate_percent) {
         return None;
     }
-    let kind = roll_event_kind(rng);
-    Some(apply_event(kind, rng, player))
+    let candidates: Vec<(&String, i32)> = book()
+        .iter()
+        .filter(|(_, def)| def.when.is_met(player, region))
+        .map(|(id, def)| {
+            let weight = profile.event_weights.get(id).copied().unwrap_or(def.weight);
+            (id, weight.max(0))
+        })
+        .collect();
+    let id = pick_weighted(&candidates, rng)?;
+    let def = book().get(id)?;
+    enter_step(id, &def.start, rng, player)
 }
 
-fn roll_event_kind(rng: &mut impl Rng) -> WorldEventKind {
-    let total = EVENT_WEIGHT_GOLD_CACHE
-        + EVENT_WEIGHT_POTION_STASH
-        + EVENT_WEIGHT_ETHER_STASH
-        + EVENT_WEIGHT_CAMPFIRE
-        + EVENT_WEIGHT_SPIKE_TRAP;
-    let roll = rng.random_range(0..total);
-    event_kind_from_roll(roll)
+/// Resolves the `index`-th visible choice of the current step and enters the step it leads to.
+pub fn choose(
+    state: &EventState,
+    index: usize,
+    rng: &mut impl Rng,
+    player: &mut Player,
+) -> Option<StepOutcome> {
+    let step = current_step(state)?;
+    let choice = *visible_choices(step, player).get(index)?;
+    let next = if choice.roll.is_empty() {
+        choice.next.as_ref()
+    } else {
+        let branches: Vec<(&String, i32)> = choice
+            .roll
+            .iter()
+            .map(|branch| (&branch.next, branch.weight.max(0)))
+            .collect();
+        pick_weighted(&branches, rng)
+    };
+    match next {
+        Some(next) => enter_step(&state.event, next, rng, player),
+        None => Some(StepOutcome {
+            state: EventState {
+                event: state.event.clone(),
+                step: state.step.clone(),
+                message: EventMessage::default(),
+            },
+            player_dead: false,
+            start_battle: false,
+            finished: true,
+            gold_paid: 0,
+        }),
+    }
 }
 
-fn event_kind_from_roll(roll: i32) -> WorldEventKind {
-    if roll < EVENT_WEIGHT_GOLD_CACHE {
-        return WorldEventKind::GoldCache;
-    }
-    if roll < EVENT_WEIGHT_GOLD_CACHE + EVENT_WEIGHT_POTION_STASH {
-        return WorldEventKind::PotionStash;
+fn pick_weighted<'a, T>(options: &[(&'a T, i32)], rng: &mut impl Rng) -> Option<&'a T>
+where
+    T: ?Sized,
+{
+    let total: i32 = options.iter().map(|(_, weight)| weight).sum();
+    if total <= 0 {
+        return None;
     }
-    if roll < EVENT_WEIGHT_GOLD_CACHE + EVENT_WEIGHT_POTION_STASH + EVENT_WEIGHT_ETHER_STASH {
-        return WorldEventKind::EtherStash;
+    pick_from_roll(options, rng.random_range(0..total))
+}
+
+fn pick_from_roll<'a, T>(options: &[(&'a T, i32)], mut roll: i32) -> Option<&'a T>
+where
+    T: ?Sized,
+{
+    for (option, weight) in options {
+        if roll < *weight {
+            return Some(option);
+        }
+        roll -= weight;
     }
-    if roll
-        < EVENT_WEIGHT_GOLD_CACHE
-            + EVENT_WEIGHT_POTION_STASH
-            + EVENT_WEIGHT_ETHER_STASH
-            + EVENT_WEIGHT_CAMPFIRE
-    {
-        return WorldEventKind::Campfire;
+    None
+}
+
+/// The message in the current language; empty when the step said nothing.
+pub fn message_text(message: &EventMessage) -> String {
+    if message.key.is_empty() {
+        return String::new();
     }
-    WorldEventKind::SpikeTrap
+    t!(
+        message.key.as_str(),
+        gold = message.gold,
+        count = message.count,
+        cost = message.cost,
+        dmg = message.dmg,
+        before = message.before,
+        after = message.after
+    )
+    .to_string()
 }
 
-fn apply_event(kind: WorldEventKind, rng: &mut impl Rng, player: &mut Player) -> WorldEventResult {
-    match kind {
-        WorldEventKind::GoldCache => {
-            let gold = rng.random_range(EVENT_GOLD_MIN..=EVENT_GOLD_MAX)
-                + player.level * EVENT_GOLD_PER_LEVEL;
-            player.gold += gold;
-            WorldEventResult {
-                message: t!("log.event.gold_cache", gold = gold).to_string(),
-                player_dead: false,
+fn enter_step(
+    event: &str,
+    step_id: &str,
+    rng: &mut impl Rng,
+    player: &mut Player,
+) -> Option<StepOutcome> {
+    let step = book().get(event)?.steps.get(step_id)?;
+    let mut tally = Tally::default();
+    let mut start_battle = false;
+    for effect in &step.effects {
+        match effect {
+            EventEffect::Gold {
+                min,
+                max,
+                per_level,
+            } => {
+                let gold = rng.random_range(*min..=*max) + player.level * per_level;
+                player.gold += gold;
+                tally.gold += gold;
             }
-        }
-        WorldEventKind::PotionStash => {
-            let amount = rng.random_range(EVENT_POTION_MIN..=EVENT_POTION_MAX);
-            player.bag.potion += amount;
-            WorldEventResult {
-                message: t!("log.event.potion_stash", count = amount).to_string(),
-                player_dead: false,
+            EventEffect::Potion { min, max } => {
+                let amount = rng.random_range(*min..=*max);
+                player.bag.potion += amount;
+                tally.count += amount;
             }
-        }
-        WorldEventKind::EtherStash => {
-            let amount = rng.random_range(EVENT_ETHER_MIN..=EVENT_ETHER_MAX);
-            player.bag.ether += amount;
-            WorldEventResult {
-                message: t!("log.event.ether_stash", count = amount).to_string(),
-                player_dead: false,
+            EventEffect::Ether { min, max } => {
+                let amount = rng.random_range(*min..=*max);
+                player.bag.ether += amount;
+                tally.count += amount;
             }
-        }
-        WorldEventKind::Campfire => {
-            let heal = EVENT_CAMPFIRE_HEAL_BASE + player.level * EVENT_CAMPFIRE_HEAL_PER_LEVEL;
-            let before = player.hp;
-            player.hp = (player.hp + heal).min(player.max_hp);
-            WorldEventResult {
-                message: t!(
-                    "log.event.campfire_heal",
-                    before = before,
-                    after = player.hp
-                )
-                .to_string(),
-                player_dead: false,
+            EventEffect::Heal { base, per_level } => {
+                let before = player.hp;
+                player.hp = (player.hp + base + player.level * per_level).min(player.max_hp);
+                tally.heal = Some((before, player.hp));
             }
-        }
-        WorldEventKind::SpikeTrap => {
-            let damage = rng.random_range(EVENT_TRAP_DAMAGE_MIN..=EVENT_TRAP_DAMAGE_MAX);
-            player.hp -= damage;
-            if player.hp <= 0 {
-                player.hp = 0;
-                return WorldEventResult {
-                    message: t!("log.event.spike_trap_deadly", dmg = damage).to_string(),
-                    player_dead: true,
-                };
+            EventEffect::Damage { min, max } => {
+                let damage = rng.random_range(*min..=*max);
+                player.hp = (player.hp - damage).max(0);
+                tally.dmg += damage;
             }
-            WorldEventResult {
-                message: t!("log.event.spike_trap", dmg = damage).to_string(),
-                player_dead: false,
+            EventEffect::PayGold(cost) => {
+                let paid = (*cost).min(player.gold);
+                player.gold -= paid;
+                tally.cost += paid;
             }
+            EventEffect::Battle => start_battle = true,
         }
     }
+
+    let player_dead = player.hp <= 0;
+    let key = match (&step.fatal_text, player_dead) {
+        (Some(fatal), true) => fatal.as_str(),
+        _ => step.text.as_str(),
+    };
+    let (before, after) = tally.heal.unwrap_or((player.hp, player.hp));
+    let message = EventMessage {
+        key: key.to_string(),
+        gold: tally.gold,
+        count: tally.count,
+        cost: tally.cost,
+        dmg: tally.dmg,
+        before,
+        after,
+    };
+
+    Some(StepOutcome {
+        state: EventState {
+            event: event.to_string(),
+            step: step_id.to_string(),
+            message,
+        },
+        player_dead,
+        start_battle: start_battle && !player_dead,
+        finished: step.choices.is_empty() || player_dead || start_battle,
+        gold_paid: tally.cost,
+    })
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
     use rand::SeedableRng;
     use rand::rngs::StdRng;
+    use rust_i18n::t;
 
-    use super::{WorldEventKind, apply_event, event_kind_from_roll, maybe_trigger_event};
-    use crate::game::balance::{
-        EVENT_WEIGHT_CAMPFIRE, EVENT_WEIGHT_ETHER_STASH, EVENT_WEIGHT_GOLD_CACHE,
-        EVENT_WEIGHT_POTION_STASH,
+    use super::{
+        Region, book, choose, enter_step, maybe_trigger_event, message_text, pick_from_roll,
+        region_at, visible_choices,
     };
-    use crate::game::model::Player;
+    use crate::game::config::profile_for;
+    use crate::game::model::{Difficulty, EventMessage, EventState, Player, Position};
+    use crate::game::world::generate_world;
 
     #[test]
-    fn event_roll_boundaries_map_to_expected_kinds() {
-        assert_eq!(event_kind_from_roll(0), WorldEventKind::GoldCache);
-        assert_eq!(
-            event_kind_from_roll(EVENT_WEIGHT_GOLD_CACHE),
-            WorldEventKind::PotionStash
-        );
-        assert_eq!(
-            event_kind_from_roll(EVENT_WEIGHT_GOLD_CACHE + EVENT_WEIGHT_POTION_STASH),
-            WorldEventKind::EtherStash
-        );
-        assert_eq!(
-            event_kind_from_roll(
-                EVENT_WEIGHT_GOLD_CACHE + EVENT_WEIGHT_POTION_STASH + EVENT_WEIGHT_ETHER_STASH
-            ),
-            WorldEventKind::Campfire
-        );
-        assert_eq!(
-            event_kind_from_roll(
-                EVENT_WEIGHT_GOLD_CACHE
-                    + EVENT_WEIGHT_POTION_STASH
-                    + EVENT_WEIGHT_ETHER_STASH
-                    + EVENT_WEIGHT_CAMPFIRE
-            ),
-            WorldEventKind::SpikeTrap
-        );
+    fn embedded_events_reference_existing_steps_and_locale_keys() {
+        rust_i18n::set_locale("en");
+        for (id, def) in book() {
+            assert!(def.steps.contains_key(&def.start), "{id} start");
+            for (name, step) in &def.steps {
+                assert_ne!(t!(step.text.as_str()), step.text, "{id}.{name}");
+                if let Some(fatal) = &step.fatal_text {
+                    assert_ne!(t!(fatal.as_str()), *fatal, "{id}.{name}");
+                }
+                for choice in &step.choices {
+                    assert_ne!(t!(choice.text.as_str()), choice.text);
+                    let targets = choice
+                        .next
+                        .iter()
+                        .chain(choice.roll.iter().map(|branch| &branch.next));
+                    for next in targets {
+                        assert!(def.steps.contains_key(next), "{id}.{name} -> {next}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn weighted_roll_boundaries_map_to_expected_options() {
+        let options = [("a", 30), ("b", 22), ("c", 0), ("d", 18)];
+        assert_eq!(pick_from_roll(&options, 0), Some("a"));
+        assert_eq!(pick_from_roll(&options, 29), Some("a"));
+        assert_eq!(pick_from_roll(&options, 30), Some("b"));
+        assert_eq!(pick_from_roll(&options, 52), Some("d"));
+        assert_eq!(pick_from_roll(&options, 70), None);
     }
 
     #[test]
@@ -171,8 +402,9 @@ mod tests {
         let mut rng = StdRng::seed_from_u64(10);
         let mut player = Player::new();
         player.hp = player.max_hp - 1;
-        let result = apply_event(WorldEventKind::Campfire, &mut rng, &mut player);
-        assert!(!result.player_dead);
+        let outcome = enter_step("campfire", "rest", &mut rng, &mut player).expect("campfire");
+        assert!(!outcome.player_dead);
+        assert!(outcome.finished);
         assert_eq!(player.hp, player.max_hp);
     }
 
@@ -182,9 +414,12 @@ mod tests {
         let mut rng = StdRng::seed_from_u64(1);
         let mut player = Player::new();
         player.hp = 1;
-        let result = apply_event(WorldEventKind::SpikeTrap, &mut rng, &mut player);
-        assert!(result.player_dead);
+        let outcome = enter_step("spike_trap", "sprung", &mut rng, &mut player).expect("trap");
+        assert!(outcome.player_dead);
         assert_eq!(player.hp, 0);
+        assert!((4..=10).any(|dmg| {
+            message_text(&outcome.state.message) == t!("log.event.spike_trap_deadly", dmg = dmg)
+        }));
     }
 
     #[test]
@@ -192,6 +427,69 @@ mod tests {
         rust_i18n::set_locale("en");
         let mut rng = StdRng::seed_from_u64(5);
         let mut player = Player::new();
-        assert!(maybe_trigger_event(&mut rng, &mut player, 0).is_none());
+        let mut profile = profile_for(Difficulty::Normal);
+        profile.world_event_rate_percent = 0;
+        assert!(maybe_trigger_event(&mut rng, &mut player, Region::Wilds, &profile).is_none());
+    }
+
+    #[test]
+    fn difficulty_weights_and_conditions_filter_events() {
+        rust_i18n::set_locale("en");
+        let mut profile = profile_for(Difficulty::Normal);
+        profile.world_event_rate_percent = 100;
+        profile.event_weights = book()
+            .keys()
+            .map(|id| (id.clone(), 0))
+            .collect::<HashMap<_, _>>();
+        profile.event_weights.insert("bandit_toll".to_string(), 5);
+        let mut rng = StdRng::seed_from_u64(3);
+
+        let mut player = Player::new();
+        player.level = 1;
+        assert!(maybe_trigger_event(&mut rng, &mut player, Region::Wilds, &profile).is_none());
+
+        player.level = 2;
+        assert!(maybe_trigger_event(&mut rng, &mut player, Region::NearTown, &profile).is_none());
+        let outcome = maybe_trigger_event(&mut rng, &mut player, Region::Wilds, &profile)
+            .expect("bandits should be the only candidate");
+        assert_eq!(outcome.state.event, "bandit_toll");
+        assert!(!outcome.finished);
+    }
+
+    #[test]
+    fn choices_gate_on_gold_and_lead_to_battles() {
+        rust_i18n::set_locale("en");
+        let mut rng = StdRng::seed_from_u64(8);
+        let mut player = Player::new();
+        player.level = 2;
+        player.gold = 0;
+        let state = EventState {
+            event: "bandit_toll".to_string(),
+            step: "ambush".to_string(),
+            message: EventMessage::default(),
+        };
+        let step = super::current_step(&state).expect("ambush step");
+        assert_eq!(visible_choices(step, &player).len(), 2);
+        player.gold = 50;
+        assert_eq!(visible_choices(step, &player).len(), 3);
+
+        let paid = choose(&state, 0, &mut rng, &mut player).expect("pay the toll");
+        assert!(paid.finished && !paid.start_battle);
+        assert_eq!(player.gold, 30);
+
+        let fight = choose(&state, 1, &mut rng, &mut player).expect("fight");
+        assert!(fight.finished && fight.start_battle);
+    }
+
+    #[test]
+    fn regions_follow_distance_to_town_and_lair() {
+        let (map, _) = generate_world(42);
+        assert_eq!(region_at(&map, Position { x: 2, y: 3 }), Region::NearTown);
+        let lair = Position {
+            x: map[0].len() - 3,
+            y: map.len() - 2,
+        };
+        assert_eq!(region_at(&map, lair), Region::NearLair);
+        assert_eq!(region_at(&map, Position { x: 18, y: 2 }), Region::Wilds);
     }
 }
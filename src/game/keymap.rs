@@ -0,0 +1,419 @@
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use crossterm::event::KeyCode;
+use serde::Deserialize;
+
+use crate::game::model::{Command, GameMode};
+
+const KEYMAP_DATA: &str = include_str!("../../config/keymap.toml");
+pub const DEFAULT_PRESET: &str = "wasd";
+
+#[derive(Deserialize)]
+struct KeymapFile {
+    base: BTreeMap<Command, Vec<String>>,
+    preset: Vec<Preset>,
+}
+
+#[derive(Deserialize)]
+pub struct Preset {
+    pub id: String,
+    /// Locale key of the preset's display name.
+    pub name: String,
+    bindings: BTreeMap<Command, Vec<String>>,
+}
+
+fn data() -> &'static KeymapFile {
+    static FILE: OnceLock<KeymapFile> = OnceLock::new();
+    FILE.get_or_init(|| toml::from_str(KEYMAP_DATA).expect("embedded keymap data is valid"))
+}
+
+/// Keymap presets in menu order.
+pub fn presets() -> &'static [Preset] {
+    &data().preset
+}
+
+/// Which commands a key press can resolve to depends on the screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Scope {
+    Global,
+    World,
+    Movement,
+    Menu,
+    Adjust,
+    Battle,
+    Prompt,
+    Outcome,
+    Setup,
+    Settings,
+    Party,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layer {
+    Exploration,
+    Menus,
+    Battle,
+    /// A confirmation prompt, which swallows every other key.
+    Prompt,
+    /// Victory and game over.
+    Outcome,
+    /// Class select and the legacy and challenge screens before a run.
+    Setup,
+    /// Settings and key bindings.
+    Settings,
+    Party,
+}
+
+impl Layer {
+    const ALL: [Self; 8] = [
+        Self::Exploration,
+        Self::Menus,
+        Self::Battle,
+        Self::Prompt,
+        Self::Outcome,
+        Self::Setup,
+        Self::Settings,
+        Self::Party,
+    ];
+
+    pub fn for_mode(mode: GameMode) -> Self {
+        match mode {
+            GameMode::Exploration => Self::Exploration,
+            GameMode::Battle => Self::Battle,
+            GameMode::Victory | GameMode::GameOver => Self::Outcome,
+            GameMode::ClassSelect | GameMode::Legacy | GameMode::Challenge => Self::Setup,
+            GameMode::Settings | GameMode::Keybinds => Self::Settings,
+            GameMode::Party => Self::Party,
+            _ => Self::Menus,
+        }
+    }
+
+    fn scopes(self) -> &'static [Scope] {
+        match self {
+            Self::Exploration => &[Scope::Global, Scope::World, Scope::Movement],
+            Self::Menus => &[Scope::Global, Scope::World, Scope::Menu, Scope::Adjust],
+            Self::Battle => &[Scope::Global, Scope::Menu, Scope::Adjust, Scope::Battle],
+            Self::Prompt => &[Scope::Menu, Scope::Prompt],
+            Self::Outcome => &[
+                Scope::Global,
+                Scope::World,
+                Scope::Menu,
+                Scope::Adjust,
+                Scope::Outcome,
+            ],
+            Self::Setup => &[Scope::Global, Scope::Menu, Scope::Setup],
+            Self::Settings => &[
+                Scope::Global,
+                Scope::World,
+                Scope::Menu,
+                Scope::Adjust,
+                Scope::Settings,
+            ],
+            Self::Party => &[Scope::Global, Scope::World, Scope::Menu, Scope::Party],
+        }
+    }
+}
+
+fn scope(command: Command) -> Scope {
+    match command {
+        Command::Quit | Command::Save | Command::Load | Command::NextPanel => Scope::Global,
+        Command::Interact
+        | Command::Settings
+        | Command::Party
+        | Command::LevelUp
+        | Command::Records
+        | Command::WorldMap => Scope::World,
+        Command::MoveUp | Command::MoveDown | Command::MoveLeft | Command::MoveRight => {
+            Scope::Movement
+        }
+        Command::MenuUp | Command::MenuDown | Command::Confirm | Command::Back => Scope::Menu,
+        Command::MenuLeft | Command::MenuRight => Scope::Adjust,
+        Command::Attack
+        | Command::Skill
+        | Command::Defend
+        | Command::Potion
+        | Command::Ether
+        | Command::Run => Scope::Battle,
+        Command::Accept | Command::AcceptAlways | Command::Decline => Scope::Prompt,
+        Command::Restart | Command::NewGamePlus => Scope::Outcome,
+        Command::OpenLegacy
+        | Command::OpenChallenges
+        | Command::ToggleRoguelite
+        | Command::ToggleDaily => Scope::Setup,
+        Command::ResetDifficulty | Command::ResetPrompts | Command::ResetBinding => Scope::Settings,
+        Command::MoveMemberUp
+        | Command::MoveMemberDown
+        | Command::DismissMember
+        | Command::UpgradeMemberWeapon
+        | Command::UpgradeMemberArmor => Scope::Party,
+    }
+}
+
+/// Number keys pick menu entries by position on every screen that lists
+/// them: `1` is the first entry and `0` the tenth.
+pub fn entry(code: KeyCode) -> Option<usize> {
+    let KeyCode::Char(key) = code else {
+        return None;
+    };
+    let digit = key.to_digit(10)? as usize;
+    Some((digit + 9) % 10)
+}
+
+/// Two commands may share a key only if no screen listens for both.
+fn share_a_layer(a: Command, b: Command) -> bool {
+    Layer::ALL.into_iter().any(|layer| {
+        let scopes = layer.scopes();
+        scopes.contains(&scope(a)) && scopes.contains(&scope(b))
+    })
+}
+
+pub fn parse_key(text: &str) -> Option<KeyCode> {
+    let mut chars = text.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+    let code = match text {
+        "Up" => KeyCode::Up,
+        "Down" => KeyCode::Down,
+        "Left" => KeyCode::Left,
+        "Right" => KeyCode::Right,
+        "Enter" => KeyCode::Enter,
+        "Space" => KeyCode::Char(' '),
+        "Tab" => KeyCode::Tab,
+        "Backspace" => KeyCode::Backspace,
+        "Delete" => KeyCode::Delete,
+        "Insert" => KeyCode::Insert,
+        "Home" => KeyCode::Home,
+        "End" => KeyCode::End,
+        "PageUp" => KeyCode::PageUp,
+        "PageDown" => KeyCode::PageDown,
+        _ => {
+            let number = text.strip_prefix('F')?.parse::<u8>().ok()?;
+            return (1..=12).contains(&number).then_some(KeyCode::F(number));
+        }
+    };
+    Some(code)
+}
+
+/// The spelling `parse_key` accepts, or `None` for keys that cannot be bound.
+pub fn key_name(code: KeyCode) -> Option<String> {
+    let name = match code {
+        KeyCode::Char(' ') => "Space",
+        KeyCode::Char(c) => return Some(c.to_string()),
+        KeyCode::Up => "Up",
+        KeyCode::Down => "Down",
+        KeyCode::Left => "Left",
+        KeyCode::Right => "Right",
+        KeyCode::Enter => "Enter",
+        KeyCode::Tab => "Tab",
+        KeyCode::Backspace => "Backspace",
+        KeyCode::Delete => "Delete",
+        KeyCode::Insert => "Insert",
+        KeyCode::Home => "Home",
+        KeyCode::End => "End",
+        KeyCode::PageUp => "PageUp",
+        KeyCode::PageDown => "PageDown",
+        KeyCode::F(number) if (1..=12).contains(&number) => return Some(format!("F{number}")),
+        _ => return None,
+    };
+    Some(name.to_string())
+}
+
+/// Resolved bindings: the base keys, then the preset, then the player's own
+/// rebinds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Keymap {
+    bindings: BTreeMap<Command, Vec<KeyCode>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::build(DEFAULT_PRESET, &BTreeMap::new()).expect("default keymap is valid")
+    }
+}
+
+impl Keymap {
+    /// Builds the keymap, rejecting unknown presets, unreadable keys and
+    /// keys bound twice on the same screen.
+    pub fn build(preset: &str, overrides: &BTreeMap<Command, Vec<String>>) -> Result<Self, String> {
+        let preset = presets()
+            .iter()
+            .find(|candidate| candidate.id == preset)
+            .ok_or_else(|| format!("unknown keymap preset `{preset}`"))?;
+        let mut bindings = BTreeMap::new();
+        for layer in [&data().base, &preset.bindings, overrides] {
+            for (command, keys) in layer {
+                let keys = keys
+                    .iter()
+                    .map(|key| {
+                        parse_key(key).ok_or_else(|| format!("{command:?}: unknown key `{key}`"))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                bindings.insert(*command, keys);
+            }
+        }
+        let keymap = Self { bindings };
+        for (command, keys) in &keymap.bindings {
+            if let Some((key, other)) = keys
+                .iter()
+                .find_map(|key| keymap.conflict(*command, *key).map(|other| (key, other)))
+            {
+                let key = key_name(*key).unwrap_or_default();
+                return Err(format!(
+                    "`{key}` is bound to both {command:?} and {other:?}"
+                ));
+            }
+        }
+        Ok(keymap)
+    }
+
+    /// The command a key press means on screens of the given layer.
+    pub fn command(&self, code: KeyCode, layer: Layer) -> Option<Command> {
+        let scopes = layer.scopes();
+        self.bindings
+            .iter()
+            .find(|(command, keys)| scopes.contains(&scope(**command)) && keys.contains(&code))
+            .map(|(command, _)| *command)
+    }
+
+    pub fn keys(&self, command: Command) -> &[KeyCode] {
+        self.bindings.get(&command).map_or(&[], Vec::as_slice)
+    }
+
+    /// Another command that already answers to `code` on a screen where
+    /// `command` is also live.
+    pub fn conflict(&self, command: Command, code: KeyCode) -> Option<Command> {
+        self.bindings
+            .iter()
+            .find(|(other, keys)| {
+                **other != command && keys.contains(&code) && share_a_layer(command, **other)
+            })
+            .map(|(other, _)| *other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crossterm::event::KeyCode;
+
+    use super::{Keymap, Layer, entry, key_name, parse_key, presets};
+    use crate::game::model::Command;
+
+    #[test]
+    fn every_preset_builds_and_binds_every_command() {
+        for preset in presets() {
+            let keymap = Keymap::build(&preset.id, &BTreeMap::new())
+                .unwrap_or_else(|error| panic!("{}: {error}", preset.id));
+            for command in Command::ALL {
+                assert!(
+                    !keymap.keys(command).is_empty(),
+                    "{}: {command:?}",
+                    preset.id
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn keys_resolve_per_screen() {
+        let wasd = Keymap::default();
+        assert_eq!(
+            wasd.command(KeyCode::Char('w'), Layer::Exploration),
+            Some(Command::MoveUp)
+        );
+        assert_eq!(
+            wasd.command(KeyCode::Char('w'), Layer::Menus),
+            Some(Command::MenuUp)
+        );
+        assert_eq!(
+            wasd.command(KeyCode::Char('1'), Layer::Battle),
+            Some(Command::Attack)
+        );
+        assert_eq!(wasd.command(KeyCode::Char('1'), Layer::Menus), None);
+
+        let vi = Keymap::build("vi", &BTreeMap::new()).unwrap();
+        assert_eq!(
+            vi.command(KeyCode::Char('l'), Layer::Exploration),
+            Some(Command::MoveRight)
+        );
+        assert_eq!(
+            vi.command(KeyCode::Char('L'), Layer::Exploration),
+            Some(Command::Load)
+        );
+
+        // Screen shortcuts reuse letters other screens read as menu moves.
+        assert_eq!(
+            wasd.command(KeyCode::Char('d'), Layer::Setup),
+            Some(Command::ToggleDaily)
+        );
+        assert_eq!(
+            wasd.command(KeyCode::Char('a'), Layer::Prompt),
+            Some(Command::AcceptAlways)
+        );
+        assert_eq!(
+            wasd.command(KeyCode::Char('a'), Layer::Settings),
+            Some(Command::MenuLeft)
+        );
+    }
+
+    #[test]
+    fn conflicting_rebinds_are_rejected() {
+        let clash = BTreeMap::from([(Command::Party, vec!["w".to_string()])]);
+        let error = Keymap::build("wasd", &clash).unwrap_err();
+        assert!(error.contains("Party"), "{error}");
+
+        // Battle keys never share a screen with movement.
+        let numpad = Keymap::build("numpad", &BTreeMap::new()).unwrap();
+        assert_eq!(numpad.conflict(Command::MoveUp, KeyCode::Char('8')), None);
+        assert_eq!(
+            numpad.conflict(Command::Interact, KeyCode::Char('8')),
+            Some(Command::MoveUp)
+        );
+
+        // Screen shortcuts clash only with the commands their screen hears.
+        let wasd = Keymap::default();
+        assert_eq!(
+            wasd.conflict(Command::Party, KeyCode::Char('x')),
+            Some(Command::DismissMember)
+        );
+        assert_eq!(
+            wasd.conflict(Command::Save, KeyCode::Char('g')),
+            Some(Command::ToggleRoguelite)
+        );
+        assert_eq!(wasd.conflict(Command::Attack, KeyCode::Char('x')), None);
+        let reset = BTreeMap::from([(Command::MenuLeft, vec!["r".to_string()])]);
+        let error = Keymap::build("wasd", &reset).unwrap_err();
+        assert!(error.contains("MenuLeft"), "{error}");
+
+        assert!(Keymap::build("dvorak", &BTreeMap::new()).is_err());
+        let typo = BTreeMap::from([(Command::Save, vec!["Ctrl".to_string()])]);
+        assert!(Keymap::build("wasd", &typo).is_err());
+    }
+
+    #[test]
+    fn number_keys_pick_entries_by_position() {
+        assert_eq!(entry(KeyCode::Char('1')), Some(0));
+        assert_eq!(entry(KeyCode::Char('9')), Some(8));
+        assert_eq!(entry(KeyCode::Char('0')), Some(9));
+        assert_eq!(entry(KeyCode::Char('x')), None);
+        assert_eq!(entry(KeyCode::Enter), None);
+    }
+
+    #[test]
+    fn key_names_round_trip() {
+        for code in [
+            KeyCode::Char('k'),
+            KeyCode::Char(' '),
+            KeyCode::Up,
+            KeyCode::Enter,
+            KeyCode::F(5),
+        ] {
+            assert_eq!(parse_key(&key_name(code).unwrap()), Some(code));
+        }
+        assert_eq!(key_name(KeyCode::Esc), None);
+        assert_eq!(parse_key("F13"), None);
+    }
+}
@@ -0,0 +1,189 @@
+use std::collections::BTreeSet;
+
+use rand::Rng;
+use rust_i18n::t;
+
+use crate::game::balance::{
+    ELITE_HP_PERCENT, ELITE_REWARD_MULTIPLIER, ELITE_SPAWN_PERCENT, HARDENED_MAX_HP,
+    INHERITANCE_GOLD, MODIFIER_SHARD_BONUS_PERCENT, PROVISIONS_ETHERS, PROVISIONS_POTIONS,
+    SCORE_PER_SHARD, unlock_cost,
+};
+use crate::game::model::{Enemy, Legacy, Player, Unlock, UnlockKind};
+
+/// Buys a locked unlock, or switches an owned one on or off for the next run.
+pub fn buy_or_toggle(legacy: &mut Legacy, unlock: Unlock) -> String {
+    let name = t!(unlock.name_key());
+    if legacy.unlocked.contains(&unlock) {
+        if legacy.active.remove(&unlock) {
+            return t!("log.legacy.disabled", name = name).to_string();
+        }
+        legacy.active.insert(unlock);
+        return t!("log.legacy.enabled", name = name).to_string();
+    }
+
+    let cost = unlock_cost(unlock);
+    if legacy.shards < cost {
+        return t!("log.legacy.need_shards", name = name, cost = cost).to_string();
+    }
+    legacy.shards -= cost;
+    legacy.unlocked.insert(unlock);
+    legacy.active.insert(unlock);
+    t!("log.legacy.unlocked", name = name, cost = cost).to_string()
+}
+
+/// Gives a freshly chosen hero the starting perks among `unlocks`.
+pub fn apply_perks(player: &mut Player, unlocks: &BTreeSet<Unlock>) {
+    for unlock in unlocks {
+        match unlock {
+            Unlock::Provisions => {
+                player.bag.potion += PROVISIONS_POTIONS;
+                player.bag.ether += PROVISIONS_ETHERS;
+            }
+            Unlock::Inheritance => player.gold += INHERITANCE_GOLD,
+            Unlock::Hardened => {
+                player.max_hp += HARDENED_MAX_HP;
+                player.hp += HARDENED_MAX_HP;
+            }
+            Unlock::EliteFoes | Unlock::Bloodlust | Unlock::Famine => {}
+        }
+    }
+}
+
+/// Applies enemy variants and difficulty modifiers to a freshly generated foe.
+/// Nothing is rolled unless elite foes are unlocked, so plain runs keep their
+/// random sequence.
+pub fn adjust_enemy(enemy: &mut Enemy, unlocks: &BTreeSet<Unlock>, rng: &mut impl Rng) {
+    if unlocks.contains(&Unlock::EliteFoes)
+        && !enemy.is_boss
+        && rng.random_range(0..100) < ELITE_SPAWN_PERCENT
+    {
+        enemy.name = t!("enemy.elite", name = enemy.name.as_str()).to_string();
+        enemy.max_hp = enemy.max_hp * ELITE_HP_PERCENT / 100;
+        enemy.hp = enemy.hp * ELITE_HP_PERCENT / 100;
+        enemy.atk += enemy.atk / 4;
+        enemy.def += enemy.def / 4;
+        enemy.exp_reward *= ELITE_REWARD_MULTIPLIER;
+        enemy.gold_reward *= ELITE_REWARD_MULTIPLIER;
+    }
+    if unlocks.contains(&Unlock::Bloodlust) {
+        enemy.atk += enemy.atk / 4;
+    }
+    if unlocks.contains(&Unlock::Famine) {
+        enemy.gold_reward /= 2;
+    }
+}
+
+/// Shards earned by a finished roguelite run; each active difficulty
+/// modifier raises the payout.
+pub fn shards_for_run(score: i32, unlocks: &BTreeSet<Unlock>) -> u32 {
+    let base = (score.max(0) / SCORE_PER_SHARD) as u32;
+    let modifiers = unlocks
+        .iter()
+        .filter(|unlock| unlock.kind() == UnlockKind::Modifier)
+        .count() as u32;
+    base + base * modifiers * MODIFIER_SHARD_BONUS_PERCENT / 100
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use super::{adjust_enemy, apply_perks, buy_or_toggle, shards_for_run};
+    use crate::game::balance::unlock_cost;
+    use crate::game::model::{AiKind, Enemy, EnemyStyle, Legacy, Player, Unlock};
+
+    fn wolf(is_boss: bool) -> Enemy {
+        Enemy {
+            name: "Wolf".to_string(),
+            name_key: "enemy.wolf".to_string(),
+            hp: 20,
+            max_hp: 20,
+            atk: 8,
+            def: 4,
+            exp_reward: 10,
+            gold_reward: 10,
+            is_boss,
+            style: EnemyStyle::Predator,
+            ai: AiKind::Aggressive,
+            loot: None,
+            art: String::new(),
+        }
+    }
+
+    #[test]
+    fn unlocks_are_bought_once_then_toggled() {
+        rust_i18n::set_locale("en");
+        let mut legacy = Legacy::default();
+        buy_or_toggle(&mut legacy, Unlock::Provisions);
+        assert!(legacy.unlocked.is_empty());
+
+        legacy.shards = unlock_cost(Unlock::Provisions) + 1;
+        buy_or_toggle(&mut legacy, Unlock::Provisions);
+        assert_eq!(legacy.shards, 1);
+        assert!(legacy.active.contains(&Unlock::Provisions));
+
+        buy_or_toggle(&mut legacy, Unlock::Provisions);
+        assert!(!legacy.active.contains(&Unlock::Provisions));
+        assert!(legacy.unlocked.contains(&Unlock::Provisions));
+        buy_or_toggle(&mut legacy, Unlock::Provisions);
+        assert!(legacy.active.contains(&Unlock::Provisions));
+        assert_eq!(legacy.shards, 1);
+    }
+
+    #[test]
+    fn perks_stock_a_fresh_hero() {
+        let plain = Player::new();
+        let mut hero = Player::new();
+        apply_perks(
+            &mut hero,
+            &BTreeSet::from([Unlock::Provisions, Unlock::Inheritance, Unlock::Hardened]),
+        );
+        assert!(hero.bag.potion > plain.bag.potion);
+        assert!(hero.gold > plain.gold);
+        assert!(hero.max_hp > plain.max_hp);
+        assert_eq!(hero.hp, hero.max_hp);
+    }
+
+    #[test]
+    fn elites_pay_more_and_never_replace_bosses() {
+        rust_i18n::set_locale("en");
+        let unlocks = BTreeSet::from([Unlock::EliteFoes]);
+        let mut rng = StdRng::seed_from_u64(9);
+        let elites: Vec<Enemy> = (0..60)
+            .map(|_| {
+                let mut enemy = wolf(false);
+                adjust_enemy(&mut enemy, &unlocks, &mut rng);
+                enemy
+            })
+            .filter(|enemy| enemy.name != "Wolf")
+            .collect();
+        assert!(!elites.is_empty());
+        assert!(
+            elites
+                .iter()
+                .all(|enemy| enemy.max_hp > 20 && enemy.gold_reward > 10)
+        );
+
+        for _ in 0..60 {
+            let mut boss = wolf(true);
+            adjust_enemy(&mut boss, &unlocks, &mut rng);
+            assert_eq!(boss.max_hp, 20);
+        }
+    }
+
+    #[test]
+    fn modifiers_harden_foes_and_raise_the_shard_payout() {
+        let unlocks = BTreeSet::from([Unlock::Bloodlust, Unlock::Famine]);
+        let mut enemy = wolf(true);
+        adjust_enemy(&mut enemy, &unlocks, &mut StdRng::seed_from_u64(1));
+        assert_eq!(enemy.atk, 10);
+        assert_eq!(enemy.gold_reward, 5);
+
+        assert_eq!(shards_for_run(850, &BTreeSet::new()), 8);
+        assert_eq!(shards_for_run(850, &unlocks), 12);
+        assert_eq!(shards_for_run(-40, &unlocks), 0);
+    }
+}
@@ -1,11 +1,22 @@
 mod balance;
 mod battle;
+mod boss_ai;
+mod challenge;
 mod combat;
 mod config;
+mod crafting;
+mod dialogue;
 mod encounter;
+mod enemy_ai;
 mod event;
+mod keymap;
+mod legacy;
 pub mod model;
+mod party;
+mod prefs;
 mod progression;
+mod records;
+mod roaming;
 mod save;
 mod state;
 mod town;
@@ -1,7 +1,12 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 
 use serde::{Deserialize, Serialize};
 
+use crate::game::balance::{
+    CLOCK_START_HOUR, DAWN_HOUR, DAY_HOUR, DUSK_HOUR, NIGHT_HOUR, SHOP_ETHER_STOCK,
+    SHOP_POTION_STOCK, TURNS_PER_HOUR, class_template,
+};
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Tile {
     Floor,
@@ -16,6 +21,15 @@ pub enum GameMode {
     Town,
     Settings,
     Battle,
+    Dialogue,
+    Event,
+    Party,
+    ClassSelect,
+    LevelUp,
+    Records,
+    Legacy,
+    Challenge,
+    Keybinds,
     Victory,
     GameOver,
 }
@@ -94,15 +108,23 @@ pub enum Difficulty {
     Easy,
     Normal,
     Hard,
+    /// The player's own profile, edited in the Settings screen.
+    Custom,
 }
 
 impl Difficulty {
-    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+        Difficulty::Custom,
+    ];
 
     pub fn from_tag(tag: &str) -> Self {
         match tag.to_ascii_lowercase().as_str() {
             "easy" => Self::Easy,
             "hard" => Self::Hard,
+            "custom" => Self::Custom,
             _ => Self::Normal,
         }
     }
@@ -112,6 +134,7 @@ impl Difficulty {
             Self::Easy => 0,
             Self::Normal => 1,
             Self::Hard => 2,
+            Self::Custom => 3,
         }
     }
 
@@ -120,23 +143,418 @@ impl Difficulty {
             Self::Easy => "ui.settings.diff_easy",
             Self::Normal => "ui.settings.diff_normal",
             Self::Hard => "ui.settings.diff_hard",
+            Self::Custom => "ui.settings.diff_custom",
         }
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// A rebindable input, resolved from a key press through the active keymap.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Command {
+    Quit,
+    Save,
+    Load,
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Interact,
+    Settings,
+    Party,
+    LevelUp,
+    Records,
+    MenuUp,
+    MenuDown,
+    MenuLeft,
+    MenuRight,
+    Confirm,
+    Back,
+    Attack,
+    Skill,
+    Defend,
+    Potion,
+    Ether,
+    Run,
+    NextPanel,
+    WorldMap,
+    Restart,
+    NewGamePlus,
+    Accept,
+    AcceptAlways,
+    Decline,
+    OpenLegacy,
+    OpenChallenges,
+    ToggleRoguelite,
+    ToggleDaily,
+    ResetDifficulty,
+    ResetPrompts,
+    ResetBinding,
+    MoveMemberUp,
+    MoveMemberDown,
+    DismissMember,
+    UpgradeMemberWeapon,
+    UpgradeMemberArmor,
+}
+
+impl Command {
+    pub const ALL: [Self; 43] = [
+        Self::Quit,
+        Self::Save,
+        Self::Load,
+        Self::MoveUp,
+        Self::MoveDown,
+        Self::MoveLeft,
+        Self::MoveRight,
+        Self::Interact,
+        Self::Settings,
+        Self::Party,
+        Self::LevelUp,
+        Self::Records,
+        Self::MenuUp,
+        Self::MenuDown,
+        Self::MenuLeft,
+        Self::MenuRight,
+        Self::Confirm,
+        Self::Back,
+        Self::Attack,
+        Self::Skill,
+        Self::Defend,
+        Self::Potion,
+        Self::Ether,
+        Self::Run,
+        Self::NextPanel,
+        Self::WorldMap,
+        Self::Restart,
+        Self::NewGamePlus,
+        Self::Accept,
+        Self::AcceptAlways,
+        Self::Decline,
+        Self::OpenLegacy,
+        Self::OpenChallenges,
+        Self::ToggleRoguelite,
+        Self::ToggleDaily,
+        Self::ResetDifficulty,
+        Self::ResetPrompts,
+        Self::ResetBinding,
+        Self::MoveMemberUp,
+        Self::MoveMemberDown,
+        Self::DismissMember,
+        Self::UpgradeMemberWeapon,
+        Self::UpgradeMemberArmor,
+    ];
+
+    pub fn label_key(self) -> &'static str {
+        match self {
+            Self::Quit => "command.quit",
+            Self::Save => "command.save",
+            Self::Load => "command.load",
+            Self::MoveUp => "command.move_up",
+            Self::MoveDown => "command.move_down",
+            Self::MoveLeft => "command.move_left",
+            Self::MoveRight => "command.move_right",
+            Self::Interact => "command.interact",
+            Self::Settings => "command.settings",
+            Self::Party => "command.party",
+            Self::LevelUp => "command.level_up",
+            Self::Records => "command.records",
+            Self::MenuUp => "command.menu_up",
+            Self::MenuDown => "command.menu_down",
+            Self::MenuLeft => "command.menu_left",
+            Self::MenuRight => "command.menu_right",
+            Self::Confirm => "command.confirm",
+            Self::Back => "command.back",
+            Self::Attack => "command.attack",
+            Self::Skill => "command.skill",
+            Self::Defend => "command.defend",
+            Self::Potion => "command.potion",
+            Self::Ether => "command.ether",
+            Self::Run => "command.run",
+            Self::NextPanel => "command.next_panel",
+            Self::WorldMap => "command.world_map",
+            Self::Restart => "command.restart",
+            Self::NewGamePlus => "command.new_game_plus",
+            Self::Accept => "command.accept",
+            Self::AcceptAlways => "command.accept_always",
+            Self::Decline => "command.decline",
+            Self::OpenLegacy => "command.open_legacy",
+            Self::OpenChallenges => "command.open_challenges",
+            Self::ToggleRoguelite => "command.toggle_roguelite",
+            Self::ToggleDaily => "command.toggle_daily",
+            Self::ResetDifficulty => "command.reset_difficulty",
+            Self::ResetPrompts => "command.reset_prompts",
+            Self::ResetBinding => "command.reset_binding",
+            Self::MoveMemberUp => "command.move_member_up",
+            Self::MoveMemberDown => "command.move_member_down",
+            Self::DismissMember => "command.dismiss_member",
+            Self::UpgradeMemberWeapon => "command.upgrade_member_weapon",
+            Self::UpgradeMemberArmor => "command.upgrade_member_armor",
+        }
+    }
+}
+
+/// The side panel shown under the map when the terminal is too narrow for
+/// the sidebar.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PanelTab {
+    #[default]
+    Hero,
+    Log,
+    Controls,
+}
+
+impl PanelTab {
+    pub const ALL: [Self; 3] = [Self::Hero, Self::Log, Self::Controls];
+
+    pub fn label_key(self) -> &'static str {
+        match self {
+            Self::Hero => "ui.panel.hero",
+            Self::Log => "ui.panel.battle_log",
+            Self::Controls => "ui.panel.controls",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::Hero => Self::Log,
+            Self::Log => Self::Controls,
+            Self::Controls => Self::Hero,
+        }
+    }
+}
+
+/// A destructive action that waits for the player to confirm it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfirmAction {
+    Quit,
+    Load,
+    Save,
+    Restart,
+    Resume,
+}
+
+impl ConfirmAction {
+    pub fn title_key(self) -> &'static str {
+        match self {
+            Self::Quit => "ui.confirm.quit.title",
+            Self::Load => "ui.confirm.load.title",
+            Self::Save => "ui.confirm.save.title",
+            Self::Restart => "ui.confirm.restart.title",
+            Self::Resume => "ui.confirm.resume.title",
+        }
+    }
+
+    pub fn name_key(self) -> &'static str {
+        match self {
+            Self::Quit => "ui.confirm.quit.name",
+            Self::Load => "ui.confirm.load.name",
+            Self::Save => "ui.confirm.save.name",
+            Self::Restart => "ui.confirm.restart.name",
+            Self::Resume => "ui.confirm.resume.name",
+        }
+    }
+
+    pub fn body_key(self) -> &'static str {
+        match self {
+            Self::Quit => "ui.confirm.quit.body",
+            Self::Load => "ui.confirm.load.body",
+            Self::Save => "ui.confirm.save.body",
+            Self::Restart => "ui.confirm.restart.body",
+            Self::Resume => "ui.confirm.resume.body",
+        }
+    }
+}
+
+/// What a mouse click landed on: a footer menu option by its cursor index, a
+/// map tile, or a panel tab in the compact layout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PointerTarget {
+    MenuOption(usize),
+    Tile(Position),
+    Tab(PanelTab),
+}
+
+/// When the game writes to the autosave slot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AutosaveOption {
+    EnterTown,
+    AfterBattle,
+    EveryMoves,
+}
+
+impl AutosaveOption {
+    pub const ALL: [Self; 3] = [Self::EnterTown, Self::AfterBattle, Self::EveryMoves];
+
+    pub fn label_key(self) -> &'static str {
+        match self {
+            Self::EnterTown => "ui.settings.autosave.town",
+            Self::AfterBattle => "ui.settings.autosave.battle",
+            Self::EveryMoves => "ui.settings.autosave.moves",
+        }
+    }
+}
+
+/// A color theme from the Settings screen; `Auto` picks one by what the
+/// terminal supports.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemeId {
+    #[default]
+    Auto,
+    Default,
+    HighContrast,
+    Deuteranopia,
+    Ansi16,
+    Monochrome,
+}
+
+impl ThemeId {
+    pub const ALL: [Self; 6] = [
+        Self::Auto,
+        Self::Default,
+        Self::HighContrast,
+        Self::Deuteranopia,
+        Self::Ansi16,
+        Self::Monochrome,
+    ];
+
+    pub fn label_key(self) -> &'static str {
+        match self {
+            Self::Auto => "ui.settings.theme.auto",
+            Self::Default => "ui.settings.theme.default",
+            Self::HighContrast => "ui.settings.theme.high_contrast",
+            Self::Deuteranopia => "ui.settings.theme.deuteranopia",
+            Self::Ansi16 => "ui.settings.theme.ansi16",
+            Self::Monochrome => "ui.settings.theme.monochrome",
+        }
+    }
+}
+
+/// The characters the map is drawn with; the emoji set takes two terminal
+/// cells per tile.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GlyphSetId {
+    #[default]
+    Ascii,
+    Unicode,
+    Emoji,
+}
+
+impl GlyphSetId {
+    pub const ALL: [Self; 3] = [Self::Ascii, Self::Unicode, Self::Emoji];
+
+    pub fn label_key(self) -> &'static str {
+        match self {
+            Self::Ascii => "ui.settings.glyphs.ascii",
+            Self::Unicode => "ui.settings.glyphs.unicode",
+            Self::Emoji => "ui.settings.glyphs.emoji",
+        }
+    }
+}
+
+/// A slider of the custom difficulty editor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProfileField {
+    EncounterRate,
+    EventRate,
+    EnemyHp,
+    EnemyAtk,
+    EnemyDef,
+    EnemyReward,
+    SkillRate,
+    RunBonus,
+}
+
+impl ProfileField {
+    pub const ALL: [Self; 8] = [
+        Self::EncounterRate,
+        Self::EventRate,
+        Self::EnemyHp,
+        Self::EnemyAtk,
+        Self::EnemyDef,
+        Self::EnemyReward,
+        Self::SkillRate,
+        Self::RunBonus,
+    ];
+
+    pub fn label_key(self) -> &'static str {
+        match self {
+            Self::EncounterRate => "ui.settings.field.encounter_rate",
+            Self::EventRate => "ui.settings.field.event_rate",
+            Self::EnemyHp => "ui.settings.field.enemy_hp",
+            Self::EnemyAtk => "ui.settings.field.enemy_atk",
+            Self::EnemyDef => "ui.settings.field.enemy_def",
+            Self::EnemyReward => "ui.settings.field.enemy_reward",
+            Self::SkillRate => "ui.settings.field.skill_rate",
+            Self::RunBonus => "ui.settings.field.run_bonus",
+        }
+    }
+
+    /// Whether the field is a stat multiplier rather than a percentage.
+    pub fn is_scale(self) -> bool {
+        matches!(
+            self,
+            Self::EnemyHp | Self::EnemyAtk | Self::EnemyDef | Self::EnemyReward
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EncounterMode {
+    #[default]
+    Random,
+    Roaming,
+}
+
+impl EncounterMode {
+    pub fn label_key(self) -> &'static str {
+        match self {
+            Self::Random => "ui.settings.encounter_random",
+            Self::Roaming => "ui.settings.encounter_roaming",
+        }
+    }
+
+    pub fn toggled(self) -> Self {
+        match self {
+            Self::Random => Self::Roaming,
+            Self::Roaming => Self::Random,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum EnemyStyle {
     Skirmisher,
     Brute,
     Caster,
     Predator,
     Undead,
+    Warlord,
+    Golem,
+    Lich,
     Boss,
 }
 
+/// Turn-by-turn behaviour of a regular enemy; see `enemy_ai`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AiKind {
+    #[default]
+    Aggressive,
+    CautiousHealer,
+    Coward,
+    ManaBurner,
+    Guardian,
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Enemy {
     pub name: String,
+    /// Locale key of the plain enemy this one was made from, so the records
+    /// can name it in whatever language is active when they are read.
+    #[serde(default)]
+    pub name_key: String,
     pub hp: i32,
     pub max_hp: i32,
     pub atk: i32,
@@ -145,12 +563,107 @@ pub struct Enemy {
     pub gold_reward: i32,
     pub is_boss: bool,
     pub style: EnemyStyle,
+    #[serde(default)]
+    pub ai: AiKind,
+    /// Material this enemy may drop when defeated.
+    #[serde(default)]
+    pub loot: Option<Material>,
+    /// Picture drawn in the battle scene, a key of `config/enemy_art.toml`;
+    /// empty for foes without one of their own.
+    #[serde(default)]
+    pub art: String,
+}
+
+impl Enemy {
+    /// What the run's records file this enemy under: its locale key, or its
+    /// name for foes without one, such as those of older saves.
+    pub fn record_key(&self) -> &str {
+        if self.name_key.is_empty() {
+            &self.name
+        } else {
+            &self.name_key
+        }
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Battle {
     pub enemy: Enemy,
     pub defending: bool,
+    #[serde(default)]
+    pub script: BossScriptState,
+    /// Minions summoned by a boss. Plain attacks hit them before the boss.
+    #[serde(default)]
+    pub adds: Vec<Enemy>,
+    /// Set by a guarding enemy; halves the player's next hit on it.
+    #[serde(default)]
+    pub enemy_guarding: bool,
+    /// Party slots of companions bracing this round.
+    #[serde(default)]
+    pub companions_defending: Vec<usize>,
+    /// Damage the party has dealt so far in this battle.
+    #[serde(default)]
+    pub damage_dealt: i32,
+    /// Damage the hero has taken so far in this battle.
+    #[serde(default)]
+    pub damage_taken: i32,
+}
+
+impl Battle {
+    pub fn new(enemy: Enemy) -> Self {
+        Self {
+            enemy,
+            defending: false,
+            script: BossScriptState::default(),
+            adds: Vec::new(),
+            enemy_guarding: false,
+            companions_defending: Vec::new(),
+            damage_dealt: 0,
+            damage_taken: 0,
+        }
+    }
+
+    /// Remaining HP across the enemy and its adds.
+    pub fn foe_hp(&self) -> i32 {
+        self.enemy.hp.max(0) + self.adds.iter().map(|add| add.hp.max(0)).sum::<i32>()
+    }
+}
+
+/// Which side of the battle scene an effect plays over.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BattleSide {
+    Hero,
+    Foes,
+}
+
+/// A damage number over one side of the battle scene, with the hit flash
+/// that goes with it. `frame` counts animation ticks since the hit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BattleEffect {
+    pub side: BattleSide,
+    pub amount: i32,
+    pub frame: u8,
+}
+
+impl BattleEffect {
+    /// Animation ticks an effect stays on screen.
+    pub const FRAMES: u8 = 6;
+    /// The hit side flashes for this many of them.
+    pub const FLASH_FRAMES: u8 = 2;
+
+    pub fn flashing(&self) -> bool {
+        self.frame < Self::FLASH_FRAMES
+    }
+}
+
+/// Progress through a boss behaviour script from `config/bosses.toml`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BossScriptState {
+    pub phase: usize,
+    pub turns: u32,
+    pub enraged: bool,
+    /// Telegraphed move released on the boss's next turn, as (phase, move).
+    pub charging: Option<(usize, usize)>,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -158,6 +671,8 @@ pub enum WeaponTier {
     WoodenSword,
     BronzeSword,
     KnightSword,
+    RuneBlade,
+    Dragonfang,
 }
 
 impl WeaponTier {
@@ -166,6 +681,8 @@ impl WeaponTier {
             Self::WoodenSword => "item.weapon.wooden_sword",
             Self::BronzeSword => "item.weapon.bronze_sword",
             Self::KnightSword => "item.weapon.knight_sword",
+            Self::RuneBlade => "item.weapon.rune_blade",
+            Self::Dragonfang => "item.weapon.dragonfang",
         }
     }
 
@@ -174,6 +691,8 @@ impl WeaponTier {
             Self::WoodenSword => 0,
             Self::BronzeSword => 3,
             Self::KnightSword => 7,
+            Self::RuneBlade => 12,
+            Self::Dragonfang => 18,
         }
     }
 
@@ -181,7 +700,9 @@ impl WeaponTier {
         match self {
             Self::WoodenSword => Some(Self::BronzeSword),
             Self::BronzeSword => Some(Self::KnightSword),
-            Self::KnightSword => None,
+            Self::KnightSword => Some(Self::RuneBlade),
+            Self::RuneBlade => Some(Self::Dragonfang),
+            Self::Dragonfang => None,
         }
     }
 
@@ -189,9 +710,20 @@ impl WeaponTier {
         match self.next() {
             Some(Self::BronzeSword) => Some(30),
             Some(Self::KnightSword) => Some(85),
+            Some(Self::RuneBlade) => Some(160),
+            Some(Self::Dragonfang) => Some(280),
             _ => None,
         }
     }
+
+    /// The New Game+ cycle in which the smith starts selling this tier.
+    pub fn ng_tier(self) -> u32 {
+        match self {
+            Self::WoodenSword | Self::BronzeSword | Self::KnightSword => 0,
+            Self::RuneBlade => 1,
+            Self::Dragonfang => 2,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -199,6 +731,8 @@ pub enum ArmorTier {
     ClothArmor,
     ChainArmor,
     SteelArmor,
+    MithrilMail,
+    DragonScale,
 }
 
 impl ArmorTier {
@@ -207,6 +741,8 @@ impl ArmorTier {
             Self::ClothArmor => "item.armor.cloth_armor",
             Self::ChainArmor => "item.armor.chain_armor",
             Self::SteelArmor => "item.armor.steel_armor",
+            Self::MithrilMail => "item.armor.mithril_mail",
+            Self::DragonScale => "item.armor.dragon_scale",
         }
     }
 
@@ -215,6 +751,8 @@ impl ArmorTier {
             Self::ClothArmor => 0,
             Self::ChainArmor => 2,
             Self::SteelArmor => 6,
+            Self::MithrilMail => 10,
+            Self::DragonScale => 15,
         }
     }
 
@@ -222,7 +760,9 @@ impl ArmorTier {
         match self {
             Self::ClothArmor => Some(Self::ChainArmor),
             Self::ChainArmor => Some(Self::SteelArmor),
-            Self::SteelArmor => None,
+            Self::SteelArmor => Some(Self::MithrilMail),
+            Self::MithrilMail => Some(Self::DragonScale),
+            Self::DragonScale => None,
         }
     }
 
@@ -230,12 +770,23 @@ impl ArmorTier {
         match self.next() {
             Some(Self::ChainArmor) => Some(26),
             Some(Self::SteelArmor) => Some(80),
+            Some(Self::MithrilMail) => Some(150),
+            Some(Self::DragonScale) => Some(260),
             _ => None,
         }
     }
+
+    /// The New Game+ cycle in which the smith starts selling this tier.
+    pub fn ng_tier(self) -> u32 {
+        match self {
+            Self::ClothArmor | Self::ChainArmor | Self::SteelArmor => 0,
+            Self::MithrilMail => 1,
+            Self::DragonScale => 2,
+        }
+    }
 }
 
-#[derive(Clone, Copy, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Equipment {
     pub weapon: WeaponTier,
     pub armor: ArmorTier,
@@ -245,6 +796,90 @@ pub struct Equipment {
 pub struct Bag {
     pub potion: i32,
     pub ether: i32,
+    #[serde(default)]
+    pub materials: Materials,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Material {
+    Herb,
+    Ore,
+    Hide,
+}
+
+impl Material {
+    pub const ALL: [Self; 3] = [Self::Herb, Self::Ore, Self::Hide];
+
+    pub fn name_key(self) -> &'static str {
+        match self {
+            Self::Herb => "material.herb",
+            Self::Ore => "material.ore",
+            Self::Hide => "material.hide",
+        }
+    }
+}
+
+/// A count of each crafting material, used both for the hero's stash and for
+/// recipe costs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Materials {
+    pub herb: i32,
+    pub ore: i32,
+    pub hide: i32,
+}
+
+impl Materials {
+    pub fn count(&self, material: Material) -> i32 {
+        match material {
+            Material::Herb => self.herb,
+            Material::Ore => self.ore,
+            Material::Hide => self.hide,
+        }
+    }
+
+    pub fn add(&mut self, material: Material, amount: i32) {
+        match material {
+            Material::Herb => self.herb += amount,
+            Material::Ore => self.ore += amount,
+            Material::Hide => self.hide += amount,
+        }
+    }
+
+    pub fn covers(&self, cost: &Materials) -> bool {
+        Material::ALL
+            .into_iter()
+            .all(|material| self.count(material) >= cost.count(material))
+    }
+
+    pub fn spend(&mut self, cost: &Materials) {
+        for material in Material::ALL {
+            self.add(material, -cost.count(material));
+        }
+    }
+}
+
+/// A workshop recipe loaded from `config/recipes.toml`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Recipe {
+    pub id: String,
+    /// Locale key of the recipe name.
+    pub name: String,
+    #[serde(default)]
+    pub gold: i32,
+    #[serde(default)]
+    pub cost: Materials,
+    pub output: RecipeOutput,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecipeOutput {
+    Potion(i32),
+    Ether(i32),
+    WeaponUpgrade,
+    ArmorUpgrade,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -263,19 +898,29 @@ pub struct Player {
     pub gold: i32,
     pub equipment: Equipment,
     pub bag: Bag,
+    #[serde(default)]
+    pub class: HeroClass,
+    /// Unspent points from level-ups, allocated on the level-up screen.
+    #[serde(default)]
+    pub stat_points: i32,
 }
 
 impl Player {
     pub fn new() -> Self {
+        Self::with_class(HeroClass::Warrior)
+    }
+
+    pub fn with_class(class: HeroClass) -> Self {
+        let template = class_template(class);
         Self {
             x: 1,
             y: 1,
-            hp: 40,
-            max_hp: 40,
-            mp: 12,
-            max_mp: 12,
-            base_atk: 10,
-            base_def: 4,
+            hp: template.hp,
+            max_hp: template.hp,
+            mp: template.mp,
+            max_mp: template.mp,
+            base_atk: template.atk,
+            base_def: template.def,
             level: 1,
             exp: 0,
             next_exp: 20,
@@ -287,10 +932,23 @@ impl Player {
             bag: Bag {
                 potion: 1,
                 ether: 1,
+                materials: Materials::default(),
             },
+            class,
+            stat_points: 0,
         }
     }
 
+    /// Class skills the hero has already learned, in menu order.
+    pub fn skills(&self) -> Vec<HeroSkill> {
+        self.class
+            .skills()
+            .iter()
+            .copied()
+            .filter(|skill| skill.unlock_level() <= self.level)
+            .collect()
+    }
+
     pub fn total_atk(&self) -> i32 {
         self.base_atk + self.equipment.weapon.bonus()
     }
@@ -306,12 +964,227 @@ impl Default for Player {
     }
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeroClass {
+    #[default]
+    Warrior,
+    Mage,
+    Rogue,
+}
+
+impl HeroClass {
+    pub const ALL: [Self; 3] = [Self::Warrior, Self::Mage, Self::Rogue];
+
+    pub fn label_key(self) -> &'static str {
+        match self {
+            Self::Warrior => "class.name.warrior",
+            Self::Mage => "class.name.mage",
+            Self::Rogue => "class.name.rogue",
+        }
+    }
+
+    pub fn desc_key(self) -> &'static str {
+        match self {
+            Self::Warrior => "class.desc.warrior",
+            Self::Mage => "class.desc.mage",
+            Self::Rogue => "class.desc.rogue",
+        }
+    }
+
+    pub fn skills(self) -> &'static [HeroSkill] {
+        match self {
+            Self::Warrior => &[HeroSkill::FireSlash, HeroSkill::PowerStrike],
+            Self::Mage => &[HeroSkill::Fireball, HeroSkill::Heal],
+            Self::Rogue => &[HeroSkill::Backstab, HeroSkill::Steal],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HeroSkill {
+    FireSlash,
+    PowerStrike,
+    Fireball,
+    Heal,
+    Backstab,
+    Steal,
+}
+
+impl HeroSkill {
+    pub fn name_key(self) -> &'static str {
+        match self {
+            Self::FireSlash => "skill.fire_slash",
+            Self::PowerStrike => "skill.power_strike",
+            Self::Fireball => "skill.fireball",
+            Self::Heal => "skill.heal",
+            Self::Backstab => "skill.backstab",
+            Self::Steal => "skill.steal",
+        }
+    }
+
+    pub fn mp_cost(self) -> i32 {
+        match self {
+            Self::FireSlash => 4,
+            Self::PowerStrike => 6,
+            Self::Fireball => 5,
+            Self::Heal => 6,
+            Self::Backstab => 3,
+            Self::Steal => 2,
+        }
+    }
+
+    pub fn unlock_level(self) -> i32 {
+        match self {
+            Self::FireSlash | Self::Fireball | Self::Backstab => 1,
+            Self::Heal | Self::Steal => 3,
+            Self::PowerStrike => 4,
+        }
+    }
+}
+
+/// A stat the player can put a level-up point into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StatKind {
+    Hp,
+    Mp,
+    Atk,
+    Def,
+}
+
+impl StatKind {
+    pub const ALL: [Self; 4] = [Self::Hp, Self::Mp, Self::Atk, Self::Def];
+
+    pub fn label_key(self) -> &'static str {
+        match self {
+            Self::Hp => "ui.stats.hp",
+            Self::Mp => "ui.stats.mp",
+            Self::Atk => "ui.stats.atk",
+            Self::Def => "ui.stats.def",
+        }
+    }
+}
+
+/// The hero plus up to three companions.
+pub const PARTY_MAX: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompanionKind {
+    Knight,
+    Cleric,
+    Mage,
+    Archer,
+}
+
+impl CompanionKind {
+    pub fn name_key(self) -> &'static str {
+        match self {
+            Self::Knight => "companion.knight",
+            Self::Cleric => "companion.cleric",
+            Self::Mage => "companion.mage",
+            Self::Archer => "companion.archer",
+        }
+    }
+
+    pub fn skill_key(self) -> &'static str {
+        match self {
+            Self::Knight => "companion.skill.shield_wall",
+            Self::Cleric => "companion.skill.heal",
+            Self::Mage => "companion.skill.firebolt",
+            Self::Archer => "companion.skill.double_shot",
+        }
+    }
+
+    pub fn skill_mp(self) -> i32 {
+        match self {
+            Self::Knight => 3,
+            Self::Cleric => 4,
+            Self::Mage => 5,
+            Self::Archer => 3,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Companion {
+    pub kind: CompanionKind,
+    pub hp: i32,
+    pub max_hp: i32,
+    pub mp: i32,
+    pub max_mp: i32,
+    pub base_atk: i32,
+    pub base_def: i32,
+    pub level: i32,
+    pub exp: i32,
+    pub next_exp: i32,
+    pub equipment: Equipment,
+}
+
+impl Companion {
+    pub fn total_atk(&self) -> i32 {
+        self.base_atk + self.equipment.weapon.bonus()
+    }
+
+    pub fn total_def(&self) -> i32 {
+        self.base_def + self.equipment.armor.bonus()
+    }
+
+    pub fn is_down(&self) -> bool {
+        self.hp <= 0
+    }
+
+    pub fn restore(&mut self) {
+        self.hp = self.max_hp;
+        self.mp = self.max_mp;
+    }
+}
+
+/// What a companion was told to do this round.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompanionOrder {
+    Attack,
+    Skill,
+    Defend,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Position {
     pub x: usize,
     pub y: usize,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Facing {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Facing {
+    pub const ALL: [Facing; 4] = [Facing::Up, Facing::Down, Facing::Left, Facing::Right];
+
+    pub fn delta(self) -> (i32, i32) {
+        match self {
+            Self::Up => (0, -1),
+            Self::Down => (0, 1),
+            Self::Left => (-1, 0),
+            Self::Right => (1, 0),
+        }
+    }
+
+    pub fn from_delta(dx: i32, dy: i32) -> Option<Self> {
+        match (dx.signum(), dy.signum()) {
+            (0, -1) => Some(Self::Up),
+            (0, 1) => Some(Self::Down),
+            (-1, 0) => Some(Self::Left),
+            (1, 0) => Some(Self::Right),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum NpcKind {
     Traveler,
@@ -320,11 +1193,131 @@ pub enum NpcKind {
 }
 
 impl NpcKind {
-    pub fn line_key(self) -> &'static str {
+    pub fn dialogue_id(self) -> &'static str {
+        match self {
+            Self::Traveler => "traveler",
+            Self::Scout => "scout",
+            Self::Sage => "sage",
+        }
+    }
+
+    /// Whether this NPC keeps to its spot during the given part of the day.
+    pub fn is_present(self, time: TimeOfDay) -> bool {
+        match self {
+            Self::Traveler => time != TimeOfDay::Night,
+            Self::Scout => matches!(time, TimeOfDay::Dawn | TimeOfDay::Day),
+            Self::Sage => time != TimeOfDay::Day,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeOfDay {
+    Dawn,
+    Day,
+    Dusk,
+    Night,
+}
+
+impl TimeOfDay {
+    pub fn label_key(self) -> &'static str {
         match self {
-            Self::Traveler => "log.world.npc_traveler",
-            Self::Scout => "log.world.npc_scout",
-            Self::Sage => "log.world.npc_sage",
+            Self::Dawn => "ui.time.dawn",
+            Self::Day => "ui.time.day",
+            Self::Dusk => "ui.time.dusk",
+            Self::Night => "ui.time.night",
+        }
+    }
+
+    pub fn is_night(self) -> bool {
+        self == Self::Night
+    }
+}
+
+/// Turn-driven world clock; every step on the overworld advances one turn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorldClock {
+    pub turn: u32,
+}
+
+impl WorldClock {
+    pub const TURNS_PER_DAY: u32 = 24 * TURNS_PER_HOUR;
+
+    pub fn at(day: u32, hour: u32) -> Self {
+        Self {
+            turn: (day.max(1) - 1) * Self::TURNS_PER_DAY + hour % 24 * TURNS_PER_HOUR,
+        }
+    }
+
+    pub fn day(self) -> u32 {
+        self.turn / Self::TURNS_PER_DAY + 1
+    }
+
+    pub fn hour(self) -> u32 {
+        self.turn % Self::TURNS_PER_DAY / TURNS_PER_HOUR
+    }
+
+    pub fn minute(self) -> u32 {
+        self.turn % TURNS_PER_HOUR * (60 / TURNS_PER_HOUR)
+    }
+
+    pub fn time_of_day(self) -> TimeOfDay {
+        match self.hour() {
+            h if h < DAWN_HOUR => TimeOfDay::Night,
+            h if h < DAY_HOUR => TimeOfDay::Dawn,
+            h if h < DUSK_HOUR => TimeOfDay::Day,
+            h if h < NIGHT_HOUR => TimeOfDay::Dusk,
+            _ => TimeOfDay::Night,
+        }
+    }
+
+    pub fn advance(&mut self, turns: u32) {
+        self.turn = self.turn.saturating_add(turns);
+    }
+
+    /// Skips ahead to the next dawn, e.g. after sleeping at the inn.
+    pub fn advance_to_morning(&mut self) {
+        let mut morning = Self::at(self.day(), DAWN_HOUR);
+        if morning.turn <= self.turn {
+            morning = Self::at(self.day() + 1, DAWN_HOUR);
+        }
+        *self = morning;
+    }
+}
+
+impl Default for WorldClock {
+    fn default() -> Self {
+        Self::at(1, CLOCK_START_HOUR)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShopStock {
+    pub potion: i32,
+    pub ether: i32,
+    pub restocked_day: u32,
+}
+
+impl ShopStock {
+    /// Refills the shelves once per day, starting at dawn. Returns whether a restock happened.
+    pub fn restock_if_due(&mut self, clock: WorldClock) -> bool {
+        if clock.day() <= self.restocked_day || clock.time_of_day().is_night() {
+            return false;
+        }
+        *self = Self {
+            restocked_day: clock.day(),
+            ..Self::default()
+        };
+        true
+    }
+}
+
+impl Default for ShopStock {
+    fn default() -> Self {
+        Self {
+            potion: SHOP_POTION_STOCK,
+            ether: SHOP_ETHER_STOCK,
+            restocked_day: 1,
         }
     }
 }
@@ -338,6 +1331,15 @@ pub struct Chest {
     pub ether: i32,
 }
 
+/// A herb patch or ore vein that can be gathered once by stepping on it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ResourceNode {
+    pub position: Position,
+    pub material: Material,
+    pub amount: i32,
+    pub gathered: bool,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct NpcPoint {
     pub position: Position,
@@ -346,11 +1348,348 @@ pub struct NpcPoint {
     pub reward_gold: i32,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DialogueState {
+    pub script: String,
+    pub node: String,
+    pub npc: Option<Position>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EventState {
+    pub event: String,
+    pub step: String,
+    #[serde(default)]
+    pub message: EventMessage,
+}
+
+/// What an event step told the hero, kept as its locale key and the numbers
+/// it was formatted with so it is translated when shown.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventMessage {
+    pub key: String,
+    pub gold: i32,
+    pub count: i32,
+    pub cost: i32,
+    pub dmg: i32,
+    pub before: i32,
+    pub after: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BossId {
+    GoblinWarlord,
+    StoneGolem,
+    Lich,
+    AncientDragon,
+}
+
+impl BossId {
+    /// Guardians whose seals must be broken before the final lair opens.
+    pub const SEAL_GUARDIANS: [BossId; 3] =
+        [BossId::GoblinWarlord, BossId::StoneGolem, BossId::Lich];
+
+    pub fn name_key(self) -> &'static str {
+        match self {
+            Self::GoblinWarlord => "enemy.goblin_warlord",
+            Self::StoneGolem => "enemy.stone_golem",
+            Self::Lich => "enemy.lich",
+            Self::AncientDragon => "enemy.ancient_dragon",
+        }
+    }
+
+    /// Dialogue script played as a story beat after this boss falls. The
+    /// dragon ends the run, so it goes straight to the victory screen.
+    pub fn story_script(self) -> Option<&'static str> {
+        match self {
+            Self::GoblinWarlord => Some("story_warlord"),
+            Self::StoneGolem => Some("story_golem"),
+            Self::Lich => Some("story_lich"),
+            Self::AncientDragon => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BossSite {
+    pub boss: BossId,
+    pub position: Position,
+    pub defeated: bool,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CampaignState {
+    pub bosses_defeated: Vec<BossId>,
+    pub enemies_defeated: u32,
+    pub gold_earned: i32,
+    #[serde(default)]
+    pub stats: RunStats,
+    /// Permadeath run: the save is deleted on defeat and shards are earned.
+    #[serde(default)]
+    pub roguelite: bool,
+    /// Legacy unlocks that were active when the run began.
+    #[serde(default)]
+    pub unlocks: BTreeSet<Unlock>,
+    /// Challenge rules chosen for this run.
+    #[serde(default)]
+    pub modifiers: BTreeSet<RunModifier>,
+    /// Days since the Unix epoch when this is a daily challenge run.
+    #[serde(default)]
+    pub daily: Option<u64>,
+    /// New Game+ cycle; 0 is the first playthrough.
+    #[serde(default)]
+    pub ng_plus: u32,
+}
+
+/// A challenge rule stacked on top of the difficulty profile for one run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunModifier {
+    NoShops,
+    DoubleEncounters,
+    GlassCannon,
+    IronMan,
+}
+
+impl RunModifier {
+    pub const ALL: [Self; 4] = [
+        Self::NoShops,
+        Self::DoubleEncounters,
+        Self::GlassCannon,
+        Self::IronMan,
+    ];
+
+    pub fn name_key(self) -> &'static str {
+        match self {
+            Self::NoShops => "modifier.no_shops.name",
+            Self::DoubleEncounters => "modifier.double_encounters.name",
+            Self::GlassCannon => "modifier.glass_cannon.name",
+            Self::IronMan => "modifier.iron_man.name",
+        }
+    }
+
+    pub fn desc_key(self) -> &'static str {
+        match self {
+            Self::NoShops => "modifier.no_shops.desc",
+            Self::DoubleEncounters => "modifier.double_encounters.desc",
+            Self::GlassCannon => "modifier.glass_cannon.desc",
+            Self::IronMan => "modifier.iron_man.desc",
+        }
+    }
+}
+
+/// Running tallies for the current run, shown on the result screens.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunStats {
+    pub turns: u32,
+    pub battles: u32,
+    /// Defeated enemies counted by [`Enemy::record_key`].
+    pub kills: BTreeMap<String, u32>,
+    pub damage_dealt: i32,
+    pub damage_taken: i32,
+    pub gold_spent: i32,
+    pub potions_used: u32,
+    pub items_crafted: u32,
+    /// Locale key of what felled the hero.
+    pub death_cause: Option<String>,
+}
+
+impl RunStats {
+    /// The most-defeated enemies first, ties broken by key.
+    pub fn top_kills(&self, limit: usize) -> Vec<(&str, u32)> {
+        let mut kills: Vec<(&str, u32)> = self
+            .kills
+            .iter()
+            .map(|(name, count)| (name.as_str(), *count))
+            .collect();
+        kills.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        kills.truncate(limit);
+        kills
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Achievement {
+    FirstBlood,
+    SealBreaker,
+    Dragonslayer,
+    FullParty,
+    Artisan,
+    Wealthy,
+    Veteran,
+}
+
+impl Achievement {
+    pub const ALL: [Self; 7] = [
+        Self::FirstBlood,
+        Self::SealBreaker,
+        Self::Dragonslayer,
+        Self::FullParty,
+        Self::Artisan,
+        Self::Wealthy,
+        Self::Veteran,
+    ];
+
+    pub fn name_key(self) -> &'static str {
+        match self {
+            Self::FirstBlood => "achievement.first_blood.name",
+            Self::SealBreaker => "achievement.seal_breaker.name",
+            Self::Dragonslayer => "achievement.dragonslayer.name",
+            Self::FullParty => "achievement.full_party.name",
+            Self::Artisan => "achievement.artisan.name",
+            Self::Wealthy => "achievement.wealthy.name",
+            Self::Veteran => "achievement.veteran.name",
+        }
+    }
+
+    pub fn desc_key(self) -> &'static str {
+        match self {
+            Self::FirstBlood => "achievement.first_blood.desc",
+            Self::SealBreaker => "achievement.seal_breaker.desc",
+            Self::Dragonslayer => "achievement.dragonslayer.desc",
+            Self::FullParty => "achievement.full_party.desc",
+            Self::Artisan => "achievement.artisan.desc",
+            Self::Wealthy => "achievement.wealthy.desc",
+            Self::Veteran => "achievement.veteran.desc",
+        }
+    }
+}
+
+/// One finished run on the hall of fame.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HallEntry {
+    pub seed: u64,
+    pub difficulty: Difficulty,
+    pub class: HeroClass,
+    pub level: i32,
+    pub victory: bool,
+    pub day: u32,
+    pub bosses: usize,
+    pub enemies: u32,
+    pub score: i32,
+    #[serde(default)]
+    pub modifiers: BTreeSet<RunModifier>,
+    #[serde(default)]
+    pub daily: Option<u64>,
+    #[serde(default)]
+    pub ng_plus: u32,
+}
+
+/// Achievements and the hall of fame, kept across runs in their own file.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Records {
+    pub achievements: BTreeSet<Achievement>,
+    pub hall_of_fame: Vec<HallEntry>,
+    pub legacy: Legacy,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnlockKind {
+    Perk,
+    Variant,
+    Modifier,
+}
+
+impl UnlockKind {
+    pub fn label_key(self) -> &'static str {
+        match self {
+            Self::Perk => "unlock.kind.perk",
+            Self::Variant => "unlock.kind.variant",
+            Self::Modifier => "unlock.kind.modifier",
+        }
+    }
+}
+
+/// Something bought with shards between roguelite runs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Unlock {
+    Provisions,
+    Inheritance,
+    Hardened,
+    EliteFoes,
+    Bloodlust,
+    Famine,
+}
+
+impl Unlock {
+    pub const ALL: [Self; 6] = [
+        Self::Provisions,
+        Self::Inheritance,
+        Self::Hardened,
+        Self::EliteFoes,
+        Self::Bloodlust,
+        Self::Famine,
+    ];
+
+    pub fn kind(self) -> UnlockKind {
+        match self {
+            Self::Provisions | Self::Inheritance | Self::Hardened => UnlockKind::Perk,
+            Self::EliteFoes => UnlockKind::Variant,
+            Self::Bloodlust | Self::Famine => UnlockKind::Modifier,
+        }
+    }
+
+    pub fn name_key(self) -> &'static str {
+        match self {
+            Self::Provisions => "unlock.provisions.name",
+            Self::Inheritance => "unlock.inheritance.name",
+            Self::Hardened => "unlock.hardened.name",
+            Self::EliteFoes => "unlock.elite_foes.name",
+            Self::Bloodlust => "unlock.bloodlust.name",
+            Self::Famine => "unlock.famine.name",
+        }
+    }
+
+    pub fn desc_key(self) -> &'static str {
+        match self {
+            Self::Provisions => "unlock.provisions.desc",
+            Self::Inheritance => "unlock.inheritance.desc",
+            Self::Hardened => "unlock.hardened.desc",
+            Self::EliteFoes => "unlock.elite_foes.desc",
+            Self::Bloodlust => "unlock.bloodlust.desc",
+            Self::Famine => "unlock.famine.desc",
+        }
+    }
+}
+
+/// Roguelite meta-progression: the shard purse, what has been bought and
+/// which purchases the next run starts with.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Legacy {
+    pub roguelite: bool,
+    pub shards: u32,
+    pub unlocked: BTreeSet<Unlock>,
+    pub active: BTreeSet<Unlock>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RoamingMonster {
+    pub id: u32,
+    pub position: Position,
+    pub facing: Facing,
+    pub template: usize,
+    pub hp_percent: i32,
+    pub stunned_turns: i32,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct WorldObjects {
     pub chests: Vec<Chest>,
     pub npcs: Vec<NpcPoint>,
     pub cleared_tiles: HashSet<Position>,
+    #[serde(default)]
+    pub monsters: Vec<RoamingMonster>,
+    #[serde(default)]
+    pub explored: HashSet<Position>,
+    #[serde(default)]
+    pub boss_sites: Vec<BossSite>,
+    #[serde(default)]
+    pub resource_nodes: Vec<ResourceNode>,
 }
 
 impl WorldObjects {
@@ -359,15 +1698,60 @@ impl WorldObjects {
             chests,
             npcs,
             cleared_tiles: HashSet::new(),
+            monsters: Vec::new(),
+            explored: HashSet::new(),
+            boss_sites: Vec::new(),
+            resource_nodes: Vec::new(),
         }
     }
 
+    pub fn boss_site_at(&self, x: usize, y: usize) -> Option<&BossSite> {
+        self.boss_sites
+            .iter()
+            .find(|site| !site.defeated && site.position.x == x && site.position.y == y)
+    }
+
+    pub fn seals_broken(&self) -> usize {
+        self.boss_sites.iter().filter(|site| site.defeated).count()
+    }
+
+    /// The final lair stays sealed while any guardian still stands.
+    pub fn lair_sealed(&self) -> bool {
+        self.boss_sites.iter().any(|site| !site.defeated)
+    }
+
+    pub fn monster_at(&self, x: usize, y: usize) -> Option<&RoamingMonster> {
+        self.monsters
+            .iter()
+            .find(|monster| monster.position.x == x && monster.position.y == y)
+    }
+
+    pub fn monster_mut(&mut self, id: u32) -> Option<&mut RoamingMonster> {
+        self.monsters.iter_mut().find(|monster| monster.id == id)
+    }
+
+    pub fn remove_monster(&mut self, id: u32) {
+        self.monsters.retain(|monster| monster.id != id);
+    }
+
     pub fn chest_at_mut(&mut self, x: usize, y: usize) -> Option<&mut Chest> {
         self.chests
             .iter_mut()
             .find(|chest| chest.position.x == x && chest.position.y == y)
     }
 
+    pub fn resource_node_at(&self, x: usize, y: usize) -> Option<&ResourceNode> {
+        self.resource_nodes
+            .iter()
+            .find(|node| !node.gathered && node.position.x == x && node.position.y == y)
+    }
+
+    pub fn resource_node_at_mut(&mut self, x: usize, y: usize) -> Option<&mut ResourceNode> {
+        self.resource_nodes
+            .iter_mut()
+            .find(|node| !node.gathered && node.position.x == x && node.position.y == y)
+    }
+
     pub fn npc_at_mut(&mut self, x: usize, y: usize) -> Option<&mut NpcPoint> {
         self.npcs
             .iter_mut()
@@ -388,10 +1772,32 @@ impl WorldObjects {
             .any(|chest| !chest.opened && chest.position.x == x && chest.position.y == y)
     }
 
-    pub fn has_active_npc(&self, x: usize, y: usize) -> bool {
+    pub fn npc_present(&self, x: usize, y: usize, time: TimeOfDay) -> bool {
         self.npcs
             .iter()
-            .any(|npc| !npc.interacted && npc.position.x == x && npc.position.y == y)
+            .any(|npc| npc.position.x == x && npc.position.y == y && npc.kind.is_present(time))
+    }
+
+    pub fn has_npc(&self, x: usize, y: usize) -> bool {
+        self.npcs
+            .iter()
+            .any(|npc| npc.position.x == x && npc.position.y == y)
+    }
+
+    pub fn is_explored(&self, x: usize, y: usize) -> bool {
+        self.explored.contains(&Position { x, y })
+    }
+
+    pub fn reveal_around(&mut self, center: Position, radius: usize) {
+        let min_x = center.x.saturating_sub(radius);
+        let min_y = center.y.saturating_sub(radius);
+        let max_x = (center.x + radius).min(MAP_W - 1);
+        let max_y = (center.y + radius).min(MAP_H - 1);
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                self.explored.insert(Position { x, y });
+            }
+        }
     }
 }
 
@@ -446,7 +1852,7 @@ pub const LOG_CAPACITY: usize = 10;
 
 #[cfg(test)]
 mod tests {
-    use super::{Difficulty, Language, QuestState};
+    use super::{Difficulty, Language, NpcKind, QuestState, ShopStock, TimeOfDay, WorldClock};
 
     #[test]
     fn locale_tag_mapping_supports_new_languages() {
@@ -473,4 +1879,49 @@ mod tests {
         assert!(quest.register_kill());
         assert!(quest.completed);
     }
+
+    #[test]
+    fn clock_cycles_through_day_and_night() {
+        let mut clock = WorldClock::at(1, 8);
+        assert_eq!(clock.time_of_day(), TimeOfDay::Day);
+        clock.advance(12 * (WorldClock::TURNS_PER_DAY / 24));
+        assert_eq!(clock.hour(), 20);
+        assert_eq!(clock.time_of_day(), TimeOfDay::Dusk);
+        clock.advance(WorldClock::TURNS_PER_DAY / 24 * 2 + 1);
+        assert_eq!(clock.time_of_day(), TimeOfDay::Night);
+        assert_eq!((clock.hour(), clock.minute()), (22, 15));
+
+        clock.advance_to_morning();
+        assert_eq!(clock.day(), 2);
+        assert_eq!(clock.time_of_day(), TimeOfDay::Dawn);
+        assert_eq!(clock.minute(), 0);
+    }
+
+    #[test]
+    fn shop_restocks_once_per_day_after_dawn() {
+        let mut shop = ShopStock {
+            potion: 0,
+            ..ShopStock::default()
+        };
+        assert!(!shop.restock_if_due(WorldClock::at(1, 12)));
+        assert!(!shop.restock_if_due(WorldClock::at(2, 1)));
+        assert!(shop.restock_if_due(WorldClock::at(2, 6)));
+        assert_eq!(
+            shop,
+            ShopStock {
+                restocked_day: 2,
+                ..ShopStock::default()
+            }
+        );
+        shop.potion = 0;
+        assert!(!shop.restock_if_due(WorldClock::at(2, 15)));
+    }
+
+    #[test]
+    fn npcs_keep_schedules() {
+        assert!(NpcKind::Scout.is_present(TimeOfDay::Day));
+        assert!(!NpcKind::Scout.is_present(TimeOfDay::Night));
+        assert!(NpcKind::Sage.is_present(TimeOfDay::Night));
+        assert!(!NpcKind::Sage.is_present(TimeOfDay::Day));
+    }
 }
@@ -0,0 +1,184 @@
+use rand::Rng;
+use rust_i18n::t;
+
+use crate::game::balance::{NEXT_EXP_BASE_INCREASE, NEXT_EXP_LEVEL_MULTIPLIER, companion_template};
+use crate::game::model::{ArmorTier, Companion, CompanionKind, Equipment, PARTY_MAX, WeaponTier};
+
+/// Enemies aim at the front of the formation more often. The hero always leads.
+const HERO_TARGET_WEIGHT: i32 = 4;
+
+pub fn new_companion(kind: CompanionKind, level: i32) -> Companion {
+    let template = companion_template(kind);
+    let scale = level.max(1) - 1;
+    let max_hp = template.hp + scale * template.hp_per_level;
+    let max_mp = template.mp + scale * template.mp_per_level;
+    Companion {
+        kind,
+        hp: max_hp,
+        max_hp,
+        mp: max_mp,
+        max_mp,
+        base_atk: template.atk + scale * template.atk_per_level,
+        base_def: template.def + scale * template.def_per_level,
+        level: level.max(1),
+        exp: 0,
+        next_exp: next_exp_for(level.max(1)),
+        equipment: Equipment {
+            weapon: WeaponTier::WoodenSword,
+            armor: ArmorTier::ClothArmor,
+        },
+    }
+}
+
+/// Adds a companion at the hero's level, or explains why it cannot join.
+pub fn recruit(
+    party: &mut Vec<Companion>,
+    kind: CompanionKind,
+    level: i32,
+) -> Result<String, String> {
+    if party.len() + 1 >= PARTY_MAX {
+        return Err(t!("log.party.full").to_string());
+    }
+    if party.iter().any(|member| member.kind == kind) {
+        return Err(t!("log.party.already_joined", name = t!(kind.name_key())).to_string());
+    }
+    party.push(new_companion(kind, level));
+    Ok(t!("log.party.joined", name = t!(kind.name_key())).to_string())
+}
+
+pub fn has_room(party: &[Companion]) -> bool {
+    party.len() + 1 < PARTY_MAX
+}
+
+pub fn restore_all(party: &mut [Companion]) {
+    for member in party {
+        member.restore();
+    }
+}
+
+/// Swaps a companion with its neighbour in the formation; returns its new slot.
+pub fn move_member(party: &mut [Companion], slot: usize, forward: bool) -> usize {
+    if forward && slot > 0 && slot < party.len() {
+        party.swap(slot, slot - 1);
+        slot - 1
+    } else if !forward && slot + 1 < party.len() {
+        party.swap(slot, slot + 1);
+        slot + 1
+    } else {
+        slot
+    }
+}
+
+pub fn gain_exp(member: &mut Companion, exp: i32) -> Vec<String> {
+    let template = companion_template(member.kind);
+    let mut logs = Vec::new();
+    member.exp += exp;
+    while member.exp >= member.next_exp {
+        member.exp -= member.next_exp;
+        member.level += 1;
+        member.next_exp = next_exp_for(member.level);
+        member.max_hp += template.hp_per_level;
+        member.max_mp += template.mp_per_level;
+        member.base_atk += template.atk_per_level;
+        member.base_def += template.def_per_level;
+        member.restore();
+        logs.push(
+            t!(
+                "log.party.level_up",
+                name = t!(member.kind.name_key()),
+                level = member.level
+            )
+            .to_string(),
+        );
+    }
+    logs
+}
+
+/// Picks who an enemy's plain attack lands on: `None` for the hero, or a party slot.
+pub fn pick_target(party: &[Companion], rng: &mut impl Rng) -> Option<usize> {
+    if party.iter().all(Companion::is_down) {
+        return None;
+    }
+    let weights: Vec<(Option<usize>, i32)> = std::iter::once((None, HERO_TARGET_WEIGHT))
+        .chain(
+            party
+                .iter()
+                .enumerate()
+                .filter(|(_, member)| !member.is_down())
+                .map(|(slot, _)| (Some(slot), (HERO_TARGET_WEIGHT - 1 - slot as i32).max(1))),
+        )
+        .collect();
+    let total: i32 = weights.iter().map(|(_, weight)| weight).sum();
+    let mut roll = rng.random_range(0..total);
+    for (target, weight) in weights {
+        if roll < weight {
+            return target;
+        }
+        roll -= weight;
+    }
+    None
+}
+
+fn next_exp_for(level: i32) -> i32 {
+    (1..level).fold(20, |next, lv| {
+        next + NEXT_EXP_BASE_INCREASE + (lv + 1) * NEXT_EXP_LEVEL_MULTIPLIER
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use super::{gain_exp, move_member, new_companion, pick_target, recruit};
+    use crate::game::model::CompanionKind;
+
+    #[test]
+    fn recruiting_respects_party_size_and_duplicates() {
+        rust_i18n::set_locale("en");
+        let mut party = Vec::new();
+        assert!(recruit(&mut party, CompanionKind::Knight, 1).is_ok());
+        assert!(recruit(&mut party, CompanionKind::Knight, 1).is_err());
+        assert!(recruit(&mut party, CompanionKind::Cleric, 1).is_ok());
+        assert!(recruit(&mut party, CompanionKind::Mage, 1).is_ok());
+        assert!(recruit(&mut party, CompanionKind::Archer, 1).is_err());
+        assert_eq!(party.len(), 3);
+    }
+
+    #[test]
+    fn companions_scale_with_join_level_and_grow_on_level_up() {
+        rust_i18n::set_locale("en");
+        let rookie = new_companion(CompanionKind::Mage, 1);
+        let veteran = new_companion(CompanionKind::Mage, 4);
+        assert!(veteran.max_hp > rookie.max_hp);
+        assert!(veteran.next_exp > rookie.next_exp);
+
+        let mut member = rookie.clone();
+        member.hp = 1;
+        let needed = member.next_exp;
+        let logs = gain_exp(&mut member, needed);
+        assert_eq!(member.level, 2);
+        assert_eq!(member.hp, member.max_hp);
+        assert_eq!(logs.len(), 1);
+    }
+
+    #[test]
+    fn formation_moves_stay_in_bounds() {
+        let mut party = vec![
+            new_companion(CompanionKind::Knight, 1),
+            new_companion(CompanionKind::Cleric, 1),
+        ];
+        assert_eq!(move_member(&mut party, 0, true), 0);
+        assert_eq!(move_member(&mut party, 0, false), 1);
+        assert_eq!(party[0].kind, CompanionKind::Cleric);
+        assert_eq!(move_member(&mut party, 1, false), 1);
+    }
+
+    #[test]
+    fn downed_companions_are_never_targeted() {
+        let mut party = vec![new_companion(CompanionKind::Knight, 1)];
+        party[0].hp = 0;
+        let mut rng = StdRng::seed_from_u64(1);
+        assert!((0..50).all(|_| pick_target(&party, &mut rng).is_none()));
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::balance::AUTOSAVE_MOVE_CHOICES;
+use crate::game::config::{self, DifficultyProfile};
+use crate::game::keymap::{DEFAULT_PRESET, Keymap};
+use crate::game::model::{Command, ConfirmAction, Difficulty, GlyphSetId, ThemeId};
+use crate::game::save;
+
+/// Player preferences kept across runs.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Preferences {
+    #[serde(default = "default_custom_difficulty")]
+    pub custom_difficulty: DifficultyProfile,
+    #[serde(default)]
+    pub keymap: KeymapPrefs,
+    /// Confirmation prompts the player chose not to see again.
+    #[serde(default)]
+    pub skip_confirm: BTreeSet<ConfirmAction>,
+    #[serde(default)]
+    pub autosave: AutosavePrefs,
+    #[serde(default)]
+    pub theme: ThemeId,
+    #[serde(default)]
+    pub glyphs: GlyphSetId,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            custom_difficulty: default_custom_difficulty(),
+            keymap: KeymapPrefs::default(),
+            skip_confirm: BTreeSet::new(),
+            autosave: AutosavePrefs::default(),
+            theme: ThemeId::Auto,
+            glyphs: GlyphSetId::Ascii,
+        }
+    }
+}
+
+/// Which moments write the autosave slot; `every_moves` of 0 turns the
+/// step counter off.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutosavePrefs {
+    pub enter_town: bool,
+    pub after_battle: bool,
+    pub every_moves: u32,
+}
+
+impl Default for AutosavePrefs {
+    fn default() -> Self {
+        Self {
+            enter_town: true,
+            after_battle: true,
+            every_moves: AUTOSAVE_MOVE_CHOICES[2],
+        }
+    }
+}
+
+/// The chosen keymap preset and the commands the player rebound on top of it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeymapPrefs {
+    pub preset: String,
+    pub overrides: BTreeMap<Command, Vec<String>>,
+}
+
+impl Default for KeymapPrefs {
+    fn default() -> Self {
+        Self {
+            preset: DEFAULT_PRESET.to_string(),
+            overrides: BTreeMap::new(),
+        }
+    }
+}
+
+impl KeymapPrefs {
+    pub fn keymap(&self) -> Result<Keymap, String> {
+        Keymap::build(&self.preset, &self.overrides)
+    }
+}
+
+pub fn default_custom_difficulty() -> DifficultyProfile {
+    config::profile_for(Difficulty::Custom).within_sliders()
+}
+
+pub fn default_path() -> String {
+    std::env::var("RPG_PREFS_PATH").unwrap_or_else(|_| "preferences.json".to_string())
+}
+
+/// Loads the preferences file; a missing file means defaults, while a custom
+/// difficulty outside the slider bounds or a broken keymap is an error.
+pub fn load_from_path(path: &str) -> Result<Preferences, String> {
+    if !Path::new(path).exists() {
+        return Ok(Preferences::default());
+    }
+    let content = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let prefs: Preferences = serde_json::from_str(&content).map_err(|err| err.to_string())?;
+    prefs.custom_difficulty.validate_custom()?;
+    prefs.keymap.keymap()?;
+    Ok(prefs)
+}
+
+pub fn save_to_path(prefs: &Preferences, path: &str) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(prefs).map_err(|err| err.to_string())?;
+    save::write_atomically(path, &content)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{Preferences, load_from_path, save_to_path};
+    use crate::game::model::{Command, ProfileField};
+
+    fn temp_prefs_path() -> PathBuf {
+        let mut path = std::env::temp_dir();
+        path.push(format!("rpg_tui_prefs_{}.json", rand::random::<u64>()));
+        path
+    }
+
+    #[test]
+    fn preferences_round_trip_and_reject_out_of_range_values() {
+        let path = temp_prefs_path();
+        let path = path.to_string_lossy();
+        assert_eq!(load_from_path(&path), Ok(Preferences::default()));
+
+        let mut prefs = Preferences::default();
+        prefs.custom_difficulty.nudge(ProfileField::SkillRate, 5);
+        save_to_path(&prefs, &path).expect("save should succeed");
+        assert_eq!(load_from_path(&path), Ok(prefs.clone()));
+
+        prefs.custom_difficulty.world_event_rate_percent = 400;
+        save_to_path(&prefs, &path).expect("save should succeed");
+        let error = load_from_path(&path).unwrap_err();
+        assert!(error.contains("world_event_rate_percent"), "{error}");
+
+        let mut prefs = Preferences::default();
+        prefs.keymap.preset = "vi".to_string();
+        prefs
+            .keymap
+            .overrides
+            .insert(Command::Party, vec!["j".to_string()]);
+        save_to_path(&prefs, &path).expect("save should succeed");
+        let error = load_from_path(&path).unwrap_err();
+        assert!(error.contains("Party"), "{error}");
+
+        let _ = std::fs::remove_file(path.as_ref());
+    }
+}
@@ -1,48 +1,131 @@
 use crate::game::balance::{
-    LEVEL_UP_ATK_INCREASE, LEVEL_UP_DEF_INCREASE, LEVEL_UP_HP_INCREASE, LEVEL_UP_MP_INCREASE,
-    NEXT_EXP_BASE_INCREASE, NEXT_EXP_LEVEL_MULTIPLIER,
+    NEXT_EXP_BASE_INCREASE, NEXT_EXP_LEVEL_MULTIPLIER, STAT_POINT_ATK, STAT_POINT_DEF,
+    STAT_POINT_HP, STAT_POINT_MP, STAT_POINTS_PER_LEVEL, class_template,
 };
-use crate::game::model::{Enemy, Player};
+use crate::game::model::{Companion, Enemy, Player, StatKind};
+use crate::game::party;
 use rust_i18n::t;
 
-pub fn apply_battle_rewards(player: &mut Player, enemy: &Enemy) -> Vec<String> {
+/// Gold goes to the shared purse; EXP is split evenly between the hero and
+/// every companion still standing.
+pub fn apply_battle_rewards(
+    player: &mut Player,
+    companions: &mut [Companion],
+    enemy: &Enemy,
+) -> Vec<String> {
     let mut logs = Vec::new();
-    player.exp += enemy.exp_reward;
+    let members = 1 + companions.iter().filter(|m| !m.is_down()).count() as i32;
+    let share = if members > 1 {
+        (enemy.exp_reward / members).max(1)
+    } else {
+        enemy.exp_reward
+    };
+    player.exp += share;
     player.gold += enemy.gold_reward;
+    let key = if members > 1 {
+        "log.progression.defeated_reward_split"
+    } else {
+        "log.progression.defeated_reward"
+    };
     logs.push(
         t!(
-            "log.progression.defeated_reward",
+            key,
             enemy = enemy.name.as_str(),
-            exp = enemy.exp_reward,
+            exp = share,
             gold = enemy.gold_reward
         )
         .to_string(),
     );
+    for member in companions.iter_mut().filter(|m| !m.is_down()) {
+        logs.extend(party::gain_exp(member, share));
+    }
 
+    let growth = class_template(player.class);
+    let mut leveled = false;
     while player.exp >= player.next_exp {
         player.exp -= player.next_exp;
         player.level += 1;
         player.next_exp += NEXT_EXP_BASE_INCREASE + player.level * NEXT_EXP_LEVEL_MULTIPLIER;
-        player.max_hp += LEVEL_UP_HP_INCREASE;
-        player.max_mp += LEVEL_UP_MP_INCREASE;
-        player.base_atk += LEVEL_UP_ATK_INCREASE;
-        player.base_def += LEVEL_UP_DEF_INCREASE;
+        player.max_hp += growth.hp_growth;
+        player.max_mp += growth.mp_growth;
+        player.base_atk += growth.atk_growth;
+        player.base_def += growth.def_growth;
+        player.stat_points += STAT_POINTS_PER_LEVEL;
         player.hp = player.max_hp;
         player.mp = player.max_mp;
         logs.push(t!("log.progression.level_up", level = player.level).to_string());
+        for skill in player.class.skills() {
+            if skill.unlock_level() == player.level {
+                logs.push(
+                    t!(
+                        "log.progression.skill_learned",
+                        skill = t!(skill.name_key())
+                    )
+                    .to_string(),
+                );
+            }
+        }
+        leveled = true;
+    }
+    if leveled {
+        logs.push(t!("log.progression.stat_points", points = player.stat_points).to_string());
     }
 
     logs
 }
 
+/// How much one point raises `stat`.
+pub fn stat_point_gain(stat: StatKind) -> i32 {
+    match stat {
+        StatKind::Hp => STAT_POINT_HP,
+        StatKind::Mp => STAT_POINT_MP,
+        StatKind::Atk => STAT_POINT_ATK,
+        StatKind::Def => STAT_POINT_DEF,
+    }
+}
+
+/// Spends one unallocated point on `stat`; `None` when no points are left.
+pub fn allocate_point(player: &mut Player, stat: StatKind) -> Option<String> {
+    if player.stat_points <= 0 {
+        return None;
+    }
+    player.stat_points -= 1;
+    let gain = stat_point_gain(stat);
+    match stat {
+        StatKind::Hp => {
+            player.max_hp += gain;
+            player.hp += gain;
+        }
+        StatKind::Mp => {
+            player.max_mp += gain;
+            player.mp += gain;
+        }
+        StatKind::Atk => player.base_atk += gain,
+        StatKind::Def => player.base_def += gain,
+    }
+    Some(
+        t!(
+            "log.progression.point_spent",
+            stat = t!(stat.label_key()),
+            gain = gain,
+            points = player.stat_points
+        )
+        .to_string(),
+    )
+}
+
 #[cfg(test)]
 mod tests {
-    use super::apply_battle_rewards;
-    use crate::game::model::{Enemy, EnemyStyle, Player};
+    use super::{allocate_point, apply_battle_rewards};
+    use crate::game::model::{
+        AiKind, CompanionKind, Enemy, EnemyStyle, HeroClass, HeroSkill, Player, StatKind,
+    };
+    use crate::game::party::new_companion;
 
     fn enemy(exp_reward: i32, gold_reward: i32) -> Enemy {
         Enemy {
             name: "Test".to_string(),
+            name_key: String::new(),
             hp: 1,
             max_hp: 1,
             atk: 1,
@@ -51,6 +134,9 @@ mod tests {
             gold_reward,
             is_boss: false,
             style: EnemyStyle::Skirmisher,
+            ai: AiKind::Aggressive,
+            loot: None,
+            art: String::new(),
         }
     }
 
@@ -61,7 +147,7 @@ mod tests {
         player.exp = 0;
         player.gold = 0;
 
-        let logs = apply_battle_rewards(&mut player, &enemy(5, 9));
+        let logs = apply_battle_rewards(&mut player, &mut [], &enemy(5, 9));
         assert!(!logs.is_empty());
         assert_eq!(player.gold, 9);
         assert_eq!(player.exp, 5);
@@ -76,9 +162,65 @@ mod tests {
         player.hp = 1;
         player.mp = 1;
 
-        let _ = apply_battle_rewards(&mut player, &enemy(5, 0));
+        let _ = apply_battle_rewards(&mut player, &mut [], &enemy(5, 0));
         assert_eq!(player.level, 2);
         assert_eq!(player.hp, player.max_hp);
         assert_eq!(player.mp, player.max_mp);
     }
+
+    #[test]
+    fn exp_is_split_between_standing_members() {
+        rust_i18n::set_locale("en");
+        let mut player = Player::new();
+        player.exp = 0;
+        let mut companions = vec![
+            new_companion(CompanionKind::Knight, 1),
+            new_companion(CompanionKind::Cleric, 1),
+        ];
+        companions[1].hp = 0;
+
+        let _ = apply_battle_rewards(&mut player, &mut companions, &enemy(10, 4));
+        assert_eq!(player.exp, 5);
+        assert_eq!(companions[0].exp, 5);
+        assert_eq!(companions[1].exp, 0);
+        assert_eq!(player.gold, 19);
+    }
+
+    #[test]
+    fn classes_grow_along_their_own_curves() {
+        rust_i18n::set_locale("en");
+        let mut warrior = Player::with_class(HeroClass::Warrior);
+        let mut mage = Player::with_class(HeroClass::Mage);
+        assert!(mage.max_mp > warrior.max_mp);
+        assert!(warrior.max_hp > mage.max_hp);
+
+        for player in [&mut warrior, &mut mage] {
+            player.exp = player.next_exp - 1;
+            let _ = apply_battle_rewards(player, &mut [], &enemy(1, 0));
+        }
+        assert_eq!(warrior.max_hp, 46);
+        assert_eq!(mage.max_mp, 26);
+        assert_eq!(warrior.stat_points, 2);
+    }
+
+    #[test]
+    fn stat_points_are_spent_one_at_a_time() {
+        rust_i18n::set_locale("en");
+        let mut player = Player::new();
+        player.stat_points = 1;
+        let atk = player.base_atk;
+
+        assert!(allocate_point(&mut player, StatKind::Atk).is_some());
+        assert_eq!(player.base_atk, atk + 1);
+        assert!(allocate_point(&mut player, StatKind::Hp).is_none());
+        assert_eq!(player.stat_points, 0);
+    }
+
+    #[test]
+    fn skills_unlock_with_level() {
+        let mut rogue = Player::with_class(HeroClass::Rogue);
+        assert_eq!(rogue.skills(), vec![HeroSkill::Backstab]);
+        rogue.level = 3;
+        assert_eq!(rogue.skills(), vec![HeroSkill::Backstab, HeroSkill::Steal]);
+    }
 }
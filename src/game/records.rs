@@ -0,0 +1,179 @@
+use std::fs;
+use std::path::Path;
+
+use crate::game::balance::{HALL_OF_FAME_SIZE, VETERAN_LEVEL, WEALTHY_GOLD};
+use crate::game::model::{
+    Achievement, BossId, CampaignState, Difficulty, HallEntry, PARTY_MAX, Player, Records,
+    WorldClock,
+};
+use crate::game::save;
+
+pub fn default_path() -> String {
+    std::env::var("RPG_RECORDS_PATH").unwrap_or_else(|_| "records.json".to_string())
+}
+
+/// Loads the records file; a missing file is a fresh start, not an error.
+pub fn load_from_path(path: &str) -> Result<Records, String> {
+    if !Path::new(path).exists() {
+        return Ok(Records::default());
+    }
+    let content = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    serde_json::from_str(&content).map_err(|err| err.to_string())
+}
+
+pub fn save_to_path(records: &Records, path: &str) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(records).map_err(|err| err.to_string())?;
+    save::write_atomically(path, &content)
+}
+
+/// Achievements whose condition the current run meets but the records lack.
+pub fn newly_earned(
+    records: &Records,
+    player: &Player,
+    campaign: &CampaignState,
+    party_size: usize,
+) -> Vec<Achievement> {
+    Achievement::ALL
+        .into_iter()
+        .filter(|achievement| !records.achievements.contains(achievement))
+        .filter(|achievement| match achievement {
+            Achievement::FirstBlood => campaign.enemies_defeated > 0,
+            Achievement::SealBreaker => campaign
+                .bosses_defeated
+                .iter()
+                .any(|boss| BossId::SEAL_GUARDIANS.contains(boss)),
+            Achievement::Dragonslayer => campaign.bosses_defeated.contains(&BossId::AncientDragon),
+            Achievement::FullParty => party_size + 1 >= PARTY_MAX,
+            Achievement::Artisan => campaign.stats.items_crafted > 0,
+            Achievement::Wealthy => player.gold >= WEALTHY_GOLD,
+            Achievement::Veteran => player.level >= VETERAN_LEVEL,
+        })
+        .collect()
+}
+
+/// Bosses weigh most, then level, kills and gold; a win adds a flat bonus.
+pub fn run_score(player: &Player, campaign: &CampaignState, victory: bool) -> i32 {
+    let bosses = campaign.bosses_defeated.len() as i32 * 250;
+    let kills = campaign.enemies_defeated as i32 * 10;
+    let bonus = if victory { 1000 } else { 0 };
+    bosses + player.level * 50 + kills + campaign.gold_earned + bonus
+}
+
+pub fn hall_entry(
+    seed: u64,
+    difficulty: Difficulty,
+    player: &Player,
+    campaign: &CampaignState,
+    clock: WorldClock,
+    victory: bool,
+) -> HallEntry {
+    HallEntry {
+        seed,
+        difficulty,
+        class: player.class,
+        level: player.level,
+        victory,
+        day: clock.day(),
+        bosses: campaign.bosses_defeated.len(),
+        enemies: campaign.enemies_defeated,
+        score: run_score(player, campaign, victory),
+        modifiers: campaign.modifiers.clone(),
+        daily: campaign.daily,
+        ng_plus: campaign.ng_plus,
+    }
+}
+
+/// Ranks the entry among past runs and returns its 1-based place, or `None`
+/// when it did not make the board.
+pub fn add_entry(records: &mut Records, entry: HallEntry) -> Option<usize> {
+    let place = records
+        .hall_of_fame
+        .iter()
+        .position(|other| entry.score > other.score)
+        .unwrap_or(records.hall_of_fame.len());
+    if place >= HALL_OF_FAME_SIZE {
+        return None;
+    }
+    records.hall_of_fame.insert(place, entry);
+    records.hall_of_fame.truncate(HALL_OF_FAME_SIZE);
+    Some(place + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{add_entry, hall_entry, load_from_path, newly_earned, save_to_path};
+    use crate::game::balance::HALL_OF_FAME_SIZE;
+    use crate::game::model::{
+        Achievement, BossId, CampaignState, Difficulty, Player, Records, WorldClock,
+    };
+
+    fn temp_records_path() -> PathBuf {
+        let mut path = std::env::temp_dir();
+        path.push(format!("rpg_tui_records_{}.json", rand::random::<u64>()));
+        path
+    }
+
+    fn entry_with_score(score: i32) -> crate::game::model::HallEntry {
+        let mut entry = hall_entry(
+            7,
+            Difficulty::Normal,
+            &Player::new(),
+            &CampaignState::default(),
+            WorldClock::default(),
+            false,
+        );
+        entry.score = score;
+        entry
+    }
+
+    #[test]
+    fn hall_of_fame_keeps_the_best_runs_in_order() {
+        let mut records = Records::default();
+        for score in 0..HALL_OF_FAME_SIZE as i32 {
+            add_entry(&mut records, entry_with_score(score * 10));
+        }
+        assert_eq!(add_entry(&mut records, entry_with_score(-5)), None);
+        assert_eq!(add_entry(&mut records, entry_with_score(55)), Some(5));
+        assert_eq!(records.hall_of_fame.len(), HALL_OF_FAME_SIZE);
+        assert_eq!(records.hall_of_fame[0].score, 90);
+        assert_eq!(records.hall_of_fame[4].score, 55);
+    }
+
+    #[test]
+    fn achievements_are_only_earned_once() {
+        let mut records = Records::default();
+        let player = Player::new();
+        let mut campaign = CampaignState::default();
+        assert!(newly_earned(&records, &player, &campaign, 0).is_empty());
+
+        campaign.enemies_defeated = 1;
+        campaign.bosses_defeated.push(BossId::Lich);
+        let earned = newly_earned(&records, &player, &campaign, 0);
+        assert_eq!(
+            earned,
+            vec![Achievement::FirstBlood, Achievement::SealBreaker]
+        );
+        records.achievements.extend(earned);
+        assert!(newly_earned(&records, &player, &campaign, 0).is_empty());
+    }
+
+    #[test]
+    fn records_round_trip_and_missing_file_starts_fresh() {
+        let path = temp_records_path();
+        let path = path.to_string_lossy();
+        let fresh = load_from_path(&path).expect("missing file is not an error");
+        assert!(fresh.hall_of_fame.is_empty());
+
+        let mut records = Records::default();
+        records.achievements.insert(Achievement::Artisan);
+        add_entry(&mut records, entry_with_score(300));
+        save_to_path(&records, &path).expect("save should succeed");
+        let loaded = load_from_path(&path).expect("load should succeed");
+        assert!(loaded.achievements.contains(&Achievement::Artisan));
+        assert_eq!(loaded.hall_of_fame, records.hall_of_fame);
+
+        let _ = std::fs::remove_file(path.as_ref());
+    }
+}
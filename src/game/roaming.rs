@@ -0,0 +1,267 @@
+use rand::Rng;
+
+use crate::game::balance::{ROAMING_CHASE_RANGE, ROAMING_FLEE_HP_PERCENT, ROAMING_IDLE_PERCENT};
+use crate::game::encounter::spawn_pool;
+use crate::game::model::{Facing, MAP_H, MAP_W, Position, RoamingMonster, Tile, WorldObjects};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MonsterIntent {
+    Wander,
+    Chase,
+    Flee,
+}
+
+pub fn intent_for(monster: &RoamingMonster, player: Position) -> MonsterIntent {
+    if distance(monster.position, player) > ROAMING_CHASE_RANGE {
+        return MonsterIntent::Wander;
+    }
+    if monster.hp_percent <= ROAMING_FLEE_HP_PERCENT {
+        MonsterIntent::Flee
+    } else {
+        MonsterIntent::Chase
+    }
+}
+
+/// A bump counts as "from behind" when the player moves in the direction the monster faces.
+pub fn is_rear_approach(monster: &RoamingMonster, dx: i32, dy: i32) -> bool {
+    Facing::from_delta(dx, dy) == Some(monster.facing)
+}
+
+/// Swaps who roams when night falls or lifts: unhurt monsters are drawn
+/// again from the pool for the new time, and nocturnal ones never outlast
+/// the dawn.
+pub fn change_shift(world: &mut WorldObjects, night: bool, rng: &mut impl Rng) {
+    let pool = spawn_pool(night);
+    for monster in &mut world.monsters {
+        if monster.hp_percent == 100 || !pool.contains(&monster.template) {
+            monster.template = pool[rng.random_range(0..pool.len())];
+        }
+    }
+}
+
+/// Moves every monster one step and returns the id of the first one that walks into the player.
+pub fn advance_monsters(
+    map: &[Vec<Tile>],
+    world: &mut WorldObjects,
+    player: Position,
+    rng: &mut impl Rng,
+) -> Option<u32> {
+    let mut contact = None;
+    for index in 0..world.monsters.len() {
+        let monster = world.monsters[index].clone();
+        if monster.stunned_turns > 0 {
+            world.monsters[index].stunned_turns -= 1;
+            continue;
+        }
+        let Some(facing) = choose_step(map, world, &monster, player, rng) else {
+            continue;
+        };
+        let Some(target) = step(monster.position, facing) else {
+            continue;
+        };
+
+        world.monsters[index].facing = facing;
+        if target == player {
+            contact.get_or_insert(monster.id);
+            continue;
+        }
+        world.monsters[index].position = target;
+    }
+    contact
+}
+
+fn choose_step(
+    map: &[Vec<Tile>],
+    world: &WorldObjects,
+    monster: &RoamingMonster,
+    player: Position,
+    rng: &mut impl Rng,
+) -> Option<Facing> {
+    let options: Vec<(Facing, Position)> = Facing::ALL
+        .into_iter()
+        .filter_map(|facing| {
+            let target = step(monster.position, facing)?;
+            can_enter(map, world, target, player).then_some((facing, target))
+        })
+        .collect();
+    if options.is_empty() {
+        return None;
+    }
+
+    match intent_for(monster, player) {
+        MonsterIntent::Wander => {
+            if rng.random_range(0..100) < ROAMING_IDLE_PERCENT {
+                return None;
+            }
+            let idx = rng.random_range(0..options.len());
+            Some(options[idx].0)
+        }
+        MonsterIntent::Chase => options
+            .into_iter()
+            .min_by_key(|(_, target)| distance(*target, player))
+            .map(|(facing, _)| facing),
+        MonsterIntent::Flee => options
+            .into_iter()
+            .filter(|(_, target)| *target != player)
+            .max_by_key(|(_, target)| distance(*target, player))
+            .map(|(facing, _)| facing),
+    }
+}
+
+fn can_enter(map: &[Vec<Tile>], world: &WorldObjects, target: Position, player: Position) -> bool {
+    if target == player {
+        return true;
+    }
+    map[target.y][target.x] == Tile::Floor
+        && world.monster_at(target.x, target.y).is_none()
+        && !world.has_unopened_chest(target.x, target.y)
+        && !world.has_npc(target.x, target.y)
+        && world.boss_site_at(target.x, target.y).is_none()
+}
+
+fn step(from: Position, facing: Facing) -> Option<Position> {
+    let (dx, dy) = facing.delta();
+    let x = from.x as i32 + dx;
+    let y = from.y as i32 + dy;
+    if x < 0 || y < 0 || x as usize >= MAP_W || y as usize >= MAP_H {
+        return None;
+    }
+    Some(Position {
+        x: x as usize,
+        y: y as usize,
+    })
+}
+
+fn distance(a: Position, b: Position) -> usize {
+    a.x.abs_diff(b.x) + a.y.abs_diff(b.y)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use super::{MonsterIntent, advance_monsters, change_shift, intent_for, is_rear_approach};
+    use crate::game::encounter::spawn_pool;
+    use crate::game::model::{Facing, MAP_H, MAP_W, Position, RoamingMonster, Tile, WorldObjects};
+
+    fn open_map() -> Vec<Vec<Tile>> {
+        vec![vec![Tile::Floor; MAP_W]; MAP_H]
+    }
+
+    fn monster_at(x: usize, y: usize) -> RoamingMonster {
+        RoamingMonster {
+            id: 1,
+            position: Position { x, y },
+            facing: Facing::Left,
+            template: 0,
+            hp_percent: 100,
+            stunned_turns: 0,
+        }
+    }
+
+    #[test]
+    fn night_brings_nocturnal_monsters_and_dawn_sends_them_home() {
+        let day = spawn_pool(false);
+        let nocturnal: Vec<usize> = spawn_pool(true)
+            .into_iter()
+            .filter(|template| !day.contains(template))
+            .collect();
+        let mut world = WorldObjects::new(Vec::new(), Vec::new());
+        world.monsters = (0..40).map(|x| monster_at(x, 1)).collect();
+        let mut rng = StdRng::seed_from_u64(6);
+
+        change_shift(&mut world, true, &mut rng);
+        assert!(
+            world
+                .monsters
+                .iter()
+                .any(|monster| nocturnal.contains(&monster.template))
+        );
+        world.monsters[0].template = nocturnal[0];
+        world.monsters[0].hp_percent = 30;
+        change_shift(&mut world, false, &mut rng);
+        assert!(
+            world
+                .monsters
+                .iter()
+                .all(|monster| day.contains(&monster.template))
+        );
+        assert_eq!(world.monsters[0].hp_percent, 30);
+    }
+
+    #[test]
+    fn intent_depends_on_range_and_health() {
+        let player = Position { x: 5, y: 5 };
+        assert_eq!(
+            intent_for(&monster_at(20, 5), player),
+            MonsterIntent::Wander
+        );
+        assert_eq!(intent_for(&monster_at(7, 6), player), MonsterIntent::Chase);
+
+        let mut wounded = monster_at(7, 6);
+        wounded.hp_percent = 20;
+        assert_eq!(intent_for(&wounded, player), MonsterIntent::Flee);
+    }
+
+    #[test]
+    fn chasing_monster_closes_in_and_reports_contact() {
+        let map = open_map();
+        let mut world = WorldObjects::new(Vec::new(), Vec::new());
+        world.monsters.push(monster_at(8, 5));
+        let player = Position { x: 5, y: 5 };
+        let mut rng = StdRng::seed_from_u64(4);
+
+        assert_eq!(advance_monsters(&map, &mut world, player, &mut rng), None);
+        assert_eq!(world.monsters[0].position, Position { x: 7, y: 5 });
+        assert_eq!(advance_monsters(&map, &mut world, player, &mut rng), None);
+        assert_eq!(
+            advance_monsters(&map, &mut world, player, &mut rng),
+            Some(1)
+        );
+        assert_eq!(world.monsters[0].position, Position { x: 6, y: 5 });
+    }
+
+    #[test]
+    fn fleeing_monster_moves_away_and_walls_block_movement() {
+        let mut map = open_map();
+        let mut world = WorldObjects::new(Vec::new(), Vec::new());
+        let mut wounded = monster_at(7, 5);
+        wounded.hp_percent = 10;
+        world.monsters.push(wounded);
+        let player = Position { x: 5, y: 5 };
+        let mut rng = StdRng::seed_from_u64(4);
+
+        advance_monsters(&map, &mut world, player, &mut rng);
+        assert_eq!(world.monsters[0].position, Position { x: 8, y: 5 });
+
+        for (x, y) in [(9, 5), (8, 4), (8, 6), (7, 5)] {
+            map[y][x] = Tile::Wall;
+        }
+        world.monsters[0].hp_percent = 100;
+        advance_monsters(&map, &mut world, player, &mut rng);
+        assert_eq!(world.monsters[0].position, Position { x: 8, y: 5 });
+    }
+
+    #[test]
+    fn stunned_monster_skips_its_turn() {
+        let map = open_map();
+        let mut world = WorldObjects::new(Vec::new(), Vec::new());
+        let mut stunned = monster_at(7, 5);
+        stunned.stunned_turns = 1;
+        world.monsters.push(stunned);
+        let mut rng = StdRng::seed_from_u64(4);
+
+        advance_monsters(&map, &mut world, Position { x: 5, y: 5 }, &mut rng);
+        assert_eq!(world.monsters[0].position, Position { x: 7, y: 5 });
+        assert_eq!(world.monsters[0].stunned_turns, 0);
+    }
+
+    #[test]
+    fn bumping_in_facing_direction_is_rear_approach() {
+        let monster = monster_at(5, 5);
+        assert!(is_rear_approach(&monster, -1, 0));
+        assert!(!is_rear_approach(&monster, 1, 0));
+        assert!(!is_rear_approach(&monster, 0, 1));
+    }
+}
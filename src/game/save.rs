@@ -1,9 +1,14 @@
+use std::collections::HashSet;
 use std::fs;
+use std::io::Write;
+use std::path::Path;
 
 use serde::{Deserialize, Serialize};
 
+use crate::game::config::DifficultyProfile;
 use crate::game::model::{
-    Battle, Difficulty, GameMode, Language, Player, Position, QuestState, Tile, WorldObjects,
+    Battle, BossId, CampaignState, Companion, DialogueState, Difficulty, EncounterMode, EventState,
+    GameMode, Language, Player, Position, QuestState, ShopStock, Tile, WorldClock, WorldObjects,
 };
 
 const SAVE_FILE_VERSION: u32 = 1;
@@ -18,6 +23,9 @@ pub struct SaveData {
     pub battle: Option<Battle>,
     pub current_language: Language,
     pub difficulty: Difficulty,
+    /// The profile of a custom-difficulty run, as it was when saved.
+    #[serde(default)]
+    pub custom_difficulty: Option<DifficultyProfile>,
     #[serde(default)]
     pub hero_scroll: usize,
     #[serde(default)]
@@ -29,9 +37,38 @@ pub struct SaveData {
     pub log: Vec<String>,
     pub recent_event: Option<String>,
     pub battle_origin: Option<Position>,
+    #[serde(default)]
+    pub encounter_mode: EncounterMode,
+    #[serde(default)]
+    pub battle_monster: Option<u32>,
+    #[serde(default)]
+    pub dialogue: Option<DialogueState>,
+    #[serde(default)]
+    pub dialogue_cursor: usize,
+    #[serde(default)]
+    pub story_flags: HashSet<String>,
+    #[serde(default)]
+    pub world_event: Option<EventState>,
+    #[serde(default)]
+    pub event_cursor: usize,
+    #[serde(default)]
+    pub event_origin: Option<Position>,
+    #[serde(default)]
+    pub clock: WorldClock,
+    #[serde(default)]
+    pub shop: ShopStock,
+    #[serde(default)]
+    pub campaign: CampaignState,
+    #[serde(default)]
+    pub battle_boss: Option<BossId>,
+    #[serde(default)]
+    pub party: Vec<Companion>,
     pub settings_cursor: usize,
     #[serde(default)]
     pub town_cursor: usize,
+    /// The Town screen is a travelling merchant's stall, not the town itself.
+    #[serde(default)]
+    pub peddler: bool,
     #[serde(default)]
     pub battle_cursor: usize,
 }
@@ -54,10 +91,66 @@ pub fn load_from_default_file() -> Result<(SaveData, String), String> {
     Ok((save, path))
 }
 
+pub fn default_file_exists() -> bool {
+    Path::new(&SaveData::path()).exists()
+}
+
+/// Removes the save file, returning its path when there was one to remove.
+pub fn delete_default_file() -> Result<Option<String>, String> {
+    delete_file(SaveData::path())
+}
+
+pub fn delete_file(path: String) -> Result<Option<String>, String> {
+    if !Path::new(&path).exists() {
+        return Ok(None);
+    }
+    fs::remove_file(&path).map_err(|err| err.to_string())?;
+    Ok(Some(path))
+}
+
+/// Writes `content` to a sibling temp file and renames it over `path`, so a
+/// crash mid-write leaves the old file in place rather than a truncated one.
+pub fn write_atomically(path: &str, content: &str) -> Result<(), String> {
+    let temp = format!("{path}.tmp");
+    let write = || -> std::io::Result<()> {
+        let mut file = fs::File::create(&temp)?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&temp, path)
+    };
+    write().map_err(|err| {
+        let _ = fs::remove_file(&temp);
+        err.to_string()
+    })
+}
+
+pub fn autosave_path() -> String {
+    std::env::var("RPG_AUTOSAVE_PATH").unwrap_or_else(|_| "autosave.json".to_string())
+}
+
+/// The marker that exists while a session using `autosave` is running.
+fn session_marker(autosave: &str) -> String {
+    format!("{autosave}.session")
+}
+
+/// Whether the last session writing to `autosave` ended without a clean
+/// quit and left an autosave behind.
+pub fn session_was_interrupted(autosave: &str) -> bool {
+    Path::new(&session_marker(autosave)).exists() && Path::new(autosave).exists()
+}
+
+pub fn open_session(autosave: &str) -> Result<(), String> {
+    fs::write(session_marker(autosave), std::process::id().to_string())
+        .map_err(|err| err.to_string())
+}
+
+pub fn close_session(autosave: &str) -> Result<(), String> {
+    delete_file(session_marker(autosave)).map(|_| ())
+}
+
 pub fn save_to_path(save: &SaveData, path: &str) -> Result<(), String> {
     let content = serde_json::to_string_pretty(save).map_err(|err| err.to_string())?;
-    fs::write(path, content).map_err(|err| err.to_string())?;
-    Ok(())
+    write_atomically(path, &content)
 }
 
 pub fn load_from_path(path: &str) -> Result<SaveData, String> {
@@ -69,17 +162,28 @@ pub fn load_from_path(path: &str) -> Result<SaveData, String> {
             save.version, SAVE_FILE_VERSION
         ));
     }
+    if let Some(profile) = &save.custom_difficulty {
+        profile.validate_custom()?;
+    }
     Ok(save)
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeSet;
     use std::path::PathBuf;
 
-    use super::{SaveData, load_from_path, save_to_path};
+    use super::{
+        SaveData, close_session, load_from_path, open_session, save_to_path,
+        session_was_interrupted, write_atomically,
+    };
     use crate::game::model::{
-        Difficulty, GameMode, Language, Player, Position, QuestState, WorldObjects,
+        BossId, CampaignState, CompanionKind, DialogueState, Difficulty, EncounterMode,
+        EventMessage, EventState, Facing, GameMode, HeroClass, Language, Material, Player,
+        Position, QuestState, ResourceNode, RoamingMonster, RunModifier, RunStats, ShopStock,
+        Unlock, WorldClock, WorldObjects,
     };
+    use crate::game::party::new_companion;
 
     fn temp_save_path() -> PathBuf {
         let mut path = std::env::temp_dir();
@@ -99,6 +203,24 @@ mod tests {
         player.x = 7;
         player.y = 9;
         player.gold = 123;
+        player.class = HeroClass::Rogue;
+        player.stat_points = 3;
+        player.bag.materials.hide = 4;
+        let mut world = WorldObjects::new(Vec::new(), Vec::new());
+        world.monsters.push(RoamingMonster {
+            id: 4,
+            position: Position { x: 2, y: 1 },
+            facing: Facing::Down,
+            template: 3,
+            hp_percent: 55,
+            stunned_turns: 2,
+        });
+        world.resource_nodes.push(ResourceNode {
+            position: Position { x: 3, y: 2 },
+            material: Material::Herb,
+            amount: 2,
+            gathered: true,
+        });
 
         let save = SaveData {
             version: 1,
@@ -109,27 +231,79 @@ mod tests {
             battle: None,
             current_language: Language::Ja,
             difficulty: Difficulty::Hard,
+            custom_difficulty: None,
             hero_scroll: 1,
             log_scroll: 2,
             controls_scroll: 3,
-            world: WorldObjects::new(Vec::new(), Vec::new()),
+            world,
             quest: QuestState::new(),
             log: vec!["a".to_string(), "b".to_string()],
             recent_event: Some("recent".to_string()),
             battle_origin: Some(Position { x: 7, y: 9 }),
+            encounter_mode: EncounterMode::Roaming,
+            battle_monster: Some(4),
+            dialogue: Some(DialogueState {
+                script: "sage".to_string(),
+                node: "wisdom".to_string(),
+                npc: Some(Position { x: 3, y: 4 }),
+            }),
+            dialogue_cursor: 1,
+            story_flags: ["lair_revealed".to_string()].into_iter().collect(),
+            world_event: Some(EventState {
+                event: "shrine".to_string(),
+                step: "found".to_string(),
+                message: EventMessage {
+                    key: "log.event.gold_cache".to_string(),
+                    gold: 12,
+                    ..EventMessage::default()
+                },
+            }),
+            event_cursor: 2,
+            event_origin: Some(Position { x: 5, y: 6 }),
+            clock: WorldClock::at(3, 23),
+            shop: ShopStock {
+                potion: 2,
+                ether: 0,
+                restocked_day: 3,
+            },
+            campaign: CampaignState {
+                bosses_defeated: vec![BossId::StoneGolem],
+                enemies_defeated: 12,
+                gold_earned: 340,
+                stats: RunStats {
+                    battles: 14,
+                    death_cause: Some("Wolf".to_string()),
+                    ..RunStats::default()
+                },
+                roguelite: true,
+                unlocks: BTreeSet::from([Unlock::Famine]),
+                modifiers: BTreeSet::from([RunModifier::GlassCannon]),
+                daily: Some(20_744),
+                ng_plus: 2,
+            },
+            battle_boss: Some(BossId::Lich),
+            party: vec![new_companion(CompanionKind::Cleric, 3)],
             settings_cursor: 3,
             town_cursor: 2,
+            peddler: false,
             battle_cursor: 4,
         };
 
         save_to_path(&save, path.to_string_lossy().as_ref()).expect("save should succeed");
         let loaded = load_from_path(path.to_string_lossy().as_ref()).expect("load should succeed");
 
+        assert_eq!(loaded.party.len(), 1);
+        assert_eq!(loaded.party[0].kind, CompanionKind::Cleric);
+        assert_eq!(loaded.party[0].level, 3);
         assert_eq!(loaded.mode, GameMode::Battle);
         assert_eq!(loaded.map_seed, 88);
         assert_eq!(loaded.player.x, 7);
         assert_eq!(loaded.player.y, 9);
         assert_eq!(loaded.player.gold, 123);
+        assert_eq!(loaded.player.class, HeroClass::Rogue);
+        assert_eq!(loaded.player.stat_points, 3);
+        assert_eq!(loaded.player.bag.materials.hide, 4);
+        assert!(loaded.world.resource_nodes[0].gathered);
         assert_eq!(loaded.current_language, Language::Ja);
         assert_eq!(loaded.difficulty, Difficulty::Hard);
         assert_eq!(loaded.hero_scroll, 1);
@@ -140,7 +314,80 @@ mod tests {
         assert_eq!(loaded.battle_cursor, 4);
         assert_eq!(loaded.recent_event.as_deref(), Some("recent"));
         assert_eq!(loaded.log.len(), 2);
+        assert_eq!(loaded.encounter_mode, EncounterMode::Roaming);
+        assert_eq!(loaded.battle_monster, Some(4));
+        assert_eq!(loaded.world.monsters.len(), 1);
+        assert_eq!(loaded.world.monsters[0].position, Position { x: 2, y: 1 });
+        assert_eq!(loaded.world.monsters[0].hp_percent, 55);
+        let dialogue = loaded.dialogue.expect("dialogue should round-trip");
+        assert_eq!(dialogue.script, "sage");
+        assert_eq!(dialogue.node, "wisdom");
+        assert_eq!(loaded.dialogue_cursor, 1);
+        assert!(loaded.story_flags.contains("lair_revealed"));
+        let world_event = loaded.world_event.expect("event should round-trip");
+        assert_eq!(world_event.event, "shrine");
+        assert_eq!(world_event.step, "found");
+        assert_eq!(world_event.message.key, "log.event.gold_cache");
+        assert_eq!(world_event.message.gold, 12);
+        assert_eq!(loaded.event_cursor, 2);
+        assert_eq!(loaded.event_origin, Some(Position { x: 5, y: 6 }));
+        assert_eq!(loaded.clock, WorldClock::at(3, 23));
+        assert_eq!(loaded.shop.potion, 2);
+        assert_eq!(loaded.shop.restocked_day, 3);
+        assert_eq!(loaded.campaign.bosses_defeated, vec![BossId::StoneGolem]);
+        assert_eq!(loaded.campaign.enemies_defeated, 12);
+        assert_eq!(loaded.campaign.stats.battles, 14);
+        assert_eq!(loaded.campaign.stats.death_cause.as_deref(), Some("Wolf"));
+        assert!(loaded.campaign.roguelite);
+        assert!(loaded.campaign.unlocks.contains(&Unlock::Famine));
+        assert!(
+            loaded
+                .campaign
+                .modifiers
+                .contains(&RunModifier::GlassCannon)
+        );
+        assert_eq!(loaded.campaign.daily, Some(20_744));
+        assert_eq!(loaded.campaign.ng_plus, 2);
+        assert_eq!(loaded.battle_boss, Some(BossId::Lich));
 
         let _ = std::fs::remove_file(path);
     }
+
+    #[test]
+    fn events_from_older_saves_load_without_their_message() {
+        let json = r#"{"event":"shrine","step":"found","text":"An old shrine"}"#;
+        let state: EventState = serde_json::from_str(json).expect("older event state");
+        assert_eq!(state.step, "found");
+        assert_eq!(state.message, EventMessage::default());
+    }
+
+    #[test]
+    fn only_an_unclosed_session_with_an_autosave_counts_as_interrupted() {
+        let path = temp_save_path();
+        let path = path.to_string_lossy();
+        open_session(&path).expect("marker should be written");
+        assert!(!session_was_interrupted(&path));
+
+        std::fs::write(path.as_ref(), "{}").expect("autosave stand-in");
+        assert!(session_was_interrupted(&path));
+        close_session(&path).expect("marker should be removed");
+        assert!(!session_was_interrupted(&path));
+
+        let _ = std::fs::remove_file(path.as_ref());
+    }
+
+    #[test]
+    fn files_are_replaced_whole_through_a_temp_file() {
+        let path = temp_save_path();
+        let path = path.to_string_lossy();
+        write_atomically(&path, "old").expect("first write");
+        write_atomically(&path, "new").expect("second write");
+        assert_eq!(std::fs::read_to_string(path.as_ref()).unwrap(), "new");
+        assert!(!std::path::Path::new(&format!("{path}.tmp")).exists());
+
+        let missing_dir = format!("{path}.d/save.json");
+        assert!(write_atomically(&missing_dir, "x").is_err());
+
+        let _ = std::fs::remove_file(path.as_ref());
+    }
 }
@@ -1,4 +1,7 @@
-use std::collections::VecDeque;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
 
 use crossterm::event::KeyCode;
 use rand::Rng;
@@ -6,24 +9,49 @@ use rand::SeedableRng;
 use rand::rngs::StdRng;
 use rust_i18n::t;
 
-use crate::game::battle::{self, BattleAction, BattleOutcome};
+use crate::game::balance::{
+    AUTOSAVE_MOVE_CHOICES, EXPLORE_SIGHT_RADIUS, NIGHT_ENCOUNTER_RATE_BONUS,
+    ROAMING_ESCAPE_STUN_TURNS, companion_template, unlock_cost,
+};
+use crate::game::battle::{self, BattleAction, BattleOutcome, TurnResult};
+use crate::game::challenge;
 use crate::game::config::{self, DifficultyProfile};
+use crate::game::crafting;
+use crate::game::dialogue::{self, DialogueContext, DialogueEffect, ItemKind, RevealTarget};
 use crate::game::encounter;
-use crate::game::event;
+use crate::game::event::{self, StepOutcome};
+use crate::game::keymap::{self, Keymap, Layer};
+use crate::game::legacy;
 use crate::game::model::{
-    Battle, Difficulty, Enemy, GameMode, LOG_CAPACITY, Language, MAP_H, MAP_W, Player, Position,
-    QuestState, Tile, WorldObjects,
+    AutosaveOption, Battle, BattleEffect, BattleSide, BossId, CampaignState, Command, Companion,
+    CompanionKind, CompanionOrder, ConfirmAction, DialogueState, Difficulty, EncounterMode, Enemy,
+    EventState, GameMode, GlyphSetId, HeroClass, HeroSkill, LOG_CAPACITY, Language, MAP_H, MAP_W,
+    PanelTab, Player, PointerTarget, Position, ProfileField, QuestState, Recipe, Records,
+    RunModifier, ShopStock, StatKind, ThemeId, Tile, TimeOfDay, Unlock, WorldClock, WorldObjects,
 };
+use crate::game::party;
+use crate::game::prefs::{self, Preferences};
 use crate::game::progression;
+use crate::game::records;
+use crate::game::roaming;
 use crate::game::save::{self, SaveData};
 use crate::game::town::{self, TownAction, TownOutcome};
-use crate::game::world::generate_world;
+use crate::game::world::{self, generate_world};
 
 const RNG_SALT: u64 = 0x9E37_79B9_7F4A_7C15;
 const LANGUAGE_OPTION_COUNT: usize = Language::ALL.len();
 const DIFFICULTY_OPTION_START: usize = LANGUAGE_OPTION_COUNT;
-const SETTINGS_OPTION_COUNT: usize = LANGUAGE_OPTION_COUNT + Difficulty::ALL.len();
-const TOWN_OPTION_COUNT: usize = 8;
+const ENCOUNTER_OPTION_INDEX: usize = DIFFICULTY_OPTION_START + Difficulty::ALL.len();
+const KEYBINDS_OPTION_INDEX: usize = ENCOUNTER_OPTION_INDEX + 1;
+const THEME_OPTION_INDEX: usize = KEYBINDS_OPTION_INDEX + 1;
+const GLYPHS_OPTION_INDEX: usize = THEME_OPTION_INDEX + 1;
+const AUTOSAVE_OPTION_START: usize = GLYPHS_OPTION_INDEX + 1;
+const CUSTOM_OPTION_START: usize = AUTOSAVE_OPTION_START + AutosaveOption::ALL.len();
+const SETTINGS_OPTION_COUNT: usize = CUSTOM_OPTION_START + ProfileField::ALL.len();
+const TOWN_OPTION_COUNT: usize = 10;
+/// Potion, Ether, weapon, armor and leave at a travelling merchant.
+const PEDDLER_OPTION_COUNT: usize = 5;
+const ORDER_OPTION_COUNT: usize = 3;
 
 pub struct Game {
     pub mode: GameMode,
@@ -36,6 +64,15 @@ pub struct Game {
     pub hero_scroll: usize,
     pub log_scroll: usize,
     pub controls_scroll: usize,
+    /// Last pointer position over the terminal, for hover highlights.
+    pub mouse: Option<(u16, u16)>,
+    /// Damage numbers and hit flashes playing over the battle scene.
+    pub battle_effects: Vec<BattleEffect>,
+    /// Queue battle effects at all; only the terminal front end turns this
+    /// on, so tests and snapshots always see still frames.
+    pub animations: bool,
+    /// Side panel shown by the compact layout.
+    pub panel_tab: PanelTab,
     pub current_language: Language,
     pub settings_cursor: usize,
     pub town_cursor: usize,
@@ -44,10 +81,73 @@ pub struct Game {
     pub map_seed: u64,
     pub recent_event: Option<String>,
     pub quest: QuestState,
+    pub encounter_mode: EncounterMode,
+    pub dialogue: Option<DialogueState>,
+    pub dialogue_cursor: usize,
+    pub story_flags: HashSet<String>,
+    pub world_event: Option<EventState>,
+    pub event_cursor: usize,
+    pub clock: WorldClock,
+    pub shop: ShopStock,
+    pub campaign: CampaignState,
+    pub party: Vec<Companion>,
+    pub party_cursor: usize,
+    pub battle_orders: Vec<CompanionOrder>,
+    pub order_cursor: usize,
+    pub skill_menu: bool,
+    pub skill_cursor: usize,
+    pub class_cursor: usize,
+    pub level_up_cursor: usize,
+    pub workshop: bool,
+    pub workshop_cursor: usize,
+    /// Trading with an NPC out in the field: the Town screen shows only the
+    /// shop and leads back to exploration.
+    pub peddler: bool,
+    pub records: Records,
+    pub legacy_cursor: usize,
+    /// The next run is today's daily challenge.
+    pub daily_challenge: bool,
+    /// Modifiers picked for the next custom run.
+    pub run_modifiers: BTreeSet<RunModifier>,
+    pub challenge_cursor: usize,
+    pub prefs: Preferences,
+    pub keybind_cursor: usize,
+    /// The next key press rebinds the command under the keybinds cursor.
+    pub keybind_capture: bool,
+    /// A destructive action waiting for a yes or no over the current screen.
+    pub confirm: Option<ConfirmAction>,
+    /// The full-screen world map is drawn over the exploration screen.
+    pub world_map_open: bool,
+    keymap: Keymap,
     rng: StdRng,
     difficulty_profile: DifficultyProfile,
     settings_return_mode: GameMode,
     battle_origin: Option<Position>,
+    battle_monster: Option<u32>,
+    event_origin: Option<Position>,
+    battle_boss: Option<BossId>,
+    pending_action: Option<BattleAction>,
+    party_return_mode: GameMode,
+    level_up_return_mode: GameMode,
+    records_return_mode: GameMode,
+    /// Where records are persisted; seeded games keep them in memory only.
+    records_path: Option<String>,
+    /// Where preferences are persisted; seeded games keep them in memory only.
+    prefs_path: Option<String>,
+    run_recorded: bool,
+    /// Fingerprint of the run when it last matched the save file or began.
+    saved_progress: Option<u64>,
+    /// The autosave slot; only a session started by the app writes it.
+    autosave_path: Option<String>,
+    moves_since_autosave: u32,
+    /// Tiles still to walk after a click on the map.
+    auto_walk: VecDeque<Position>,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Game {
@@ -56,7 +156,71 @@ impl Game {
         let difficulty = Difficulty::Normal;
         let profile = config::profile_for(difficulty);
         let map_seed = rand::rng().random::<u64>();
-        Self::new_with_setup(initial_language, difficulty, profile, map_seed)
+        let mut game = Self::new_with_setup(initial_language, difficulty, profile, map_seed);
+        game.mode = GameMode::ClassSelect;
+        game.attach_records(records::default_path());
+        game.attach_prefs(prefs::default_path());
+        for error in config::diagnostics() {
+            game.push_log(t!("log.config.error", error = error.as_str()));
+        }
+        game
+    }
+
+    /// Searches the given difficulty file ahead of the standard locations.
+    /// Only takes effect before the first game is created.
+    pub fn use_config_path(path: PathBuf) {
+        config::set_cli_path(path);
+    }
+
+    /// Problems with the difficulty file found at startup.
+    pub fn config_diagnostics() -> Vec<String> {
+        config::diagnostics()
+    }
+
+    /// Picks up edits to the difficulty file made while the game runs; a
+    /// broken edit is reported and the current values stay in play.
+    pub fn reload_config(&mut self) {
+        let Some(report) = config::reload_if_changed() else {
+            return;
+        };
+        if report.errors.is_empty() {
+            self.difficulty_profile = self.run_profile(&self.campaign);
+            self.push_log(t!("log.config.reloaded", source = report.source.as_str()));
+            return;
+        }
+        for error in report.errors {
+            self.push_log(t!("log.config.error", error = error.as_str()));
+        }
+        self.push_log(t!("log.config.kept"));
+    }
+
+    /// Loads achievements and the hall of fame from `path` and keeps them
+    /// there as the run progresses.
+    pub fn attach_records(&mut self, path: String) {
+        match records::load_from_path(&path) {
+            Ok(loaded) => self.records = loaded,
+            Err(error) => {
+                self.push_log(t!("log.records.load_failed", error = error.as_str()));
+            }
+        }
+        self.records_path = Some(path);
+    }
+
+    /// Loads preferences such as the custom difficulty and key bindings from
+    /// `path`; a file that fails validation is reported and replaced by
+    /// defaults.
+    pub fn attach_prefs(&mut self, path: String) {
+        match prefs::load_from_path(&path) {
+            Ok(loaded) => self.prefs = loaded,
+            Err(error) => {
+                self.push_log(t!("log.prefs.load_failed", error = error.as_str()));
+            }
+        }
+        self.prefs_path = Some(path);
+        self.keymap = self.prefs.keymap.keymap().unwrap_or_default();
+        if self.difficulty == Difficulty::Custom {
+            self.difficulty_profile = self.run_profile(&self.campaign);
+        }
     }
 
     pub fn new_with_seed(map_seed: u64) -> Self {
@@ -84,6 +248,8 @@ impl Game {
             hero_scroll: 0,
             log_scroll: 0,
             controls_scroll: 0,
+            mouse: None,
+            panel_tab: PanelTab::default(),
             current_language: language,
             settings_cursor: language.index(),
             town_cursor: 0,
@@ -92,11 +258,60 @@ impl Game {
             map_seed,
             recent_event: None,
             quest: QuestState::new(),
+            encounter_mode: EncounterMode::Random,
+            dialogue: None,
+            dialogue_cursor: 0,
+            story_flags: HashSet::new(),
+            world_event: None,
+            event_cursor: 0,
+            clock: WorldClock::default(),
+            shop: ShopStock::default(),
+            campaign: CampaignState::default(),
+            party: Vec::new(),
+            party_cursor: 0,
+            battle_orders: Vec::new(),
+            order_cursor: 0,
+            skill_menu: false,
+            skill_cursor: 0,
+            class_cursor: 0,
+            level_up_cursor: 0,
+            workshop: false,
+            workshop_cursor: 0,
+            peddler: false,
+            records: Records::default(),
+            legacy_cursor: 0,
+            daily_challenge: false,
+            run_modifiers: BTreeSet::new(),
+            challenge_cursor: 0,
+            prefs: Preferences::default(),
+            keybind_cursor: 0,
+            keybind_capture: false,
+            confirm: None,
+            world_map_open: false,
+            keymap: Keymap::default(),
             rng: StdRng::seed_from_u64(map_seed ^ RNG_SALT),
             difficulty_profile,
             settings_return_mode: GameMode::Exploration,
             battle_origin: None,
+            battle_monster: None,
+            event_origin: None,
+            battle_boss: None,
+            pending_action: None,
+            party_return_mode: GameMode::Exploration,
+            level_up_return_mode: GameMode::Exploration,
+            records_return_mode: GameMode::Exploration,
+            records_path: None,
+            prefs_path: None,
+            run_recorded: false,
+            saved_progress: None,
+            autosave_path: None,
+            moves_since_autosave: 0,
+            auto_walk: VecDeque::new(),
+            battle_effects: Vec::new(),
+            animations: false,
         };
+        game.reveal_around_player();
+        game.mark_progress_saved();
         game.push_log(t!("log.game.welcome"));
         game.push_log(t!("log.game.town_hint"));
         game.push_log(t!("log.game.difficulty", diff = t!(difficulty.label_key())));
@@ -111,8 +326,29 @@ impl Game {
     }
 
     pub fn handle_key(&mut self, code: KeyCode) {
-        if matches!(code, KeyCode::Char('q')) {
-            self.should_quit = true;
+        self.auto_walk.clear();
+        if self.skip_battle_effects() {
+            return;
+        }
+        if self.keybind_capture {
+            self.capture_binding(code);
+            return;
+        }
+        if let Some(action) = self.confirm {
+            self.handle_confirm_key(action, code);
+            return;
+        }
+        let command = self.keymap.command(code, Layer::for_mode(self.mode));
+        if self.world_map_open {
+            self.handle_world_map_key(command, code);
+            return;
+        }
+        if command == Some(Command::Quit) {
+            self.request(ConfirmAction::Quit);
+            return;
+        }
+        if command == Some(Command::NextPanel) {
+            self.panel_tab = self.panel_tab.next();
             return;
         }
         if matches!(code, KeyCode::Esc) {
@@ -120,41 +356,520 @@ impl Game {
                 self.close_settings();
                 return;
             }
-            self.should_quit = true;
+            if self.mode == GameMode::Keybinds {
+                self.mode = GameMode::Settings;
+                return;
+            }
+            if self.mode == GameMode::Dialogue {
+                self.end_dialogue();
+                return;
+            }
+            if self.mode == GameMode::Party {
+                self.mode = self.party_return_mode;
+                return;
+            }
+            if self.mode == GameMode::LevelUp {
+                self.mode = self.level_up_return_mode;
+                return;
+            }
+            if self.mode == GameMode::Records {
+                self.mode = self.records_return_mode;
+                return;
+            }
+            if matches!(self.mode, GameMode::Legacy | GameMode::Challenge) {
+                self.mode = GameMode::ClassSelect;
+                return;
+            }
+            if self.mode == GameMode::Town && self.workshop {
+                self.workshop = false;
+                return;
+            }
+            if self.mode == GameMode::Town && self.peddler {
+                self.leave_peddler();
+                return;
+            }
+            self.request(ConfirmAction::Quit);
             return;
         }
-        if matches!(code, KeyCode::Char('k')) {
-            self.save_game();
+        if command == Some(Command::Save) {
+            if self.iron_man_run() {
+                self.push_log(t!("log.challenge.iron_man_save"));
+                return;
+            }
+            if self.campaign.roguelite && self.mode == GameMode::GameOver {
+                self.push_log(t!("log.legacy.no_save"));
+                return;
+            }
+            self.request(ConfirmAction::Save);
             return;
         }
-        if matches!(code, KeyCode::Char('l')) {
-            self.load_game();
+        if command == Some(Command::Load) {
+            if self.iron_man_run() {
+                self.push_log(t!("log.challenge.iron_man_load"));
+                return;
+            }
+            if self.campaign.roguelite && self.run_underway() {
+                self.push_log(t!("log.legacy.no_load"));
+                return;
+            }
+            self.request(ConfirmAction::Load);
             return;
         }
 
         match self.mode {
-            GameMode::Exploration => self.handle_exploration_key(code),
-            GameMode::Town => self.handle_town_key(code),
-            GameMode::Settings => self.handle_settings_key(code),
-            GameMode::Battle => self.handle_battle_key(code),
-            GameMode::Victory | GameMode::GameOver => {
-                if matches!(code, KeyCode::Char('r')) {
-                    self.restart();
+            GameMode::Exploration => self.handle_exploration_key(command),
+            GameMode::Town => self.handle_town_key(code, command),
+            GameMode::Settings => self.handle_settings_key(code, command),
+            GameMode::Battle => self.handle_battle_key(code, command),
+            GameMode::Dialogue => self.handle_dialogue_key(code, command),
+            GameMode::Event => self.handle_event_key(code, command),
+            GameMode::Party => self.handle_party_key(command),
+            GameMode::ClassSelect => self.handle_class_key(code, command),
+            GameMode::LevelUp => self.handle_level_up_key(code, command),
+            GameMode::Records => {
+                if matches!(command, Some(Command::Back | Command::Records)) {
+                    self.mode = self.records_return_mode;
+                }
+            }
+            GameMode::Legacy => self.handle_legacy_key(code, command),
+            GameMode::Challenge => self.handle_challenge_key(code, command),
+            GameMode::Keybinds => self.handle_keybinds_key(command),
+            GameMode::Victory | GameMode::GameOver => match command {
+                Some(Command::Records) => self.open_records(self.mode),
+                Some(Command::Restart) => self.request(ConfirmAction::Restart),
+                Some(Command::NewGamePlus) if self.mode == GameMode::Victory => {
+                    self.start_new_game_plus();
                 }
+                _ => {}
+            },
+        }
+        self.update_records();
+    }
+
+    /// Whether a run is underway, rather than still being set up on the
+    /// class, legacy or challenge screens.
+    fn run_underway(&self) -> bool {
+        !matches!(
+            self.mode,
+            GameMode::ClassSelect | GameMode::Legacy | GameMode::Challenge
+        )
+    }
+
+    /// Iron Man runs can neither be saved nor loaded by hand, so a death
+    /// cannot be undone from a save made before it.
+    fn iron_man_run(&self) -> bool {
+        self.run_underway() && self.campaign.modifiers.contains(&RunModifier::IronMan)
+    }
+
+    fn open_records(&mut self, from_mode: GameMode) {
+        self.records_return_mode = from_mode;
+        self.mode = GameMode::Records;
+    }
+
+    /// Unlocks any achievements the run has just earned and, once the run
+    /// ends, enters it on the hall of fame.
+    fn update_records(&mut self) {
+        let earned = records::newly_earned(
+            &self.records,
+            &self.player,
+            &self.campaign,
+            self.party.len(),
+        );
+        let mut changed = !earned.is_empty();
+        for achievement in earned {
+            self.records.achievements.insert(achievement);
+            self.announce_event(
+                t!("log.records.achievement", name = t!(achievement.name_key())).to_string(),
+            );
+        }
+
+        let victory = self.mode == GameMode::Victory;
+        if !self.run_recorded && (victory || self.mode == GameMode::GameOver) {
+            self.run_recorded = true;
+            changed = true;
+            let entry = records::hall_entry(
+                self.map_seed,
+                self.difficulty,
+                &self.player,
+                &self.campaign,
+                self.clock,
+                victory,
+            );
+            let score = entry.score;
+            if let Some(place) = records::add_entry(&mut self.records, entry) {
+                self.push_log(t!("log.records.ranked", place = place));
+            }
+            if self.campaign.roguelite {
+                self.end_roguelite_run(score, victory);
+            }
+        }
+
+        if changed {
+            self.save_records();
+        }
+    }
+
+    /// Pays out the run's shards and, on defeat, deletes the save and the
+    /// autosave so the run cannot be resumed. Seeded games never touch files on disk.
+    fn end_roguelite_run(&mut self, score: i32, victory: bool) {
+        let shards = legacy::shards_for_run(score, &self.campaign.unlocks);
+        self.records.legacy.shards += shards;
+        self.push_log(t!(
+            "log.legacy.shards_earned",
+            shards = shards,
+            total = self.records.legacy.shards
+        ));
+        if victory || self.records_path.is_none() {
+            return;
+        }
+        let mut deletions = vec![save::delete_default_file()];
+        if let Some(path) = self.autosave_path.clone() {
+            deletions.push(save::delete_file(path));
+        }
+        for result in deletions {
+            match result {
+                Ok(Some(path)) => {
+                    self.push_log(t!("log.legacy.save_deleted", path = path.as_str()));
+                }
+                Ok(None) => {}
+                Err(error) => {
+                    self.push_log(t!("log.legacy.delete_failed", error = error.as_str()));
+                }
+            }
+        }
+    }
+
+    fn handle_legacy_key(&mut self, code: KeyCode, command: Option<Command>) {
+        let count = Unlock::ALL.len();
+        match (command, keymap::entry(code)) {
+            (Some(Command::MenuUp), _) => {
+                self.legacy_cursor = (self.legacy_cursor + count - 1) % count;
+            }
+            (Some(Command::MenuDown), _) => {
+                self.legacy_cursor = (self.legacy_cursor + 1) % count;
+            }
+            (Some(Command::Confirm), _) => self.choose_unlock(self.legacy_cursor),
+            (Some(Command::Back | Command::OpenLegacy), _) => {
+                self.mode = GameMode::ClassSelect;
+            }
+            (Some(Command::ToggleRoguelite), _) => {
+                let legacy = &mut self.records.legacy;
+                legacy.roguelite = !legacy.roguelite;
+                let key = if legacy.roguelite {
+                    "log.legacy.roguelite_on"
+                } else {
+                    "log.legacy.roguelite_off"
+                };
+                self.announce_event(t!(key).to_string());
+                self.save_records();
+            }
+            (_, Some(idx)) => self.choose_unlock(idx),
+            _ => {}
+        }
+    }
+
+    fn choose_unlock(&mut self, idx: usize) {
+        let Some(unlock) = Unlock::ALL.get(idx).copied() else {
+            return;
+        };
+        self.legacy_cursor = idx;
+        let message = legacy::buy_or_toggle(&mut self.records.legacy, unlock);
+        self.announce_event(message);
+        self.save_records();
+    }
+
+    fn handle_challenge_key(&mut self, code: KeyCode, command: Option<Command>) {
+        let count = RunModifier::ALL.len();
+        match (command, keymap::entry(code)) {
+            (Some(Command::MenuUp), _) => {
+                self.challenge_cursor = (self.challenge_cursor + count - 1) % count;
+            }
+            (Some(Command::MenuDown), _) => {
+                self.challenge_cursor = (self.challenge_cursor + 1) % count;
+            }
+            (Some(Command::Confirm), _) => self.toggle_run_modifier(self.challenge_cursor),
+            (Some(Command::Back | Command::OpenChallenges), _) => {
+                self.mode = GameMode::ClassSelect;
+            }
+            (Some(Command::ToggleDaily), _) => {
+                self.daily_challenge = !self.daily_challenge;
+                let message = if self.daily_challenge {
+                    t!(
+                        "log.challenge.daily_on",
+                        date = challenge::date_label(challenge::today())
+                    )
+                } else {
+                    t!("log.challenge.daily_off")
+                };
+                self.announce_event(message.to_string());
+            }
+            (_, Some(idx)) => self.toggle_run_modifier(idx),
+            _ => {}
+        }
+    }
+
+    fn toggle_run_modifier(&mut self, idx: usize) {
+        let Some(modifier) = RunModifier::ALL.get(idx).copied() else {
+            return;
+        };
+        self.challenge_cursor = idx;
+        if self.daily_challenge {
+            self.push_log(t!("log.challenge.daily_fixed"));
+            return;
+        }
+        let name = t!(modifier.name_key());
+        let message = if self.run_modifiers.remove(&modifier) {
+            t!("log.challenge.modifier_off", name = name)
+        } else {
+            self.run_modifiers.insert(modifier);
+            t!("log.challenge.modifier_on", name = name)
+        };
+        self.announce_event(message.to_string());
+    }
+
+    /// Modifiers the next run will start with: the day's draw for a daily
+    /// challenge, otherwise the player's own picks.
+    pub fn planned_modifiers(&self) -> BTreeSet<RunModifier> {
+        if self.daily_challenge {
+            challenge::daily_modifiers(challenge::today())
+        } else {
+            self.run_modifiers.clone()
+        }
+    }
+
+    pub fn today(&self) -> u64 {
+        challenge::today()
+    }
+
+    pub fn date_label(&self, day: u64) -> String {
+        challenge::date_label(day)
+    }
+
+    /// Rebuilds the world from another seed before the run begins.
+    fn reseed(&mut self, map_seed: u64) {
+        let (map, world) = generate_world(map_seed);
+        self.map = map;
+        self.world = world;
+        self.map_seed = map_seed;
+        self.rng = StdRng::seed_from_u64(map_seed ^ RNG_SALT);
+        self.reveal_around_player();
+    }
+
+    /// Double Encounters sends out a second pack of roaming monsters, so the
+    /// modifier still bites when encounters roam rather than roll.
+    fn place_modifier_monsters(&mut self) {
+        if self
+            .campaign
+            .modifiers
+            .contains(&RunModifier::DoubleEncounters)
+        {
+            world::reinforce_roaming(&self.map, &mut self.world, self.map_seed);
+        }
+    }
+
+    fn shops_closed(&mut self) -> bool {
+        if !self.campaign.modifiers.contains(&RunModifier::NoShops) {
+            return false;
+        }
+        let message = t!("log.challenge.shops_closed").to_string();
+        self.recent_event = Some(message.clone());
+        self.push_log(message);
+        true
+    }
+
+    /// Runs the action, or asks first when it would throw away progress or
+    /// an existing save and the player has not waived that prompt.
+    fn request(&mut self, action: ConfirmAction) {
+        let risky = match action {
+            ConfirmAction::Save => save::default_file_exists(),
+            ConfirmAction::Quit | ConfirmAction::Load | ConfirmAction::Restart => {
+                self.has_unsaved_progress()
+            }
+            ConfirmAction::Resume => true,
+        };
+        if risky && !self.prefs.skip_confirm.contains(&action) {
+            self.confirm = Some(action);
+        } else {
+            self.perform(action);
+        }
+    }
+
+    fn perform(&mut self, action: ConfirmAction) {
+        match action {
+            ConfirmAction::Quit => self.should_quit = true,
+            ConfirmAction::Load => self.load_game(),
+            ConfirmAction::Save => self.save_game(),
+            ConfirmAction::Restart => self.restart(),
+            ConfirmAction::Resume => self.resume_autosave(),
+        }
+    }
+
+    /// Accept or confirm goes ahead, accept-always goes ahead and stops
+    /// asking, decline/back/Esc cancels; every other key is swallowed by the
+    /// prompt.
+    fn handle_confirm_key(&mut self, action: ConfirmAction, code: KeyCode) {
+        let command = self.keymap.command(code, Layer::Prompt);
+        match (command, code) {
+            (Some(Command::Accept | Command::Confirm), _) => {
+                self.confirm = None;
+                self.perform(action);
+            }
+            (Some(Command::AcceptAlways), _) => {
+                self.confirm = None;
+                self.prefs.skip_confirm.insert(action);
+                self.save_prefs();
+                self.push_log(t!(
+                    "log.confirm.waived",
+                    action = t!(action.name_key()),
+                    key = self.key_label(Command::ResetPrompts)
+                ));
+                self.perform(action);
             }
+            (Some(Command::Decline | Command::Back), _) | (_, KeyCode::Esc) => {
+                self.confirm = None;
+            }
+            _ => {}
+        }
+    }
+
+    fn reset_confirm_prompts(&mut self) {
+        if self.prefs.skip_confirm.is_empty() {
+            return;
+        }
+        self.prefs.skip_confirm.clear();
+        self.save_prefs();
+        let message = t!("log.confirm.restored").to_string();
+        self.recent_event = Some(message.clone());
+        self.push_log(message);
+    }
+
+    /// Whether quitting or loading now would lose play since the last save
+    /// (or since the run began). Menus between runs have nothing to lose.
+    pub fn has_unsaved_progress(&self) -> bool {
+        if matches!(
+            self.mode,
+            GameMode::ClassSelect | GameMode::Legacy | GameMode::Challenge | GameMode::GameOver
+        ) {
+            return false;
+        }
+        self.saved_progress != Some(self.progress_fingerprint())
+    }
+
+    fn mark_progress_saved(&mut self) {
+        self.saved_progress = Some(self.progress_fingerprint());
+    }
+
+    /// A digest of the run state a save preserves; screens, cursors and the
+    /// log are left out so browsing menus does not count as progress.
+    fn progress_fingerprint(&self) -> u64 {
+        let snapshot = serde_json::to_string(&(
+            &self.player,
+            &self.campaign,
+            self.clock,
+            &self.party,
+            &self.quest,
+            &self.world,
+            &self.battle,
+        ))
+        .unwrap_or_default();
+        let mut hasher = DefaultHasher::new();
+        snapshot.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn save_prefs(&mut self) {
+        if let Some(path) = &self.prefs_path
+            && let Err(error) = prefs::save_to_path(&self.prefs, path)
+        {
+            self.push_log(t!("log.prefs.save_failed", error = error.as_str()));
+        }
+    }
+
+    fn save_records(&mut self) {
+        if let Some(path) = &self.records_path
+            && let Err(error) = records::save_to_path(&self.records, path)
+        {
+            self.push_log(t!("log.records.save_failed", error = error.as_str()));
         }
     }
 
     fn restart(&mut self) {
         let map_seed = rand::rng().random::<u64>();
-        let profile = config::profile_for(self.difficulty);
+        let profile = self.run_profile(&CampaignState::default());
+        let encounter_mode = self.encounter_mode;
+        let records = std::mem::take(&mut self.records);
+        let records_path = self.records_path.take();
+        let prefs = std::mem::take(&mut self.prefs);
+        let prefs_path = self.prefs_path.take();
+        let keymap = std::mem::take(&mut self.keymap);
+        let autosave_path = self.autosave_path.take();
+        let daily_challenge = self.daily_challenge;
+        let run_modifiers = std::mem::take(&mut self.run_modifiers);
+        *self = Self::new_with_setup(self.current_language, self.difficulty, profile, map_seed);
+        self.encounter_mode = encounter_mode;
+        self.daily_challenge = daily_challenge;
+        self.run_modifiers = run_modifiers;
+        self.records = records;
+        self.records_path = records_path;
+        self.prefs = prefs;
+        self.prefs_path = prefs_path;
+        self.keymap = keymap;
+        self.autosave_path = autosave_path;
+        self.mode = GameMode::ClassSelect;
+    }
+
+    /// Starts the next New Game+ cycle: the hero, party and bag carry over
+    /// into a freshly generated world where every foe is tougher.
+    fn start_new_game_plus(&mut self) {
+        let map_seed = self.rng.random::<u64>();
+        let mut player = self.player.clone();
+        player.x = 1;
+        player.y = 1;
+        player.hp = player.max_hp;
+        player.mp = player.max_mp;
+        let mut companions = std::mem::take(&mut self.party);
+        party::restore_all(&mut companions);
+        let campaign = CampaignState {
+            ng_plus: self.campaign.ng_plus + 1,
+            roguelite: self.campaign.roguelite,
+            unlocks: std::mem::take(&mut self.campaign.unlocks),
+            modifiers: std::mem::take(&mut self.campaign.modifiers),
+            ..CampaignState::default()
+        };
+        let profile = self.run_profile(&campaign);
+        let encounter_mode = self.encounter_mode;
+        let records = std::mem::take(&mut self.records);
+        let records_path = self.records_path.take();
+        let prefs = std::mem::take(&mut self.prefs);
+        let prefs_path = self.prefs_path.take();
+        let keymap = std::mem::take(&mut self.keymap);
+        let autosave_path = self.autosave_path.take();
+        let daily_challenge = self.daily_challenge;
+        let run_modifiers = std::mem::take(&mut self.run_modifiers);
+
         *self = Self::new_with_setup(self.current_language, self.difficulty, profile, map_seed);
+        self.encounter_mode = encounter_mode;
+        self.records = records;
+        self.records_path = records_path;
+        self.prefs = prefs;
+        self.prefs_path = prefs_path;
+        self.keymap = keymap;
+        self.autosave_path = autosave_path;
+        self.daily_challenge = daily_challenge;
+        self.run_modifiers = run_modifiers;
+        self.player = player;
+        self.party = companions;
+        self.campaign = campaign;
+        self.place_modifier_monsters();
+        self.mark_progress_saved();
+        self.announce_event(t!("log.ng_plus.started", tier = self.campaign.ng_plus).to_string());
     }
 
     fn save_game(&mut self) {
         let save = self.to_save_data();
         match save::save_to_default_file(&save) {
             Ok(path) => {
+                self.mark_progress_saved();
                 let message = t!("log.game.saved_to", path = path.as_str()).to_string();
                 self.recent_event = Some(message.clone());
                 self.push_log(message);
@@ -167,12 +882,29 @@ impl Game {
 
     fn load_game(&mut self) {
         match save::load_from_default_file() {
+            // Older builds let Iron Man runs be saved by hand.
+            Ok((save_data, _)) if save_data.campaign.modifiers.contains(&RunModifier::IronMan) => {
+                self.push_log(t!("log.challenge.iron_man_load"));
+            }
             Ok((save_data, path)) => {
-                let mut loaded = Self::from_save_data(save_data);
                 let message = t!("log.game.loaded_from", path = path.as_str()).to_string();
-                loaded.recent_event = Some(message.clone());
-                loaded.push_log(message);
-                *self = loaded;
+                self.adopt_save(save_data, message);
+            }
+            Err(error) => {
+                self.push_log(t!("log.game.load_failed", error = error.as_str()));
+            }
+        }
+    }
+
+    /// Picks up the run an interrupted session left in the autosave slot.
+    fn resume_autosave(&mut self) {
+        let Some(path) = self.autosave_path.clone() else {
+            return;
+        };
+        match save::load_from_path(&path) {
+            Ok(save_data) => {
+                let message = t!("log.autosave.resumed", path = path.as_str()).to_string();
+                self.adopt_save(save_data, message);
             }
             Err(error) => {
                 self.push_log(t!("log.game.load_failed", error = error.as_str()));
@@ -180,6 +912,61 @@ impl Game {
         }
     }
 
+    /// Replaces the run with a loaded one; records, preferences and file
+    /// locations belong to the session and stay.
+    fn adopt_save(&mut self, save_data: SaveData, message: String) {
+        let recorded = save_data.custom_difficulty.clone();
+        let mut loaded = Self::from_save_data(save_data);
+        loaded.records = std::mem::take(&mut self.records);
+        loaded.records_path = self.records_path.take();
+        loaded.prefs = std::mem::take(&mut self.prefs);
+        loaded.prefs_path = self.prefs_path.take();
+        loaded.keymap = std::mem::take(&mut self.keymap);
+        loaded.autosave_path = self.autosave_path.take();
+        if let Some(profile) = recorded {
+            loaded.prefs.custom_difficulty = profile;
+        }
+        loaded.recent_event = Some(message.clone());
+        loaded.push_log(message);
+        *self = loaded;
+    }
+
+    /// Starts writing the standard autosave slot and, when the previous
+    /// session ended without a clean quit, offers to resume it.
+    pub fn begin_session(&mut self) {
+        self.begin_session_at(save::autosave_path());
+    }
+
+    fn begin_session_at(&mut self, path: String) {
+        if save::session_was_interrupted(&path) {
+            self.autosave_path = Some(path.clone());
+            self.request(ConfirmAction::Resume);
+        }
+        if let Err(error) = save::open_session(&path) {
+            self.push_log(t!("log.autosave.failed", error = error.as_str()));
+        }
+        self.autosave_path = Some(path);
+    }
+
+    /// Marks the session as cleanly closed so the next start skips the
+    /// resume offer.
+    pub fn end_session(&self) {
+        if let Some(path) = &self.autosave_path {
+            let _ = save::close_session(path);
+        }
+    }
+
+    fn autosave(&mut self, reason_key: &str) {
+        self.moves_since_autosave = 0;
+        let Some(path) = self.autosave_path.clone() else {
+            return;
+        };
+        match save::save_to_path(&self.to_save_data(), &path) {
+            Ok(()) => self.push_log(t!("log.autosave.saved", reason = t!(reason_key))),
+            Err(error) => self.push_log(t!("log.autosave.failed", error = error.as_str())),
+        }
+    }
+
     fn to_save_data(&self) -> SaveData {
         SaveData {
             version: 1,
@@ -190,6 +977,8 @@ impl Game {
             battle: self.battle.clone(),
             current_language: self.current_language,
             difficulty: self.difficulty,
+            custom_difficulty: (self.difficulty == Difficulty::Custom)
+                .then(|| self.prefs.custom_difficulty.clone()),
             hero_scroll: self.hero_scroll,
             log_scroll: self.log_scroll,
             controls_scroll: self.controls_scroll,
@@ -198,14 +987,39 @@ impl Game {
             log: self.log.iter().cloned().collect(),
             recent_event: self.recent_event.clone(),
             battle_origin: self.battle_origin,
+            encounter_mode: self.encounter_mode,
+            battle_monster: self.battle_monster,
+            dialogue: self.dialogue.clone(),
+            dialogue_cursor: self.dialogue_cursor,
+            story_flags: self.story_flags.clone(),
+            world_event: self.world_event.clone(),
+            event_cursor: self.event_cursor,
+            event_origin: self.event_origin,
+            clock: self.clock,
+            shop: self.shop.clone(),
+            campaign: self.campaign.clone(),
+            battle_boss: self.battle_boss,
+            party: self.party.clone(),
             settings_cursor: self.settings_cursor,
             town_cursor: self.town_cursor,
+            peddler: self.peddler,
             battle_cursor: self.battle_cursor,
         }
     }
 
     fn from_save_data(save_data: SaveData) -> Self {
-        let profile = config::profile_for(save_data.difficulty);
+        let prefs = Preferences {
+            custom_difficulty: save_data
+                .custom_difficulty
+                .clone()
+                .unwrap_or_else(prefs::default_custom_difficulty),
+            ..Preferences::default()
+        };
+        let profile = run_profile(
+            save_data.difficulty,
+            &prefs.custom_difficulty,
+            &save_data.campaign,
+        );
         rust_i18n::set_locale(save_data.current_language.locale_code());
 
         let mut game = Self {
@@ -219,6 +1033,8 @@ impl Game {
             hero_scroll: save_data.hero_scroll,
             log_scroll: save_data.log_scroll,
             controls_scroll: save_data.controls_scroll,
+            mouse: None,
+            panel_tab: PanelTab::default(),
             current_language: save_data.current_language,
             settings_cursor: save_data
                 .settings_cursor
@@ -233,12 +1049,69 @@ impl Game {
             map_seed: save_data.map_seed,
             recent_event: save_data.recent_event,
             quest: save_data.quest,
+            encounter_mode: save_data.encounter_mode,
+            dialogue: save_data.dialogue,
+            dialogue_cursor: save_data.dialogue_cursor,
+            story_flags: save_data.story_flags,
+            world_event: save_data.world_event,
+            event_cursor: save_data.event_cursor,
+            clock: save_data.clock,
+            shop: save_data.shop,
+            campaign: save_data.campaign,
+            party: save_data.party,
+            party_cursor: 0,
+            battle_orders: Vec::new(),
+            order_cursor: 0,
+            skill_menu: false,
+            skill_cursor: 0,
+            class_cursor: 0,
+            level_up_cursor: 0,
+            workshop: false,
+            workshop_cursor: 0,
+            peddler: save_data.peddler && save_data.mode == GameMode::Town,
+            records: Records::default(),
+            legacy_cursor: 0,
+            daily_challenge: false,
+            run_modifiers: BTreeSet::new(),
+            challenge_cursor: 0,
+            prefs,
+            keybind_cursor: 0,
+            keybind_capture: false,
+            confirm: None,
+            world_map_open: false,
+            keymap: Keymap::default(),
             rng: StdRng::seed_from_u64(save_data.map_seed ^ RNG_SALT),
             difficulty_profile: profile,
             settings_return_mode: GameMode::Exploration,
             battle_origin: save_data.battle_origin,
+            battle_monster: save_data.battle_monster,
+            event_origin: save_data.event_origin,
+            battle_boss: save_data.battle_boss,
+            pending_action: None,
+            party_return_mode: GameMode::Exploration,
+            level_up_return_mode: GameMode::Exploration,
+            records_return_mode: GameMode::Exploration,
+            records_path: None,
+            prefs_path: None,
+            run_recorded: matches!(save_data.mode, GameMode::Victory | GameMode::GameOver),
+            saved_progress: None,
+            autosave_path: None,
+            moves_since_autosave: 0,
+            auto_walk: VecDeque::new(),
+            battle_effects: Vec::new(),
+            animations: false,
         };
 
+        if game.mode == GameMode::Dialogue && game.dialogue.is_none() {
+            game.mode = GameMode::Exploration;
+        }
+        if game.mode == GameMode::Event && game.world_event.is_none() {
+            game.mode = GameMode::Exploration;
+        }
+        if game.world.explored.is_empty() {
+            game.reveal_around_player();
+        }
+        game.mark_progress_saved();
         for message in save_data.log.into_iter().rev().take(LOG_CAPACITY).rev() {
             game.push_log(message);
         }
@@ -250,103 +1123,392 @@ impl Game {
         self.push_log(message);
     }
 
-    fn handle_exploration_key(&mut self, code: KeyCode) {
-        if matches!(code, KeyCode::Char('o')) {
-            self.open_settings(GameMode::Exploration);
+    /// Acts on a mouse click: a menu option is selected and activated as if
+    /// confirmed, a map tile starts a walk towards it.
+    pub fn click(&mut self, target: PointerTarget) {
+        if self.confirm.is_some()
+            || self.keybind_capture
+            || self.world_map_open
+            || self.skip_battle_effects()
+        {
             return;
         }
-        if matches!(code, KeyCode::Char('t')) && self.current_tile() == Tile::Town {
-            self.mode = GameMode::Town;
-            self.push_log(t!("log.town.menu_opened"));
-            return;
+        self.auto_walk.clear();
+        match target {
+            PointerTarget::Tile(position) => self.walk_to(position),
+            PointerTarget::MenuOption(index) => self.click_option(index),
+            PointerTarget::Tab(tab) => self.panel_tab = tab,
         }
-
-        let (dx, dy) = match code {
-            KeyCode::Up | KeyCode::Char('w') => (0, -1),
-            KeyCode::Down | KeyCode::Char('s') => (0, 1),
-            KeyCode::Left | KeyCode::Char('a') => (-1, 0),
-            KeyCode::Right | KeyCode::Char('d') => (1, 0),
-            _ => return,
-        };
-        self.try_move_player(dx, dy);
+        self.update_records();
     }
 
-    fn handle_town_key(&mut self, code: KeyCode) {
-        if matches!(code, KeyCode::Char('o')) {
-            self.open_settings(GameMode::Town);
-            return;
-        }
-
-        let action = match code {
-            KeyCode::Up | KeyCode::Char('w') => {
-                if self.town_cursor == 0 {
-                    self.town_cursor = TOWN_OPTION_COUNT - 1;
-                } else {
-                    self.town_cursor -= 1;
-                }
-                None
+    fn click_option(&mut self, index: usize) {
+        let confirm = Some(Command::Confirm);
+        match self.mode {
+            GameMode::Town if self.peddler => {
+                self.town_cursor = index;
+                self.handle_peddler_key(KeyCode::Null, confirm);
             }
-            KeyCode::Down | KeyCode::Char('s') => {
-                self.town_cursor = (self.town_cursor + 1) % TOWN_OPTION_COUNT;
-                None
+            GameMode::Town if self.workshop => {
+                self.workshop_cursor = index;
+                self.handle_workshop_key(KeyCode::Null, confirm);
             }
-            KeyCode::Enter => Some(town_action_from_cursor(self.town_cursor)),
-            KeyCode::Char('1') => Some(TownAction::BuyPotion),
-            KeyCode::Char('2') => Some(TownAction::BuyEther),
-            KeyCode::Char('3') => Some(TownAction::UpgradeWeapon),
-            KeyCode::Char('4') => Some(TownAction::UpgradeArmor),
-            KeyCode::Char('5') => Some(TownAction::Healer),
-            KeyCode::Char('6') => Some(TownAction::Inn),
-            KeyCode::Char('7') => Some(TownAction::QuestBoard),
-            KeyCode::Char('8') => Some(TownAction::Leave),
-            _ => None,
-        };
-
-        let Some(action) = action else {
-            return;
-        };
-        self.town_cursor = town_cursor_from_action(action);
-        match town::apply_action(&mut self.player, &mut self.quest, action) {
-            TownOutcome::Stay(message) => {
-                self.recent_event = Some(message.clone());
-                self.push_log(message);
+            GameMode::Town => {
+                self.town_cursor = index;
+                self.handle_town_key(KeyCode::Null, confirm);
             }
-            TownOutcome::Leave(message) => {
-                self.mode = GameMode::Exploration;
-                self.push_log(message);
+            GameMode::Battle if self.pending_action.is_some() => {
+                self.order_cursor = index;
+                self.handle_order_key(KeyCode::Null, confirm);
+            }
+            GameMode::Battle if self.skill_menu => {
+                self.skill_cursor = index;
+                self.handle_skill_key(KeyCode::Null, confirm);
             }
+            GameMode::Battle => {
+                self.battle_cursor = index;
+                self.handle_battle_key(KeyCode::Null, confirm);
+            }
+            GameMode::Settings => {
+                self.settings_cursor = index;
+                self.select_setting_at_cursor();
+            }
+            _ => {}
         }
     }
 
-    fn handle_settings_key(&mut self, code: KeyCode) {
-        match code {
-            KeyCode::Up | KeyCode::Char('w') => {
-                if self.settings_cursor == 0 {
+    fn walk_to(&mut self, target: Position) {
+        if self.mode != GameMode::Exploration {
+            return;
+        }
+        let from = Position {
+            x: self.player.x,
+            y: self.player.y,
+        };
+        match world::find_path(&self.map, &self.world, from, target) {
+            Some(path) => self.auto_walk = path.into(),
+            None => self.push_log(t!("log.world.no_path")),
+        }
+    }
+
+    /// Takes the next step of a click-to-walk; anything that interrupts
+    /// exploration, or a step that did not land, ends the walk.
+    pub fn step_auto_walk(&mut self) {
+        if self.mode != GameMode::Exploration || self.confirm.is_some() {
+            self.auto_walk.clear();
+            return;
+        }
+        let Some(next) = self.auto_walk.pop_front() else {
+            return;
+        };
+        let dx = next.x as i32 - self.player.x as i32;
+        let dy = next.y as i32 - self.player.y as i32;
+        self.try_move_player(dx, dy);
+        if (self.player.x, self.player.y) != (next.x, next.y) {
+            self.auto_walk.clear();
+        }
+        self.update_records();
+    }
+
+    /// Only closing the map, or quitting, gets past the world map overlay.
+    fn handle_world_map_key(&mut self, command: Option<Command>, code: KeyCode) {
+        match (command, code) {
+            (Some(Command::WorldMap | Command::Back), _) | (_, KeyCode::Esc) => {
+                self.world_map_open = false;
+            }
+            (Some(Command::Quit), _) => self.request(ConfirmAction::Quit),
+            _ => {}
+        }
+    }
+
+    fn handle_exploration_key(&mut self, command: Option<Command>) {
+        if command == Some(Command::WorldMap) {
+            self.world_map_open = true;
+            return;
+        }
+        if command == Some(Command::Settings) {
+            self.open_settings(GameMode::Exploration);
+            return;
+        }
+        if command == Some(Command::Party) {
+            self.open_party(GameMode::Exploration);
+            return;
+        }
+        if command == Some(Command::LevelUp) {
+            self.open_level_up(GameMode::Exploration);
+            return;
+        }
+        if command == Some(Command::Records) {
+            self.open_records(GameMode::Exploration);
+            return;
+        }
+        if command == Some(Command::Interact) && self.current_tile() == Tile::Town {
+            self.enter_town_menu();
+            self.push_log(t!("log.town.menu_opened"));
+            return;
+        }
+        if command == Some(Command::Interact) {
+            if !self.interact_npc(self.player.x, self.player.y)
+                && let Some(npc) = self.world.npc_at_mut(self.player.x, self.player.y)
+            {
+                let kind = npc.kind;
+                if let Some(script) = dialogue::script(kind.dialogue_id()) {
+                    self.push_log(t!(
+                        "log.time.npc_away",
+                        speaker = t!(script.speaker.as_str())
+                    ));
+                }
+            }
+            return;
+        }
+
+        let (dx, dy) = match command {
+            Some(Command::MoveUp) => (0, -1),
+            Some(Command::MoveDown) => (0, 1),
+            Some(Command::MoveLeft) => (-1, 0),
+            Some(Command::MoveRight) => (1, 0),
+            _ => return,
+        };
+        self.try_move_player(dx, dy);
+    }
+
+    fn handle_town_key(&mut self, code: KeyCode, command: Option<Command>) {
+        if self.workshop {
+            self.handle_workshop_key(code, command);
+            return;
+        }
+        if self.peddler {
+            self.handle_peddler_key(code, command);
+            return;
+        }
+        if command == Some(Command::Settings) {
+            self.open_settings(GameMode::Town);
+            return;
+        }
+        if command == Some(Command::Party) {
+            self.open_party(GameMode::Town);
+            return;
+        }
+        if command == Some(Command::LevelUp) {
+            self.open_level_up(GameMode::Town);
+            return;
+        }
+
+        let action = match (command, keymap::entry(code)) {
+            (Some(Command::MenuUp), _) => {
+                if self.town_cursor == 0 {
+                    self.town_cursor = TOWN_OPTION_COUNT - 1;
+                } else {
+                    self.town_cursor -= 1;
+                }
+                None
+            }
+            (Some(Command::MenuDown), _) => {
+                self.town_cursor = (self.town_cursor + 1) % TOWN_OPTION_COUNT;
+                None
+            }
+            (Some(Command::Confirm), _) => Some(town_action_from_cursor(self.town_cursor)),
+            (_, Some(idx)) => Some(town_action_from_cursor(idx)),
+            _ => None,
+        };
+
+        let Some(action) = action else {
+            return;
+        };
+        self.town_cursor = town_cursor_from_action(action);
+        self.apply_town_action(action);
+    }
+
+    fn handle_workshop_key(&mut self, code: KeyCode, command: Option<Command>) {
+        let count = crafting::recipes().len();
+        let index = match (command, keymap::entry(code)) {
+            (Some(Command::MenuUp), _) if count > 0 => {
+                self.workshop_cursor = (self.workshop_cursor + count - 1) % count;
+                None
+            }
+            (Some(Command::MenuDown), _) if count > 0 => {
+                self.workshop_cursor = (self.workshop_cursor + 1) % count;
+                None
+            }
+            (Some(Command::Confirm), _) => Some(self.workshop_cursor),
+            (Some(Command::Back), _) => {
+                self.workshop = false;
+                None
+            }
+            (_, entry) => entry,
+        };
+
+        let Some(index) = index.filter(|index| *index < count) else {
+            return;
+        };
+        self.workshop_cursor = index;
+        self.apply_town_action(TownAction::Craft(index));
+    }
+
+    /// A merchant met in the field sells supplies and gear, nothing else.
+    fn handle_peddler_key(&mut self, code: KeyCode, command: Option<Command>) {
+        let purchases = [
+            TownAction::BuyPotion,
+            TownAction::BuyEther,
+            TownAction::UpgradeWeapon,
+            TownAction::UpgradeArmor,
+        ];
+        let index = match (command, keymap::entry(code)) {
+            (Some(Command::MenuUp), _) => {
+                self.town_cursor =
+                    (self.town_cursor + PEDDLER_OPTION_COUNT - 1) % PEDDLER_OPTION_COUNT;
+                None
+            }
+            (Some(Command::MenuDown), _) => {
+                self.town_cursor = (self.town_cursor + 1) % PEDDLER_OPTION_COUNT;
+                None
+            }
+            (Some(Command::Confirm), _) => Some(self.town_cursor),
+            (Some(Command::Back), _) => Some(purchases.len()),
+            (_, entry) => entry,
+        };
+        let Some(index) = index.filter(|index| *index < PEDDLER_OPTION_COUNT) else {
+            return;
+        };
+        self.town_cursor = index;
+        match purchases.get(index) {
+            Some(action) => self.apply_town_action(*action),
+            None => self.leave_peddler(),
+        }
+    }
+
+    fn leave_peddler(&mut self) {
+        self.peddler = false;
+        self.town_cursor = 0;
+        self.mode = GameMode::Exploration;
+        self.push_log(t!("log.town.peddler_left"));
+    }
+
+    fn apply_town_action(&mut self, action: TownAction) {
+        if matches!(
+            action,
+            TownAction::BuyPotion
+                | TownAction::BuyEther
+                | TownAction::UpgradeWeapon
+                | TownAction::UpgradeArmor
+        ) && self.shops_closed()
+        {
+            return;
+        }
+        let gold_before = self.player.gold;
+        let materials_before = self.player.bag.materials;
+        let outcome = town::apply_action(
+            &mut self.player,
+            &mut self.party,
+            &mut self.quest,
+            &mut self.shop,
+            &mut self.clock,
+            self.campaign.ng_plus,
+            action,
+        );
+        self.campaign.stats.gold_spent += (gold_before - self.player.gold).max(0);
+        if matches!(action, TownAction::Craft(_)) && self.player.bag.materials != materials_before {
+            self.campaign.stats.items_crafted += 1;
+        }
+        match outcome {
+            TownOutcome::Stay(message) => {
+                self.recent_event = Some(message.clone());
+                self.push_log(message);
+            }
+            TownOutcome::OpenWorkshop(message) => {
+                self.workshop = true;
+                self.workshop_cursor = 0;
+                self.push_log(message);
+            }
+            TownOutcome::Leave(message) => {
+                self.mode = GameMode::Exploration;
+                self.push_log(message);
+            }
+        }
+    }
+
+    fn handle_settings_key(&mut self, code: KeyCode, command: Option<Command>) {
+        match (command, keymap::entry(code)) {
+            (Some(Command::MenuUp), _) => {
+                if self.settings_cursor == 0 {
                     self.settings_cursor = SETTINGS_OPTION_COUNT - 1;
                 } else {
                     self.settings_cursor -= 1;
                 }
             }
-            KeyCode::Down | KeyCode::Char('s') => {
+            (Some(Command::MenuDown), _) => {
                 self.settings_cursor = (self.settings_cursor + 1) % SETTINGS_OPTION_COUNT;
             }
-            KeyCode::Char('1') => self.select_language(0),
-            KeyCode::Char('2') => self.select_language(1),
-            KeyCode::Char('3') => self.select_language(2),
-            KeyCode::Char('4') => self.select_language(3),
-            KeyCode::Char('5') => self.select_language(4),
-            KeyCode::Char('6') => self.select_difficulty(0),
-            KeyCode::Char('7') => self.select_difficulty(1),
-            KeyCode::Char('8') => self.select_difficulty(2),
-            KeyCode::Enter => self.select_setting_at_cursor(),
-            KeyCode::Char('b') => self.close_settings(),
+            (Some(Command::MenuLeft), _) => self.adjust_setting(-1),
+            (Some(Command::MenuRight), _) => self.adjust_setting(1),
+            (Some(Command::Confirm), _) => self.select_setting_at_cursor(),
+            (Some(Command::Back), _) => self.close_settings(),
+            (Some(Command::ResetDifficulty), _) => self.reset_custom_difficulty(),
+            (Some(Command::ResetPrompts), _) => self.reset_confirm_prompts(),
+            // Languages, difficulties and encounters lead the list, so the
+            // number keys reach all of them.
+            (_, Some(idx)) => {
+                self.settings_cursor = idx;
+                self.select_setting_at_cursor();
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_dialogue_key(&mut self, code: KeyCode, command: Option<Command>) {
+        let choice_count = self.dialogue_choice_texts().len();
+        match (command, keymap::entry(code)) {
+            (Some(Command::MenuUp), _) if choice_count > 0 => {
+                if self.dialogue_cursor == 0 {
+                    self.dialogue_cursor = choice_count - 1;
+                } else {
+                    self.dialogue_cursor -= 1;
+                }
+            }
+            (Some(Command::MenuDown), _) if choice_count > 0 => {
+                self.dialogue_cursor = (self.dialogue_cursor + 1) % choice_count;
+            }
+            (Some(Command::Confirm), _) => self.choose_dialogue_option(self.dialogue_cursor),
+            (Some(Command::Back), _) => self.end_dialogue(),
+            (_, Some(idx)) => self.choose_dialogue_option(idx),
             _ => {}
         }
     }
 
-    fn handle_battle_key(&mut self, code: KeyCode) {
-        let action = match code {
-            KeyCode::Up | KeyCode::Char('w') => {
+    fn handle_event_key(&mut self, code: KeyCode, command: Option<Command>) {
+        let choice_count = self.event_choice_texts().len();
+        match (command, keymap::entry(code)) {
+            (Some(Command::MenuUp), _) if choice_count > 0 => {
+                if self.event_cursor == 0 {
+                    self.event_cursor = choice_count - 1;
+                } else {
+                    self.event_cursor -= 1;
+                }
+            }
+            (Some(Command::MenuDown), _) if choice_count > 0 => {
+                self.event_cursor = (self.event_cursor + 1) % choice_count;
+            }
+            (Some(Command::Confirm), _) => self.choose_event_option(self.event_cursor),
+            (_, Some(idx)) => self.choose_event_option(idx),
+            _ => {}
+        }
+    }
+
+    fn handle_battle_key(&mut self, code: KeyCode, command: Option<Command>) {
+        if self.pending_action.is_some() {
+            self.handle_order_key(code, command);
+            return;
+        }
+        if self.skill_menu {
+            self.handle_skill_key(code, command);
+            return;
+        }
+        let Some(command) = command else {
+            return;
+        };
+        let skill = self.signature_skill();
+        let action = match command {
+            Command::MenuUp => {
                 if self.battle_cursor == 0 {
                     self.battle_cursor = battle::ACTION_COUNT - 1;
                 } else {
@@ -354,56 +1516,265 @@ impl Game {
                 }
                 None
             }
-            KeyCode::Down | KeyCode::Char('s') => {
+            Command::MenuDown => {
                 self.battle_cursor = (self.battle_cursor + 1) % battle::ACTION_COUNT;
                 None
             }
-            KeyCode::Enter => Some(battle::action_from_index(self.battle_cursor)),
-            _ => battle::action_from_key(code),
+            Command::Confirm => Some(battle::action_from_index(self.battle_cursor, skill)),
+            _ => battle::action_from_command(command, skill),
         };
 
         let Some(action) = action else {
             return;
         };
         self.battle_cursor = battle::action_index(action);
+        if matches!(action, BattleAction::Skill(_)) && self.player.skills().len() > 1 {
+            self.skill_menu = true;
+            self.skill_cursor = 0;
+            return;
+        }
+        self.commit_hero_action(action);
+    }
+
+    /// The class's first skill, which is always known.
+    fn signature_skill(&self) -> HeroSkill {
+        self.player.class.skills()[0]
+    }
+
+    /// Number keys pick skills by position here, ahead of battle commands.
+    fn handle_skill_key(&mut self, code: KeyCode, command: Option<Command>) {
+        let skills = self.player.skills();
+        let skill = match (command, keymap::entry(code)) {
+            (_, Some(idx)) => skills.get(idx).copied(),
+            (Some(Command::MenuUp), _) => {
+                self.skill_cursor = (self.skill_cursor + skills.len() - 1) % skills.len();
+                None
+            }
+            (Some(Command::MenuDown), _) => {
+                self.skill_cursor = (self.skill_cursor + 1) % skills.len();
+                None
+            }
+            (Some(Command::Confirm), _) => skills.get(self.skill_cursor).copied(),
+            (Some(Command::Back), _) => {
+                self.skill_menu = false;
+                None
+            }
+            _ => None,
+        };
+
+        let Some(skill) = skill else {
+            return;
+        };
+        self.skill_menu = false;
+        self.commit_hero_action(BattleAction::Skill(skill));
+    }
+
+    /// Queues the hero's action behind companion orders, or resolves it at once.
+    fn commit_hero_action(&mut self, action: BattleAction) {
+        if action != BattleAction::Run && self.party.iter().any(|member| !member.is_down()) {
+            self.pending_action = Some(action);
+            self.battle_orders.clear();
+            self.order_cursor = 0;
+            self.skip_downed_members();
+            return;
+        }
         self.resolve_battle_action(action);
     }
 
+    /// Collects one order per standing companion after the hero has chosen.
+    /// Number keys pick orders by position, ahead of battle commands.
+    fn handle_order_key(&mut self, code: KeyCode, command: Option<Command>) {
+        let order = match (command, keymap::entry(code)) {
+            (_, Some(idx)) if idx < ORDER_OPTION_COUNT => Some(order_from_index(idx)),
+            (Some(Command::MenuUp), _) => {
+                if self.order_cursor == 0 {
+                    self.order_cursor = ORDER_OPTION_COUNT - 1;
+                } else {
+                    self.order_cursor -= 1;
+                }
+                None
+            }
+            (Some(Command::MenuDown), _) => {
+                self.order_cursor = (self.order_cursor + 1) % ORDER_OPTION_COUNT;
+                None
+            }
+            (Some(Command::Confirm), _) => Some(order_from_index(self.order_cursor)),
+            (Some(Command::Back), _) => {
+                self.pending_action = None;
+                self.battle_orders.clear();
+                None
+            }
+            _ => None,
+        };
+
+        let Some(order) = order else {
+            return;
+        };
+        self.battle_orders.push(order);
+        self.order_cursor = 0;
+        self.skip_downed_members();
+        if self.battle_orders.len() >= self.party.len()
+            && let Some(action) = self.pending_action.take()
+        {
+            self.resolve_battle_action(action);
+        }
+    }
+
+    fn skip_downed_members(&mut self) {
+        while let Some(member) = self.party.get(self.battle_orders.len())
+            && member.is_down()
+        {
+            self.battle_orders.push(CompanionOrder::Defend);
+        }
+    }
+
+    pub fn stat_point_gain(&self, stat: StatKind) -> i32 {
+        progression::stat_point_gain(stat)
+    }
+
+    /// Who the tavern would hire next, and for how much.
+    pub fn tavern_offer(&self) -> Option<(CompanionKind, i32)> {
+        town::tavern_offer(&self.party).map(|kind| (kind, companion_template(kind).hire_cost))
+    }
+
+    pub fn recipes(&self) -> &'static [Recipe] {
+        crafting::recipes()
+    }
+
+    pub fn ng_tier(&self) -> u32 {
+        self.campaign.ng_plus
+    }
+
+    pub fn unlock_cost(&self, unlock: Unlock) -> u32 {
+        unlock_cost(unlock)
+    }
+
+    pub fn can_craft(&self, recipe: &Recipe) -> bool {
+        crafting::can_craft(&self.player, recipe)
+    }
+
+    /// Locale key of the warning over the enemy in the battle scene, if any.
+    pub fn enemy_tell(&self) -> Option<&'static str> {
+        battle::enemy_tell(self.battle.as_ref()?, &self.player)
+    }
+
+    /// The party slot whose order is being chosen, if the hero is waiting on one.
+    pub fn choosing_member(&self) -> Option<usize> {
+        self.pending_action?;
+        Some(self.battle_orders.len()).filter(|slot| *slot < self.party.len())
+    }
+
     fn resolve_battle_action(&mut self, action: BattleAction) {
+        let orders = std::mem::take(&mut self.battle_orders);
+        self.pending_action = None;
         let Some(mut battle) = self.battle.take() else {
             self.mode = GameMode::Exploration;
             return;
         };
 
-        let result = battle::resolve_turn(
+        let potions_before = self.player.bag.potion;
+        let before = (battle.damage_dealt, battle.damage_taken);
+        let result = battle::resolve_round(
             action,
+            &orders,
             &mut battle,
             &mut self.player,
+            &mut self.party,
             &mut self.rng,
             &self.difficulty_profile,
         );
+        self.campaign.stats.potions_used += (potions_before - self.player.bag.potion).max(0) as u32;
+        self.queue_battle_effects(&battle, before);
+        self.apply_turn_result(battle, result);
+    }
+
+    /// Queues the damage numbers of the round that moved the battle's damage
+    /// totals on from `before` (dealt, taken).
+    fn queue_battle_effects(&mut self, battle: &Battle, before: (i32, i32)) {
+        self.battle_effects.clear();
+        if !self.animations {
+            return;
+        }
+        let dealt = battle.damage_dealt - before.0;
+        let taken = battle.damage_taken - before.1;
+        for (side, amount) in [(BattleSide::Foes, dealt), (BattleSide::Hero, taken)] {
+            if amount > 0 {
+                self.battle_effects.push(BattleEffect {
+                    side,
+                    amount,
+                    frame: 0,
+                });
+            }
+        }
+    }
+
+    /// Moves battle effects on by one animation tick.
+    pub fn step_animations(&mut self) {
+        for effect in &mut self.battle_effects {
+            effect.frame += 1;
+        }
+        self.battle_effects
+            .retain(|effect| effect.frame < BattleEffect::FRAMES);
+    }
+
+    /// Input while effects play only cuts them short.
+    fn skip_battle_effects(&mut self) -> bool {
+        if self.battle_effects.is_empty() {
+            return false;
+        }
+        self.battle_effects.clear();
+        true
+    }
+
+    fn apply_turn_result(&mut self, battle: Battle, result: TurnResult) {
         for message in result.logs {
             self.push_log(message);
         }
+        let survived = matches!(
+            result.outcome,
+            BattleOutcome::Escaped | BattleOutcome::EnemyFled | BattleOutcome::EnemyDefeated(_)
+        );
+        if !matches!(result.outcome, BattleOutcome::Continue) {
+            self.campaign.stats.damage_dealt += battle.damage_dealt;
+            self.campaign.stats.damage_taken += battle.damage_taken;
+            // The scene is gone, so nothing is left to play the effects over.
+            self.battle_effects.clear();
+        }
 
         match result.outcome {
             BattleOutcome::Continue => {
                 self.mode = GameMode::Battle;
                 self.battle = Some(battle);
             }
-            BattleOutcome::Escaped => {
+            BattleOutcome::Escaped | BattleOutcome::EnemyFled => {
                 self.mode = GameMode::Exploration;
                 self.battle = None;
                 self.battle_origin = None;
+                self.battle_boss = None;
+                if let Some(id) = self.battle_monster.take()
+                    && let Some(monster) = self.world.monster_mut(id)
+                {
+                    monster.hp_percent = battle.enemy.hp * 100 / battle.enemy.max_hp.max(1);
+                    monster.stunned_turns = ROAMING_ESCAPE_STUN_TURNS;
+                }
             }
             BattleOutcome::EnemyDefeated(enemy) => self.win_battle(enemy),
             BattleOutcome::PlayerDefeated => {
+                self.campaign.stats.death_cause = Some(battle.enemy.record_key().to_string());
                 self.mode = GameMode::GameOver;
                 self.battle = None;
                 self.battle_origin = None;
-                self.push_log(t!("log.game.player_fallen_restart"));
+                self.battle_monster = None;
+                self.battle_boss = None;
+                self.push_log(t!(
+                    "log.game.player_fallen_restart",
+                    key = self.key_label(Command::Restart)
+                ));
             }
         }
+        if survived && self.prefs.autosave.after_battle {
+            self.autosave("log.autosave.reason.battle");
+        }
     }
 
     fn try_move_player(&mut self, dx: i32, dy: i32) {
@@ -418,27 +1789,114 @@ impl Game {
         if self.map[ny][nx] == Tile::Wall {
             return;
         }
+        if self.roaming_enabled()
+            && let Some(monster) = self.world.monster_at(nx, ny)
+        {
+            let preemptive = roaming::is_rear_approach(monster, dx, dy);
+            self.start_roaming_battle(monster.id, preemptive);
+            return;
+        }
 
         self.player.x = nx;
         self.player.y = ny;
+        self.reveal_around_player();
+        self.advance_clock(1);
+        self.moves_since_autosave += 1;
         match self.map[ny][nx] {
             Tile::Town => {
                 self.player.hp = self.player.max_hp;
                 self.player.mp = self.player.max_mp;
-                self.mode = GameMode::Town;
-                self.town_cursor = 0;
+                party::restore_all(&mut self.party);
                 self.push_log(t!("log.town.arrived_restore"));
+                self.enter_town_menu();
+                self.town_cursor = 0;
+            }
+            Tile::Lair if self.world.lair_sealed() => {
+                self.announce_event(
+                    t!(
+                        "log.campaign.lair_sealed",
+                        broken = self.world.seals_broken(),
+                        total = self.world.boss_sites.len()
+                    )
+                    .to_string(),
+                );
             }
-            Tile::Lair => self.start_boss_battle(),
+            Tile::Lair => self.start_boss_battle(BossId::AncientDragon),
             Tile::Floor => self.handle_floor_tile(nx, ny),
             Tile::Wall => {}
         }
+        if self.mode == GameMode::Exploration && self.roaming_enabled() {
+            self.advance_monsters();
+        }
+        let every = self.prefs.autosave.every_moves;
+        if self.mode == GameMode::Exploration && every > 0 && self.moves_since_autosave >= every {
+            self.autosave("log.autosave.reason.moves");
+        }
+    }
+
+    pub fn time_of_day(&self) -> TimeOfDay {
+        self.clock.time_of_day()
+    }
+
+    fn advance_clock(&mut self, turns: u32) {
+        self.campaign.stats.turns += turns;
+        let before = self.time_of_day();
+        self.clock.advance(turns);
+        let after = self.time_of_day();
+        if before == after {
+            return;
+        }
+        if before.is_night() != after.is_night() && self.roaming_enabled() {
+            roaming::change_shift(&mut self.world, after.is_night(), &mut self.rng);
+        }
+        match after {
+            TimeOfDay::Night => self.push_log(t!("log.time.nightfall")),
+            TimeOfDay::Dawn => self.push_log(t!("log.time.dawn", day = self.clock.day())),
+            TimeOfDay::Day | TimeOfDay::Dusk => {}
+        }
+    }
+
+    fn enter_town_menu(&mut self) {
+        self.mode = GameMode::Town;
+        self.workshop = false;
+        self.peddler = false;
+        if self.shop.restock_if_due(self.clock) {
+            self.push_log(t!("log.town.shop_restocked"));
+        }
+        if self.prefs.autosave.enter_town {
+            self.autosave("log.autosave.reason.town");
+        }
+    }
+
+    fn roaming_enabled(&self) -> bool {
+        self.encounter_mode == EncounterMode::Roaming
+    }
+
+    fn advance_monsters(&mut self) {
+        let player = Position {
+            x: self.player.x,
+            y: self.player.y,
+        };
+        if let Some(id) =
+            roaming::advance_monsters(&self.map, &mut self.world, player, &mut self.rng)
+        {
+            self.start_roaming_battle(id, false);
+        }
     }
 
     fn handle_floor_tile(&mut self, x: usize, y: usize) {
+        if let Some(site) = self.world.boss_site_at(x, y) {
+            self.start_boss_battle(site.boss);
+            return;
+        }
         if self.interact_chest(x, y) {
             return;
         }
+        if let Some(message) = crafting::gather(&mut self.world, &mut self.player, x, y) {
+            self.world.mark_tile_cleared(x, y);
+            self.announce_event(message);
+            return;
+        }
         if self.interact_npc(x, y) {
             return;
         }
@@ -446,28 +1904,93 @@ impl Game {
             return;
         }
 
-        let encounter_rate = self
-            .difficulty_profile
-            .random_encounter_rate_percent
-            .clamp(0, 100);
-        if self.rng.random_range(0..100) < encounter_rate {
+        let night_bonus = if self.time_of_day().is_night() {
+            NIGHT_ENCOUNTER_RATE_BONUS
+        } else {
+            0
+        };
+        let encounter_rate =
+            (self.difficulty_profile.random_encounter_rate_percent + night_bonus).clamp(0, 100);
+        if !self.roaming_enabled() && self.rng.random_range(0..100) < encounter_rate {
             self.start_random_battle(Position { x, y });
             return;
         }
 
-        if let Some(event_result) = event::maybe_trigger_event(
+        let region = event::region_at(&self.map, Position { x, y });
+        if let Some(outcome) = event::maybe_trigger_event(
             &mut self.rng,
             &mut self.player,
-            self.difficulty_profile.world_event_rate_percent,
+            region,
+            &self.difficulty_profile,
         ) {
-            self.announce_event(event_result.message);
-            if event_result.player_dead {
-                self.mode = GameMode::GameOver;
-                self.battle = None;
-                self.push_log(t!("log.game.player_fallen_restart"));
-                return;
-            }
-            self.world.mark_tile_cleared(x, y);
+            self.event_origin = Some(Position { x, y });
+            self.apply_event_outcome(outcome);
+        }
+    }
+
+    pub fn event_text(&self) -> Option<String> {
+        self.world_event
+            .as_ref()
+            .map(|state| event::message_text(&state.message))
+    }
+
+    pub fn event_choice_texts(&self) -> Vec<String> {
+        let Some(step) = self.world_event.as_ref().and_then(event::current_step) else {
+            return Vec::new();
+        };
+        event::visible_choices(step, &self.player)
+            .into_iter()
+            .map(|choice| t!(choice.text.as_str()).to_string())
+            .collect()
+    }
+
+    fn choose_event_option(&mut self, index: usize) {
+        let Some(state) = self.world_event.clone() else {
+            self.mode = GameMode::Exploration;
+            return;
+        };
+        if let Some(outcome) = event::choose(&state, index, &mut self.rng, &mut self.player) {
+            self.apply_event_outcome(outcome);
+        }
+    }
+
+    fn apply_event_outcome(&mut self, outcome: StepOutcome) {
+        self.campaign.stats.gold_spent += outcome.gold_paid;
+        let text = event::message_text(&outcome.state.message);
+        if !text.is_empty() {
+            self.announce_event(text);
+        }
+        if !outcome.finished {
+            self.world_event = Some(outcome.state);
+            self.event_cursor = 0;
+            self.mode = GameMode::Event;
+            return;
+        }
+
+        self.world_event = None;
+        self.event_cursor = 0;
+        self.mode = GameMode::Exploration;
+        let origin = self.event_origin.take();
+        if outcome.player_dead {
+            self.campaign.stats.death_cause = Some("ui.result.cause_event".to_string());
+            self.mode = GameMode::GameOver;
+            self.battle = None;
+            self.push_log(t!(
+                "log.game.player_fallen_restart",
+                key = self.key_label(Command::Restart)
+            ));
+            return;
+        }
+        if outcome.start_battle {
+            let origin = origin.unwrap_or(Position {
+                x: self.player.x,
+                y: self.player.y,
+            });
+            self.start_random_battle(origin);
+            return;
+        }
+        if let Some(origin) = origin {
+            self.world.mark_tile_cleared(origin.x, origin.y);
         }
     }
 
@@ -503,77 +2026,335 @@ impl Game {
     }
 
     fn interact_npc(&mut self, x: usize, y: usize) -> bool {
-        let interaction = {
-            let Some(npc) = self.world.npc_at_mut(x, y) else {
-                return false;
-            };
-            if npc.interacted {
-                return false;
-            }
-            npc.interacted = true;
-            Some((npc.kind, npc.reward_gold))
+        let Some(npc) = self.world.npc_at_mut(x, y) else {
+            return false;
         };
-
-        let Some((kind, reward_gold)) = interaction else {
+        let kind = npc.kind;
+        if !kind.is_present(self.clock.time_of_day()) {
+            return false;
+        }
+        let Some(mut state) = dialogue::start(kind.dialogue_id()) else {
             return false;
         };
-        self.player.gold += reward_gold;
+        state.npc = Some(Position { x, y });
         self.world.mark_tile_cleared(x, y);
-        self.push_log(t!(kind.line_key()).to_string());
-        self.announce_event(t!("log.world.npc_reward", gold = reward_gold).to_string());
+        if let Some(script) = dialogue::script(&state.script) {
+            self.push_log(t!(
+                "log.dialogue.started",
+                speaker = t!(script.speaker.as_str())
+            ));
+        }
+        self.dialogue = Some(state);
+        self.dialogue_cursor = 0;
+        self.mode = GameMode::Dialogue;
+        true
+    }
+
+    fn dialogue_context(&self) -> Option<(&'static dialogue::DialogueNode, DialogueContext<'_>)> {
+        let state = self.dialogue.as_ref()?;
+        let node = dialogue::current_node(state)?;
+        let first_meeting = state
+            .npc
+            .and_then(|pos| {
+                self.world
+                    .npcs
+                    .iter()
+                    .find(|npc| npc.position == pos)
+                    .map(|npc| !npc.interacted)
+            })
+            .unwrap_or(false);
+        let ctx = DialogueContext {
+            player: &self.player,
+            quest: &self.quest,
+            flags: &self.story_flags,
+            first_meeting,
+            party_room: party::has_room(&self.party),
+        };
+        Some((node, ctx))
+    }
+
+    pub fn dialogue_text(&self) -> Option<(String, String)> {
+        let state = self.dialogue.as_ref()?;
+        let script = dialogue::script(&state.script)?;
+        let node = dialogue::current_node(state)?;
+        Some((
+            t!(script.speaker.as_str()).to_string(),
+            t!(node.text.as_str()).to_string(),
+        ))
+    }
+
+    pub fn dialogue_choice_texts(&self) -> Vec<String> {
+        let Some((node, ctx)) = self.dialogue_context() else {
+            return Vec::new();
+        };
+        dialogue::visible_choices(node, &ctx)
+            .into_iter()
+            .map(|choice| t!(choice.text.as_str()).to_string())
+            .collect()
+    }
+
+    fn choose_dialogue_option(&mut self, index: usize) {
+        let choice = {
+            let Some((node, ctx)) = self.dialogue_context() else {
+                self.end_dialogue();
+                return;
+            };
+            let Some(choice) = dialogue::visible_choices(node, &ctx).get(index).copied() else {
+                return;
+            };
+            choice.clone()
+        };
+
+        for effect in &choice.effects {
+            if !self.apply_dialogue_effect(effect) {
+                break;
+            }
+        }
+        if self.mode != GameMode::Dialogue {
+            return;
+        }
+        match (choice.next, self.dialogue.as_mut()) {
+            (Some(next), Some(state)) => {
+                state.node = next;
+                self.dialogue_cursor = 0;
+            }
+            _ => self.end_dialogue(),
+        }
+    }
+
+    /// Applies one effect of a dialogue choice; `false` when it fell through,
+    /// so the effects after it are skipped.
+    fn apply_dialogue_effect(&mut self, effect: &DialogueEffect) -> bool {
+        match effect {
+            DialogueEffect::GiveGold(gold) => {
+                self.player.gold += gold;
+                self.push_log(t!("log.dialogue.gold_received", gold = gold));
+            }
+            DialogueEffect::TakeGold(gold) => {
+                let paid = (*gold).min(self.player.gold);
+                self.player.gold -= paid;
+                self.campaign.stats.gold_spent += paid;
+                self.push_log(t!("log.dialogue.gold_paid", gold = paid));
+            }
+            DialogueEffect::GiveItem { item, count } => {
+                match item {
+                    ItemKind::Potion => self.player.bag.potion += count,
+                    ItemKind::Ether => self.player.bag.ether += count,
+                }
+                self.announce_event(
+                    t!(
+                        "log.dialogue.item_received",
+                        item = t!(item.i18n_key()),
+                        count = count
+                    )
+                    .to_string(),
+                );
+            }
+            DialogueEffect::StartQuest => {
+                if !self.quest.accepted {
+                    self.quest.accepted = true;
+                    self.announce_event(
+                        t!(
+                            "log.quest.accepted",
+                            target = self.quest.target_kills,
+                            reward = self.quest.reward_gold
+                        )
+                        .to_string(),
+                    );
+                }
+            }
+            DialogueEffect::OpenShop => {
+                self.finish_dialogue();
+                self.mode = GameMode::Town;
+                self.workshop = false;
+                self.peddler = true;
+                self.town_cursor = 0;
+                self.push_log(t!("log.town.peddler_opened"));
+            }
+            DialogueEffect::RevealArea { target, radius } => {
+                if let Some(center) = self.reveal_target_position(*target) {
+                    self.world.reveal_around(center, *radius);
+                    self.announce_event(t!("log.dialogue.area_revealed").to_string());
+                }
+            }
+            DialogueEffect::SetFlag(flag) => {
+                self.story_flags.insert(flag.clone());
+            }
+            DialogueEffect::NpcGift => {
+                let gift = self
+                    .dialogue
+                    .as_ref()
+                    .and_then(|state| state.npc)
+                    .and_then(|pos| self.world.npc_at_mut(pos.x, pos.y))
+                    .filter(|npc| !npc.interacted)
+                    .map(|npc| {
+                        npc.interacted = true;
+                        npc.reward_gold
+                    });
+                if let Some(gold) = gift {
+                    self.player.gold += gold;
+                    self.announce_event(t!("log.world.npc_reward", gold = gold).to_string());
+                }
+            }
+            DialogueEffect::Recruit(kind) => {
+                let result = party::recruit(&mut self.party, *kind, self.player.level);
+                let joined = result.is_ok();
+                let (Ok(message) | Err(message)) = result;
+                self.announce_event(message);
+                return joined;
+            }
+        }
         true
     }
 
+    fn reveal_target_position(&self, target: RevealTarget) -> Option<Position> {
+        match target {
+            RevealTarget::Lair => self.map.iter().enumerate().find_map(|(y, row)| {
+                row.iter()
+                    .position(|tile| *tile == Tile::Lair)
+                    .map(|x| Position { x, y })
+            }),
+            RevealTarget::NearestChest => self
+                .world
+                .chests
+                .iter()
+                .filter(|chest| !chest.opened)
+                .min_by_key(|chest| {
+                    chest.position.x.abs_diff(self.player.x)
+                        + chest.position.y.abs_diff(self.player.y)
+                })
+                .map(|chest| chest.position),
+        }
+    }
+
+    fn finish_dialogue(&mut self) {
+        if let Some(pos) = self.dialogue.take().and_then(|state| state.npc)
+            && let Some(npc) = self.world.npc_at_mut(pos.x, pos.y)
+        {
+            npc.interacted = true;
+        }
+        self.dialogue_cursor = 0;
+    }
+
+    fn end_dialogue(&mut self) {
+        let with_npc = self
+            .dialogue
+            .as_ref()
+            .is_some_and(|state| state.npc.is_some());
+        self.finish_dialogue();
+        self.mode = GameMode::Exploration;
+        if with_npc {
+            self.push_log(t!("log.dialogue.ended"));
+        }
+    }
+
+    fn reveal_around_player(&mut self) {
+        let center = Position {
+            x: self.player.x,
+            y: self.player.y,
+        };
+        self.world.reveal_around(center, EXPLORE_SIGHT_RADIUS);
+    }
+
     fn start_random_battle(&mut self, origin: Position) {
-        let enemy = encounter::generate_enemy(
+        let mut enemy = encounter::generate_enemy(
             &mut self.rng,
             self.player.level,
             false,
+            self.clock.time_of_day().is_night(),
             &self.difficulty_profile,
         );
+        legacy::adjust_enemy(&mut enemy, &self.campaign.unlocks, &mut self.rng);
         self.push_log(t!("log.battle.wild_appears", enemy = enemy.name.as_str()));
-        self.battle = Some(Battle {
-            enemy,
-            defending: false,
-        });
+        self.campaign.stats.battles += 1;
+        self.battle = Some(Battle::new(enemy));
         self.mode = GameMode::Battle;
         self.battle_cursor = 0;
         self.battle_origin = Some(origin);
     }
 
-    fn start_boss_battle(&mut self) {
-        let enemy = encounter::generate_enemy(
-            &mut self.rng,
+    fn start_roaming_battle(&mut self, id: u32, preemptive: bool) {
+        let Some(monster) = self.world.monsters.iter().find(|m| m.id == id).cloned() else {
+            return;
+        };
+        let mut enemy = encounter::generate_from_template(
+            monster.template,
             self.player.level,
-            true,
             &self.difficulty_profile,
         );
+        legacy::adjust_enemy(&mut enemy, &self.campaign.unlocks, &mut self.rng);
+        enemy.hp = (enemy.max_hp * monster.hp_percent / 100).clamp(1, enemy.max_hp);
+        let key = if preemptive {
+            "log.battle.roaming_bumped"
+        } else {
+            "log.battle.roaming_engaged"
+        };
+        self.push_log(t!(key, enemy = enemy.name.as_str()));
+        self.campaign.stats.battles += 1;
+
+        let mut battle = Battle::new(enemy);
+        self.mode = GameMode::Battle;
+        self.battle_cursor = 0;
+        self.battle_origin = None;
+        self.battle_monster = Some(id);
+        if preemptive {
+            let result = battle::preemptive_strike(&mut battle, &self.player, &mut self.rng);
+            self.queue_battle_effects(&battle, (0, 0));
+            self.apply_turn_result(battle, result);
+        } else {
+            self.battle = Some(battle);
+        }
+    }
+
+    fn start_boss_battle(&mut self, boss: BossId) {
+        let mut enemy = encounter::generate_boss(boss, self.player.level, &self.difficulty_profile);
+        legacy::adjust_enemy(&mut enemy, &self.campaign.unlocks, &mut self.rng);
         self.push_log(t!(
             "log.battle.boss_blocks_path",
             enemy = enemy.name.as_str()
         ));
-        self.battle = Some(Battle {
-            enemy,
-            defending: false,
-        });
+        self.campaign.stats.battles += 1;
+        self.battle = Some(Battle::new(enemy));
         self.mode = GameMode::Battle;
         self.battle_cursor = 0;
         self.battle_origin = None;
+        self.battle_boss = Some(boss);
     }
 
     fn win_battle(&mut self, enemy: Enemy) {
         self.battle = None;
-        self.battle_origin
-            .take()
-            .map(|origin| self.world.mark_tile_cleared(origin.x, origin.y));
+        if let Some(origin) = self.battle_origin.take() {
+            self.world.mark_tile_cleared(origin.x, origin.y);
+        }
+        if let Some(id) = self.battle_monster.take() {
+            self.world.remove_monster(id);
+        }
+        self.campaign.enemies_defeated += 1;
+        self.campaign.gold_earned += enemy.gold_reward;
+        *self
+            .campaign
+            .stats
+            .kills
+            .entry(enemy.record_key().to_string())
+            .or_default() += 1;
 
-        let reward_logs = progression::apply_battle_rewards(&mut self.player, &enemy);
+        let reward_logs =
+            progression::apply_battle_rewards(&mut self.player, &mut self.party, &enemy);
         if let Some(first) = reward_logs.first() {
             self.recent_event = Some(first.clone());
         }
         for log in reward_logs {
             self.push_log(log);
         }
+        if let Some((material, count)) = crafting::roll_loot(&enemy, &mut self.rng) {
+            self.player.bag.materials.add(material, count);
+            self.push_log(t!(
+                "log.battle.loot",
+                enemy = enemy.name.as_str(),
+                material = t!(material.name_key()),
+                count = count
+            ));
+        }
 
         if !enemy.is_boss {
             if self.quest.register_kill() {
@@ -598,8 +2379,53 @@ impl Game {
             return;
         }
 
-        self.mode = GameMode::Victory;
-        self.announce_event(t!("log.game.dragon_defeated_restart_or_quit").to_string());
+        let boss = self.battle_boss.take().unwrap_or(BossId::AncientDragon);
+        self.campaign.bosses_defeated.push(boss);
+        if boss == BossId::AncientDragon {
+            self.mode = GameMode::Victory;
+            self.announce_event(
+                t!(
+                    "log.game.dragon_defeated_restart_or_quit",
+                    quit = self.key_label(Command::Quit),
+                    restart = self.key_label(Command::Restart)
+                )
+                .to_string(),
+            );
+            return;
+        }
+        self.break_seal(boss);
+    }
+
+    fn break_seal(&mut self, boss: BossId) {
+        if let Some(site) = self
+            .world
+            .boss_sites
+            .iter_mut()
+            .find(|site| site.boss == boss && !site.defeated)
+        {
+            site.defeated = true;
+            let pos = site.position;
+            self.world.mark_tile_cleared(pos.x, pos.y);
+        }
+        let remaining = self.world.boss_sites.len() - self.world.seals_broken();
+        let message = if remaining == 0 {
+            t!("log.campaign.lair_unsealed").to_string()
+        } else {
+            t!(
+                "log.campaign.seal_broken",
+                boss = t!(boss.name_key()),
+                remaining = remaining
+            )
+            .to_string()
+        };
+        self.announce_event(message);
+
+        self.mode = GameMode::Exploration;
+        if let Some(state) = boss.story_script().and_then(dialogue::start) {
+            self.dialogue = Some(state);
+            self.dialogue_cursor = 0;
+            self.mode = GameMode::Dialogue;
+        }
     }
 
     fn current_tile(&self) -> Tile {
@@ -622,6 +2448,159 @@ impl Game {
         self.mode = to;
     }
 
+    fn handle_class_key(&mut self, code: KeyCode, command: Option<Command>) {
+        let count = HeroClass::ALL.len();
+        match (command, keymap::entry(code)) {
+            (Some(Command::MenuUp), _) => {
+                self.class_cursor = (self.class_cursor + count - 1) % count;
+            }
+            (Some(Command::MenuDown), _) => {
+                self.class_cursor = (self.class_cursor + 1) % count;
+            }
+            (Some(Command::Confirm), _) => self.choose_class(self.class_cursor),
+            (Some(Command::OpenLegacy), _) => self.mode = GameMode::Legacy,
+            (Some(Command::OpenChallenges), _) => self.mode = GameMode::Challenge,
+            (_, Some(idx)) => self.choose_class(idx),
+            _ => {}
+        }
+    }
+
+    fn choose_class(&mut self, idx: usize) {
+        let Some(class) = HeroClass::ALL.get(idx).copied() else {
+            return;
+        };
+        let (x, y) = (self.player.x, self.player.y);
+        self.player = Player::with_class(class);
+        self.player.x = x;
+        self.player.y = y;
+        self.class_cursor = idx;
+        self.mode = GameMode::Exploration;
+        self.announce_event(t!("log.class.chosen", class = t!(class.label_key())).to_string());
+        if self.daily_challenge {
+            let day = challenge::today();
+            self.reseed(challenge::daily_seed(day));
+            self.campaign.daily = Some(day);
+            self.push_log(t!(
+                "log.challenge.daily_started",
+                date = challenge::date_label(day)
+            ));
+        }
+        self.campaign.modifiers = self.planned_modifiers();
+        self.difficulty_profile = self.run_profile(&self.campaign);
+        self.place_modifier_monsters();
+        if self.records.legacy.roguelite {
+            self.campaign.roguelite = true;
+            self.campaign.unlocks = self.records.legacy.active.clone();
+            legacy::apply_perks(&mut self.player, &self.campaign.unlocks);
+            self.push_log(t!("log.legacy.run_started"));
+        }
+        self.mark_progress_saved();
+    }
+
+    fn open_level_up(&mut self, from_mode: GameMode) {
+        self.level_up_return_mode = from_mode;
+        self.mode = GameMode::LevelUp;
+    }
+
+    fn handle_level_up_key(&mut self, code: KeyCode, command: Option<Command>) {
+        let count = StatKind::ALL.len();
+        let stat = match (command, keymap::entry(code)) {
+            (Some(Command::MenuUp), _) => {
+                self.level_up_cursor = (self.level_up_cursor + count - 1) % count;
+                None
+            }
+            (Some(Command::MenuDown), _) => {
+                self.level_up_cursor = (self.level_up_cursor + 1) % count;
+                None
+            }
+            (Some(Command::Confirm), _) => StatKind::ALL.get(self.level_up_cursor).copied(),
+            (Some(Command::Back | Command::LevelUp), _) => {
+                self.mode = self.level_up_return_mode;
+                None
+            }
+            (_, Some(idx)) => StatKind::ALL.get(idx).copied(),
+            _ => None,
+        };
+
+        let Some(stat) = stat else {
+            return;
+        };
+        self.level_up_cursor = StatKind::ALL.iter().position(|s| *s == stat).unwrap_or(0);
+        match progression::allocate_point(&mut self.player, stat) {
+            Some(message) => {
+                self.recent_event = Some(message.clone());
+                self.push_log(message);
+            }
+            None => self.push_log(t!("log.progression.no_points")),
+        }
+    }
+
+    fn open_party(&mut self, from_mode: GameMode) {
+        self.party_return_mode = from_mode;
+        self.party_cursor = self.party_cursor.min(self.party.len().saturating_sub(1));
+        self.mode = GameMode::Party;
+    }
+
+    fn handle_party_key(&mut self, command: Option<Command>) {
+        let count = self.party.len();
+        match command {
+            Some(Command::MenuUp) if count > 0 => {
+                self.party_cursor = (self.party_cursor + count - 1) % count;
+            }
+            Some(Command::MenuDown) if count > 0 => {
+                self.party_cursor = (self.party_cursor + 1) % count;
+            }
+            Some(Command::Back | Command::Party) => self.mode = self.party_return_mode,
+            Some(Command::MoveMemberUp) => {
+                self.party_cursor = party::move_member(&mut self.party, self.party_cursor, true);
+            }
+            Some(Command::MoveMemberDown) => {
+                self.party_cursor = party::move_member(&mut self.party, self.party_cursor, false);
+            }
+            Some(Command::UpgradeMemberWeapon) => self.upgrade_member(true),
+            Some(Command::UpgradeMemberArmor) => self.upgrade_member(false),
+            Some(Command::DismissMember) if self.party_cursor < count => {
+                let member = self.party.remove(self.party_cursor);
+                self.party_cursor = self.party_cursor.min(self.party.len().saturating_sub(1));
+                self.push_log(t!("log.party.dismissed", name = t!(member.kind.name_key())));
+            }
+            _ => {}
+        }
+    }
+
+    /// Companion gear is bought from the same smith as the hero's, so only in town.
+    fn upgrade_member(&mut self, weapon: bool) {
+        if self.current_tile() != Tile::Town {
+            self.push_log(t!("log.party.smith_in_town"));
+            return;
+        }
+        if self.shops_closed() {
+            return;
+        }
+        let Some(member) = self.party.get_mut(self.party_cursor) else {
+            return;
+        };
+        let name = t!(member.kind.name_key());
+        let gold_before = self.player.gold;
+        let result = if weapon {
+            town::upgrade_weapon(
+                &mut self.player.gold,
+                &mut member.equipment,
+                self.campaign.ng_plus,
+            )
+        } else {
+            town::upgrade_armor(
+                &mut self.player.gold,
+                &mut member.equipment,
+                self.campaign.ng_plus,
+            )
+        };
+        self.campaign.stats.gold_spent += gold_before - self.player.gold;
+        let message = t!("log.party.upgrade", name = name, result = result).to_string();
+        self.recent_event = Some(message.clone());
+        self.push_log(message);
+    }
+
     fn select_language(&mut self, idx: usize) {
         if idx >= Language::ALL.len() {
             return;
@@ -648,79 +2627,535 @@ impl Game {
         }
 
         self.difficulty = difficulty;
-        self.difficulty_profile = config::profile_for(difficulty);
+        self.difficulty_profile = self.run_profile(&self.campaign);
         let message = t!("log.game.difficulty", diff = t!(difficulty.label_key())).to_string();
         self.recent_event = Some(message.clone());
         self.push_log(message);
     }
 
+    fn toggle_encounter_mode(&mut self) {
+        self.settings_cursor = ENCOUNTER_OPTION_INDEX;
+        self.encounter_mode = self.encounter_mode.toggled();
+        let message = t!(
+            "log.settings.encounter_changed",
+            mode = t!(self.encounter_mode.label_key())
+        )
+        .to_string();
+        self.recent_event = Some(message.clone());
+        self.push_log(message);
+    }
+
     fn select_setting_at_cursor(&mut self) {
         if self.settings_cursor < LANGUAGE_OPTION_COUNT {
             self.select_language(self.settings_cursor);
             return;
         }
+        if self.settings_cursor == ENCOUNTER_OPTION_INDEX {
+            self.toggle_encounter_mode();
+            return;
+        }
+        if self.settings_cursor == KEYBINDS_OPTION_INDEX {
+            self.keybind_capture = false;
+            self.mode = GameMode::Keybinds;
+            return;
+        }
+        if self.settings_cursor == THEME_OPTION_INDEX {
+            self.change_theme(1);
+            return;
+        }
+        if self.settings_cursor == GLYPHS_OPTION_INDEX {
+            self.change_glyphs(1);
+            return;
+        }
+        if let Some(option) = self.selected_autosave_option() {
+            self.change_autosave(option, 1);
+            return;
+        }
+        if self.settings_cursor >= CUSTOM_OPTION_START {
+            self.select_difficulty(Difficulty::Custom.index());
+            return;
+        }
         self.select_difficulty(self.settings_cursor - DIFFICULTY_OPTION_START);
     }
-}
 
-fn town_action_from_cursor(cursor: usize) -> TownAction {
-    match cursor {
-        0 => TownAction::BuyPotion,
-        1 => TownAction::BuyEther,
-        2 => TownAction::UpgradeWeapon,
-        3 => TownAction::UpgradeArmor,
-        4 => TownAction::Healer,
-        5 => TownAction::Inn,
-        6 => TownAction::QuestBoard,
-        _ => TownAction::Leave,
+    /// The autosave setting under the settings cursor, if any.
+    pub fn selected_autosave_option(&self) -> Option<AutosaveOption> {
+        self.settings_cursor
+            .checked_sub(AUTOSAVE_OPTION_START)
+            .and_then(|idx| AutosaveOption::ALL.get(idx).copied())
     }
-}
 
-fn town_cursor_from_action(action: TownAction) -> usize {
-    match action {
-        TownAction::BuyPotion => 0,
-        TownAction::BuyEther => 1,
-        TownAction::UpgradeWeapon => 2,
-        TownAction::UpgradeArmor => 3,
-        TownAction::Healer => 4,
-        TownAction::Inn => 5,
-        TownAction::QuestBoard => 6,
-        TownAction::Leave => 7,
+    pub fn autosave_value_text(&self, option: AutosaveOption) -> String {
+        let autosave = &self.prefs.autosave;
+        let on = match option {
+            AutosaveOption::EnterTown => autosave.enter_town,
+            AutosaveOption::AfterBattle => autosave.after_battle,
+            AutosaveOption::EveryMoves => autosave.every_moves > 0,
+        };
+        if !on {
+            t!("ui.settings.autosave.off").to_string()
+        } else if option == AutosaveOption::EveryMoves {
+            t!("ui.settings.autosave.every", count = autosave.every_moves).to_string()
+        } else {
+            t!("ui.settings.autosave.on").to_string()
+        }
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use crossterm::event::KeyCode;
-
-    use super::Game;
-    use crate::game::model::{Battle, Enemy, EnemyStyle};
-    use crate::game::model::{GameMode, Position};
-
-    #[test]
-    fn town_purchase_through_handle_key_updates_player_state() {
-        rust_i18n::set_locale("en");
-        let mut game = Game::new_with_seed(7);
-        game.mode = GameMode::Town;
-        game.player.gold = 20;
-        game.player.bag.potion = 0;
 
-        game.handle_key(KeyCode::Char('1'));
-
-        assert_eq!(game.player.gold, 10);
-        assert_eq!(game.player.bag.potion, 1);
-        assert_eq!(game.mode, GameMode::Town);
+    fn adjust_setting(&mut self, steps: i32) {
+        if self.settings_cursor == THEME_OPTION_INDEX {
+            self.change_theme(steps);
+        } else if self.settings_cursor == GLYPHS_OPTION_INDEX {
+            self.change_glyphs(steps);
+        } else if let Some(option) = self.selected_autosave_option() {
+            self.change_autosave(option, steps);
+        } else {
+            self.adjust_custom_field(steps);
+        }
     }
 
-    #[test]
-    fn town_leave_through_handle_key_returns_to_exploration() {
-        rust_i18n::set_locale("en");
-        let mut game = Game::new_with_seed(11);
-        game.mode = GameMode::Town;
-
-        game.handle_key(KeyCode::Char('8'));
-
-        assert_eq!(game.mode, GameMode::Exploration);
+    /// Steps through the color themes and persists the choice.
+    fn change_theme(&mut self, steps: i32) {
+        self.prefs.theme = cycle(&ThemeId::ALL, self.prefs.theme, steps);
+        self.save_prefs();
+        self.announce_event(
+            t!(
+                "log.settings.theme_changed",
+                theme = t!(self.prefs.theme.label_key())
+            )
+            .to_string(),
+        );
+    }
+
+    /// Steps through the map glyph sets and persists the choice.
+    fn change_glyphs(&mut self, steps: i32) {
+        self.prefs.glyphs = cycle(&GlyphSetId::ALL, self.prefs.glyphs, steps);
+        self.save_prefs();
+        self.announce_event(
+            t!(
+                "log.settings.glyphs_changed",
+                glyphs = t!(self.prefs.glyphs.label_key())
+            )
+            .to_string(),
+        );
+    }
+
+    /// Flips an autosave toggle, or steps the move interval through its
+    /// choices, and persists the result.
+    fn change_autosave(&mut self, option: AutosaveOption, steps: i32) {
+        let autosave = &mut self.prefs.autosave;
+        match option {
+            AutosaveOption::EnterTown => autosave.enter_town = !autosave.enter_town,
+            AutosaveOption::AfterBattle => autosave.after_battle = !autosave.after_battle,
+            AutosaveOption::EveryMoves => {
+                autosave.every_moves = cycle(&AUTOSAVE_MOVE_CHOICES, autosave.every_moves, steps);
+            }
+        }
+        self.save_prefs();
+        self.announce_event(
+            t!(
+                "log.settings.autosave_changed",
+                option = t!(option.label_key()),
+                value = self.autosave_value_text(option)
+            )
+            .to_string(),
+        );
+    }
+
+    /// The custom difficulty slider under the settings cursor, if any.
+    pub fn selected_profile_field(&self) -> Option<ProfileField> {
+        self.settings_cursor
+            .checked_sub(CUSTOM_OPTION_START)
+            .and_then(|idx| ProfileField::ALL.get(idx).copied())
+    }
+
+    fn adjust_custom_field(&mut self, steps: i32) {
+        let Some(field) = self.selected_profile_field() else {
+            return;
+        };
+        let profile = &mut self.prefs.custom_difficulty;
+        let before = profile.value(field);
+        profile.nudge(field, steps);
+        if profile.value(field) == before {
+            return;
+        }
+        let message = t!(
+            "log.settings.custom_changed",
+            field = t!(field.label_key()),
+            value = profile.value_text(field)
+        )
+        .to_string();
+        self.custom_difficulty_changed(message);
+    }
+
+    fn reset_custom_difficulty(&mut self) {
+        self.prefs.custom_difficulty = prefs::default_custom_difficulty();
+        self.custom_difficulty_changed(t!("log.settings.custom_reset").to_string());
+    }
+
+    /// Applies an edited custom profile to a custom run and persists it.
+    fn custom_difficulty_changed(&mut self, message: String) {
+        if self.difficulty == Difficulty::Custom {
+            self.difficulty_profile = self.run_profile(&self.campaign);
+        }
+        self.save_prefs();
+        self.recent_event = Some(message.clone());
+        self.push_log(message);
+    }
+
+    fn run_profile(&self, campaign: &CampaignState) -> DifficultyProfile {
+        run_profile(self.difficulty, &self.prefs.custom_difficulty, campaign)
+    }
+
+    fn handle_keybinds_key(&mut self, command: Option<Command>) {
+        let count = Command::ALL.len();
+        match command {
+            Some(Command::MenuUp) => {
+                self.keybind_cursor = (self.keybind_cursor + count - 1) % count;
+            }
+            Some(Command::MenuDown) => {
+                self.keybind_cursor = (self.keybind_cursor + 1) % count;
+            }
+            Some(Command::MenuLeft) => self.cycle_keymap_preset(false),
+            Some(Command::MenuRight) => self.cycle_keymap_preset(true),
+            Some(Command::Confirm) => self.keybind_capture = true,
+            Some(Command::Back) => self.mode = GameMode::Settings,
+            Some(Command::ResetBinding) => self.reset_binding(),
+            _ => {}
+        }
+    }
+
+    /// Switches to the previous or next preset, dropping personal rebinds.
+    fn cycle_keymap_preset(&mut self, forward: bool) {
+        let presets = keymap::presets();
+        let current = presets
+            .iter()
+            .position(|preset| preset.id == self.prefs.keymap.preset)
+            .unwrap_or(0);
+        let next = if forward {
+            (current + 1) % presets.len()
+        } else {
+            (current + presets.len() - 1) % presets.len()
+        };
+        let preset = &presets[next];
+        self.prefs.keymap.preset = preset.id.clone();
+        self.prefs.keymap.overrides.clear();
+        let message = t!("log.keymap.preset", preset = t!(preset.name.as_str())).to_string();
+        self.keymap_changed(message);
+    }
+
+    /// Binds the pressed key to the command under the cursor, unless another
+    /// command already answers to it on the same screens. Esc cancels.
+    fn capture_binding(&mut self, code: KeyCode) {
+        self.keybind_capture = false;
+        if code == KeyCode::Esc {
+            return;
+        }
+        let command = Command::ALL[self.keybind_cursor % Command::ALL.len()];
+        let Some(name) = keymap::key_name(code) else {
+            self.push_log(t!("log.keymap.unbindable"));
+            return;
+        };
+        if let Some(other) = self.keymap.conflict(command, code) {
+            self.push_log(t!(
+                "log.keymap.conflict",
+                key = name.as_str(),
+                command = t!(other.label_key())
+            ));
+            return;
+        }
+        self.prefs
+            .keymap
+            .overrides
+            .insert(command, vec![name.clone()]);
+        let message = t!(
+            "log.keymap.bound",
+            command = t!(command.label_key()),
+            key = name.as_str()
+        )
+        .to_string();
+        self.keymap_changed(message);
+    }
+
+    fn reset_binding(&mut self) {
+        let command = Command::ALL[self.keybind_cursor % Command::ALL.len()];
+        let Some(previous) = self.prefs.keymap.overrides.remove(&command) else {
+            return;
+        };
+        if let Err(error) = self.prefs.keymap.keymap() {
+            // The preset key now clashes with another rebind; keep the override.
+            self.prefs.keymap.overrides.insert(command, previous);
+            self.push_log(t!("log.keymap.invalid", error = error.as_str()));
+            return;
+        }
+        let message = t!("log.keymap.reset", command = t!(command.label_key())).to_string();
+        self.keymap_changed(message);
+    }
+
+    /// Rebuilds the live keymap from the preferences and persists them.
+    fn keymap_changed(&mut self, message: String) {
+        match self.prefs.keymap.keymap() {
+            Ok(keymap) => self.keymap = keymap,
+            Err(error) => {
+                self.push_log(t!("log.keymap.invalid", error = error.as_str()));
+                return;
+            }
+        }
+        self.save_prefs();
+        self.recent_event = Some(message.clone());
+        self.push_log(message);
+    }
+
+    /// The first key bound to the command, as shown in control hints.
+    pub fn key_label(&self, command: Command) -> String {
+        self.keymap
+            .keys(command)
+            .first()
+            .map(|code| key_label(*code))
+            .unwrap_or_default()
+    }
+
+    /// Every key bound to the command, joined for the keybinds screen.
+    pub fn keys_label(&self, command: Command) -> String {
+        self.keymap
+            .keys(command)
+            .iter()
+            .map(|code| key_label(*code))
+            .collect::<Vec<_>>()
+            .join(" / ")
+    }
+
+    pub fn keymap_preset_name(&self) -> String {
+        keymap::presets()
+            .iter()
+            .find(|preset| preset.id == self.prefs.keymap.preset)
+            .map(|preset| t!(preset.name.as_str()).to_string())
+            .unwrap_or_default()
+    }
+
+    pub fn keybind_overridden(&self, command: Command) -> bool {
+        self.prefs.keymap.overrides.contains_key(&command)
+    }
+}
+
+/// Arrow keys read better as glyphs in hints than by their config names.
+fn key_label(code: KeyCode) -> String {
+    match code {
+        KeyCode::Up => "↑".to_string(),
+        KeyCode::Down => "↓".to_string(),
+        KeyCode::Left => "←".to_string(),
+        KeyCode::Right => "→".to_string(),
+        _ => keymap::key_name(code).unwrap_or_default(),
+    }
+}
+
+/// The difficulty profile with the run's challenge modifiers and New Game+
+/// cycle folded in; custom runs start from the player's own profile.
+fn run_profile(
+    difficulty: Difficulty,
+    custom: &DifficultyProfile,
+    campaign: &CampaignState,
+) -> DifficultyProfile {
+    let base = if difficulty == Difficulty::Custom {
+        custom.clone()
+    } else {
+        config::profile_for(difficulty)
+    };
+    challenge::adjust_profile(base, &campaign.modifiers).for_ng_plus(campaign.ng_plus)
+}
+
+/// The choice `steps` places after `current` in `all`, wrapping at either
+/// end; a value missing from `all` counts from the first choice.
+fn cycle<T: Copy + PartialEq>(all: &[T], current: T, steps: i32) -> T {
+    let current = all.iter().position(|item| *item == current).unwrap_or(0) as i32;
+    all[(current + steps).rem_euclid(all.len() as i32) as usize]
+}
+
+fn town_action_from_cursor(cursor: usize) -> TownAction {
+    match cursor {
+        0 => TownAction::BuyPotion,
+        1 => TownAction::BuyEther,
+        2 => TownAction::UpgradeWeapon,
+        3 => TownAction::UpgradeArmor,
+        4 => TownAction::Healer,
+        5 => TownAction::Inn,
+        6 => TownAction::QuestBoard,
+        7 => TownAction::Tavern,
+        8 => TownAction::Workshop,
+        _ => TownAction::Leave,
+    }
+}
+
+fn order_from_index(index: usize) -> CompanionOrder {
+    match index {
+        0 => CompanionOrder::Attack,
+        1 => CompanionOrder::Skill,
+        _ => CompanionOrder::Defend,
+    }
+}
+
+fn town_cursor_from_action(action: TownAction) -> usize {
+    match action {
+        TownAction::BuyPotion => 0,
+        TownAction::BuyEther => 1,
+        TownAction::UpgradeWeapon => 2,
+        TownAction::UpgradeArmor => 3,
+        TownAction::Healer => 4,
+        TownAction::Inn => 5,
+        TownAction::QuestBoard => 6,
+        TownAction::Tavern => 7,
+        TownAction::Workshop | TownAction::Craft(_) => 8,
+        TownAction::Leave => 9,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crossterm::event::KeyCode;
+    use rust_i18n::t;
+
+    use super::{AUTOSAVE_OPTION_START, GLYPHS_OPTION_INDEX, Game, THEME_OPTION_INDEX};
+    use crate::game::challenge;
+    use crate::game::model::{
+        Achievement, AiKind, AutosaveOption, Battle, BattleEffect, BattleSide, BossId, Command,
+        CompanionKind, ConfirmAction, Difficulty, EncounterMode, Enemy, EnemyStyle, Facing,
+        GlyphSetId, HeroClass, Language, Player, PointerTarget, ProfileField, RoamingMonster,
+        RunModifier, ThemeId, Tile, Unlock, WeaponTier, WorldObjects,
+    };
+    use crate::game::model::{
+        EventMessage, EventState, GameMode, MAP_H, MAP_W, NpcKind, Position, WorldClock,
+    };
+    use crate::game::party::new_companion;
+
+    fn game_at_npc(kind: NpcKind) -> Game {
+        let mut game = Game::new_with_seed(42);
+        let npc = game
+            .world
+            .npcs
+            .iter()
+            .find(|npc| npc.kind == kind)
+            .expect("npc should be generated")
+            .position;
+        game.player.x = npc.x;
+        game.player.y = npc.y;
+        game.clock = WorldClock::at(1, if kind == NpcKind::Sage { 19 } else { 9 });
+        game
+    }
+
+    fn game_in_event(event: &str, step: &str) -> Game {
+        let mut game = Game::new_with_seed(42);
+        game.mode = GameMode::Event;
+        game.world_event = Some(EventState {
+            event: event.to_string(),
+            step: step.to_string(),
+            message: EventMessage::default(),
+        });
+        game.event_origin = Some(Position {
+            x: game.player.x,
+            y: game.player.y,
+        });
+        game
+    }
+
+    fn roaming_game_with_monster(x: usize, y: usize, facing: Facing) -> Game {
+        let mut game = Game::new_with_seed(42);
+        game.encounter_mode = EncounterMode::Roaming;
+        game.world.monsters = vec![RoamingMonster {
+            id: 9,
+            position: Position { x, y },
+            facing,
+            template: 0,
+            hp_percent: 100,
+            stunned_turns: 0,
+        }];
+        game
+    }
+
+    #[test]
+    fn town_purchase_through_handle_key_updates_player_state() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(7);
+        game.mode = GameMode::Town;
+        game.player.gold = 20;
+        game.player.bag.potion = 0;
+
+        game.handle_key(KeyCode::Char('1'));
+
+        assert_eq!(game.player.gold, 10);
+        assert_eq!(game.player.bag.potion, 1);
+        assert_eq!(game.mode, GameMode::Town);
+    }
+
+    #[test]
+    fn clicking_an_option_selects_and_activates_it() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(7);
+        game.mode = GameMode::Town;
+        game.player.gold = 20;
+        game.player.bag.potion = 0;
+        game.click(PointerTarget::MenuOption(0));
+        assert_eq!(game.player.bag.potion, 1);
+
+        game.click(PointerTarget::MenuOption(9));
+        assert_eq!(game.mode, GameMode::Exploration);
+        assert_eq!(game.town_cursor, 9);
+
+        game.mode = GameMode::Settings;
+        game.click(PointerTarget::MenuOption(2));
+        assert_eq!(game.current_language, Language::ALL[2]);
+
+        game.confirm = Some(ConfirmAction::Quit);
+        game.click(PointerTarget::MenuOption(0));
+        assert_eq!(game.current_language, Language::ALL[2]);
+    }
+
+    #[test]
+    fn clicking_a_tile_walks_there_one_step_per_tick() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(7);
+        game.map = vec![vec![Tile::Floor; MAP_W]; MAP_H];
+        for row in game.map.iter_mut().take(5) {
+            row[4] = Tile::Wall;
+        }
+        game.world = WorldObjects::new(Vec::new(), Vec::new());
+        game.difficulty_profile.random_encounter_rate_percent = 0;
+        game.difficulty_profile.world_event_rate_percent = 0;
+        game.player.x = 2;
+        game.player.y = 2;
+        game.world.reveal_around(Position { x: 4, y: 4 }, 4);
+
+        let target = Position { x: 6, y: 2 };
+        game.click(PointerTarget::Tile(target));
+        let mut steps = 0;
+        while game.auto_walk.front().is_some() {
+            game.step_auto_walk();
+            steps += 1;
+        }
+        assert_eq!((game.player.x, game.player.y), (6, 2));
+        assert_eq!(steps, 10);
+
+        // A key press takes the controls back mid-walk.
+        game.click(PointerTarget::Tile(Position { x: 2, y: 2 }));
+        game.step_auto_walk();
+        game.handle_key(KeyCode::Char('p'));
+        assert!(game.auto_walk.is_empty());
+
+        game.mode = GameMode::Exploration;
+        game.click(PointerTarget::Tile(Position { x: 4, y: 0 }));
+        assert!(game.auto_walk.is_empty());
+        assert!(game.log.back().is_some_and(|line| line.contains("path")));
+    }
+
+    #[test]
+    fn town_leave_through_handle_key_returns_to_exploration() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(11);
+        game.mode = GameMode::Town;
+
+        game.handle_key(KeyCode::Char('0'));
+
+        assert_eq!(game.mode, GameMode::Exploration);
     }
 
     #[test]
@@ -752,7 +3187,7 @@ mod tests {
 
         game.player.gold = 100;
         game.handle_key(KeyCode::Char('1'));
-        game.handle_key(KeyCode::Char('8'));
+        game.handle_key(KeyCode::Char('0'));
         assert_eq!(game.mode, GameMode::Exploration);
 
         game.player.exp = game.player.next_exp - 1;
@@ -764,11 +3199,57 @@ mod tests {
         assert_eq!(game.mode, GameMode::Exploration);
 
         game.player.base_atk = 999;
-        game.start_boss_battle();
+        game.start_boss_battle(BossId::AncientDragon);
         game.handle_key(KeyCode::Char('1'));
         assert_eq!(game.mode, GameMode::Victory);
     }
 
+    #[test]
+    fn lair_stays_sealed_until_every_guardian_falls() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(42);
+        game.player.x = MAP_W - 3;
+        game.player.y = MAP_H - 2;
+
+        game.handle_key(KeyCode::Right);
+        assert_eq!(game.mode, GameMode::Exploration);
+        assert!(game.log.iter().any(|line| line.contains("sealed")));
+
+        for site in &mut game.world.boss_sites {
+            site.defeated = true;
+        }
+        game.player.x = MAP_W - 3;
+        game.handle_key(KeyCode::Right);
+        assert_eq!(game.mode, GameMode::Battle);
+    }
+
+    #[test]
+    fn defeating_a_guardian_breaks_its_seal_and_plays_story() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(42);
+        let site = game.world.boss_sites[0].clone();
+        game.player.x = site.position.x;
+        game.player.y = site.position.y;
+        game.player.base_atk = 999;
+
+        game.start_boss_battle(site.boss);
+        game.handle_key(KeyCode::Char('1'));
+
+        assert_eq!(game.mode, GameMode::Dialogue);
+        assert!(game.world.boss_sites[0].defeated);
+        assert_eq!(game.world.seals_broken(), 1);
+        assert_eq!(game.campaign.bosses_defeated, vec![site.boss]);
+        assert_eq!(game.campaign.enemies_defeated, 1);
+
+        game.handle_key(KeyCode::Enter);
+        assert_eq!(game.mode, GameMode::Exploration);
+        assert!(
+            game.world
+                .boss_site_at(site.position.x, site.position.y)
+                .is_none()
+        );
+    }
+
     #[test]
     fn town_enter_executes_selected_action() {
         rust_i18n::set_locale("en");
@@ -791,23 +3272,1091 @@ mod tests {
         game.mode = GameMode::Battle;
         game.battle_cursor = 0;
         game.player.base_atk = 999;
-        game.battle = Some(Battle {
-            enemy: Enemy {
-                name: "Dummy".to_string(),
-                hp: 3,
-                max_hp: 3,
-                atk: 1,
-                def: 0,
-                exp_reward: 1,
-                gold_reward: 1,
-                is_boss: false,
-                style: EnemyStyle::Skirmisher,
+        game.battle = Some(Battle::new(Enemy {
+            name: "Dummy".to_string(),
+            name_key: String::new(),
+            hp: 3,
+            max_hp: 3,
+            atk: 1,
+            def: 0,
+            exp_reward: 1,
+            gold_reward: 1,
+            is_boss: false,
+            style: EnemyStyle::Skirmisher,
+            ai: AiKind::Aggressive,
+            loot: None,
+            art: String::new(),
+        }));
+
+        game.handle_key(KeyCode::Enter);
+
+        assert_eq!(game.mode, GameMode::Exploration);
+    }
+
+    #[test]
+    fn bumping_roaming_monster_from_behind_grants_preemptive_strike() {
+        rust_i18n::set_locale("en");
+        let mut game = roaming_game_with_monster(2, 1, Facing::Right);
+        game.player.x = 1;
+        game.player.y = 1;
+        game.player.base_atk = 999;
+
+        game.try_move_player(1, 0);
+
+        assert_eq!(game.mode, GameMode::Exploration);
+        assert!(game.world.monsters.is_empty());
+        assert_eq!((game.player.x, game.player.y), (1, 1));
+    }
+
+    #[test]
+    fn roaming_monster_contact_starts_battle_and_escape_stuns_it() {
+        rust_i18n::set_locale("en");
+        let mut game = roaming_game_with_monster(3, 3, Facing::Left);
+        game.player.x = 1;
+        game.player.y = 3;
+
+        game.try_move_player(1, 0);
+        assert_eq!(game.mode, GameMode::Battle);
+        assert_eq!(game.battle_monster, Some(9));
+
+        let battle = game.battle.take().expect("battle should be active");
+        game.apply_turn_result(
+            battle,
+            super::TurnResult {
+                outcome: super::BattleOutcome::Escaped,
+                logs: Vec::new(),
             },
-            defending: false,
-        });
+        );
+        assert_eq!(game.mode, GameMode::Exploration);
+        assert_eq!(game.world.monsters.len(), 1);
+        assert!(game.world.monsters[0].stunned_turns > 0);
+    }
+
+    #[test]
+    fn random_encounters_are_disabled_in_roaming_mode() {
+        rust_i18n::set_locale("en");
+        let mut game = roaming_game_with_monster(30, 15, Facing::Left);
+        game.world.monsters.clear();
+        for _ in 0..40 {
+            game.try_move_player(0, 1);
+            game.try_move_player(0, -1);
+        }
+        assert_ne!(game.mode, GameMode::Battle);
+    }
+
+    #[test]
+    fn npc_dialogue_gift_is_paid_once_and_can_open_shop() {
+        rust_i18n::set_locale("en");
+        let mut game = game_at_npc(NpcKind::Traveler);
+
+        game.handle_key(KeyCode::Char('t'));
+        assert_eq!(game.mode, GameMode::Dialogue);
+        assert_eq!(game.dialogue_choice_texts().len(), 4);
+
+        let before = game.player.gold;
+        game.handle_key(KeyCode::Char('2'));
+        assert!(game.player.gold > before);
+        let after_gift = game.player.gold;
 
         game.handle_key(KeyCode::Enter);
+        assert_eq!(game.dialogue_choice_texts().len(), 3);
+        assert_eq!(game.player.gold, after_gift);
+
+        game.handle_key(KeyCode::Char('2'));
+        assert_eq!(game.mode, GameMode::Town);
+        assert!(game.peddler);
+        assert!(game.dialogue.is_none());
+
+        // Only the shop travels: the inn's key neither heals nor passes time.
+        game.player.hp = 1;
+        let clock = game.clock;
+        game.handle_key(KeyCode::Char('6'));
+        assert_eq!((game.player.hp, game.clock), (1, clock));
 
+        let potions = game.player.bag.potion;
+        game.handle_key(KeyCode::Char('1'));
+        assert_eq!(game.player.bag.potion, potions + 1);
+
+        game.handle_key(KeyCode::Char('5'));
+        assert_eq!(game.mode, GameMode::Exploration);
+        assert!(!game.peddler);
+    }
+
+    #[test]
+    fn npc_can_be_talked_to_repeatedly() {
+        rust_i18n::set_locale("en");
+        let mut game = game_at_npc(NpcKind::Scout);
+
+        game.handle_key(KeyCode::Char('t'));
+        game.handle_key(KeyCode::Esc);
         assert_eq!(game.mode, GameMode::Exploration);
+        assert!(!game.should_quit);
+
+        game.handle_key(KeyCode::Char('t'));
+        assert_eq!(game.mode, GameMode::Dialogue);
+    }
+
+    #[test]
+    fn sage_reveals_lair_only_when_strong_enough() {
+        rust_i18n::set_locale("en");
+        let mut game = game_at_npc(NpcKind::Sage);
+        let lair = Position {
+            x: MAP_W - 2,
+            y: MAP_H - 2,
+        };
+        assert!(!game.world.is_explored(lair.x, lair.y));
+
+        game.handle_key(KeyCode::Char('t'));
+        game.handle_key(KeyCode::Char('1'));
+        assert!(!game.world.is_explored(lair.x, lair.y));
+        game.handle_key(KeyCode::Char('b'));
+
+        game.player.level = 3;
+        game.handle_key(KeyCode::Char('t'));
+        game.handle_key(KeyCode::Char('1'));
+        assert!(game.world.is_explored(lair.x, lair.y));
+        assert!(game.story_flags.contains("lair_revealed"));
+    }
+
+    #[test]
+    fn leaving_an_event_returns_to_exploration_and_clears_the_tile() {
+        rust_i18n::set_locale("en");
+        let mut game = game_in_event("suspicious_chest", "found");
+        assert_eq!(game.event_choice_texts().len(), 2);
+
+        game.handle_key(KeyCode::Down);
+        game.handle_key(KeyCode::Enter);
+        assert_eq!(game.mode, GameMode::Exploration);
+        assert!(game.world_event.is_none());
+        assert!(game.world.tile_is_cleared(game.player.x, game.player.y));
+    }
+
+    #[test]
+    fn event_text_follows_a_language_switch() {
+        rust_i18n::set_locale("en");
+        let mut game = game_in_event("suspicious_chest", "found");
+        if let Some(state) = game.world_event.as_mut() {
+            state.message = EventMessage {
+                key: "event.suspicious_chest.treasure".to_string(),
+                gold: 15,
+                count: 1,
+                ..EventMessage::default()
+            };
+        }
+        let english = game.event_text().expect("event text");
+        assert!(english.contains("+15 G"));
+
+        let ja = Language::ALL
+            .iter()
+            .position(|lang| lang.locale_code() == "ja")
+            .expect("Japanese");
+        game.select_language(ja);
+        let japanese = game.event_text().expect("event text");
+        assert_ne!(japanese, english);
+        assert!(japanese.contains("+15 G"));
+        rust_i18n::set_locale("en");
+    }
+
+    #[test]
+    fn event_choice_can_start_a_battle() {
+        rust_i18n::set_locale("en");
+        let mut game = game_in_event("bandit_toll", "ambush");
+        game.player.gold = 0;
+        assert_eq!(game.event_choice_texts().len(), 2);
+
+        game.handle_key(KeyCode::Char('1'));
+        assert_eq!(game.mode, GameMode::Battle);
+        assert!(game.battle.is_some());
+        assert!(game.world_event.is_none());
+    }
+
+    #[test]
+    fn tolls_paid_to_npcs_and_events_count_as_gold_spent() {
+        rust_i18n::set_locale("en");
+        let mut game = game_at_npc(NpcKind::Scout);
+        game.player.gold = 50;
+        game.handle_key(KeyCode::Char('t'));
+        game.handle_key(KeyCode::Char('1'));
+        assert_eq!(game.player.gold, 40);
+        assert_eq!(game.campaign.stats.gold_spent, 10);
+
+        let mut game = game_in_event("bandit_toll", "ambush");
+        game.player.gold = 50;
+        game.handle_key(KeyCode::Char('1'));
+        assert_eq!(game.player.gold, 30);
+        assert_eq!(game.campaign.stats.gold_spent, 20);
+    }
+
+    #[test]
+    fn walking_advances_the_clock_into_night() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(42);
+        game.clock = WorldClock::at(1, 20);
+        game.clock.advance(WorldClock::TURNS_PER_DAY / 24 - 1);
+        game.player.x = 3;
+        game.player.y = 3;
+        game.map[3][4] = crate::game::model::Tile::Floor;
+        game.world.mark_tile_cleared(4, 3);
+
+        game.handle_key(KeyCode::Right);
+        assert!(game.time_of_day().is_night());
+        assert!(game.log.iter().any(|line| line.contains("Night falls")));
+    }
+
+    #[test]
+    fn npcs_are_away_outside_their_schedule() {
+        rust_i18n::set_locale("en");
+        let mut game = game_at_npc(NpcKind::Sage);
+        game.clock = WorldClock::at(1, 12);
+        game.handle_key(KeyCode::Char('t'));
+        assert_eq!(game.mode, GameMode::Exploration);
+        assert!(
+            game.log
+                .back()
+                .is_some_and(|line| line.contains("not here"))
+        );
+
+        game.clock = WorldClock::at(1, 22);
+        game.handle_key(KeyCode::Char('t'));
+        assert_eq!(game.mode, GameMode::Dialogue);
+    }
+
+    fn sturdy_battle() -> Battle {
+        Battle::new(Enemy {
+            name: "Training Dummy".to_string(),
+            name_key: String::new(),
+            hp: 500,
+            max_hp: 500,
+            atk: 1,
+            def: 0,
+            exp_reward: 1,
+            gold_reward: 1,
+            is_boss: false,
+            style: EnemyStyle::Skirmisher,
+            ai: AiKind::Aggressive,
+            loot: None,
+            art: String::new(),
+        })
+    }
+
+    #[test]
+    fn each_standing_companion_gets_an_order_before_the_round_resolves() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(8);
+        game.party = vec![
+            new_companion(CompanionKind::Knight, 1),
+            new_companion(CompanionKind::Cleric, 1),
+            new_companion(CompanionKind::Archer, 1),
+        ];
+        game.party[1].hp = 0;
+        game.mode = GameMode::Battle;
+        game.battle = Some(sturdy_battle());
+
+        game.handle_key(KeyCode::Char('1'));
+        assert_eq!(game.choosing_member(), Some(0));
+        game.handle_key(KeyCode::Char('b'));
+        assert_eq!(game.choosing_member(), None);
+        assert_eq!(game.battle.as_ref().map(|b| b.enemy.hp), Some(500));
+
+        game.handle_key(KeyCode::Char('1'));
+        game.handle_key(KeyCode::Char('3'));
+        assert_eq!(game.choosing_member(), Some(2));
+        game.handle_key(KeyCode::Char('1'));
+
+        assert_eq!(game.choosing_member(), None);
+        assert!(game.battle.as_ref().is_some_and(|b| b.enemy.hp < 500));
+    }
+
+    #[test]
+    fn party_screen_reorders_and_dismisses_members() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(8);
+        game.party = vec![
+            new_companion(CompanionKind::Knight, 1),
+            new_companion(CompanionKind::Cleric, 1),
+        ];
+
+        game.handle_key(KeyCode::Char('p'));
+        assert_eq!(game.mode, GameMode::Party);
+        game.handle_key(KeyCode::Down);
+        game.handle_key(KeyCode::Char('['));
+        assert_eq!(game.party[0].kind, CompanionKind::Cleric);
+        assert_eq!(game.party_cursor, 0);
+
+        game.handle_key(KeyCode::Char('x'));
+        assert_eq!(game.party.len(), 1);
+        assert_eq!(game.party[0].kind, CompanionKind::Knight);
+        game.handle_key(KeyCode::Esc);
+        assert_eq!(game.mode, GameMode::Exploration);
+        assert!(!game.should_quit);
+    }
+
+    #[test]
+    fn scout_joins_the_party_only_once() {
+        rust_i18n::set_locale("en");
+        let mut game = game_at_npc(NpcKind::Scout);
+        game.player.level = 2;
+        game.handle_key(KeyCode::Char('t'));
+        let ask = game
+            .dialogue_choice_texts()
+            .iter()
+            .position(|text| text == "Want to travel together?")
+            .expect("join option should be offered");
+        game.handle_key(KeyCode::Char(char::from(b'1' + ask as u8)));
+
+        assert_eq!(game.party.len(), 1);
+        assert_eq!(game.party[0].kind, CompanionKind::Archer);
+        assert_eq!(game.party[0].level, 2);
+        game.handle_key(KeyCode::Char('1'));
+        assert!(
+            !game
+                .dialogue_choice_texts()
+                .contains(&"Want to travel together?".to_string())
+        );
+    }
+
+    #[test]
+    fn a_failed_recruit_leaves_the_join_flag_unset() {
+        rust_i18n::set_locale("en");
+        let mut game = game_at_npc(NpcKind::Scout);
+        game.player.level = 2;
+        game.party = vec![new_companion(CompanionKind::Archer, 1)];
+        game.handle_key(KeyCode::Char('t'));
+        let ask = game
+            .dialogue_choice_texts()
+            .iter()
+            .position(|text| text == "Want to travel together?")
+            .expect("join option should be offered");
+        game.handle_key(KeyCode::Char(char::from(b'1' + ask as u8)));
+
+        assert_eq!(game.party.len(), 1);
+        assert!(!game.story_flags.contains("archer_joined"));
+    }
+
+    #[test]
+    fn new_game_starts_by_choosing_a_class() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new();
+        assert_eq!(game.mode, GameMode::ClassSelect);
+
+        game.handle_key(KeyCode::Down);
+        game.handle_key(KeyCode::Enter);
+
+        assert_eq!(game.mode, GameMode::Exploration);
+        assert_eq!(game.player.class, HeroClass::Mage);
+        assert_eq!(
+            game.player.max_mp,
+            Player::with_class(HeroClass::Mage).max_mp
+        );
+    }
+
+    #[test]
+    fn level_up_screen_spends_points_until_none_are_left() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(8);
+        game.player.stat_points = 2;
+        let atk = game.player.base_atk;
+        let max_hp = game.player.max_hp;
+
+        game.handle_key(KeyCode::Char('u'));
+        assert_eq!(game.mode, GameMode::LevelUp);
+        game.handle_key(KeyCode::Char('3'));
+        game.handle_key(KeyCode::Up);
+        game.handle_key(KeyCode::Up);
+        game.handle_key(KeyCode::Enter);
+        game.handle_key(KeyCode::Char('4'));
+
+        assert_eq!(game.player.base_atk, atk + 1);
+        assert!(game.player.max_hp > max_hp);
+        assert_eq!(game.player.stat_points, 0);
+        game.handle_key(KeyCode::Char('b'));
+        assert_eq!(game.mode, GameMode::Exploration);
+    }
+
+    #[test]
+    fn skill_menu_opens_once_a_second_skill_is_known() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(8);
+        game.player = Player::with_class(HeroClass::Rogue);
+        game.mode = GameMode::Battle;
+        game.battle = Some(sturdy_battle());
+
+        game.handle_key(KeyCode::Char('2'));
+        assert!(!game.skill_menu);
+        assert!(game.battle.as_ref().is_some_and(|b| b.enemy.hp < 500));
+
+        game.player.level = 3;
+        let gold = game.player.gold;
+        game.handle_key(KeyCode::Char('2'));
+        assert!(game.skill_menu);
+        game.handle_key(KeyCode::Char('2'));
+        assert!(!game.skill_menu);
+        assert!(game.player.gold > gold);
+    }
+
+    #[test]
+    fn workshop_crafts_from_gathered_materials() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(12);
+        let node = game.world.resource_nodes[0].clone();
+        game.player.x = node.position.x;
+        game.player.y = node.position.y;
+        game.handle_floor_tile(node.position.x, node.position.y);
+        assert_eq!(game.player.bag.materials.count(node.material), node.amount);
+        assert!(
+            game.world
+                .resource_node_at(node.position.x, node.position.y)
+                .is_none()
+        );
+
+        game.mode = GameMode::Town;
+        game.handle_key(KeyCode::Char('9'));
+        assert!(game.workshop);
+        game.player.bag.materials.herb = 2;
+        let potions = game.player.bag.potion;
+        game.handle_key(KeyCode::Char('1'));
+        assert_eq!(game.player.bag.potion, potions + 1);
+        assert_eq!(game.player.bag.materials.herb, 0);
+
+        game.handle_key(KeyCode::Char('b'));
+        assert!(!game.workshop);
+        game.handle_key(KeyCode::Char('0'));
+        assert_eq!(game.mode, GameMode::Exploration);
+    }
+
+    #[test]
+    fn finished_run_is_tallied_and_entered_in_the_hall_of_fame_once() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(2026);
+        game.player.base_atk = 999;
+        game.start_boss_battle(BossId::AncientDragon);
+        game.handle_key(KeyCode::Char('1'));
+        assert_eq!(game.mode, GameMode::Victory);
+
+        let stats = &game.campaign.stats;
+        assert_eq!(stats.battles, 1);
+        assert!(stats.damage_dealt > 0);
+        assert_eq!(stats.kills.values().sum::<u32>(), 1);
+        assert_eq!(game.records.hall_of_fame.len(), 1);
+        assert!(game.records.hall_of_fame[0].victory);
+        assert!(
+            game.records
+                .achievements
+                .contains(&Achievement::Dragonslayer)
+        );
+
+        game.handle_key(KeyCode::Char('h'));
+        assert_eq!(game.mode, GameMode::Records);
+        game.handle_key(KeyCode::Char('b'));
+        assert_eq!(game.mode, GameMode::Victory);
+        assert_eq!(game.records.hall_of_fame.len(), 1);
+    }
+
+    #[test]
+    fn defeat_records_what_felled_the_hero() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(4);
+        game.mode = GameMode::Battle;
+        game.battle = Some(sturdy_battle());
+        game.player.hp = 1;
+        game.player.base_def = 0;
+        game.handle_key(KeyCode::Char('1'));
+        assert_eq!(game.mode, GameMode::GameOver);
+        assert_eq!(
+            game.campaign.stats.death_cause.as_deref(),
+            Some("Training Dummy")
+        );
+        assert!(!game.records.hall_of_fame[0].victory);
+    }
+
+    #[test]
+    fn a_battle_lost_by_click_is_recorded_too() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(4);
+        game.mode = GameMode::Battle;
+        game.battle = Some(sturdy_battle());
+        game.player.hp = 1;
+        game.player.base_def = 0;
+        game.click(PointerTarget::MenuOption(0));
+        assert_eq!(game.mode, GameMode::GameOver);
+        assert!(game.run_recorded);
+        assert_eq!(game.records.hall_of_fame.len(), 1);
+    }
+
+    #[test]
+    fn run_records_keep_enemy_keys_rather_than_names() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(4);
+        let slime = crate::game::encounter::generate_from_template(0, 1, &game.difficulty_profile);
+        let key = slime.name_key.clone();
+        assert_eq!(key, "enemy.slime");
+
+        let mut weak = slime.clone();
+        weak.hp = 1;
+        weak.name = "Elite Slime".to_string();
+        game.mode = GameMode::Battle;
+        game.battle = Some(Battle::new(weak));
+        game.handle_key(KeyCode::Char('1'));
+        assert_eq!(game.campaign.stats.kills.get(&key), Some(&1));
+
+        let mut strong = slime;
+        strong.hp = 500;
+        strong.atk = 999;
+        game.mode = GameMode::Battle;
+        game.battle = Some(Battle::new(strong));
+        game.player.hp = 1;
+        game.handle_key(KeyCode::Char('1'));
+        assert_eq!(game.mode, GameMode::GameOver);
+        assert_eq!(game.campaign.stats.death_cause, Some(key));
+    }
+
+    #[test]
+    fn roguelite_run_starts_with_bought_perks_and_pays_shards_on_defeat() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(4);
+        game.mode = GameMode::ClassSelect;
+        game.records.legacy.shards = 10;
+        game.handle_key(KeyCode::Char('m'));
+        assert_eq!(game.mode, GameMode::Legacy);
+        game.handle_key(KeyCode::Char('g'));
+        game.handle_key(KeyCode::Char('1'));
+        assert!(game.records.legacy.active.contains(&Unlock::Provisions));
+        let shards = game.records.legacy.shards;
+        assert!(shards < 10);
+        game.handle_key(KeyCode::Char('b'));
+        assert_eq!(game.mode, GameMode::ClassSelect);
+
+        let potions = Player::with_class(HeroClass::ALL[0]).bag.potion;
+        game.handle_key(KeyCode::Char('1'));
+        assert!(game.campaign.roguelite);
+        assert!(game.player.bag.potion > potions);
+
+        game.player.level = 5;
+        game.mode = GameMode::Battle;
+        game.battle = Some(sturdy_battle());
+        game.player.hp = 1;
+        game.player.base_def = 0;
+        game.handle_key(KeyCode::Char('1'));
+        assert_eq!(game.mode, GameMode::GameOver);
+        assert!(game.records.legacy.shards > shards);
+        let total = game.records.legacy.shards;
+        game.handle_key(KeyCode::Char('h'));
+        game.handle_key(KeyCode::Char('h'));
+        assert_eq!(game.records.legacy.shards, total);
+    }
+
+    #[test]
+    fn daily_challenge_reseeds_the_world_with_the_day_draw() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(4);
+        game.mode = GameMode::ClassSelect;
+        game.handle_key(KeyCode::Char('c'));
+        assert_eq!(game.mode, GameMode::Challenge);
+        game.handle_key(KeyCode::Char('d'));
+        game.handle_key(KeyCode::Char('b'));
+        game.handle_key(KeyCode::Char('1'));
+
+        let day = challenge::today();
+        assert_eq!(game.map_seed, challenge::daily_seed(day));
+        assert_eq!(game.campaign.daily, Some(day));
+        assert_eq!(game.campaign.modifiers, challenge::daily_modifiers(day));
+        assert_eq!(game.map, Game::new_with_seed(game.map_seed).map);
+    }
+
+    #[test]
+    fn custom_modifiers_close_shops_and_forbid_loading() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(4);
+        game.mode = GameMode::ClassSelect;
+        game.handle_key(KeyCode::Char('c'));
+        game.handle_key(KeyCode::Char('1'));
+        game.handle_key(KeyCode::Char('4'));
+        game.handle_key(KeyCode::Esc);
+        game.handle_key(KeyCode::Char('1'));
+        assert_eq!(
+            game.campaign.modifiers,
+            BTreeSet::from([RunModifier::NoShops, RunModifier::IronMan])
+        );
+
+        game.mode = GameMode::Town;
+        game.player.gold = 100;
+        game.handle_key(KeyCode::Char('1'));
+        assert_eq!(game.player.gold, 100);
+
+        game.mode = GameMode::Exploration;
+        game.handle_key(KeyCode::Char('l'));
+        assert_eq!(game.mode, GameMode::Exploration);
+        assert!(
+            game.log
+                .back()
+                .is_some_and(|line| line.contains("Iron Man"))
+        );
+    }
+
+    #[test]
+    fn a_roguelite_run_cannot_load_its_way_past_a_death() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(4);
+        game.mode = GameMode::ClassSelect;
+        game.records.legacy.roguelite = true;
+        game.handle_key(KeyCode::Char('1'));
+        assert!(game.campaign.roguelite);
+
+        game.player.gold += 1;
+        game.handle_key(KeyCode::Char('l'));
+        assert_eq!(game.confirm, None);
+        assert!(
+            game.log
+                .back()
+                .is_some_and(|line| line.contains("cannot load"))
+        );
+
+        game.mode = GameMode::GameOver;
+        game.handle_key(KeyCode::Char('l'));
+        assert_eq!(game.confirm, None);
+        assert_eq!(game.mode, GameMode::GameOver);
+    }
+
+    #[test]
+    fn double_encounters_send_out_a_second_roaming_pack() {
+        rust_i18n::set_locale("en");
+        let mut plain = Game::new_with_seed(4);
+        plain.mode = GameMode::ClassSelect;
+        plain.handle_key(KeyCode::Char('1'));
+
+        let mut doubled = Game::new_with_seed(4);
+        doubled.mode = GameMode::ClassSelect;
+        doubled.run_modifiers = BTreeSet::from([RunModifier::DoubleEncounters]);
+        doubled.handle_key(KeyCode::Char('1'));
+        assert_eq!(doubled.world.monsters.len(), 2 * plain.world.monsters.len());
+
+        doubled.start_new_game_plus();
+        assert_eq!(doubled.world.monsters.len(), 2 * plain.world.monsters.len());
+    }
+
+    #[test]
+    fn iron_man_leaves_no_save_to_come_back_to_after_a_death() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(4);
+        game.mode = GameMode::ClassSelect;
+        game.run_modifiers = BTreeSet::from([RunModifier::IronMan]);
+        game.handle_key(KeyCode::Char('1'));
+        assert!(game.campaign.modifiers.contains(&RunModifier::IronMan));
+        let saved = game.saved_progress;
+
+        game.player.gold += 1;
+        game.handle_key(KeyCode::Char('k'));
+        assert_eq!(game.confirm, None);
+        assert_eq!(game.saved_progress, saved);
+        assert!(
+            game.log
+                .back()
+                .is_some_and(|line| line.contains("Iron Man"))
+        );
+
+        game.mode = GameMode::Battle;
+        game.battle = Some(sturdy_battle());
+        game.player.hp = 1;
+        game.player.base_def = 0;
+        game.handle_key(KeyCode::Char('1'));
+        assert_eq!(game.mode, GameMode::GameOver);
+        game.handle_key(KeyCode::Char('k'));
+        game.handle_key(KeyCode::Char('l'));
+        assert_eq!(game.confirm, None);
+        assert_eq!(game.saved_progress, saved);
+
+        game.handle_key(KeyCode::Char('r'));
+        assert_eq!(game.mode, GameMode::ClassSelect);
+        assert!(game.campaign.modifiers.is_empty());
+    }
+
+    #[test]
+    fn custom_difficulty_sliders_drive_the_run_and_are_saved() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(5);
+        game.handle_key(KeyCode::Char('o'));
+        game.handle_key(KeyCode::Char('9'));
+        assert_eq!(game.difficulty, Difficulty::Custom);
+
+        game.handle_key(KeyCode::Char('0'));
+        for _ in 0..7 {
+            game.handle_key(KeyCode::Down);
+        }
+        assert_eq!(
+            game.selected_profile_field(),
+            Some(ProfileField::EncounterRate)
+        );
+        let before = game.difficulty_profile.random_encounter_rate_percent;
+        game.handle_key(KeyCode::Right);
+        game.handle_key(KeyCode::Right);
+        assert_eq!(
+            game.difficulty_profile.random_encounter_rate_percent,
+            before + 2
+        );
+        game.handle_key(KeyCode::Left);
+        assert_eq!(
+            game.prefs.custom_difficulty.random_encounter_rate_percent,
+            before + 1
+        );
+
+        let save = game.to_save_data();
+        assert_eq!(
+            save.custom_difficulty,
+            Some(game.prefs.custom_difficulty.clone())
+        );
+        let loaded = Game::from_save_data(save);
+        assert_eq!(loaded.difficulty_profile, game.difficulty_profile);
+
+        game.handle_key(KeyCode::Char('r'));
+        assert_eq!(
+            game.difficulty_profile.random_encounter_rate_percent,
+            before
+        );
+        game.handle_key(KeyCode::Char('7'));
+        assert_eq!(game.to_save_data().custom_difficulty, None);
+    }
+
+    #[test]
+    fn keys_can_be_rebound_from_settings_without_clashes() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(5);
+        game.handle_key(KeyCode::Char('o'));
+        game.handle_key(KeyCode::Char('0'));
+        game.handle_key(KeyCode::Down);
+        game.handle_key(KeyCode::Enter);
+        assert_eq!(game.mode, GameMode::Keybinds);
+
+        game.keybind_cursor = Command::ALL
+            .iter()
+            .position(|command| *command == Command::Party)
+            .unwrap();
+        game.handle_key(KeyCode::Enter);
+        assert!(game.keybind_capture);
+        game.handle_key(KeyCode::Char('w'));
+        assert!(!game.keybind_capture);
+        assert_eq!(game.keys_label(Command::Party), "p");
+
+        // The party screen dismisses companions with `x`.
+        game.handle_key(KeyCode::Enter);
+        game.handle_key(KeyCode::Char('x'));
+        assert_eq!(game.keys_label(Command::Party), "p");
+        assert!(
+            game.log
+                .back()
+                .is_some_and(|line| line.contains("Dismiss companion"))
+        );
+
+        game.handle_key(KeyCode::Enter);
+        game.handle_key(KeyCode::Char('g'));
+        assert_eq!(game.keys_label(Command::Party), "g");
+        assert!(game.keybind_overridden(Command::Party));
+
+        game.handle_key(KeyCode::Esc);
+        game.handle_key(KeyCode::Esc);
+        assert_eq!(game.mode, GameMode::Exploration);
+        game.handle_key(KeyCode::Char('p'));
+        assert_eq!(game.mode, GameMode::Exploration);
+        game.handle_key(KeyCode::Char('g'));
+        assert_eq!(game.mode, GameMode::Party);
+        game.handle_key(KeyCode::Char('g'));
+        assert_eq!(game.mode, GameMode::Exploration);
+    }
+
+    #[test]
+    fn screen_shortcuts_and_their_hints_follow_rebinds() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(5);
+        game.prefs
+            .keymap
+            .overrides
+            .insert(Command::DismissMember, vec!["z".to_string()]);
+        game.prefs
+            .keymap
+            .overrides
+            .insert(Command::Accept, vec!["j".to_string()]);
+        game.keymap = game.prefs.keymap.keymap().expect("rebinds are valid");
+        game.party.push(new_companion(CompanionKind::Knight, 1));
+        game.party.push(new_companion(CompanionKind::Cleric, 1));
+        game.open_party(GameMode::Exploration);
+
+        game.handle_key(KeyCode::Char('x'));
+        assert_eq!(game.party.len(), 2);
+        game.handle_key(KeyCode::Char('z'));
+        assert_eq!(game.party.len(), 1);
+
+        game.player.gold += 1;
+        game.handle_key(KeyCode::Esc);
+        game.handle_key(KeyCode::Esc);
+        assert_eq!(game.confirm, Some(ConfirmAction::Quit));
+        game.handle_key(KeyCode::Char('y'));
+        assert!(!game.should_quit);
+        game.handle_key(KeyCode::Char('j'));
+        assert!(game.should_quit);
+
+        assert_eq!(game.key_label(Command::DismissMember), "z");
+        assert!(
+            t!(
+                "ui.controls.party.dismiss",
+                key = game.key_label(Command::DismissMember)
+            )
+            .starts_with("z:")
+        );
+    }
+
+    #[test]
+    fn switching_presets_clears_rebinds_and_moves_with_the_new_keys() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(5);
+        game.prefs
+            .keymap
+            .overrides
+            .insert(Command::Party, vec!["g".to_string()]);
+        game.mode = GameMode::Keybinds;
+        game.handle_key(KeyCode::Right);
+        game.handle_key(KeyCode::Right);
+        assert_eq!(game.prefs.keymap.preset, "vi");
+        assert!(game.prefs.keymap.overrides.is_empty());
+
+        game.handle_key(KeyCode::Char('j'));
+        assert_eq!(game.keybind_cursor, 1);
+        game.handle_key(KeyCode::Char('k'));
+        assert_eq!(game.keybind_cursor, 0);
+        assert_eq!(game.key_label(Command::MoveRight), "l");
+        assert_eq!(game.key_label(Command::Load), "L");
+    }
+
+    #[test]
+    fn quitting_or_loading_over_unsaved_progress_asks_first() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(5);
+        game.handle_key(KeyCode::Char('q'));
+        assert!(game.should_quit);
+
+        let mut game = Game::new_with_seed(5);
+        game.player.gold += 10;
+        game.handle_key(KeyCode::Esc);
+        assert_eq!(game.confirm, Some(ConfirmAction::Quit));
+        game.handle_key(KeyCode::Char('w'));
+        assert_eq!(game.confirm, Some(ConfirmAction::Quit));
+        game.handle_key(KeyCode::Char('n'));
+        assert_eq!(game.confirm, None);
+        assert!(!game.should_quit);
+
+        game.handle_key(KeyCode::Char('l'));
+        assert_eq!(game.confirm, Some(ConfirmAction::Load));
+        game.handle_key(KeyCode::Esc);
+        assert_eq!(game.confirm, None);
+        assert_eq!(game.mode, GameMode::Exploration);
+
+        game.mode = GameMode::Victory;
+        game.handle_key(KeyCode::Char('r'));
+        assert_eq!(game.confirm, Some(ConfirmAction::Restart));
+        game.handle_key(KeyCode::Char('y'));
+        assert_eq!(game.mode, GameMode::ClassSelect);
+        assert!(!game.has_unsaved_progress());
+    }
+
+    #[test]
+    fn a_waived_prompt_stays_quiet_until_restored() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(5);
+        game.player.gold += 10;
+        game.handle_key(KeyCode::Char('q'));
+        game.handle_key(KeyCode::Char('a'));
+        assert!(game.should_quit);
+        assert!(game.prefs.skip_confirm.contains(&ConfirmAction::Quit));
+
+        game.should_quit = false;
+        game.handle_key(KeyCode::Char('q'));
+        assert!(game.should_quit);
+
+        game.should_quit = false;
+        game.mode = GameMode::Settings;
+        game.handle_key(KeyCode::Char('c'));
+        assert!(game.prefs.skip_confirm.is_empty());
+        game.mode = GameMode::Exploration;
+        game.handle_key(KeyCode::Char('q'));
+        assert_eq!(game.confirm, Some(ConfirmAction::Quit));
+    }
+
+    #[test]
+    fn an_interrupted_session_offers_the_latest_autosave() {
+        rust_i18n::set_locale("en");
+        let mut path = std::env::temp_dir();
+        path.push(format!("rpg_tui_autosave_{}.json", rand::random::<u64>()));
+        let path = path.to_string_lossy().to_string();
+
+        let mut game = Game::new_with_seed(5);
+        game.begin_session_at(path.clone());
+        assert_eq!(game.confirm, None);
+        game.player.gold = 321;
+        game.enter_town_menu();
+        assert!(std::path::Path::new(&path).exists());
+
+        // The first session never calls `end_session`, as if it crashed.
+        let mut game = Game::new_with_seed(5);
+        game.begin_session_at(path.clone());
+        assert_eq!(game.confirm, Some(ConfirmAction::Resume));
+        game.handle_key(KeyCode::Char('y'));
+        assert_eq!(game.mode, GameMode::Town);
+        assert_eq!(game.player.gold, 321);
+        game.end_session();
+
+        let mut game = Game::new_with_seed(5);
+        game.begin_session_at(path.clone());
+        assert_eq!(game.confirm, None);
+        game.end_session();
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn autosave_settings_toggle_and_cycle_from_the_settings_screen() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(5);
+        game.mode = GameMode::Settings;
+        game.settings_cursor = AUTOSAVE_OPTION_START;
+        game.handle_key(KeyCode::Enter);
+        assert!(!game.prefs.autosave.enter_town);
+
+        game.settings_cursor = AUTOSAVE_OPTION_START + 2;
+        assert_eq!(game.prefs.autosave.every_moves, 50);
+        game.handle_key(KeyCode::Right);
+        assert_eq!(game.prefs.autosave.every_moves, 100);
+        game.handle_key(KeyCode::Right);
+        assert_eq!(game.prefs.autosave.every_moves, 0);
+        assert_eq!(
+            game.autosave_value_text(AutosaveOption::EveryMoves),
+            t!("ui.settings.autosave.off")
+        );
+        game.handle_key(KeyCode::Left);
+        assert_eq!(game.prefs.autosave.every_moves, 100);
+    }
+
+    #[test]
+    fn theme_setting_cycles_through_every_theme() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(5);
+        game.mode = GameMode::Settings;
+        game.settings_cursor = THEME_OPTION_INDEX;
+        assert_eq!(game.prefs.theme, ThemeId::Auto);
+        game.handle_key(KeyCode::Enter);
+        assert_eq!(game.prefs.theme, ThemeId::Default);
+        game.handle_key(KeyCode::Left);
+        game.handle_key(KeyCode::Left);
+        assert_eq!(game.prefs.theme, ThemeId::Monochrome);
+        assert!(
+            game.recent_event
+                .as_deref()
+                .is_some_and(|event| event.contains(&*t!(ThemeId::Monochrome.label_key())))
+        );
+    }
+
+    #[test]
+    fn glyph_setting_cycles_and_wraps_around() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(5);
+        game.mode = GameMode::Settings;
+        game.settings_cursor = GLYPHS_OPTION_INDEX;
+        game.handle_key(KeyCode::Left);
+        assert_eq!(game.prefs.glyphs, GlyphSetId::Emoji);
+        game.handle_key(KeyCode::Enter);
+        assert_eq!(game.prefs.glyphs, GlyphSetId::Ascii);
+    }
+
+    #[test]
+    fn battle_effects_play_only_when_animated_and_any_key_skips_them() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(12);
+        game.mode = GameMode::Battle;
+        game.battle = Some(sturdy_battle());
+        game.handle_key(KeyCode::Char('1'));
+        assert!(game.battle_effects.is_empty());
+
+        game.animations = true;
+        game.handle_key(KeyCode::Char('1'));
+        let sides: Vec<_> = game
+            .battle_effects
+            .iter()
+            .map(|effect| effect.side)
+            .collect();
+        assert_eq!(sides, [BattleSide::Foes, BattleSide::Hero]);
+        assert!(game.battle_effects.iter().all(BattleEffect::flashing));
+
+        let hp = game.battle.as_ref().unwrap().enemy.hp;
+        game.handle_key(KeyCode::Char('1'));
+        assert!(game.battle_effects.is_empty());
+        assert_eq!(game.battle.as_ref().unwrap().enemy.hp, hp);
+
+        game.handle_key(KeyCode::Char('1'));
+        for _ in 0..BattleEffect::FRAMES {
+            assert!(!game.battle_effects.is_empty());
+            game.step_animations();
+        }
+        assert!(game.battle_effects.is_empty());
+
+        // The blow that ends the fight leaves nothing to skip afterwards.
+        game.battle.as_mut().unwrap().enemy.hp = 1;
+        game.handle_key(KeyCode::Char('1'));
+        assert_eq!(game.mode, GameMode::Exploration);
+        assert!(game.battle_effects.is_empty());
+        game.handle_key(KeyCode::Char('p'));
+        assert_eq!(game.mode, GameMode::Party);
+    }
+
+    #[test]
+    fn world_map_overlay_toggles_and_holds_the_hero_still() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(5);
+        let start = (game.player.x, game.player.y);
+        game.handle_key(KeyCode::Char('m'));
+        assert!(game.world_map_open);
+        game.handle_key(KeyCode::Char('d'));
+        game.click(PointerTarget::Tile(Position { x: 1, y: 1 }));
+        assert_eq!((game.player.x, game.player.y), start);
+        assert!(game.auto_walk.is_empty());
+        game.handle_key(KeyCode::Char('m'));
+        assert!(!game.world_map_open);
+
+        game.handle_key(KeyCode::Char('m'));
+        game.handle_key(KeyCode::Esc);
+        assert!(!game.world_map_open);
+        assert_eq!(game.mode, GameMode::Exploration);
+
+        game.mode = GameMode::ClassSelect;
+        game.handle_key(KeyCode::Char('m'));
+        assert!(!game.world_map_open);
+        assert_eq!(game.mode, GameMode::Legacy);
+    }
+
+    #[test]
+    fn new_game_plus_keeps_the_hero_and_hardens_a_new_world() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(2026);
+        game.player.level = 12;
+        game.player.base_atk = 999;
+        game.player.bag.potion = 7;
+        game.player.equipment.weapon = WeaponTier::KnightSword;
+        game.start_boss_battle(BossId::AncientDragon);
+        game.handle_key(KeyCode::Char('1'));
+        assert_eq!(game.mode, GameMode::Victory);
+        let old_seed = game.map_seed;
+        let level = game.player.level;
+        let old_hp_scale = game.difficulty_profile.enemy_hp_scale;
+
+        game.handle_key(KeyCode::Char('n'));
+        assert_eq!(game.mode, GameMode::Exploration);
+        assert_eq!(game.ng_tier(), 1);
+        assert_ne!(game.map_seed, old_seed);
+        assert_eq!((game.player.level, game.player.bag.potion), (level, 7));
+        assert_eq!((game.player.x, game.player.y), (1, 1));
+        assert!(game.campaign.bosses_defeated.is_empty());
+        assert!(game.difficulty_profile.enemy_hp_scale > old_hp_scale);
+
+        game.mode = GameMode::Town;
+        game.player.gold = 1000;
+        game.handle_key(KeyCode::Char('3'));
+        assert_eq!(game.player.equipment.weapon, WeaponTier::RuneBlade);
+        game.handle_key(KeyCode::Char('3'));
+        assert_eq!(game.player.equipment.weapon, WeaponTier::RuneBlade);
     }
 }
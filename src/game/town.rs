@@ -1,4 +1,9 @@
-use crate::game::model::{Player, QuestState};
+use crate::game::balance::{TAVERN_ROSTER, companion_template};
+use crate::game::crafting;
+use crate::game::model::{
+    Companion, CompanionKind, Equipment, Player, QuestState, ShopStock, WorldClock,
+};
+use crate::game::party;
 use rust_i18n::t;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -10,81 +15,64 @@ pub enum TownAction {
     Healer,
     Inn,
     QuestBoard,
+    Tavern,
+    Workshop,
+    /// Crafts the workshop recipe at this index.
+    Craft(usize),
     Leave,
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum TownOutcome {
     Stay(String),
+    OpenWorkshop(String),
     Leave(String),
 }
 
 pub fn apply_action(
     player: &mut Player,
+    companions: &mut Vec<Companion>,
     quest: &mut QuestState,
+    shop: &mut ShopStock,
+    clock: &mut WorldClock,
+    ng_tier: u32,
     action: TownAction,
 ) -> TownOutcome {
     match action {
         TownAction::BuyPotion => {
+            if shop.potion <= 0 {
+                return TownOutcome::Stay(t!("log.town.sold_out_potion").to_string());
+            }
             if player.gold < 10 {
                 return TownOutcome::Stay(t!("log.town.not_enough_gold_potion").to_string());
             }
             player.gold -= 10;
+            shop.potion -= 1;
             player.bag.potion += 1;
             TownOutcome::Stay(t!("log.town.bought_potion", count = 1).to_string())
         }
         TownAction::BuyEther => {
+            if shop.ether <= 0 {
+                return TownOutcome::Stay(t!("log.town.sold_out_ether").to_string());
+            }
             if player.gold < 12 {
                 return TownOutcome::Stay(t!("log.town.not_enough_gold_ether").to_string());
             }
             player.gold -= 12;
+            shop.ether -= 1;
             player.bag.ether += 1;
             TownOutcome::Stay(t!("log.town.bought_ether", count = 1).to_string())
         }
-        TownAction::UpgradeWeapon => {
-            let weapon = player.equipment.weapon;
-            let Some(next_weapon) = weapon.next() else {
-                return TownOutcome::Stay(t!("log.town.weapon_max").to_string());
-            };
-            let Some(cost) = weapon.upgrade_cost() else {
-                return TownOutcome::Stay(t!("log.town.weapon_upgrade_unavailable").to_string());
-            };
-            if player.gold < cost {
-                return TownOutcome::Stay(
-                    t!("log.town.need_more_gold_weapon", cost = cost).to_string(),
-                );
-            }
-
-            player.gold -= cost;
-            player.equipment.weapon = next_weapon;
-            TownOutcome::Stay(
-                t!(
-                    "log.town.weapon_upgraded",
-                    weapon = t!(next_weapon.i18n_key())
-                )
-                .to_string(),
-            )
-        }
-        TownAction::UpgradeArmor => {
-            let armor = player.equipment.armor;
-            let Some(next_armor) = armor.next() else {
-                return TownOutcome::Stay(t!("log.town.armor_max").to_string());
-            };
-            let Some(cost) = armor.upgrade_cost() else {
-                return TownOutcome::Stay(t!("log.town.armor_upgrade_unavailable").to_string());
-            };
-            if player.gold < cost {
-                return TownOutcome::Stay(
-                    t!("log.town.need_more_gold_armor", cost = cost).to_string(),
-                );
-            }
-
-            player.gold -= cost;
-            player.equipment.armor = next_armor;
-            TownOutcome::Stay(
-                t!("log.town.armor_upgraded", armor = t!(next_armor.i18n_key())).to_string(),
-            )
-        }
+        TownAction::UpgradeWeapon => TownOutcome::Stay(upgrade_weapon(
+            &mut player.gold,
+            &mut player.equipment,
+            ng_tier,
+        )),
+        TownAction::UpgradeArmor => TownOutcome::Stay(upgrade_armor(
+            &mut player.gold,
+            &mut player.equipment,
+            ng_tier,
+        )),
         TownAction::Healer => {
             let cost = 8;
             if player.hp >= player.max_hp {
@@ -96,6 +84,9 @@ pub fn apply_action(
             let before = player.hp;
             player.gold -= cost;
             player.hp = player.max_hp;
+            for member in companions.iter_mut() {
+                member.hp = member.max_hp;
+            }
             TownOutcome::Stay(
                 t!(
                     "log.town.healer_restored",
@@ -108,7 +99,8 @@ pub fn apply_action(
         }
         TownAction::Inn => {
             let cost = 18;
-            if player.hp >= player.max_hp && player.mp >= player.max_mp {
+            let rested = player.hp >= player.max_hp && player.mp >= player.max_mp;
+            if rested && !clock.time_of_day().is_night() {
                 return TownOutcome::Stay(t!("log.town.inn_not_needed").to_string());
             }
             if player.gold < cost {
@@ -117,7 +109,12 @@ pub fn apply_action(
             player.gold -= cost;
             player.hp = player.max_hp;
             player.mp = player.max_mp;
-            TownOutcome::Stay(t!("log.town.inn_restored", cost = cost).to_string())
+            party::restore_all(companions);
+            clock.advance_to_morning();
+            shop.restock_if_due(*clock);
+            TownOutcome::Stay(
+                t!("log.town.inn_restored", cost = cost, day = clock.day()).to_string(),
+            )
         }
         TownAction::QuestBoard => {
             if !quest.accepted {
@@ -150,14 +147,121 @@ pub fn apply_action(
                 t!("log.quest.progress", progress = quest.progress_text()).to_string(),
             )
         }
+        TownAction::Tavern => {
+            let Some(kind) = tavern_offer(companions) else {
+                return TownOutcome::Stay(t!("log.town.tavern_empty").to_string());
+            };
+            if !party::has_room(companions) {
+                return TownOutcome::Stay(t!("log.party.full").to_string());
+            }
+            let cost = companion_template(kind).hire_cost;
+            if player.gold < cost {
+                return TownOutcome::Stay(
+                    t!(
+                        "log.town.tavern_need_gold",
+                        name = t!(kind.name_key()),
+                        cost = cost
+                    )
+                    .to_string(),
+                );
+            }
+            match party::recruit(companions, kind, player.level) {
+                Ok(message) => {
+                    player.gold -= cost;
+                    TownOutcome::Stay(message)
+                }
+                Err(message) => TownOutcome::Stay(message),
+            }
+        }
+        TownAction::Workshop => TownOutcome::OpenWorkshop(t!("log.workshop.opened").to_string()),
+        TownAction::Craft(index) => TownOutcome::Stay(crafting::craft(player, index, ng_tier)),
         TownAction::Leave => TownOutcome::Leave(t!("log.town.leaving").to_string()),
     }
 }
 
+/// Buys the next weapon tier for whoever owns `equipment`, paid from `gold`.
+/// Tiers beyond the current New Game+ cycle stay off the shelf.
+pub fn upgrade_weapon(gold: &mut i32, equipment: &mut Equipment, ng_tier: u32) -> String {
+    let weapon = equipment.weapon;
+    let Some(next_weapon) = weapon.next() else {
+        return t!("log.town.weapon_max").to_string();
+    };
+    if next_weapon.ng_tier() > ng_tier {
+        return t!(
+            "log.town.ng_locked",
+            item = t!(next_weapon.i18n_key()),
+            tier = next_weapon.ng_tier()
+        )
+        .to_string();
+    }
+    let Some(cost) = weapon.upgrade_cost() else {
+        return t!("log.town.weapon_upgrade_unavailable").to_string();
+    };
+    if *gold < cost {
+        return t!("log.town.need_more_gold_weapon", cost = cost).to_string();
+    }
+
+    *gold -= cost;
+    equipment.weapon = next_weapon;
+    t!(
+        "log.town.weapon_upgraded",
+        weapon = t!(next_weapon.i18n_key())
+    )
+    .to_string()
+}
+
+/// The next tavern regular who has not joined yet.
+pub fn tavern_offer(companions: &[Companion]) -> Option<CompanionKind> {
+    TAVERN_ROSTER
+        .into_iter()
+        .find(|kind| companions.iter().all(|member| member.kind != *kind))
+}
+
+/// Buys the next armor tier for whoever owns `equipment`, paid from `gold`.
+/// Tiers beyond the current New Game+ cycle stay off the shelf.
+pub fn upgrade_armor(gold: &mut i32, equipment: &mut Equipment, ng_tier: u32) -> String {
+    let armor = equipment.armor;
+    let Some(next_armor) = armor.next() else {
+        return t!("log.town.armor_max").to_string();
+    };
+    if next_armor.ng_tier() > ng_tier {
+        return t!(
+            "log.town.ng_locked",
+            item = t!(next_armor.i18n_key()),
+            tier = next_armor.ng_tier()
+        )
+        .to_string();
+    }
+    let Some(cost) = armor.upgrade_cost() else {
+        return t!("log.town.armor_upgrade_unavailable").to_string();
+    };
+    if *gold < cost {
+        return t!("log.town.need_more_gold_armor", cost = cost).to_string();
+    }
+
+    *gold -= cost;
+    equipment.armor = next_armor;
+    t!("log.town.armor_upgraded", armor = t!(next_armor.i18n_key())).to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use super::{TownAction, TownOutcome, apply_action};
-    use crate::game::model::{ArmorTier, Player, QuestState, WeaponTier};
+    use crate::game::model::{
+        ArmorTier, Companion, CompanionKind, Player, QuestState, ShopStock, WeaponTier, WorldClock,
+    };
+
+    fn act(player: &mut Player, quest: &mut QuestState, action: TownAction) -> TownOutcome {
+        apply_action(
+            player,
+            &mut Vec::new(),
+            quest,
+            &mut ShopStock::default(),
+            &mut WorldClock::default(),
+            0,
+            action,
+        )
+    }
 
     #[test]
     fn buy_potion_updates_inventory_and_gold() {
@@ -167,7 +271,7 @@ mod tests {
         player.bag.potion = 0;
 
         let mut quest = QuestState::new();
-        let out = apply_action(&mut player, &mut quest, TownAction::BuyPotion);
+        let out = act(&mut player, &mut quest, TownAction::BuyPotion);
         assert!(matches!(out, TownOutcome::Stay(_)));
         assert_eq!(player.gold, 20);
         assert_eq!(player.bag.potion, 1);
@@ -181,12 +285,30 @@ mod tests {
         player.equipment.weapon = WeaponTier::WoodenSword;
 
         let mut quest = QuestState::new();
-        let out = apply_action(&mut player, &mut quest, TownAction::UpgradeWeapon);
+        let out = act(&mut player, &mut quest, TownAction::UpgradeWeapon);
         assert!(matches!(out, TownOutcome::Stay(_)));
         assert_eq!(player.equipment.weapon, WeaponTier::BronzeSword);
         assert_eq!(player.gold, 70);
     }
 
+    #[test]
+    fn tiers_past_the_base_game_need_new_game_plus() {
+        rust_i18n::set_locale("en");
+        let mut player = Player::new();
+        player.gold = 1000;
+        player.equipment.weapon = WeaponTier::KnightSword;
+        let mut equipment = player.equipment;
+
+        super::upgrade_weapon(&mut player.gold, &mut equipment, 0);
+        assert_eq!(equipment.weapon, WeaponTier::KnightSword);
+        assert_eq!(player.gold, 1000);
+
+        super::upgrade_weapon(&mut player.gold, &mut equipment, 1);
+        assert_eq!(equipment.weapon, WeaponTier::RuneBlade);
+        super::upgrade_weapon(&mut player.gold, &mut equipment, 1);
+        assert_eq!(equipment.weapon, WeaponTier::RuneBlade);
+    }
+
     #[test]
     fn upgrade_armor_fails_when_not_enough_gold() {
         rust_i18n::set_locale("en");
@@ -195,7 +317,7 @@ mod tests {
         player.equipment.armor = ArmorTier::ClothArmor;
 
         let mut quest = QuestState::new();
-        let out = apply_action(&mut player, &mut quest, TownAction::UpgradeArmor);
+        let out = act(&mut player, &mut quest, TownAction::UpgradeArmor);
         assert!(matches!(out, TownOutcome::Stay(_)));
         assert_eq!(player.equipment.armor, ArmorTier::ClothArmor);
         assert_eq!(player.gold, 5);
@@ -207,15 +329,128 @@ mod tests {
         let mut player = Player::new();
         let mut quest = QuestState::new();
 
-        let accepted = apply_action(&mut player, &mut quest, TownAction::QuestBoard);
+        let accepted = act(&mut player, &mut quest, TownAction::QuestBoard);
         assert!(matches!(accepted, TownOutcome::Stay(_)));
         assert!(quest.accepted);
 
         quest.completed = true;
         let before = player.gold;
-        let claimed = apply_action(&mut player, &mut quest, TownAction::QuestBoard);
+        let claimed = act(&mut player, &mut quest, TownAction::QuestBoard);
         assert!(matches!(claimed, TownOutcome::Stay(_)));
         assert!(quest.rewarded);
         assert_eq!(player.gold, before + quest.reward_gold);
     }
+
+    #[test]
+    fn shop_runs_out_of_stock() {
+        rust_i18n::set_locale("en");
+        let mut player = Player::new();
+        player.gold = 100;
+        let mut quest = QuestState::new();
+        let mut shop = ShopStock {
+            potion: 1,
+            ..ShopStock::default()
+        };
+        let mut clock = WorldClock::default();
+
+        apply_action(
+            &mut player,
+            &mut Vec::new(),
+            &mut quest,
+            &mut shop,
+            &mut clock,
+            0,
+            TownAction::BuyPotion,
+        );
+        let gold = player.gold;
+        apply_action(
+            &mut player,
+            &mut Vec::new(),
+            &mut quest,
+            &mut shop,
+            &mut clock,
+            0,
+            TownAction::BuyPotion,
+        );
+        assert_eq!(shop.potion, 0);
+        assert_eq!(player.gold, gold);
+    }
+
+    #[test]
+    fn inn_sleeps_until_morning_and_restocks_the_shop() {
+        rust_i18n::set_locale("en");
+        let mut player = Player::new();
+        player.gold = 100;
+        let mut quest = QuestState::new();
+        let mut shop = ShopStock {
+            potion: 0,
+            ..ShopStock::default()
+        };
+        let mut clock = WorldClock::at(1, 22);
+
+        let out = apply_action(
+            &mut player,
+            &mut Vec::new(),
+            &mut quest,
+            &mut shop,
+            &mut clock,
+            0,
+            TownAction::Inn,
+        );
+        assert!(matches!(out, TownOutcome::Stay(_)));
+        assert_eq!(player.gold, 82);
+        assert_eq!(clock.day(), 2);
+        assert!(!clock.time_of_day().is_night());
+        assert_eq!(
+            shop,
+            ShopStock {
+                restocked_day: 2,
+                ..ShopStock::default()
+            }
+        );
+    }
+
+    #[test]
+    fn tavern_hires_the_roster_in_order_until_it_runs_out() {
+        rust_i18n::set_locale("en");
+        let mut player = Player::new();
+        player.gold = 200;
+        let mut companions = Vec::new();
+        let mut quest = QuestState::new();
+        let mut hire = |player: &mut Player, companions: &mut Vec<Companion>| {
+            apply_action(
+                player,
+                companions,
+                &mut quest,
+                &mut ShopStock::default(),
+                &mut WorldClock::default(),
+                0,
+                TownAction::Tavern,
+            )
+        };
+
+        hire(&mut player, &mut companions);
+        hire(&mut player, &mut companions);
+        hire(&mut player, &mut companions);
+        assert_eq!(companions.len(), 2);
+        assert_eq!(companions[0].kind, CompanionKind::Knight);
+        assert_eq!(companions[1].kind, CompanionKind::Cleric);
+        assert_eq!(player.gold, 90);
+    }
+
+    #[test]
+    fn workshop_opens_and_crafts_from_the_bag() {
+        rust_i18n::set_locale("en");
+        let mut player = Player::new();
+        let mut quest = QuestState::new();
+        let out = act(&mut player, &mut quest, TownAction::Workshop);
+        assert!(matches!(out, TownOutcome::OpenWorkshop(_)));
+
+        player.bag.materials.herb = 2;
+        let potions = player.bag.potion;
+        let out = act(&mut player, &mut quest, TownAction::Craft(0));
+        assert!(matches!(out, TownOutcome::Stay(_)));
+        assert_eq!(player.bag.potion, potions + 1);
+        assert_eq!(player.bag.materials.herb, 0);
+    }
 }
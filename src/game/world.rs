@@ -1,23 +1,49 @@
+use std::collections::{HashMap, VecDeque};
+
 use rand::Rng;
 use rand::SeedableRng;
 use rand::rngs::StdRng;
 
-use crate::game::model::{Chest, MAP_H, MAP_W, NpcKind, NpcPoint, Position, Tile, WorldObjects};
+use crate::game::balance::{
+    BOSS_SITE_MIN_TOWN_DISTANCE, RESOURCE_NODE_COUNT, RESOURCE_NODE_MAX_AMOUNT,
+    ROAMING_MONSTER_COUNT,
+};
+use crate::game::encounter::spawn_pool;
+use crate::game::model::{
+    BossId, BossSite, Chest, Facing, MAP_H, MAP_W, Material, NpcKind, NpcPoint, Position,
+    ResourceNode, RoamingMonster, Tile, WorldObjects,
+};
+
+/// Roaming monsters draw from their own stream, so a seed lays out the same
+/// world whether or not they are in play.
+const ROAMING_SALT: u64 = 0xD1B5_4A32_D192_ED03;
+/// Reinforcements draw from a stream of their own too, so a run with them
+/// keeps the same first pack as one without.
+const REINFORCEMENT_SALT: u64 = 0x6A09_E667_F3BC_C908;
 
 pub fn generate_world(seed: u64) -> (Vec<Vec<Tile>>, WorldObjects) {
     let mut rng = StdRng::seed_from_u64(seed);
     let map = generate_map(&mut rng);
-    let objects = generate_world_objects(&map, &mut rng);
+    let mut objects = generate_world_objects(&map, &mut rng);
+    let mut roaming_rng = StdRng::seed_from_u64(seed ^ ROAMING_SALT);
+    objects.monsters =
+        generate_roaming_monsters(&map, &objects, ROAMING_MONSTER_COUNT, &mut roaming_rng);
     (map, objects)
 }
 
+/// Adds a second pack of roaming monsters to the world, as the Double
+/// Encounters modifier asks for when monsters roam.
+pub fn reinforce_roaming(map: &[Vec<Tile>], objects: &mut WorldObjects, seed: u64) {
+    let mut rng = StdRng::seed_from_u64(seed ^ REINFORCEMENT_SALT);
+    let extra = generate_roaming_monsters(map, objects, ROAMING_MONSTER_COUNT, &mut rng);
+    objects.monsters.extend(extra);
+}
+
 pub fn generate_map(rng: &mut impl Rng) -> Vec<Vec<Tile>> {
     let mut map = vec![vec![Tile::Floor; MAP_W]; MAP_H];
 
-    for x in 0..MAP_W {
-        map[0][x] = Tile::Wall;
-        map[MAP_H - 1][x] = Tile::Wall;
-    }
+    map[0].fill(Tile::Wall);
+    map[MAP_H - 1].fill(Tile::Wall);
     for row in &mut map {
         row[0] = Tile::Wall;
         row[MAP_W - 1] = Tile::Wall;
@@ -78,7 +104,91 @@ fn generate_world_objects(map: &[Vec<Tile>], rng: &mut impl Rng) -> WorldObjects
         });
     }
 
-    WorldObjects::new(chests, npcs)
+    let town = Position { x: 2, y: 2 };
+    candidates.retain(|pos| {
+        pos.x.abs_diff(town.x) + pos.y.abs_diff(town.y) >= BOSS_SITE_MIN_TOWN_DISTANCE
+    });
+    let mut boss_sites = Vec::new();
+    for boss in BossId::SEAL_GUARDIANS {
+        if candidates.is_empty() {
+            break;
+        }
+        let position = pop_random_position(&mut candidates, rng);
+        boss_sites.push(BossSite {
+            boss,
+            position,
+            defeated: false,
+        });
+    }
+
+    // Hides only come from beasts, so the wilds offer herbs and ore.
+    let mut resource_nodes = Vec::new();
+    for idx in 0..RESOURCE_NODE_COUNT {
+        if candidates.is_empty() {
+            break;
+        }
+        let position = pop_random_position(&mut candidates, rng);
+        resource_nodes.push(ResourceNode {
+            position,
+            material: if idx % 2 == 0 {
+                Material::Herb
+            } else {
+                Material::Ore
+            },
+            amount: rng.random_range(1..=RESOURCE_NODE_MAX_AMOUNT),
+            gathered: false,
+        });
+    }
+
+    let mut objects = WorldObjects::new(chests, npcs);
+    objects.boss_sites = boss_sites;
+    objects.resource_nodes = resource_nodes;
+    objects
+}
+
+/// Roaming monsters on floor tiles no other object has taken, numbered on
+/// from the ones already in the world.
+fn generate_roaming_monsters(
+    map: &[Vec<Tile>],
+    objects: &WorldObjects,
+    count: usize,
+    rng: &mut impl Rng,
+) -> Vec<RoamingMonster> {
+    let taken: Vec<Position> = objects
+        .chests
+        .iter()
+        .map(|chest| chest.position)
+        .chain(objects.npcs.iter().map(|npc| npc.position))
+        .chain(objects.boss_sites.iter().map(|site| site.position))
+        .chain(objects.resource_nodes.iter().map(|node| node.position))
+        .chain(objects.monsters.iter().map(|monster| monster.position))
+        .collect();
+    let mut candidates = floor_candidates(map);
+    candidates.retain(|pos| !taken.contains(pos));
+
+    let pool = spawn_pool(false);
+    let mut monsters = Vec::new();
+    let first_id = objects
+        .monsters
+        .iter()
+        .map(|monster| monster.id + 1)
+        .max()
+        .unwrap_or(0);
+    for id in first_id..first_id + count as u32 {
+        if candidates.is_empty() {
+            break;
+        }
+        let position = pop_random_position(&mut candidates, rng);
+        monsters.push(RoamingMonster {
+            id,
+            position,
+            facing: Facing::ALL[rng.random_range(0..Facing::ALL.len())],
+            template: pool[rng.random_range(0..pool.len())],
+            hp_percent: 100,
+            stunned_turns: 0,
+        });
+    }
+    monsters
 }
 
 fn floor_candidates(map: &[Vec<Tile>]) -> Vec<Position> {
@@ -105,9 +215,101 @@ fn pop_random_position(candidates: &mut Vec<Position>, rng: &mut impl Rng) -> Po
     candidates.swap_remove(idx)
 }
 
+/// Shortest walk over explored ground, without the starting tile. Towns and
+/// the lair are only ever the destination, never a tile passed through.
+pub fn find_path(
+    map: &[Vec<Tile>],
+    world: &WorldObjects,
+    from: Position,
+    to: Position,
+) -> Option<Vec<Position>> {
+    let passable = |at: Position| map[at.y][at.x] != Tile::Wall && world.is_explored(at.x, at.y);
+    if to.x >= MAP_W || to.y >= MAP_H || !passable(to) {
+        return None;
+    }
+    let mut came_from = HashMap::from([(from, from)]);
+    let mut queue = VecDeque::from([from]);
+    while let Some(current) = queue.pop_front() {
+        if current == to {
+            let mut path = Vec::new();
+            let mut step = to;
+            while step != from {
+                path.push(step);
+                step = came_from[&step];
+            }
+            path.reverse();
+            return Some(path);
+        }
+        if current != from && matches!(map[current.y][current.x], Tile::Town | Tile::Lair) {
+            continue;
+        }
+        for (dx, dy) in [(0, -1), (0, 1), (-1, 0), (1, 0)] {
+            let (Some(x), Some(y)) = (
+                current.x.checked_add_signed(dx),
+                current.y.checked_add_signed(dy),
+            ) else {
+                continue;
+            };
+            let next = Position { x, y };
+            if x >= MAP_W || y >= MAP_H || came_from.contains_key(&next) || !passable(next) {
+                continue;
+            }
+            came_from.insert(next, current);
+            queue.push_back(next);
+        }
+    }
+    None
+}
+
 #[cfg(test)]
 mod tests {
-    use super::generate_world;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use super::{
+        find_path, generate_map, generate_world, generate_world_objects, reinforce_roaming,
+    };
+    use crate::game::balance::ROAMING_MONSTER_COUNT;
+    use crate::game::model::{MAP_H, MAP_W, Position, Tile, WorldObjects};
+
+    #[test]
+    fn paths_go_around_walls_and_stay_on_explored_ground() {
+        let mut map = vec![vec![Tile::Floor; MAP_W]; MAP_H];
+        for row in map.iter_mut().take(4) {
+            row[3] = Tile::Wall;
+        }
+        map[1][5] = Tile::Town;
+        let mut world = WorldObjects::new(Vec::new(), Vec::new());
+        world.reveal_around(Position { x: 3, y: 3 }, 3);
+
+        let from = Position { x: 1, y: 1 };
+        let path = find_path(&map, &world, from, Position { x: 4, y: 1 }).unwrap();
+        assert_eq!(path.len(), 9);
+        assert_eq!(path.last(), Some(&Position { x: 4, y: 1 }));
+        assert!(path.iter().all(|step| map[step.y][step.x] == Tile::Floor));
+
+        // The town is a destination but never a shortcut.
+        assert_eq!(
+            find_path(&map, &world, from, Position { x: 5, y: 1 }).map(|path| path.len()),
+            Some(10)
+        );
+        assert!(
+            find_path(
+                &map,
+                &world,
+                Position { x: 4, y: 1 },
+                Position { x: 6, y: 1 }
+            )
+            .unwrap()
+            .iter()
+            .all(|step| *step != Position { x: 5, y: 1 })
+        );
+        assert_eq!(find_path(&map, &world, from, Position { x: 3, y: 0 }), None);
+        assert_eq!(
+            find_path(&map, &world, from, Position { x: 20, y: 1 }),
+            None
+        );
+    }
 
     #[test]
     fn world_generation_places_objects_deterministically_for_seed() {
@@ -120,5 +322,70 @@ mod tests {
             objects_b.chests[0].position.x
         );
         assert_eq!(objects_a.npcs[0].position.y, objects_b.npcs[0].position.y);
+        assert_eq!(
+            objects_a.monsters[0].position,
+            objects_b.monsters[0].position
+        );
+        assert_eq!(objects_a.boss_sites.len(), 3);
+        assert_eq!(
+            objects_a.boss_sites[2].position,
+            objects_b.boss_sites[2].position
+        );
+        assert!(!objects_a.resource_nodes.is_empty());
+        assert_eq!(
+            objects_a.resource_nodes[0].position,
+            objects_b.resource_nodes[0].position
+        );
+    }
+
+    #[test]
+    fn roaming_monsters_leave_the_rest_of_the_world_alone() {
+        let (map, world) = generate_world(2026);
+        let mut rng = StdRng::seed_from_u64(2026);
+        assert_eq!(generate_map(&mut rng), map);
+        let plain = generate_world_objects(&map, &mut rng);
+        let chests = |objects: &WorldObjects| {
+            objects
+                .chests
+                .iter()
+                .map(|chest| chest.position)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(chests(&plain), chests(&world));
+        assert_eq!(plain.npcs[2].position, world.npcs[2].position);
+        assert_eq!(plain.boss_sites[2].position, world.boss_sites[2].position);
+        assert!(plain.monsters.is_empty());
+
+        assert!(!world.monsters.is_empty());
+        for monster in &world.monsters {
+            assert!(!chests(&world).contains(&monster.position));
+            assert!(
+                world
+                    .npcs
+                    .iter()
+                    .all(|npc| npc.position != monster.position)
+            );
+        }
+    }
+
+    #[test]
+    fn reinforcements_join_the_first_pack_on_free_tiles() {
+        let (map, mut world) = generate_world(2026);
+        let first_pack = world.monsters.clone();
+        reinforce_roaming(&map, &mut world, 2026);
+
+        assert_eq!(world.monsters.len(), 2 * ROAMING_MONSTER_COUNT);
+        for (kept, original) in world.monsters.iter().zip(&first_pack) {
+            assert_eq!(kept.position, original.position);
+        }
+        for (idx, monster) in world.monsters.iter().enumerate() {
+            assert_eq!(monster.id, idx as u32);
+            assert_eq!(map[monster.position.y][monster.position.x], Tile::Floor);
+            assert!(
+                world.monsters[..idx]
+                    .iter()
+                    .all(|other| other.position != monster.position)
+            );
+        }
     }
 }
@@ -1,33 +1,132 @@
 use ratatui::Frame;
-use ratatui::layout::{Constraint, Direction, Layout, Margin, Rect};
-use ratatui::style::{Color, Modifier, Style};
-use ratatui::text::Line;
-use ratatui::widgets::{Block, BorderType, Borders};
+use ratatui::layout::{Alignment, Constraint, Flex, Layout, Rect};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, BorderType, Borders, Paragraph, Wrap};
+use rust_i18n::t;
 
 use crate::game::Game;
-use crate::game::model::GameMode;
+use crate::game::model::{Command, ConfirmAction, GameMode, PointerTarget};
 
+mod battle_scene;
+mod extras;
 mod footer;
+mod glyphs;
+mod layout;
 mod map;
+mod modal;
 mod sidebar;
+mod theme;
+mod worldmap;
 
-pub(crate) const SURFACE: Color = Color::Rgb(17, 20, 30);
-pub(crate) const TEXT: Color = Color::Rgb(230, 232, 241);
-pub(crate) const MUTED: Color = Color::Rgb(145, 152, 173);
-const ROOT_TOP_PERCENT: u16 = 72;
-const MAP_PERCENT: u16 = 68;
-const RIGHT_STATS_PERCENT: u16 = 40;
-const RIGHT_LOG_PERCENT: u16 = 34;
-const RIGHT_CONTROLS_PERCENT: u16 = 26;
+use layout::{Breakpoint, MIN_HEIGHT, MIN_WIDTH, rect_contains};
+use theme::{Paint, Theme};
 
 pub fn render(frame: &mut Frame, game: &Game) {
-    let layout = split_layout(frame.area());
+    let theme = theme::of(game);
+    let layout = layout::split(frame.area(), game.panel_tab);
+    if layout.breakpoint == Breakpoint::TooSmall {
+        render_too_small(frame, theme);
+        // A pending prompt still answers to keys, so it must stay readable.
+        if let Some(action) = game.confirm {
+            render_confirm(frame, game, theme, action);
+        }
+        return;
+    }
 
-    map::render(frame, game, layout.map);
-    sidebar::render_stats(frame, game, layout.hero, game.hero_scroll);
-    sidebar::render_log(frame, game, layout.log, game.log_scroll);
-    sidebar::render_controls(frame, game.mode, layout.controls, game.controls_scroll);
+    match &game.battle {
+        Some(battle) if game.mode == GameMode::Battle => {
+            battle_scene::render(frame, game, battle, layout.map);
+        }
+        _ => map::render(frame, game, layout.map),
+    }
+    if let Some(area) = layout.hero {
+        sidebar::render_stats(frame, game, area, game.hero_scroll);
+    }
+    if let Some(area) = layout.log {
+        sidebar::render_log(frame, game, area, game.log_scroll);
+    }
+    if let Some(area) = layout.controls {
+        sidebar::render_controls(frame, game, area, game.controls_scroll);
+    }
+    if let Some(area) = layout.tabs {
+        sidebar::render_tabs(frame, game, area);
+    }
+    if let Some(area) = layout.minimap {
+        extras::render_minimap(frame, game, area);
+    }
+    if let Some(area) = layout.quests {
+        extras::render_quests(frame, game, area);
+    }
+    if let Some(area) = layout.bestiary {
+        extras::render_bestiary(frame, game, area);
+    }
     footer::render(frame, game, layout.footer);
+    if game.world_map_open {
+        worldmap::render_overlay(frame, game);
+    } else if game.confirm.is_none()
+        && let Some((x, y)) = game.mouse
+        && let Some((_, region)) = pointer_region(frame.area(), game, x, y)
+    {
+        frame.buffer_mut().set_style(region, theme.hover);
+    }
+    if let Some(action) = game.confirm {
+        render_confirm(frame, game, theme, action);
+    }
+}
+
+/// Stands in for the whole screen until the terminal reaches the minimum size.
+fn render_too_small(frame: &mut Frame, theme: &Theme) {
+    let area = frame.area();
+    let lines = vec![
+        Line::from(Span::styled(
+            t!("ui.layout.too_small").to_string(),
+            theme.text.bold(),
+        )),
+        Line::from(Span::styled(
+            t!(
+                "ui.layout.resize",
+                width = MIN_WIDTH,
+                height = MIN_HEIGHT,
+                current = format!("{}x{}", area.width, area.height)
+            )
+            .to_string(),
+            theme.muted,
+        )),
+    ];
+    let [middle] = Layout::vertical([Constraint::Length(3)])
+        .flex(Flex::Center)
+        .areas(area);
+    let notice = Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+    frame.render_widget(notice, middle);
+}
+
+fn render_confirm(frame: &mut Frame, game: &Game, theme: &Theme, action: ConfirmAction) {
+    let body = vec![
+        Line::from(t!(action.body_key()).to_string()),
+        Line::from(""),
+        Line::from(Span::styled(
+            t!(
+                "ui.confirm.options",
+                yes = game.key_label(Command::Accept),
+                confirm = game.key_label(Command::Confirm),
+                no = game.key_label(Command::Decline),
+                back = game.key_label(Command::Back),
+                always = game.key_label(Command::AcceptAlways)
+            )
+            .to_string(),
+            theme.muted,
+        )),
+    ];
+    modal::render(
+        frame,
+        theme,
+        t!(action.title_key()).to_string(),
+        body,
+        theme.confirm,
+    );
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -37,95 +136,62 @@ pub enum ScrollTarget {
     Controls,
 }
 
-pub fn scroll_target_at(frame_area: Rect, x: u16, y: u16) -> Option<ScrollTarget> {
-    let layout = split_layout(frame_area);
-    if rect_contains(layout.hero, x, y) {
+pub fn scroll_target_at(frame_area: Rect, game: &Game, x: u16, y: u16) -> Option<ScrollTarget> {
+    let layout = layout::split(frame_area, game.panel_tab);
+    let over = |area: Option<Rect>| area.is_some_and(|area| rect_contains(area, x, y));
+    if over(layout.hero) {
         return Some(ScrollTarget::Hero);
     }
-    if rect_contains(layout.log, x, y) {
+    if over(layout.log) {
         return Some(ScrollTarget::BattleLog);
     }
-    if rect_contains(layout.controls, x, y) {
+    if over(layout.controls) {
         return Some(ScrollTarget::Controls);
     }
     None
 }
 
-#[derive(Clone, Copy)]
-struct UiLayout {
-    map: Rect,
-    hero: Rect,
-    log: Rect,
-    controls: Rect,
-    footer: Rect,
+/// What a click at the given cell would act on.
+pub fn pointer_target_at(frame_area: Rect, game: &Game, x: u16, y: u16) -> Option<PointerTarget> {
+    pointer_region(frame_area, game, x, y).map(|(target, _)| target)
 }
 
-fn split_layout(frame_area: Rect) -> UiLayout {
-    let area = frame_area.inner(Margin {
-        horizontal: 1,
-        vertical: 0,
-    });
-    let root = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Percentage(ROOT_TOP_PERCENT),
-            Constraint::Percentage(100 - ROOT_TOP_PERCENT),
-        ])
-        .split(area);
-    let top = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Percentage(MAP_PERCENT),
-            Constraint::Percentage(100 - MAP_PERCENT),
-        ])
-        .split(root[0]);
-    let right = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Percentage(RIGHT_STATS_PERCENT),
-            Constraint::Percentage(RIGHT_LOG_PERCENT),
-            Constraint::Percentage(RIGHT_CONTROLS_PERCENT),
-        ])
-        .split(top[1]);
-
-    UiLayout {
-        map: top[0],
-        hero: right[0],
-        log: right[1],
-        controls: right[2],
-        footer: root[1],
+/// The clickable footer option, map tile or panel tab under a cell, with the
+/// screen region that lights up while hovered.
+fn pointer_region(frame_area: Rect, game: &Game, x: u16, y: u16) -> Option<(PointerTarget, Rect)> {
+    let layout = layout::split(frame_area, game.panel_tab);
+    if let Some(tabs) = layout.tabs
+        && rect_contains(tabs, x, y)
+    {
+        return sidebar::tab_regions(tabs)
+            .into_iter()
+            .find(|(_, region)| rect_contains(*region, x, y))
+            .map(|(tab, region)| (PointerTarget::Tab(tab), region));
     }
-}
-
-fn rect_contains(rect: Rect, x: u16, y: u16) -> bool {
-    x >= rect.x
-        && x < rect.x.saturating_add(rect.width)
-        && y >= rect.y
-        && y < rect.y.saturating_add(rect.height)
-}
-
-pub(crate) fn mode_accent(mode: GameMode) -> Color {
-    match mode {
-        GameMode::Exploration => Color::Rgb(80, 175, 255),
-        GameMode::Town => Color::Rgb(115, 212, 130),
-        GameMode::Settings => Color::Rgb(240, 189, 95),
-        GameMode::Battle => Color::Rgb(255, 121, 121),
-        GameMode::Victory => Color::Rgb(118, 215, 141),
-        GameMode::GameOver => Color::Rgb(228, 94, 84),
+    if rect_contains(layout.footer, x, y) {
+        return footer::option_regions(game, layout.footer)
+            .into_iter()
+            .find(|(_, region)| rect_contains(*region, x, y))
+            .map(|(index, region)| (PointerTarget::MenuOption(index), region));
     }
+    if rect_contains(layout.map, x, y) {
+        return map::tile_at(game, layout.map, x, y)
+            .map(|(position, region)| (PointerTarget::Tile(position), region));
+    }
+    None
 }
 
-pub(crate) fn panel_block<'a, T>(title: T, accent: Color) -> Block<'a>
+pub(crate) fn panel_block<'a, T>(theme: &Theme, title: T, accent: Paint) -> Block<'a>
 where
     T: Into<Line<'a>>,
 {
     Block::default()
         .title(title)
-        .title_style(Style::default().fg(accent).add_modifier(Modifier::BOLD))
+        .title_style(accent.bold())
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
-        .border_style(Style::default().fg(accent))
-        .style(Style::default().fg(TEXT).bg(SURFACE))
+        .border_style(accent)
+        .style(Style::from(theme.text).patch(theme.surface))
 }
 
 pub(crate) fn bar(cur: i32, max: i32, width: usize) -> String {
@@ -137,15 +203,64 @@ pub(crate) fn bar(cur: i32, max: i32, width: usize) -> String {
     format!("[{}{}]", "=".repeat(filled), " ".repeat(empty))
 }
 
+/// Names an enemy, or a cause of death, that the run's records filed by
+/// locale key. Older records hold the name itself, which is shown as is.
+pub(crate) fn record_name(key: &str) -> String {
+    let name = t!(key);
+    if name == format!("{}.{key}", &*rust_i18n::locale()) {
+        key.to_string()
+    } else {
+        name.to_string()
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use crossterm::event::KeyCode;
     use ratatui::Terminal;
     use ratatui::backend::TestBackend;
+    use ratatui::layout::Rect;
+    use ratatui::style::{Color, Modifier, Style};
 
     use crate::game::Game;
-    use crate::game::model::GameMode;
+    use crate::game::model::{
+        AutosaveOption, ConfirmAction, Difficulty, GameMode, GlyphSetId, Language, PanelTab,
+        PointerTarget, Position, ThemeId,
+    };
+
+    use super::{footer, layout, pointer_target_at, record_name, render, theme};
+
+    fn row_text(terminal: &Terminal<TestBackend>, y: u16) -> String {
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.width)
+            .map(|x| buffer[(x, y)].symbol())
+            .collect()
+    }
+
+    #[test]
+    fn record_names_are_translated_but_old_names_kept() {
+        rust_i18n::set_locale("en");
+        assert_eq!(record_name("enemy.slime"), "Slime");
+        assert_ne!(
+            record_name("ui.result.cause_event"),
+            "ui.result.cause_event"
+        );
+        assert_eq!(record_name("Wolf"), "Wolf");
+    }
 
-    use super::render;
+    #[test]
+    fn a_pending_prompt_shows_even_when_the_terminal_is_too_small() {
+        rust_i18n::set_locale("en");
+        let mut terminal = Terminal::new(TestBackend::new(40, 12)).expect("terminal init");
+        let mut game = Game::new_with_seed(2026);
+        game.confirm = Some(ConfirmAction::Quit);
+        terminal
+            .draw(|frame| render(frame, &game))
+            .expect("too-small render should succeed");
+
+        let title = rust_i18n::t!(ConfirmAction::Quit.title_key()).to_string();
+        assert!((0..12).any(|y| row_text(&terminal, y).contains(&title)));
+    }
 
     #[test]
     fn render_settings_on_small_terminal_does_not_panic() {
@@ -169,4 +284,184 @@ mod tests {
             .draw(|frame| render(frame, &game))
             .expect("exploration render should succeed");
     }
+
+    #[test]
+    fn confirm_prompt_is_drawn_over_the_screen() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(2026);
+        game.confirm = Some(ConfirmAction::Quit);
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).expect("terminal init");
+        terminal
+            .draw(|frame| render(frame, &game))
+            .expect("confirm render should succeed");
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("Quit the game?"));
+
+        let mut tiny = Terminal::new(TestBackend::new(20, 5)).expect("terminal init");
+        tiny.draw(|frame| render(frame, &game))
+            .expect("confirm render should succeed on a tiny terminal");
+    }
+
+    #[test]
+    fn clicks_land_on_the_option_drawn_under_the_pointer() {
+        rust_i18n::set_locale("en");
+        let area = Rect::new(0, 0, 120, 60);
+        let footer_area = layout::split(area, PanelTab::default()).footer;
+        let mut terminal = Terminal::new(TestBackend::new(120, 60)).expect("terminal init");
+        let mut game = Game::new_with_seed(2026);
+        game.mode = GameMode::Town;
+        game.recent_event = Some("Welcome".to_string());
+
+        terminal.draw(|frame| render(frame, &game)).unwrap();
+        let regions = footer::option_regions(&game, footer_area);
+        assert_eq!(regions.len(), 10);
+        for (index, region) in regions {
+            assert_eq!(
+                pointer_target_at(area, &game, region.x + 3, region.y),
+                Some(PointerTarget::MenuOption(index))
+            );
+            let label = format!("{}. ", (index + 1) % 10);
+            assert!(row_text(&terminal, region.y).contains(&label), "{label}");
+        }
+
+        game.mode = GameMode::Settings;
+        game.settings_cursor = Language::ALL.len() + Difficulty::ALL.len() + 4;
+        terminal.draw(|frame| render(frame, &game)).unwrap();
+        let (_, region) = footer::option_regions(&game, footer_area)
+            .into_iter()
+            .find(|(index, _)| *index == game.settings_cursor)
+            .expect("the selected option is on screen");
+        let label = rust_i18n::t!(AutosaveOption::EnterTown.label_key()).to_string();
+        assert!(row_text(&terminal, region.y).contains(&label));
+    }
+
+    #[test]
+    fn map_cells_resolve_to_tiles_and_hovering_lights_them_up() {
+        rust_i18n::set_locale("en");
+        let area = Rect::new(0, 0, 120, 40);
+        let map_area = layout::split(area, PanelTab::default()).map;
+        let mut game = Game::new_with_seed(2026);
+        let hero = Position {
+            x: game.player.x,
+            y: game.player.y,
+        };
+        let (x, y) = (map_area.x..map_area.right())
+            .flat_map(|x| (map_area.y..map_area.bottom()).map(move |y| (x, y)))
+            .find(|(x, y)| {
+                pointer_target_at(area, &game, *x, *y) == Some(PointerTarget::Tile(hero))
+            })
+            .expect("the hero's tile is on the map");
+        assert_eq!(
+            pointer_target_at(area, &game, x + 1, y),
+            Some(PointerTarget::Tile(Position {
+                x: hero.x + 1,
+                y: hero.y
+            }))
+        );
+        assert_eq!(pointer_target_at(area, &game, map_area.x, map_area.y), None);
+
+        game.mouse = Some((x + 1, y));
+        game.prefs.theme = ThemeId::Default;
+        let hover = Style::from(theme::get(ThemeId::Default).hover).bg.unwrap();
+        let mut terminal = Terminal::new(TestBackend::new(120, 40)).expect("terminal init");
+        terminal.draw(|frame| render(frame, &game)).unwrap();
+        assert_eq!(terminal.backend().buffer()[(x + 1, y)].bg, hover);
+        assert_ne!(terminal.backend().buffer()[(x + 2, y)].bg, hover);
+    }
+
+    #[test]
+    fn emoji_glyphs_give_each_tile_two_cells() {
+        rust_i18n::set_locale("en");
+        let area = Rect::new(0, 0, 120, 40);
+        let map_area = layout::split(area, PanelTab::default()).map;
+        let mut game = Game::new_with_seed(2026);
+        game.prefs.glyphs = GlyphSetId::Emoji;
+        let hero = Position {
+            x: game.player.x,
+            y: game.player.y,
+        };
+        let mut terminal = Terminal::new(TestBackend::new(120, 40)).expect("terminal init");
+        terminal.draw(|frame| render(frame, &game)).unwrap();
+
+        let (x, y) = (map_area.x..map_area.right())
+            .flat_map(|x| (map_area.y..map_area.bottom()).map(move |y| (x, y)))
+            .find(|(x, y)| terminal.backend().buffer()[(*x, *y)].symbol() == "🧙")
+            .expect("the hero is drawn on the map");
+        for column in [x, x + 1] {
+            assert_eq!(
+                pointer_target_at(area, &game, column, y),
+                Some(PointerTarget::Tile(hero))
+            );
+        }
+        assert_eq!(
+            pointer_target_at(area, &game, x + 2, y),
+            Some(PointerTarget::Tile(Position {
+                x: hero.x + 1,
+                y: hero.y
+            }))
+        );
+    }
+
+    #[test]
+    fn limited_themes_keep_their_colors_off_the_screen() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(2026);
+        game.mouse = Some((60, 10));
+        let mut terminal = Terminal::new(TestBackend::new(120, 40)).expect("terminal init");
+
+        game.prefs.theme = ThemeId::Monochrome;
+        terminal.draw(|frame| render(frame, &game)).unwrap();
+        let cells = terminal.backend().buffer().content();
+        assert!(
+            cells
+                .iter()
+                .all(|cell| cell.fg == Color::Reset && cell.bg == Color::Reset)
+        );
+        assert!(
+            cells
+                .iter()
+                .any(|cell| cell.modifier.contains(Modifier::BOLD))
+        );
+
+        game.prefs.theme = ThemeId::Ansi16;
+        terminal.draw(|frame| render(frame, &game)).unwrap();
+        assert!(terminal.backend().buffer().content().iter().all(|cell| {
+            ![cell.fg, cell.bg]
+                .iter()
+                .any(|color| matches!(color, Color::Rgb(..) | Color::Indexed(_)))
+        }));
+    }
+
+    #[test]
+    fn compact_tabs_switch_with_the_tab_key_or_a_click() {
+        rust_i18n::set_locale("en");
+        let area = Rect::new(0, 0, 80, 30);
+        let tabs = layout::split(area, PanelTab::default())
+            .tabs
+            .expect("compact layout has a tab strip");
+        let mut game = Game::new_with_seed(2026);
+
+        game.handle_key(KeyCode::Tab);
+        assert_eq!(game.panel_tab, PanelTab::Log);
+        let mut terminal = Terminal::new(TestBackend::new(80, 30)).expect("terminal init");
+        terminal.draw(|frame| render(frame, &game)).unwrap();
+        assert!(row_text(&terminal, tabs.y + 1).contains("Battle Log"));
+
+        let controls = (tabs.x..tabs.right())
+            .find(|x| {
+                pointer_target_at(area, &game, *x, tabs.y)
+                    == Some(PointerTarget::Tab(PanelTab::Controls))
+            })
+            .expect("the controls tab is on the strip");
+        game.click(PointerTarget::Tab(PanelTab::Controls));
+        assert_eq!(game.panel_tab, PanelTab::Controls);
+        assert_eq!(pointer_target_at(area, &game, controls, tabs.y + 2), None);
+    }
 }
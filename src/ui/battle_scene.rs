@@ -0,0 +1,300 @@
+//! The battle view that takes the map's place during a fight: the enemy's
+//! art and HP, what it is up to, and the damage numbers of the last round.
+
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use ratatui::Frame;
+use ratatui::layout::{Alignment, Rect};
+use ratatui::style::{Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::Paragraph;
+use rust_i18n::t;
+
+use crate::game::Game;
+use crate::game::model::{Battle, BattleEffect, BattleSide};
+use crate::ui::theme::{self, Paint, Theme};
+use crate::ui::{bar, panel_block};
+
+const ART_DATA: &str = include_str!("../../config/enemy_art.toml");
+/// Drawn for enemies without art of their own.
+const DEFAULT_ART: &str = "default";
+const MAX_ART_WIDTH: usize = 24;
+const MAX_ART_HEIGHT: usize = 8;
+/// Rows a damage number climbs through above the art.
+const RISE_ROWS: usize = 3;
+const HP_BAR_WIDTH: usize = 20;
+
+fn parse_art(content: &str) -> Result<BTreeMap<String, Vec<String>>, String> {
+    let pictures: BTreeMap<String, String> =
+        toml::from_str(content).map_err(|err| err.message().to_string())?;
+    let mut art = BTreeMap::new();
+    for (key, picture) in pictures {
+        let lines: Vec<String> = picture.lines().map(str::to_string).collect();
+        if lines.len() > MAX_ART_HEIGHT {
+            return Err(format!("{key}: taller than {MAX_ART_HEIGHT} rows"));
+        }
+        if let Some(line) = lines.iter().find(|line| !line.is_ascii()) {
+            return Err(format!("{key}: `{line}` is not plain ASCII"));
+        }
+        if lines.iter().any(|line| line.len() > MAX_ART_WIDTH) {
+            return Err(format!("{key}: wider than {MAX_ART_WIDTH} columns"));
+        }
+        art.insert(key, lines);
+    }
+    if art.contains_key(DEFAULT_ART) {
+        Ok(art)
+    } else {
+        Err(format!("missing the `{DEFAULT_ART}` art"))
+    }
+}
+
+fn enemy_art() -> &'static BTreeMap<String, Vec<String>> {
+    static ART: OnceLock<BTreeMap<String, Vec<String>>> = OnceLock::new();
+    ART.get_or_init(|| parse_art(ART_DATA).expect("embedded enemy art is valid"))
+}
+
+/// The art for an enemy's `art` key, padded to one width so it stays
+/// in shape when centred.
+fn art_lines(key: &str) -> Vec<String> {
+    let art = enemy_art();
+    let lines = art.get(key).unwrap_or(&art[DEFAULT_ART]);
+    let width = lines.iter().map(String::len).max().unwrap_or(0);
+    lines.iter().map(|line| format!("{line:width$}")).collect()
+}
+
+/// The effect playing over one side of the scene, if any.
+fn effect_on(game: &Game, side: BattleSide) -> Option<BattleEffect> {
+    game.battle_effects
+        .iter()
+        .find(|effect| effect.side == side)
+        .copied()
+}
+
+fn flash(paint: Paint, effect: Option<BattleEffect>) -> Style {
+    if effect.is_some_and(|effect| effect.flashing()) {
+        Style::from(paint).add_modifier(Modifier::REVERSED)
+    } else {
+        paint.into()
+    }
+}
+
+fn tag(theme: &Theme, key: &str) -> Span<'static> {
+    Span::styled(format!(" [{}]", t!(key)), theme.reward.bold())
+}
+
+fn title_line(theme: &Theme, battle: &Battle) -> Line<'static> {
+    let mut spans = vec![Span::styled(battle.enemy.name.clone(), theme.enemy.bold())];
+    if battle.script.enraged {
+        spans.push(tag(theme, "ui.battle_scene.status.enraged"));
+    }
+    if battle.enemy_guarding {
+        spans.push(tag(theme, "ui.battle_scene.status.guarding"));
+    }
+    Line::from(spans)
+}
+
+fn damage_span(theme: &Theme, effect: BattleEffect) -> Span<'static> {
+    Span::styled(format!("-{}", effect.amount), theme.defeat.bold())
+}
+
+/// The rows the enemy's damage number climbs through, with the number on
+/// the row it has reached by this frame.
+fn rise_lines(theme: &Theme, effect: Option<BattleEffect>) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::from(""); RISE_ROWS];
+    if let Some(effect) = effect {
+        let row = RISE_ROWS - 1 - (effect.frame as usize / 2).min(RISE_ROWS - 1);
+        lines[row] = Line::from(damage_span(theme, effect));
+    }
+    lines
+}
+
+fn hero_line(game: &Game, theme: &Theme, battle: &Battle) -> Line<'static> {
+    let effect = effect_on(game, BattleSide::Hero);
+    let player = &game.player;
+    let mut spans = vec![
+        Span::styled(
+            t!(
+                "ui.battle_scene.hero_hp",
+                hp = player.hp.max(0),
+                max = player.max_hp
+            )
+            .to_string(),
+            flash(theme.hp, effect),
+        ),
+        Span::raw(" "),
+        Span::styled(
+            t!(
+                "ui.battle_scene.hero_mp",
+                mp = player.mp,
+                max = player.max_mp
+            )
+            .to_string(),
+            theme.mp,
+        ),
+    ];
+    if let Some(effect) = effect {
+        spans.push(Span::raw(" "));
+        spans.push(damage_span(theme, effect));
+    }
+    if battle.defending {
+        spans.push(tag(theme, "ui.battle_scene.status.defending"));
+    }
+    Line::from(spans)
+}
+
+fn scene_lines(game: &Game, battle: &Battle, height: usize) -> Vec<Line<'static>> {
+    let theme = theme::of(game);
+    let foe_effect = effect_on(game, BattleSide::Foes);
+    let enemy = &battle.enemy;
+
+    let mut top = vec![title_line(theme, battle)];
+    if let Some(tell) = game.enemy_tell() {
+        top.push(Line::from(Span::styled(
+            t!(tell).to_string(),
+            theme.reward.bold(),
+        )));
+    }
+
+    let mut bottom = vec![Line::from(vec![
+        Span::styled(
+            format!(
+                "{} {}/{} ",
+                t!("ui.battle_scene.enemy_hp"),
+                enemy.hp.max(0),
+                enemy.max_hp
+            ),
+            theme.enemy,
+        ),
+        Span::styled(
+            bar(enemy.hp.max(0), enemy.max_hp, HP_BAR_WIDTH),
+            flash(theme.hp, foe_effect),
+        ),
+    ])];
+    for add in &battle.adds {
+        bottom.push(Line::from(Span::styled(
+            format!("{} {}/{}", add.name, add.hp.max(0), add.max_hp),
+            theme.enemy_add,
+        )));
+    }
+    bottom.push(Line::from(""));
+    bottom.push(hero_line(game, theme, battle));
+    if !game.battle_effects.is_empty() {
+        bottom.push(Line::from(Span::styled(
+            t!("ui.battle_scene.skip").to_string(),
+            theme.muted.italic(),
+        )));
+    }
+
+    let art = art_lines(&enemy.art);
+    let mut lines = top;
+    if lines.len() + RISE_ROWS + art.len() + bottom.len() <= height {
+        lines.extend(rise_lines(theme, foe_effect));
+        let art_style = flash(theme.enemy, foe_effect);
+        lines.extend(
+            art.into_iter()
+                .map(|row| Line::from(Span::styled(row, art_style))),
+        );
+    } else if let Some(effect) = foe_effect {
+        lines.push(Line::from(damage_span(theme, effect)));
+    }
+    lines.extend(bottom);
+    let pad = height.saturating_sub(lines.len()) / 2;
+    let mut centred = vec![Line::from(""); pad];
+    centred.extend(lines);
+    centred
+}
+
+/// Draws the fight in the map's panel.
+pub fn render(frame: &mut Frame, game: &Game, battle: &Battle, area: Rect) {
+    let theme = theme::of(game);
+    let lines = scene_lines(game, battle, area.height.saturating_sub(2) as usize);
+    frame.render_widget(
+        Paragraph::new(lines)
+            .alignment(Alignment::Center)
+            .block(panel_block(
+                theme,
+                t!("ui.panel.battle_scene"),
+                theme.accent(game.mode),
+            )),
+        area,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ART_DATA, DEFAULT_ART, art_lines, enemy_art, parse_art, scene_lines};
+    use crate::game::Game;
+    use crate::game::model::{Battle, BattleEffect, BattleSide, BossId, Enemy, EnemyStyle};
+
+    fn text(lines: &[ratatui::text::Line]) -> String {
+        lines
+            .iter()
+            .map(|line| line.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn every_enemy_has_art_that_fits() {
+        assert!(parse_art(ART_DATA).is_ok());
+        let art = enemy_art();
+        for key in [
+            "slime",
+            "goblin",
+            "wolf",
+            "skeleton",
+            "orc_brute",
+            "ghoul",
+            "wraith",
+        ] {
+            assert!(art.contains_key(key), "{key}");
+        }
+        for boss in BossId::SEAL_GUARDIANS
+            .into_iter()
+            .chain([BossId::AncientDragon])
+        {
+            let key = boss.name_key().trim_start_matches("enemy.");
+            assert!(art.contains_key(key), "{key}");
+        }
+        assert_eq!(art_lines("no_such_enemy"), art_lines(DEFAULT_ART));
+
+        let wide = format!("{ART_DATA}\nwide = '{}'\n", "#".repeat(30));
+        assert!(parse_art(&wide).unwrap_err().contains("wide"));
+    }
+
+    #[test]
+    fn the_scene_shows_damage_and_drops_the_art_when_short() {
+        rust_i18n::set_locale("en");
+        let mut game = Game::new_with_seed(7);
+        let battle = Battle::new(Enemy {
+            name: "Slime".to_string(),
+            name_key: "enemy.slime".to_string(),
+            hp: 6,
+            max_hp: 10,
+            atk: 3,
+            def: 0,
+            exp_reward: 1,
+            gold_reward: 1,
+            is_boss: false,
+            style: EnemyStyle::Skirmisher,
+            ai: Default::default(),
+            loot: None,
+            art: "slime".to_string(),
+        });
+        game.battle_effects.push(BattleEffect {
+            side: BattleSide::Foes,
+            amount: 4,
+            frame: 0,
+        });
+
+        let tall = text(&scene_lines(&game, &battle, 30));
+        assert!(tall.contains("Slime"));
+        assert!(tall.contains("-4"));
+        assert!(tall.contains(art_lines("slime")[1].trim()));
+
+        let short = text(&scene_lines(&game, &battle, 6));
+        assert!(short.contains("-4"));
+        assert!(!short.contains(art_lines("slime")[1].trim()));
+    }
+}
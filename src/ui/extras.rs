@@ -0,0 +1,103 @@
+//! Panels only the wide layout has room for.
+
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::Stylize;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Paragraph, Wrap};
+use rust_i18n::t;
+
+use crate::game::Game;
+use crate::ui::sidebar::quest_status;
+use crate::ui::{panel_block, record_name, theme, worldmap};
+
+const BESTIARY_ENTRIES: usize = 8;
+
+/// The whole world in half-block cells, two tiles to a cell.
+pub fn render_minimap(frame: &mut Frame, game: &Game, area: Rect) {
+    let theme = theme::of(game);
+    let lines = worldmap::half_block_lines(
+        game,
+        area.width.saturating_sub(2) as usize,
+        area.height.saturating_sub(2) as usize,
+    );
+    frame.render_widget(
+        Paragraph::new(lines).block(panel_block(
+            theme,
+            t!("ui.panel.minimap"),
+            theme.accent(game.mode),
+        )),
+        area,
+    );
+}
+
+pub fn render_quests(frame: &mut Frame, game: &Game, area: Rect) {
+    let theme = theme::of(game);
+    let accent = theme.accent(game.mode);
+    let (hunt, hunt_paint) = quest_status(game);
+    let mut lines = vec![
+        Line::from(Span::styled(
+            t!("ui.quests.hunt").to_string(),
+            theme.text.bold(),
+        )),
+        Line::from(Span::styled(format!("  {hunt}"), hunt_paint)),
+        Line::from(Span::styled(
+            t!("ui.quests.seals", broken = game.world.seals_broken()).to_string(),
+            theme.text.bold(),
+        )),
+    ];
+    for site in &game.world.boss_sites {
+        let (mark, paint) = if site.defeated {
+            ("[x]", theme.success)
+        } else {
+            ("[ ]", theme.muted)
+        };
+        lines.push(Line::from(vec![
+            Span::styled(format!("  {mark} "), paint),
+            Span::raw(t!(site.boss.name_key()).to_string()),
+        ]));
+    }
+    let lair = if game.world.lair_sealed() {
+        Span::styled(t!("ui.quests.lair_sealed").to_string(), theme.muted)
+    } else {
+        Span::styled(t!("ui.quests.lair_open").to_string(), accent.bold())
+    };
+    lines.push(Line::from(lair));
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(panel_block(theme, t!("ui.panel.quests"), accent))
+            .wrap(Wrap { trim: false }),
+        area,
+    );
+}
+
+pub fn render_bestiary(frame: &mut Frame, game: &Game, area: Rect) {
+    let theme = theme::of(game);
+    let accent = theme.accent(game.mode);
+    let kills = game.campaign.stats.top_kills(BESTIARY_ENTRIES);
+    let mut lines: Vec<Line> = if kills.is_empty() {
+        vec![Line::from(Span::styled(
+            t!("ui.bestiary.empty").to_string(),
+            theme.muted.italic(),
+        ))]
+    } else {
+        kills
+            .into_iter()
+            .map(|(key, count)| {
+                Line::from(
+                    t!("ui.bestiary.entry", name = record_name(key), count = count).to_string(),
+                )
+            })
+            .collect()
+    };
+    lines.push(Line::from(Span::styled(
+        t!("ui.bestiary.total", count = game.campaign.enemies_defeated).to_string(),
+        theme.muted,
+    )));
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(panel_block(theme, t!("ui.panel.bestiary"), accent))
+            .wrap(Wrap { trim: false }),
+        area,
+    );
+}
@@ -1,114 +1,605 @@
 use ratatui::Frame;
-use ratatui::layout::Rect;
-use ratatui::style::{Color, Modifier, Style};
+use ratatui::layout::{Margin, Rect};
+use ratatui::style::{Modifier, Style, Stylize};
 use ratatui::text::{Line, Span};
-use ratatui::widgets::Paragraph;
+use ratatui::widgets::{Paragraph, Wrap};
 use rust_i18n::t;
 
 use crate::game::Game;
-use crate::game::model::{Difficulty, GameMode, Language};
-use crate::ui::{MUTED, TEXT, bar, mode_accent, panel_block};
+use crate::game::model::{
+    Achievement, AutosaveOption, Command, Difficulty, EncounterMode, GameMode, HeroClass,
+    HeroSkill, Language, Material, Materials, Player, ProfileField, Recipe, RunModifier, StatKind,
+    Unlock,
+};
+use crate::ui::theme::{self, Paint, Theme};
+use crate::ui::{bar, glyphs, panel_block, record_name};
+
+const TOWN_OPTION_COUNT: usize = 10;
+const PEDDLER_OPTION_COUNT: usize = 5;
+const ORDER_OPTION_COUNT: usize = 3;
+const BATTLE_ACTION_COUNT: usize = 6;
 
 pub fn render(frame: &mut Frame, game: &Game, area: Rect) {
-    let accent = mode_accent(game.mode);
+    let theme = theme::of(game);
+    let accent = theme.accent(game.mode);
     let bottom = match game.mode {
-        GameMode::Exploration => Paragraph::new(with_recent_event(
-            game,
-            vec![
-                Line::from(t!("ui.exploration.tip_1")),
-                Line::from(t!("ui.exploration.tip_2")),
-                Line::from(t!("ui.exploration.tip_3")),
-            ],
-            accent,
-        ))
-        .style(Style::default().fg(TEXT))
-        .block(panel_block(t!("ui.panel.adventure"), accent)),
+        GameMode::Exploration => {
+            Paragraph::new(with_recent_event(game, exploration_lines(game), accent))
+                .style(theme.text)
+                .block(panel_block(theme, t!("ui.panel.adventure"), accent))
+        }
         GameMode::Town => Paragraph::new(with_recent_event(game, town_lines(game, accent), accent))
             .scroll((town_scroll(game, area), 0))
-            .style(Style::default().fg(TEXT))
-            .block(panel_block(t!("ui.panel.town"), accent)),
+            .style(theme.text)
+            .block(panel_block(
+                theme,
+                if game.peddler {
+                    t!("ui.panel.peddler")
+                } else {
+                    t!("ui.panel.town")
+                },
+                accent,
+            )),
         GameMode::Battle => Paragraph::new(battle_lines(game, accent))
             .scroll((battle_scroll(game, area), 0))
-            .style(Style::default().fg(TEXT))
-            .block(panel_block(t!("ui.panel.battle"), accent)),
-        GameMode::Settings => Paragraph::new(settings_lines(game))
-            .scroll((settings_scroll(game, area), 0))
-            .style(Style::default().fg(TEXT))
-            .block(panel_block(t!("ui.panel.settings"), accent)),
-        GameMode::Victory => Paragraph::new(with_recent_event(
+            .style(theme.text)
+            .block(panel_block(theme, t!("ui.panel.battle"), accent)),
+        GameMode::Dialogue => {
+            let (speaker, text) = game.dialogue_text().unwrap_or_default();
+            Paragraph::new(dialogue_lines(game, text, accent))
+                .style(theme.text)
+                .block(panel_block(theme, speaker, accent))
+                .wrap(Wrap { trim: true })
+        }
+        GameMode::Event => Paragraph::new(event_lines(game, accent))
+            .style(theme.text)
+            .block(panel_block(theme, t!("ui.panel.event"), accent))
+            .wrap(Wrap { trim: true }),
+        GameMode::Party => {
+            Paragraph::new(with_recent_event(game, party_lines(game, accent), accent))
+                .style(theme.text)
+                .block(panel_block(theme, t!("ui.panel.party"), accent))
+        }
+        GameMode::ClassSelect => Paragraph::new(class_lines(game, accent))
+            .style(theme.text)
+            .block(panel_block(theme, t!("ui.panel.class_select"), accent))
+            .wrap(Wrap { trim: true }),
+        GameMode::LevelUp => Paragraph::new(with_recent_event(
             game,
-            vec![
-                Line::from(Span::styled(
-                    t!("ui.result.victory").to_string(),
-                    Style::default()
-                        .fg(Color::Green)
-                        .add_modifier(Modifier::BOLD),
-                )),
-                Line::from(t!("ui.result.restart_or_quit")),
-            ],
+            level_up_lines(game, accent),
             accent,
         ))
-        .style(Style::default().fg(TEXT))
-        .block(panel_block(t!("ui.panel.result"), accent)),
-        GameMode::GameOver => Paragraph::new(with_recent_event(
+        .style(theme.text)
+        .block(panel_block(theme, t!("ui.panel.level_up"), accent)),
+        GameMode::Settings => Paragraph::new(settings_lines(game))
+            .scroll((settings_scroll(game, area), 0))
+            .style(theme.text)
+            .block(panel_block(theme, t!("ui.panel.settings"), accent)),
+        GameMode::Keybinds => Paragraph::new(keybinds_lines(game, accent))
+            .scroll((keybinds_scroll(game, area), 0))
+            .style(theme.text)
+            .block(panel_block(theme, t!("ui.panel.keybinds"), accent)),
+        GameMode::Victory => Paragraph::new(victory_lines(game))
+            .style(theme.text)
+            .block(panel_block(theme, t!("ui.panel.result"), accent))
+            .wrap(Wrap { trim: true }),
+        GameMode::GameOver => {
+            Paragraph::new(with_recent_event(game, game_over_lines(game), accent))
+                .style(theme.text)
+                .block(panel_block(theme, t!("ui.panel.result"), accent))
+                .wrap(Wrap { trim: true })
+        }
+        GameMode::Legacy => {
+            Paragraph::new(with_recent_event(game, legacy_lines(game, accent), accent))
+                .style(theme.text)
+                .block(panel_block(theme, t!("ui.panel.legacy"), accent))
+                .wrap(Wrap { trim: true })
+        }
+        GameMode::Challenge => Paragraph::new(with_recent_event(
             game,
-            vec![
-                Line::from(Span::styled(
-                    t!("ui.result.game_over").to_string(),
-                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
-                )),
-                Line::from(t!("ui.result.restart_or_quit")),
-            ],
+            challenge_lines(game, accent),
             accent,
         ))
-        .style(Style::default().fg(TEXT))
-        .block(panel_block(t!("ui.panel.result"), accent)),
+        .style(theme.text)
+        .block(panel_block(theme, t!("ui.panel.challenge"), accent))
+        .wrap(Wrap { trim: true }),
+        GameMode::Records => Paragraph::new(records_lines(game, accent))
+            .style(theme.text)
+            .block(panel_block(theme, t!("ui.panel.records"), accent))
+            .wrap(Wrap { trim: true }),
     };
 
     frame.render_widget(bottom, area);
 }
 
+fn class_lines(game: &Game, accent: Paint) -> Vec<Line<'static>> {
+    let theme = theme::of(game);
+    let mut lines = vec![Line::from(Span::styled(
+        t!("ui.class.title").to_string(),
+        theme.text.bold(),
+    ))];
+    for (idx, class) in HeroClass::ALL.iter().enumerate() {
+        let hero = Player::with_class(*class);
+        lines.push(selectable_option_line(
+            theme,
+            idx + 1,
+            idx == game.class_cursor,
+            t!(
+                "ui.class.option",
+                class = t!(class.label_key()),
+                hp = hero.max_hp,
+                mp = hero.max_mp,
+                atk = hero.base_atk,
+                def = hero.base_def
+            )
+            .to_string(),
+            accent,
+        ));
+    }
+    if let Some(class) = HeroClass::ALL.get(game.class_cursor) {
+        let skills = class
+            .skills()
+            .iter()
+            .map(|skill| {
+                t!(
+                    "ui.class.skill",
+                    skill = t!(skill.name_key()),
+                    level = skill.unlock_level()
+                )
+                .to_string()
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        lines.push(Line::from(Span::styled(
+            t!(class.desc_key()).to_string(),
+            theme.text.italic(),
+        )));
+        lines.push(Line::from(Span::styled(
+            t!("ui.class.skills", skills = skills).to_string(),
+            theme.muted,
+        )));
+    }
+    lines.push(Line::from(Span::styled(
+        t!(
+            "ui.class.legacy",
+            mode = roguelite_label(game),
+            shards = game.records.legacy.shards,
+            key = game.key_label(Command::OpenLegacy)
+        )
+        .to_string(),
+        theme.muted,
+    )));
+    lines.push(Line::from(Span::styled(
+        t!(
+            "ui.class.challenge",
+            daily = daily_label(game),
+            rules = modifiers_text(&game.planned_modifiers()),
+            key = game.key_label(Command::OpenChallenges)
+        )
+        .to_string(),
+        theme.muted,
+    )));
+    lines
+}
+
+fn daily_label(game: &Game) -> String {
+    if game.daily_challenge {
+        game.date_label(game.today())
+    } else {
+        t!("ui.legacy.off").to_string()
+    }
+}
+
+fn modifiers_text<'a>(modifiers: impl IntoIterator<Item = &'a RunModifier>) -> String {
+    let names = modifiers
+        .into_iter()
+        .map(|modifier| t!(modifier.name_key()).to_string())
+        .collect::<Vec<_>>();
+    if names.is_empty() {
+        t!("ui.challenge.no_rules").to_string()
+    } else {
+        names.join(", ")
+    }
+}
+
+fn challenge_lines(game: &Game, accent: Paint) -> Vec<Line<'static>> {
+    let theme = theme::of(game);
+    let planned = game.planned_modifiers();
+    let mut lines = vec![Line::from(Span::styled(
+        t!("ui.challenge.daily", daily = daily_label(game)).to_string(),
+        theme.text.bold(),
+    ))];
+    for (idx, modifier) in RunModifier::ALL.iter().enumerate() {
+        let mark = if planned.contains(modifier) {
+            "[x]"
+        } else {
+            "[ ]"
+        };
+        lines.push(selectable_option_line(
+            theme,
+            idx + 1,
+            idx == game.challenge_cursor,
+            format!("{mark} {}", t!(modifier.name_key())),
+            accent,
+        ));
+    }
+    if let Some(modifier) = RunModifier::ALL.get(game.challenge_cursor) {
+        lines.push(Line::from(Span::styled(
+            t!(modifier.desc_key()).to_string(),
+            theme.text.italic(),
+        )));
+    }
+    if game.daily_challenge {
+        lines.push(Line::from(Span::styled(
+            t!("ui.challenge.daily_hint").to_string(),
+            theme.muted,
+        )));
+    }
+    lines
+}
+
+fn roguelite_label(game: &Game) -> String {
+    if game.records.legacy.roguelite {
+        t!("ui.legacy.on").to_string()
+    } else {
+        t!("ui.legacy.off").to_string()
+    }
+}
+
+fn legacy_lines(game: &Game, accent: Paint) -> Vec<Line<'static>> {
+    let theme = theme::of(game);
+    let legacy = &game.records.legacy;
+    let mut lines = vec![Line::from(Span::styled(
+        t!(
+            "ui.legacy.title",
+            shards = legacy.shards,
+            mode = roguelite_label(game)
+        )
+        .to_string(),
+        theme.text.bold(),
+    ))];
+    for (idx, unlock) in Unlock::ALL.iter().enumerate() {
+        let state = if legacy.active.contains(unlock) {
+            t!("ui.legacy.active").to_string()
+        } else if legacy.unlocked.contains(unlock) {
+            t!("ui.legacy.inactive").to_string()
+        } else {
+            t!("ui.legacy.cost", cost = game.unlock_cost(*unlock)).to_string()
+        };
+        lines.push(selectable_option_line(
+            theme,
+            idx + 1,
+            idx == game.legacy_cursor,
+            format!(
+                "[{}] {} - {}",
+                t!(unlock.kind().label_key()),
+                t!(unlock.name_key()),
+                state
+            ),
+            accent,
+        ));
+    }
+    if let Some(unlock) = Unlock::ALL.get(game.legacy_cursor) {
+        lines.push(Line::from(Span::styled(
+            t!(unlock.desc_key()).to_string(),
+            theme.text.italic(),
+        )));
+    }
+    lines
+}
+
+fn level_up_lines(game: &Game, accent: Paint) -> Vec<Line<'static>> {
+    let theme = theme::of(game);
+    let mut lines = vec![Line::from(Span::styled(
+        t!("ui.level_up.points", points = game.player.stat_points).to_string(),
+        theme.text.bold(),
+    ))];
+    for (idx, stat) in StatKind::ALL.iter().enumerate() {
+        let current = match stat {
+            StatKind::Hp => game.player.max_hp,
+            StatKind::Mp => game.player.max_mp,
+            StatKind::Atk => game.player.base_atk,
+            StatKind::Def => game.player.base_def,
+        };
+        lines.push(selectable_option_line(
+            theme,
+            idx + 1,
+            idx == game.level_up_cursor,
+            t!(
+                "ui.level_up.option",
+                stat = t!(stat.label_key()),
+                value = current,
+                gain = game.stat_point_gain(*stat)
+            )
+            .to_string(),
+            accent,
+        ));
+    }
+    lines
+}
+
+fn victory_lines(game: &Game) -> Vec<Line<'static>> {
+    let theme = theme::of(game);
+    let bosses = game
+        .campaign
+        .bosses_defeated
+        .iter()
+        .map(|boss| t!(boss.name_key()).to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let quest = if game.quest.rewarded || game.quest.completed {
+        t!("ui.quest.done").to_string()
+    } else if game.quest.accepted {
+        game.quest.progress_text()
+    } else {
+        t!("ui.quest.none").to_string()
+    };
+    let mut lines = vec![
+        Line::from(Span::styled(
+            t!("ui.result.victory").to_string(),
+            theme.victory.bold(),
+        )),
+        Line::from(t!(
+            "ui.result.summary_hero",
+            level = game.player.level,
+            day = game.clock.day(),
+            clock = format!("{:02}:{:02}", game.clock.hour(), game.clock.minute())
+        )),
+        Line::from(t!("ui.result.summary_bosses", bosses = bosses)),
+        Line::from(t!(
+            "ui.result.summary_totals",
+            enemies = game.campaign.enemies_defeated,
+            gold = game.campaign.gold_earned
+        )),
+        Line::from(t!("ui.result.summary_quest", quest = quest)),
+    ];
+    lines.extend(run_stats_lines(game));
+    lines.push(Line::from(Span::styled(
+        t!(
+            "ui.result.ng_plus_hint",
+            key = game.key_label(Command::NewGamePlus),
+            tier = game.ng_tier() + 1
+        )
+        .to_string(),
+        theme.reward,
+    )));
+    lines.push(Line::from(Span::styled(restart_or_quit(game), theme.muted)));
+    lines
+}
+
+fn game_over_lines(game: &Game) -> Vec<Line<'static>> {
+    let theme = theme::of(game);
+    let mut lines = vec![Line::from(Span::styled(
+        t!("ui.result.game_over").to_string(),
+        theme.defeat.bold(),
+    ))];
+    if let Some(cause) = &game.campaign.stats.death_cause {
+        lines.push(Line::from(t!(
+            "ui.result.fallen_to",
+            cause = record_name(cause)
+        )));
+    }
+    lines.extend(run_stats_lines(game));
+    lines.push(Line::from(restart_or_quit(game)));
+    lines
+}
+
+fn restart_or_quit(game: &Game) -> String {
+    t!(
+        "ui.result.restart_or_quit",
+        restart = game.key_label(Command::Restart),
+        quit = game.key_label(Command::Quit)
+    )
+    .to_string()
+}
+
+fn run_stats_lines(game: &Game) -> Vec<Line<'static>> {
+    let stats = &game.campaign.stats;
+    let kills = stats
+        .top_kills(4)
+        .into_iter()
+        .map(|(key, count)| format!("{} x{count}", record_name(key)))
+        .collect::<Vec<_>>();
+    let kills = if kills.is_empty() {
+        t!("ui.result.no_kills").to_string()
+    } else {
+        kills.join(", ")
+    };
+    vec![
+        Line::from(t!(
+            "ui.result.stats_pace",
+            turns = stats.turns,
+            battles = stats.battles
+        )),
+        Line::from(t!(
+            "ui.result.stats_damage",
+            dealt = stats.damage_dealt,
+            taken = stats.damage_taken
+        )),
+        Line::from(t!(
+            "ui.result.stats_supplies",
+            spent = stats.gold_spent,
+            potions = stats.potions_used,
+            crafted = stats.items_crafted
+        )),
+        Line::from(t!("ui.result.stats_kills", kills = kills)),
+    ]
+}
+
+fn records_lines(game: &Game, accent: Paint) -> Vec<Line<'static>> {
+    let theme = theme::of(game);
+    let mut lines = vec![Line::from(Span::styled(
+        t!("ui.records.hall_title").to_string(),
+        theme.text.bold(),
+    ))];
+    if game.records.hall_of_fame.is_empty() {
+        lines.push(Line::from(Span::styled(
+            t!("ui.records.hall_empty").to_string(),
+            theme.muted,
+        )));
+    }
+    for (idx, entry) in game.records.hall_of_fame.iter().enumerate() {
+        let result = if entry.victory {
+            t!("ui.records.victory")
+        } else {
+            t!("ui.records.fallen")
+        };
+        let mut tags = Vec::new();
+        if entry.ng_plus > 0 {
+            tags.push(t!("ui.stats.ng_plus", tier = entry.ng_plus).to_string());
+        }
+        if let Some(day) = entry.daily {
+            tags.push(t!("ui.records.daily", date = game.date_label(day)).to_string());
+        }
+        if !entry.modifiers.is_empty() {
+            tags.push(modifiers_text(&entry.modifiers));
+        }
+        let tags = if tags.is_empty() {
+            String::new()
+        } else {
+            format!("  [{}]", tags.join("; "))
+        };
+        lines.push(Line::from(vec![
+            Span::styled(format!("{:>2}. ", idx + 1), accent),
+            Span::raw(
+                t!(
+                    "ui.records.entry",
+                    score = entry.score,
+                    class = t!(entry.class.label_key()),
+                    level = entry.level,
+                    difficulty = t!(entry.difficulty.label_key()),
+                    seed = entry.seed,
+                    day = entry.day,
+                    result = result
+                )
+                .to_string(),
+            ),
+            Span::styled(tags, theme.muted),
+        ]));
+    }
+
+    let unlocked = game.records.achievements.len();
+    lines.push(Line::from(Span::styled(
+        t!(
+            "ui.records.achievements_title",
+            unlocked = unlocked,
+            total = Achievement::ALL.len()
+        )
+        .to_string(),
+        theme.text.bold(),
+    )));
+    for achievement in Achievement::ALL {
+        let earned = game.records.achievements.contains(&achievement);
+        lines.push(Line::from(vec![
+            Span::styled(
+                if earned { "[x] " } else { "[ ] " },
+                if earned { accent } else { theme.muted },
+            ),
+            Span::styled(
+                format!(
+                    "{} - {}",
+                    t!(achievement.name_key()),
+                    t!(achievement.desc_key())
+                ),
+                if earned { theme.text } else { theme.muted },
+            ),
+        ]));
+    }
+    lines
+}
+
 fn with_recent_event(
     game: &Game,
     mut lines: Vec<Line<'static>>,
-    accent: Color,
+    accent: Paint,
 ) -> Vec<Line<'static>> {
     if let Some(recent) = &game.recent_event {
         lines.insert(
             0,
             Line::from(Span::styled(
                 format!("{} {}", t!("ui.banner.recent"), recent),
-                Style::default().fg(accent).add_modifier(Modifier::BOLD),
+                accent.bold(),
             )),
         );
     }
     lines
 }
 
+fn exploration_lines(game: &Game) -> Vec<Line<'static>> {
+    let key = |command| game.key_label(command);
+    let glyphs = glyphs::of(game);
+    let mut lines = vec![
+        Line::from(t!(
+            "ui.exploration.tip_1",
+            town = glyphs.town,
+            boss = glyphs.boss,
+            lair = glyphs.lair
+        )),
+        Line::from(t!(
+            "ui.exploration.tip_2",
+            up = key(Command::MoveUp),
+            left = key(Command::MoveLeft),
+            down = key(Command::MoveDown),
+            right = key(Command::MoveRight),
+            chest = glyphs.chest,
+            npc = glyphs.npc,
+            cleared = glyphs.cleared
+        )),
+        Line::from(t!(
+            "ui.exploration.tip_3",
+            interact = key(Command::Interact),
+            save = key(Command::Save),
+            load = key(Command::Load),
+            town = glyphs.town
+        )),
+        Line::from(t!(
+            "ui.exploration.tip_gather",
+            herb = glyphs.herb,
+            ore = glyphs.ore
+        )),
+    ];
+    if game.encounter_mode == EncounterMode::Roaming {
+        lines.push(Line::from(t!(
+            "ui.exploration.tip_roaming",
+            monster = glyphs.monster
+        )));
+    }
+    lines
+}
+
 fn settings_lines(game: &Game) -> Vec<Line<'static>> {
+    let theme = theme::of(game);
     let mut lines = Vec::new();
     lines.push(Line::from(Span::styled(
         t!("ui.settings.title").to_string(),
-        Style::default().fg(TEXT).add_modifier(Modifier::BOLD),
+        theme.text.bold(),
     )));
     lines.push(Line::from(Span::styled(
         format!(
-            "1..{} {}  {}..{} {}",
+            "1..{} {}  {}..{} {}  0 {}",
             Language::ALL.len(),
             t!("ui.settings.title"),
             Language::ALL.len() + 1,
             Language::ALL.len() + Difficulty::ALL.len(),
-            t!("ui.stats.difficulty")
+            t!("ui.stats.difficulty"),
+            t!("ui.settings.encounters")
         ),
-        Style::default().fg(MUTED),
+        theme.muted,
     )));
     lines.push(Line::from(Span::styled(
-        t!("ui.settings.tip").to_string(),
-        Style::default().fg(MUTED),
+        t!(
+            "ui.settings.tip",
+            up = game.key_label(Command::MenuUp),
+            down = game.key_label(Command::MenuDown),
+            confirm = game.key_label(Command::Confirm),
+            back = game.key_label(Command::Back)
+        )
+        .to_string(),
+        theme.muted,
     )));
     lines.push(Line::from(Span::styled(
         t!("ui.settings.title").to_string(),
-        Style::default().fg(Color::Rgb(240, 189, 95)),
+        theme.heading,
     )));
     for (idx, lang) in Language::ALL.iter().enumerate() {
         let marker = if idx == game.settings_cursor {
@@ -124,32 +615,31 @@ fn settings_lines(game: &Game) -> Vec<Line<'static>> {
         lines.push(Line::from(vec![
             Span::styled(
                 format!("{marker}{}. ", idx + 1),
-                Style::default()
-                    .fg(if idx == game.settings_cursor {
-                        Color::Rgb(240, 189, 95)
-                    } else {
-                        MUTED
-                    })
-                    .add_modifier(if idx == game.settings_cursor {
-                        Modifier::BOLD
-                    } else {
-                        Modifier::empty()
-                    }),
+                Style::from(if idx == game.settings_cursor {
+                    theme.heading
+                } else {
+                    theme.muted
+                })
+                .add_modifier(if idx == game.settings_cursor {
+                    Modifier::BOLD
+                } else {
+                    Modifier::empty()
+                }),
             ),
             Span::styled(
                 t!(lang.label_key()).to_string(),
-                Style::default().fg(if idx == game.settings_cursor {
-                    TEXT
+                Style::from(if idx == game.settings_cursor {
+                    theme.text
                 } else {
-                    MUTED
+                    theme.muted
                 }),
             ),
-            Span::styled(current, Style::default().fg(Color::Rgb(113, 222, 230))),
+            Span::styled(current, theme.value),
         ]));
     }
     lines.push(Line::from(Span::styled(
         t!("ui.stats.difficulty").to_string(),
-        Style::default().fg(Color::Rgb(240, 189, 95)),
+        theme.heading,
     )));
     for (idx, difficulty) in Difficulty::ALL.iter().enumerate() {
         let option_index = Language::ALL.len() + idx;
@@ -166,36 +656,152 @@ fn settings_lines(game: &Game) -> Vec<Line<'static>> {
         lines.push(Line::from(vec![
             Span::styled(
                 format!("{marker}{}. ", option_index + 1),
-                Style::default()
-                    .fg(if option_index == game.settings_cursor {
-                        Color::Rgb(240, 189, 95)
-                    } else {
-                        MUTED
-                    })
-                    .add_modifier(if option_index == game.settings_cursor {
-                        Modifier::BOLD
-                    } else {
-                        Modifier::empty()
-                    }),
+                Style::from(if option_index == game.settings_cursor {
+                    theme.heading
+                } else {
+                    theme.muted
+                })
+                .add_modifier(if option_index == game.settings_cursor {
+                    Modifier::BOLD
+                } else {
+                    Modifier::empty()
+                }),
             ),
             Span::styled(
                 t!(difficulty.label_key()).to_string(),
-                Style::default().fg(if option_index == game.settings_cursor {
-                    TEXT
+                Style::from(if option_index == game.settings_cursor {
+                    theme.text
                 } else {
-                    MUTED
+                    theme.muted
                 }),
             ),
-            Span::styled(current, Style::default().fg(Color::Rgb(113, 222, 230))),
+            Span::styled(current, theme.value),
+        ]));
+    }
+    lines.push(Line::from(Span::styled(
+        t!("ui.settings.encounters").to_string(),
+        theme.heading,
+    )));
+    let encounter_index = Language::ALL.len() + Difficulty::ALL.len();
+    lines.push(selectable_option_line(
+        theme,
+        0,
+        encounter_index == game.settings_cursor,
+        t!(game.encounter_mode.label_key()).to_string(),
+        theme.heading,
+    ));
+    lines.push(Line::from(Span::styled(
+        t!("ui.panel.keybinds").to_string(),
+        theme.heading,
+    )));
+    let keybinds_selected = encounter_index + 1 == game.settings_cursor;
+    let keybinds_style = if keybinds_selected {
+        theme.text.bold()
+    } else {
+        Style::from(theme.muted)
+    };
+    lines.push(Line::from(Span::styled(
+        format!(
+            "{}   {}",
+            if keybinds_selected { ">" } else { " " },
+            t!(
+                "ui.settings.keybinds_row",
+                preset = game.keymap_preset_name(),
+                confirm = game.key_label(Command::Confirm)
+            )
+        ),
+        keybinds_style,
+    )));
+    lines.push(Line::from(Span::styled(
+        t!("ui.settings.theme.title").to_string(),
+        theme.heading,
+    )));
+    lines.push(cycle_option_line(
+        game,
+        encounter_index + 2 == game.settings_cursor,
+        t!(game.prefs.theme.label_key()).to_string(),
+    ));
+    lines.push(Line::from(Span::styled(
+        t!("ui.settings.glyphs.title").to_string(),
+        theme.heading,
+    )));
+    lines.push(cycle_option_line(
+        game,
+        encounter_index + 3 == game.settings_cursor,
+        t!(game.prefs.glyphs.label_key()).to_string(),
+    ));
+    lines.push(Line::from(Span::styled(
+        t!("ui.settings.autosave.title").to_string(),
+        theme.heading,
+    )));
+    let selected_autosave = game.selected_autosave_option();
+    for option in AutosaveOption::ALL {
+        let selected = selected_autosave == Some(option);
+        let style = if selected {
+            theme.text.bold()
+        } else {
+            Style::from(theme.muted)
+        };
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!(
+                    "{} {:<18}",
+                    if selected { ">" } else { " " },
+                    t!(option.label_key())
+                ),
+                style,
+            ),
+            Span::styled(game.autosave_value_text(option), theme.value),
+        ]));
+    }
+    lines.push(Line::from(Span::styled(
+        t!("ui.settings.custom_title").to_string(),
+        theme.heading,
+    )));
+    let profile = &game.prefs.custom_difficulty;
+    let selected_field = game.selected_profile_field();
+    for field in ProfileField::ALL {
+        let selected = selected_field == Some(field);
+        let style = if selected {
+            theme.text.bold()
+        } else {
+            Style::from(theme.muted)
+        };
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!(
+                    "{} {:<18}",
+                    if selected { ">" } else { " " },
+                    t!(field.label_key())
+                ),
+                style,
+            ),
+            Span::styled(
+                bar((profile.fraction(field) * 100.0).round() as i32, 100, 12),
+                if selected { theme.heading } else { theme.muted },
+            ),
+            Span::styled(format!(" {}", profile.value_text(field)), style),
         ]));
     }
     lines
 }
 
-fn town_lines(game: &Game, accent: Color) -> Vec<Line<'static>> {
-    let options = vec![
-        t!("ui.town.action_buy_potion").to_string(),
-        t!("ui.town.action_buy_ether").to_string(),
+fn town_lines(game: &Game, accent: Paint) -> Vec<Line<'static>> {
+    let theme = theme::of(game);
+    if game.workshop {
+        return workshop_lines(game, accent);
+    }
+    let mut options = vec![
+        format!(
+            "{} {}",
+            t!("ui.town.action_buy_potion"),
+            t!("ui.town.stock", count = game.shop.potion)
+        ),
+        format!(
+            "{} {}",
+            t!("ui.town.action_buy_ether"),
+            t!("ui.town.stock", count = game.shop.ether)
+        ),
         format!(
             "{} {}",
             t!("ui.town.action_upgrade_weapon"),
@@ -206,20 +812,35 @@ fn town_lines(game: &Game, accent: Color) -> Vec<Line<'static>> {
             t!("ui.town.action_upgrade_armor"),
             armor_upgrade_offer(game)
         ),
-        t!("ui.town.action_healer").to_string(),
-        t!("ui.town.action_inn").to_string(),
-        t!("ui.town.action_quest_board").to_string(),
-        t!("ui.town.action_leave").to_string(),
     ];
+    if game.peddler {
+        options.push(t!("ui.town.action_leave_peddler").to_string());
+    } else {
+        options.extend([
+            t!("ui.town.action_healer").to_string(),
+            t!("ui.town.action_inn").to_string(),
+            t!("ui.town.action_quest_board").to_string(),
+            format!("{} {}", t!("ui.town.action_tavern"), tavern_offer(game)),
+            t!("ui.town.action_workshop").to_string(),
+            t!("ui.town.action_leave").to_string(),
+        ]);
+    }
 
     let mut lines = Vec::new();
+    let title = if game.peddler {
+        t!("ui.town.peddler_title")
+    } else {
+        t!("ui.town.shop_title")
+    };
     lines.push(Line::from(Span::styled(
-        t!("ui.town.shop_title").to_string(),
-        Style::default().fg(TEXT).add_modifier(Modifier::BOLD),
+        title.to_string(),
+        theme.text.bold(),
     )));
+    // The tenth town option sits on the 0 key.
     for (idx, text) in options.into_iter().enumerate() {
         lines.push(selectable_option_line(
-            idx + 1,
+            theme,
+            (idx + 1) % 10,
             idx == game.town_cursor,
             text,
             accent,
@@ -228,20 +849,117 @@ fn town_lines(game: &Game, accent: Color) -> Vec<Line<'static>> {
     lines
 }
 
-fn battle_lines(game: &Game, accent: Color) -> Vec<Line<'static>> {
+fn workshop_lines(game: &Game, accent: Paint) -> Vec<Line<'static>> {
+    let theme = theme::of(game);
+    let mut lines = vec![
+        Line::from(Span::styled(
+            t!("ui.workshop.title").to_string(),
+            theme.text.bold(),
+        )),
+        Line::from(Span::styled(
+            t!(
+                "ui.workshop.have",
+                materials = materials_text(&game.player.bag.materials)
+            )
+            .to_string(),
+            theme.muted,
+        )),
+    ];
+    for (idx, recipe) in game.recipes().iter().enumerate() {
+        lines.push(selectable_option_line(
+            theme,
+            idx + 1,
+            idx == game.workshop_cursor,
+            recipe_label(game, recipe),
+            accent,
+        ));
+    }
+    lines
+}
+
+fn recipe_label(game: &Game, recipe: &Recipe) -> String {
+    let mut cost = cost_text(&recipe.cost);
+    if recipe.gold > 0 {
+        cost = format!("{cost} + {}G", recipe.gold);
+    }
+    let label = format!("{}: {cost}", t!(recipe.name.as_str()));
+    if game.can_craft(recipe) {
+        label
+    } else {
+        format!("{label} {}", t!("ui.workshop.missing"))
+    }
+}
+
+fn materials_text(materials: &Materials) -> String {
+    Material::ALL
+        .into_iter()
+        .map(|material| format!("{} {}", t!(material.name_key()), materials.count(material)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn cost_text(cost: &Materials) -> String {
+    Material::ALL
+        .into_iter()
+        .filter(|material| cost.count(*material) > 0)
+        .map(|material| {
+            t!(
+                "ui.workshop.cost_item",
+                count = cost.count(material),
+                material = t!(material.name_key())
+            )
+            .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join(" + ")
+}
+
+fn dialogue_lines(game: &Game, text: String, accent: Paint) -> Vec<Line<'static>> {
+    let theme = theme::of(game);
+    let mut lines = vec![Line::from(Span::styled(text, theme.text.italic()))];
+    for (idx, choice) in game.dialogue_choice_texts().into_iter().enumerate() {
+        lines.push(selectable_option_line(
+            theme,
+            idx + 1,
+            idx == game.dialogue_cursor,
+            choice,
+            accent,
+        ));
+    }
+    lines
+}
+
+fn event_lines(game: &Game, accent: Paint) -> Vec<Line<'static>> {
+    let theme = theme::of(game);
+    let mut lines = vec![Line::from(Span::styled(
+        game.event_text().unwrap_or_default(),
+        theme.text.italic(),
+    ))];
+    for (idx, choice) in game.event_choice_texts().into_iter().enumerate() {
+        lines.push(selectable_option_line(
+            theme,
+            idx + 1,
+            idx == game.event_cursor,
+            choice,
+            accent,
+        ));
+    }
+    lines
+}
+
+fn battle_lines(game: &Game, accent: Paint) -> Vec<Line<'static>> {
+    let theme = theme::of(game);
     let mut lines = Vec::new();
     if let Some(recent) = &game.recent_event {
         lines.push(Line::from(Span::styled(
             format!("{} {}", t!("ui.banner.recent"), recent),
-            Style::default().fg(accent).add_modifier(Modifier::BOLD),
+            accent.bold(),
         )));
     }
     if let Some(battle) = &game.battle {
         lines.push(Line::from(Span::styled(
             t!("ui.battle.encounter", enemy = battle.enemy.name.as_str()).to_string(),
-            Style::default()
-                .fg(Color::Rgb(255, 121, 121))
-                .add_modifier(Modifier::BOLD),
+            theme.enemy.bold(),
         )));
         lines.push(Line::from(format!(
             "{}: {}/{} {}",
@@ -250,15 +968,81 @@ fn battle_lines(game: &Game, accent: Color) -> Vec<Line<'static>> {
             battle.enemy.max_hp,
             bar(battle.enemy.hp.max(0), battle.enemy.max_hp, 20)
         )));
+        for add in &battle.adds {
+            lines.push(Line::from(Span::styled(
+                format!("  {} {}/{}", add.name, add.hp.max(0), add.max_hp),
+                theme.enemy_add,
+            )));
+        }
+        if battle.script.charging.is_some() {
+            lines.push(Line::from(Span::styled(
+                t!("ui.battle.charging").to_string(),
+                theme.reward.bold(),
+            )));
+        }
+        if !game.party.is_empty() {
+            lines.push(party_hp_line(game));
+        }
+    }
+
+    if let Some(slot) = game.choosing_member() {
+        let member = &game.party[slot];
+        lines.push(Line::from(Span::styled(
+            t!("ui.battle.orders_for", name = t!(member.kind.name_key())).to_string(),
+            theme.text.bold(),
+        )));
+        let orders = vec![
+            t!("ui.battle.order_attack").to_string(),
+            t!(
+                "ui.battle.order_skill",
+                skill = t!(member.kind.skill_key()),
+                mp = member.kind.skill_mp()
+            )
+            .to_string(),
+            t!("ui.battle.action_defend").to_string(),
+        ];
+        for (idx, text) in orders.into_iter().enumerate() {
+            lines.push(selectable_option_line(
+                theme,
+                idx + 1,
+                idx == game.order_cursor,
+                text,
+                accent,
+            ));
+        }
+        return lines;
     }
+
+    if game.skill_menu {
+        lines.push(Line::from(Span::styled(
+            t!("ui.battle.skills_title").to_string(),
+            theme.text.bold(),
+        )));
+        for (idx, skill) in game.player.skills().into_iter().enumerate() {
+            lines.push(selectable_option_line(
+                theme,
+                idx + 1,
+                idx == game.skill_cursor,
+                skill_label(skill),
+                accent,
+            ));
+        }
+        return lines;
+    }
+
     lines.push(Line::from(Span::styled(
         t!("ui.panel.controls").to_string(),
-        Style::default().fg(TEXT).add_modifier(Modifier::BOLD),
+        theme.text.bold(),
     )));
 
+    let skills = game.player.skills();
+    let skill_slot = match skills.as_slice() {
+        [only] => skill_label(*only),
+        _ => t!("ui.battle.action_skills").to_string(),
+    };
     let actions = vec![
         t!("ui.battle.action_attack").to_string(),
-        t!("ui.battle.action_fire_slash").to_string(),
+        skill_slot,
         t!("ui.battle.action_defend").to_string(),
         t!("ui.battle.action_potion").to_string(),
         t!("ui.battle.action_ether").to_string(),
@@ -266,6 +1050,7 @@ fn battle_lines(game: &Game, accent: Color) -> Vec<Line<'static>> {
     ];
     for (idx, text) in actions.into_iter().enumerate() {
         lines.push(selectable_option_line(
+            theme,
             idx + 1,
             idx == game.battle_cursor,
             text,
@@ -275,32 +1060,117 @@ fn battle_lines(game: &Game, accent: Color) -> Vec<Line<'static>> {
     lines
 }
 
+fn skill_label(skill: HeroSkill) -> String {
+    t!(
+        "ui.battle.action_skill",
+        skill = t!(skill.name_key()),
+        mp = skill.mp_cost()
+    )
+    .to_string()
+}
+
+fn party_hp_line(game: &Game) -> Line<'static> {
+    let theme = theme::of(game);
+    let members = game
+        .party
+        .iter()
+        .map(|member| {
+            format!(
+                "{} {}/{}",
+                t!(member.kind.name_key()),
+                member.hp.max(0),
+                member.max_hp
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("  ");
+    Line::from(Span::styled(
+        format!("{}: {}", t!("ui.party.title"), members),
+        theme.ally,
+    ))
+}
+
+fn party_lines(game: &Game, accent: Paint) -> Vec<Line<'static>> {
+    let theme = theme::of(game);
+    let mut lines = vec![Line::from(Span::styled(
+        t!("ui.party.title").to_string(),
+        theme.text.bold(),
+    ))];
+    if game.party.is_empty() {
+        lines.push(Line::from(Span::styled(
+            t!("ui.party.empty").to_string(),
+            theme.muted,
+        )));
+        return lines;
+    }
+    for (idx, member) in game.party.iter().enumerate() {
+        let status = if member.is_down() {
+            format!(" {}", t!("ui.party.down"))
+        } else {
+            String::new()
+        };
+        lines.push(selectable_option_line(
+            theme,
+            idx + 1,
+            idx == game.party_cursor,
+            t!(
+                "ui.party.member",
+                name = t!(member.kind.name_key()),
+                level = member.level,
+                hp = member.hp.max(0),
+                max_hp = member.max_hp,
+                mp = member.mp,
+                max_mp = member.max_mp,
+                atk = member.total_atk(),
+                def = member.total_def()
+            )
+            .to_string()
+                + &status,
+            accent,
+        ));
+    }
+    if let Some(member) = game.party.get(game.party_cursor) {
+        lines.push(Line::from(Span::styled(
+            format!(
+                "{} {}  {} {}  {} {}",
+                t!("ui.stats.weapon"),
+                t!(member.equipment.weapon.i18n_key()),
+                t!("ui.stats.armor"),
+                t!(member.equipment.armor.i18n_key()),
+                t!("ui.party.skill"),
+                t!(member.kind.skill_key())
+            ),
+            theme.muted,
+        )));
+    }
+    lines
+}
+
 fn selectable_option_line(
+    theme: &Theme,
     number: usize,
     selected: bool,
     text: String,
-    accent: Color,
+    accent: Paint,
 ) -> Line<'static> {
     Line::from(vec![
         Span::styled(
             format!("{}{}. ", if selected { ">" } else { " " }, number),
-            Style::default()
-                .fg(if selected { accent } else { MUTED })
-                .add_modifier(if selected {
-                    Modifier::BOLD
-                } else {
-                    Modifier::empty()
-                }),
+            Style::from(if selected { accent } else { theme.muted }).add_modifier(if selected {
+                Modifier::BOLD
+            } else {
+                Modifier::empty()
+            }),
         ),
         Span::styled(
             text,
-            Style::default()
-                .fg(if selected { TEXT } else { MUTED })
-                .add_modifier(if selected {
+            Style::from(if selected { theme.text } else { theme.muted }).add_modifier(
+                if selected {
                     Modifier::BOLD
                 } else {
                     Modifier::empty()
-                }),
+                },
+            ),
         ),
     ])
 }
@@ -316,7 +1186,7 @@ fn settings_scroll(game: &Game, area: Rect) -> u16 {
         return 0;
     }
 
-    let selected_row = settings_selected_row(game);
+    let selected_row = settings_row(game.settings_cursor);
     let max_scroll = total_rows.saturating_sub(visible_rows);
     selected_row
         .saturating_sub(visible_rows / 2)
@@ -324,15 +1194,154 @@ fn settings_scroll(game: &Game, area: Rect) -> u16 {
 }
 
 fn settings_total_rows() -> usize {
-    4 + Language::ALL.len() + 1 + Difficulty::ALL.len()
+    4 + Language::ALL.len()
+        + 1
+        + Difficulty::ALL.len()
+        + 2
+        + 2
+        + 2
+        + 2
+        + 1
+        + AutosaveOption::ALL.len()
+        + 1
+        + ProfileField::ALL.len()
+}
+
+fn settings_option_count() -> usize {
+    Language::ALL.len()
+        + Difficulty::ALL.len()
+        + 4
+        + AutosaveOption::ALL.len()
+        + ProfileField::ALL.len()
+}
+
+/// A setting stepped through its choices with the left and right keys.
+fn cycle_option_line(game: &Game, selected: bool, value: String) -> Line<'static> {
+    let theme = theme::of(game);
+    let style = if selected {
+        theme.text.bold()
+    } else {
+        Style::from(theme.muted)
+    };
+    Line::from(vec![
+        Span::styled(format!("{}   ", if selected { ">" } else { " " }), style),
+        Span::styled(value, theme.value),
+        Span::styled(
+            format!(
+                " {}",
+                t!(
+                    "ui.settings.cycle_row",
+                    left = game.key_label(Command::MenuLeft),
+                    right = game.key_label(Command::MenuRight)
+                )
+            ),
+            style,
+        ),
+    ])
 }
 
-fn settings_selected_row(game: &Game) -> usize {
-    if game.settings_cursor < Language::ALL.len() {
-        4 + game.settings_cursor
+/// The row of a settings option; each section header above it adds one.
+fn settings_row(cursor: usize) -> usize {
+    let encounter_index = Language::ALL.len() + Difficulty::ALL.len();
+    let theme_index = encounter_index + 2;
+    let glyphs_index = theme_index + 1;
+    let autosave_start = glyphs_index + 1;
+    if cursor < Language::ALL.len() {
+        4 + cursor
+    } else if cursor < encounter_index {
+        5 + cursor
+    } else if cursor == encounter_index {
+        6 + cursor
+    } else if cursor < theme_index {
+        7 + cursor
+    } else if cursor == theme_index {
+        8 + cursor
+    } else if cursor == glyphs_index {
+        9 + cursor
+    } else if cursor < autosave_start + AutosaveOption::ALL.len() {
+        10 + cursor
     } else {
-        5 + game.settings_cursor
+        11 + cursor
+    }
+}
+
+fn keybinds_lines(game: &Game, accent: Paint) -> Vec<Line<'static>> {
+    let theme = theme::of(game);
+    let mut lines = vec![
+        Line::from(Span::styled(
+            t!(
+                "ui.keybinds.title",
+                preset = game.keymap_preset_name(),
+                left = game.key_label(Command::MenuLeft),
+                right = game.key_label(Command::MenuRight)
+            )
+            .to_string(),
+            theme.text.bold(),
+        )),
+        keybinds_status_line(game, accent),
+    ];
+    for (idx, command) in Command::ALL.into_iter().enumerate() {
+        let selected = idx == game.keybind_cursor;
+        let style = if selected {
+            theme.text.bold()
+        } else {
+            Style::from(theme.muted)
+        };
+        let mark = if game.keybind_overridden(command) {
+            "*"
+        } else {
+            " "
+        };
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("{}{mark}", if selected { ">" } else { " " }),
+                if selected { accent } else { theme.muted },
+            ),
+            Span::styled(format!("{:<18}", t!(command.label_key())), style),
+            Span::styled(
+                game.keys_label(command),
+                if selected { accent } else { theme.text },
+            ),
+        ]));
+    }
+    lines
+}
+
+/// The capture prompt while a key is being rebound, the last change otherwise.
+fn keybinds_status_line(game: &Game, accent: Paint) -> Line<'static> {
+    let theme = theme::of(game);
+    if game.keybind_capture {
+        let command = Command::ALL[game.keybind_cursor % Command::ALL.len()];
+        return Line::from(Span::styled(
+            t!("ui.keybinds.capture", command = t!(command.label_key())).to_string(),
+            accent.bold(),
+        ));
+    }
+    let text = game.recent_event.clone().unwrap_or_else(|| {
+        t!(
+            "ui.keybinds.hint",
+            confirm = game.key_label(Command::Confirm),
+            reset = game.key_label(Command::ResetBinding)
+        )
+        .to_string()
+    });
+    Line::from(Span::styled(text, theme.muted))
+}
+
+fn keybinds_scroll(game: &Game, area: Rect) -> u16 {
+    let visible_rows = area.height.saturating_sub(2) as usize;
+    if visible_rows == 0 {
+        return 0;
     }
+    let total_rows = 2 + Command::ALL.len();
+    if total_rows <= visible_rows {
+        return 0;
+    }
+    let selected_row = 2 + game.keybind_cursor;
+    let max_scroll = total_rows.saturating_sub(visible_rows);
+    selected_row
+        .saturating_sub(visible_rows / 2)
+        .min(max_scroll) as u16
 }
 
 fn town_scroll(game: &Game, area: Rect) -> u16 {
@@ -340,7 +1349,7 @@ fn town_scroll(game: &Game, area: Rect) -> u16 {
     if visible_rows == 0 {
         return 0;
     }
-    let selected_row = town_selected_row(game);
+    let selected_row = town_option_row(game, town_cursor(game));
     let total_rows = town_total_rows(game);
     if total_rows <= visible_rows {
         return 0;
@@ -352,7 +1361,11 @@ fn town_scroll(game: &Game, area: Rect) -> u16 {
 }
 
 fn town_total_rows(game: &Game) -> usize {
-    let base_rows = 1 + 8;
+    let base_rows = if game.workshop {
+        2 + game.recipes().len()
+    } else {
+        1 + town_option_count(game)
+    };
     if game.recent_event.is_some() {
         base_rows + 1
     } else {
@@ -360,9 +1373,30 @@ fn town_total_rows(game: &Game) -> usize {
     }
 }
 
-fn town_selected_row(game: &Game) -> usize {
+fn town_cursor(game: &Game) -> usize {
+    if game.workshop {
+        game.workshop_cursor
+    } else {
+        game.town_cursor
+    }
+}
+
+fn town_option_count(game: &Game) -> usize {
+    if game.workshop {
+        game.recipes().len()
+    } else if game.peddler {
+        PEDDLER_OPTION_COUNT
+    } else {
+        TOWN_OPTION_COUNT
+    }
+}
+
+fn town_option_row(game: &Game, index: usize) -> usize {
     let base = if game.recent_event.is_some() { 2 } else { 1 };
-    base + game.town_cursor
+    if game.workshop {
+        return base + 1 + index;
+    }
+    base + index
 }
 
 fn battle_scroll(game: &Game, area: Rect) -> u16 {
@@ -370,7 +1404,7 @@ fn battle_scroll(game: &Game, area: Rect) -> u16 {
     if visible_rows == 0 {
         return 0;
     }
-    let selected_row = battle_selected_row(game);
+    let selected_row = battle_option_row(game, battle_cursor(game));
     let total_rows = battle_total_rows(game);
     if total_rows <= visible_rows {
         return 0;
@@ -382,30 +1416,97 @@ fn battle_scroll(game: &Game, area: Rect) -> u16 {
 }
 
 fn battle_total_rows(game: &Game) -> usize {
-    let mut total = 1 + 6;
-    if game.recent_event.is_some() {
-        total += 1;
-    }
-    if game.battle.is_some() {
-        total += 2;
-    }
-    total
+    battle_option_row(game, 0) + battle_option_count(game)
 }
 
-fn battle_selected_row(game: &Game) -> usize {
+/// Rows above the options: the recent event, the enemy block and the
+/// menu title, mirroring `battle_lines`.
+fn battle_option_row(game: &Game, index: usize) -> usize {
     let mut base = 1;
     if game.recent_event.is_some() {
         base += 1;
     }
-    if game.battle.is_some() {
-        base += 2;
+    if let Some(battle) = &game.battle {
+        base += 2 + battle.adds.len();
+        if battle.script.charging.is_some() {
+            base += 1;
+        }
+        if !game.party.is_empty() {
+            base += 1;
+        }
+    }
+    base + index
+}
+
+fn battle_option_count(game: &Game) -> usize {
+    if game.choosing_member().is_some() {
+        ORDER_OPTION_COUNT
+    } else if game.skill_menu {
+        game.player.skills().len()
+    } else {
+        BATTLE_ACTION_COUNT
+    }
+}
+
+fn battle_cursor(game: &Game) -> usize {
+    if game.choosing_member().is_some() {
+        game.order_cursor
+    } else if game.skill_menu {
+        game.skill_cursor
+    } else {
+        game.battle_cursor
     }
-    base + game.battle_cursor
+}
+
+/// Screen rows of the selectable options on the footer, each with the cursor
+/// index it stands for; rows scrolled out of view are left out.
+pub(crate) fn option_regions(game: &Game, area: Rect) -> Vec<(usize, Rect)> {
+    let (rows, scroll): (Vec<usize>, u16) = match game.mode {
+        GameMode::Town => (
+            (0..town_option_count(game))
+                .map(|index| town_option_row(game, index))
+                .collect(),
+            town_scroll(game, area),
+        ),
+        GameMode::Battle => (
+            (0..battle_option_count(game))
+                .map(|index| battle_option_row(game, index))
+                .collect(),
+            battle_scroll(game, area),
+        ),
+        GameMode::Settings => (
+            (0..settings_option_count()).map(settings_row).collect(),
+            settings_scroll(game, area),
+        ),
+        _ => return Vec::new(),
+    };
+    let inner = area.inner(Margin {
+        horizontal: 1,
+        vertical: 1,
+    });
+    rows.into_iter()
+        .enumerate()
+        .filter_map(|(index, row)| {
+            let y = row.checked_sub(scroll as usize)?;
+            (y < inner.height as usize).then(|| {
+                (
+                    index,
+                    Rect::new(inner.x, inner.y + y as u16, inner.width, 1),
+                )
+            })
+        })
+        .collect()
 }
 
 fn weapon_upgrade_offer(game: &Game) -> String {
     let weapon = game.player.equipment.weapon;
     match (weapon.next(), weapon.upgrade_cost()) {
+        (Some(next), Some(_)) if next.ng_tier() > game.ng_tier() => t!(
+            "ui.town.ng_locked",
+            name = t!(next.i18n_key()),
+            tier = next.ng_tier()
+        )
+        .to_string(),
         (Some(next), Some(cost)) => t!(
             "ui.town.offer_with_cost",
             name = t!(next.i18n_key()),
@@ -416,9 +1517,27 @@ fn weapon_upgrade_offer(game: &Game) -> String {
     }
 }
 
+fn tavern_offer(game: &Game) -> String {
+    match game.tavern_offer() {
+        Some((kind, cost)) => t!(
+            "ui.town.offer_with_cost",
+            name = t!(kind.name_key()),
+            cost = cost
+        )
+        .to_string(),
+        None => t!("ui.town.tavern_none").to_string(),
+    }
+}
+
 fn armor_upgrade_offer(game: &Game) -> String {
     let armor = game.player.equipment.armor;
     match (armor.next(), armor.upgrade_cost()) {
+        (Some(next), Some(_)) if next.ng_tier() > game.ng_tier() => t!(
+            "ui.town.ng_locked",
+            name = t!(next.i18n_key()),
+            tier = next.ng_tier()
+        )
+        .to_string(),
         (Some(next), Some(cost)) => t!(
             "ui.town.offer_with_cost",
             name = t!(next.i18n_key()),
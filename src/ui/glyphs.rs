@@ -0,0 +1,115 @@
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use ratatui::text::Span;
+use serde::Deserialize;
+
+use crate::game::Game;
+use crate::game::model::GlyphSetId;
+
+const GLYPH_DATA: &str = include_str!("../../config/glyphs.toml");
+
+/// The characters one glyph set draws the map with.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Glyphs {
+    /// Terminal columns each tile takes; every glyph must be this wide.
+    pub cell_width: usize,
+    pub player: String,
+    pub unexplored: String,
+    pub monster: String,
+    pub chest: String,
+    pub herb: String,
+    pub ore: String,
+    pub npc: String,
+    pub boss: String,
+    pub cleared: String,
+    pub floor: String,
+    pub wall: String,
+    pub town: String,
+    pub lair_sealed: String,
+    pub lair: String,
+}
+
+impl Glyphs {
+    fn all(&self) -> [(&'static str, &str); 14] {
+        [
+            ("player", &self.player),
+            ("unexplored", &self.unexplored),
+            ("monster", &self.monster),
+            ("chest", &self.chest),
+            ("herb", &self.herb),
+            ("ore", &self.ore),
+            ("npc", &self.npc),
+            ("boss", &self.boss),
+            ("cleared", &self.cleared),
+            ("floor", &self.floor),
+            ("wall", &self.wall),
+            ("town", &self.town),
+            ("lair_sealed", &self.lair_sealed),
+            ("lair", &self.lair),
+        ]
+    }
+}
+
+fn parse_glyphs(content: &str) -> Result<BTreeMap<GlyphSetId, Glyphs>, String> {
+    let sets: BTreeMap<GlyphSetId, Glyphs> =
+        toml::from_str(content).map_err(|err| err.message().to_string())?;
+    for (id, glyphs) in &sets {
+        if !(1..=2).contains(&glyphs.cell_width) {
+            return Err(format!("{id:?}: cell_width must be 1 or 2"));
+        }
+        for (name, glyph) in glyphs.all() {
+            let width = Span::raw(glyph).width();
+            if width != glyphs.cell_width {
+                return Err(format!(
+                    "{id:?} {name}: `{glyph}` is {width} columns wide, not {}",
+                    glyphs.cell_width
+                ));
+            }
+        }
+    }
+    match GlyphSetId::ALL.iter().find(|id| !sets.contains_key(id)) {
+        Some(id) => Err(format!("missing the `{id:?}` glyph set")),
+        None => Ok(sets),
+    }
+}
+
+fn glyph_sets() -> &'static BTreeMap<GlyphSetId, Glyphs> {
+    static GLYPHS: OnceLock<BTreeMap<GlyphSetId, Glyphs>> = OnceLock::new();
+    GLYPHS.get_or_init(|| parse_glyphs(GLYPH_DATA).expect("embedded glyph data is valid"))
+}
+
+pub fn get(id: GlyphSetId) -> &'static Glyphs {
+    &glyph_sets()[&id]
+}
+
+/// The glyph set the player picked in Settings.
+pub fn of(game: &Game) -> &'static Glyphs {
+    get(game.prefs.glyphs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GLYPH_DATA, get, parse_glyphs};
+    use crate::game::model::GlyphSetId;
+
+    #[test]
+    fn every_glyph_set_loads_with_even_widths() {
+        assert!(parse_glyphs(GLYPH_DATA).is_ok());
+        assert_eq!(get(GlyphSetId::Ascii).cell_width, 1);
+        assert_eq!(get(GlyphSetId::Unicode).cell_width, 1);
+        assert_eq!(get(GlyphSetId::Emoji).cell_width, 2);
+    }
+
+    #[test]
+    fn glyphs_of_the_wrong_width_are_rejected() {
+        let wide = GLYPH_DATA.replacen("player = \"@\"", "player = \"🧙\"", 1);
+        let error = parse_glyphs(&wide).unwrap_err();
+        assert!(error.contains("player"), "{error}");
+
+        let missing = GLYPH_DATA.split("[emoji]").next().unwrap();
+        let error = parse_glyphs(missing).unwrap_err();
+        assert!(error.contains("Emoji"), "{error}");
+    }
+}
@@ -0,0 +1,218 @@
+use ratatui::layout::{Constraint, Direction, Layout, Margin, Rect};
+
+use crate::game::model::{MAP_H, PanelTab};
+
+/// Below this size nothing but a "terminal too small" notice is drawn.
+pub(crate) const MIN_WIDTH: u16 = 44;
+pub(crate) const MIN_HEIGHT: u16 = 14;
+/// Narrower terminals stack the map, one tabbed panel and the footer.
+const COMPACT_BELOW_WIDTH: u16 = 96;
+/// Terminals at least this large get the extra wide-mode column.
+const WIDE_FROM_WIDTH: u16 = 160;
+const WIDE_FROM_HEIGHT: u16 = 36;
+/// Tall enough for the whole world at two tiles per cell, plus borders.
+const MINIMAP_HEIGHT: u16 = MAP_H.div_ceil(2) as u16 + 2;
+
+const ROOT_TOP_PERCENT: u16 = 72;
+const MAP_PERCENT: u16 = 68;
+const WIDE_MAP_PERCENT: u16 = 56;
+const WIDE_SIDEBAR_PERCENT: u16 = 24;
+const RIGHT_STATS_PERCENT: u16 = 40;
+const RIGHT_LOG_PERCENT: u16 = 34;
+const RIGHT_CONTROLS_PERCENT: u16 = 26;
+const WIDE_QUESTS_PERCENT: u16 = 60;
+const COMPACT_PANEL_PERCENT: u16 = 26;
+const COMPACT_FOOTER_PERCENT: u16 = 30;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Breakpoint {
+    TooSmall,
+    Compact,
+    Standard,
+    Wide,
+}
+
+impl Breakpoint {
+    pub fn for_area(area: Rect) -> Self {
+        if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+            Self::TooSmall
+        } else if area.width < COMPACT_BELOW_WIDTH {
+            Self::Compact
+        } else if area.width >= WIDE_FROM_WIDTH && area.height >= WIDE_FROM_HEIGHT {
+            Self::Wide
+        } else {
+            Self::Standard
+        }
+    }
+}
+
+/// Where each panel goes; panels the breakpoint does not show are `None`.
+/// In the compact layout only the active tab's panel gets the shared slot.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct UiLayout {
+    pub breakpoint: Breakpoint,
+    pub map: Rect,
+    pub hero: Option<Rect>,
+    pub log: Option<Rect>,
+    pub controls: Option<Rect>,
+    pub tabs: Option<Rect>,
+    pub minimap: Option<Rect>,
+    pub quests: Option<Rect>,
+    pub bestiary: Option<Rect>,
+    pub footer: Rect,
+}
+
+pub(crate) fn split(frame_area: Rect, tab: PanelTab) -> UiLayout {
+    let breakpoint = Breakpoint::for_area(frame_area);
+    let area = frame_area.inner(Margin {
+        horizontal: 1,
+        vertical: 0,
+    });
+    match breakpoint {
+        Breakpoint::TooSmall => UiLayout {
+            breakpoint,
+            map: Rect::default(),
+            hero: None,
+            log: None,
+            controls: None,
+            tabs: None,
+            minimap: None,
+            quests: None,
+            bestiary: None,
+            footer: Rect::default(),
+        },
+        Breakpoint::Compact => compact(area, tab),
+        Breakpoint::Standard => standard(area, MAP_PERCENT, None),
+        Breakpoint::Wide => standard(area, WIDE_MAP_PERCENT, Some(WIDE_SIDEBAR_PERCENT)),
+    }
+}
+
+fn compact(area: Rect, tab: PanelTab) -> UiLayout {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(5),
+            Constraint::Length(1),
+            Constraint::Percentage(COMPACT_PANEL_PERCENT),
+            Constraint::Percentage(COMPACT_FOOTER_PERCENT),
+        ])
+        .split(area);
+    let panel = rows[2];
+    UiLayout {
+        breakpoint: Breakpoint::Compact,
+        map: rows[0],
+        hero: (tab == PanelTab::Hero).then_some(panel),
+        log: (tab == PanelTab::Log).then_some(panel),
+        controls: (tab == PanelTab::Controls).then_some(panel),
+        tabs: Some(rows[1]),
+        minimap: None,
+        quests: None,
+        bestiary: None,
+        footer: rows[3],
+    }
+}
+
+/// Map beside the sidebar over the footer; the wide layout adds a third
+/// column for the minimap, quest log and bestiary.
+fn standard(area: Rect, map_percent: u16, sidebar_percent: Option<u16>) -> UiLayout {
+    let root = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage(ROOT_TOP_PERCENT),
+            Constraint::Percentage(100 - ROOT_TOP_PERCENT),
+        ])
+        .split(area);
+    let columns = match sidebar_percent {
+        Some(sidebar) => vec![
+            Constraint::Percentage(map_percent),
+            Constraint::Percentage(sidebar),
+            Constraint::Percentage(100 - map_percent - sidebar),
+        ],
+        None => vec![
+            Constraint::Percentage(map_percent),
+            Constraint::Percentage(100 - map_percent),
+        ],
+    };
+    let top = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(columns)
+        .split(root[0]);
+    let right = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage(RIGHT_STATS_PERCENT),
+            Constraint::Percentage(RIGHT_LOG_PERCENT),
+            Constraint::Percentage(RIGHT_CONTROLS_PERCENT),
+        ])
+        .split(top[1]);
+    let extra = top.get(2).map(|column| {
+        Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(column.height.min(MINIMAP_HEIGHT)),
+                Constraint::Percentage(WIDE_QUESTS_PERCENT),
+                Constraint::Percentage(100 - WIDE_QUESTS_PERCENT),
+            ])
+            .split(*column)
+    });
+
+    UiLayout {
+        breakpoint: if extra.is_some() {
+            Breakpoint::Wide
+        } else {
+            Breakpoint::Standard
+        },
+        map: top[0],
+        hero: Some(right[0]),
+        log: Some(right[1]),
+        controls: Some(right[2]),
+        tabs: None,
+        minimap: extra.as_ref().map(|rows| rows[0]),
+        quests: extra.as_ref().map(|rows| rows[1]),
+        bestiary: extra.as_ref().map(|rows| rows[2]),
+        footer: root[1],
+    }
+}
+
+pub(crate) fn rect_contains(rect: Rect, x: u16, y: u16) -> bool {
+    x >= rect.x
+        && x < rect.x.saturating_add(rect.width)
+        && y >= rect.y
+        && y < rect.y.saturating_add(rect.height)
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::layout::Rect;
+
+    use super::{Breakpoint, MIN_HEIGHT, MIN_WIDTH, split};
+    use crate::game::model::PanelTab;
+
+    #[test]
+    fn breakpoints_follow_the_terminal_size() {
+        let at = |width, height| Breakpoint::for_area(Rect::new(0, 0, width, height));
+        assert_eq!(at(MIN_WIDTH - 1, 40), Breakpoint::TooSmall);
+        assert_eq!(at(80, MIN_HEIGHT - 1), Breakpoint::TooSmall);
+        assert_eq!(at(80, 24), Breakpoint::Compact);
+        assert_eq!(at(120, 40), Breakpoint::Standard);
+        assert_eq!(at(200, 30), Breakpoint::Standard);
+        assert_eq!(at(200, 50), Breakpoint::Wide);
+    }
+
+    #[test]
+    fn compact_layout_gives_the_panel_slot_to_the_active_tab() {
+        let layout = split(Rect::new(0, 0, 80, 30), PanelTab::Log);
+        assert!(layout.tabs.is_some());
+        assert!(layout.hero.is_none() && layout.controls.is_none());
+        let log = layout.log.expect("log takes the panel slot");
+        assert_eq!(log.width, layout.map.width);
+        assert!(log.y > layout.map.y && log.y < layout.footer.y);
+
+        let wide = split(Rect::new(0, 0, 200, 50), PanelTab::Log);
+        assert!(wide.tabs.is_none());
+        assert!(wide.hero.is_some() && wide.log.is_some() && wide.controls.is_some());
+        let minimap = wide.minimap.expect("wide mode shows the minimap");
+        assert!(minimap.x > wide.hero.unwrap().x);
+        assert!(wide.quests.is_some() && wide.bestiary.is_some());
+    }
+}
@@ -1,23 +1,36 @@
 use ratatui::Frame;
-use ratatui::layout::Rect;
+use ratatui::layout::{Margin, Rect};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Paragraph, Wrap};
 use rust_i18n::t;
 
 use crate::game::Game;
-use crate::game::model::{MAP_H, MAP_W, Tile};
-use crate::ui::{MUTED, TEXT, mode_accent, panel_block};
+use crate::game::model::{
+    EncounterMode, GameMode, MAP_H, MAP_W, Material, Position, Tile, TimeOfDay,
+};
+use crate::ui::glyphs::{self, Glyphs};
+use crate::ui::layout::rect_contains;
+use crate::ui::panel_block;
+use crate::ui::theme::{self, Theme};
 
 pub fn render(frame: &mut Frame, game: &Game, area: Rect) {
+    let theme = theme::of(game);
     let map_widget = Paragraph::new(build_map_lines(game, area))
-        .style(Style::default().fg(TEXT))
-        .block(panel_block(t!("ui.panel.world"), mode_accent(game.mode)))
+        .style(theme.text)
+        .block(panel_block(
+            theme,
+            t!("ui.panel.world"),
+            theme.accent(game.mode),
+        ))
         .wrap(Wrap { trim: false });
     frame.render_widget(map_widget, area);
 }
 
 fn build_map_lines(game: &Game, area: Rect) -> Vec<Line<'static>> {
+    let theme = theme::of(game);
+    let glyphs = glyphs::of(game);
+    let cell = glyphs.cell_width;
     let view_w = area.width.saturating_sub(2) as usize;
     let view_h = area.height.saturating_sub(2) as usize;
     if view_w == 0 || view_h == 0 {
@@ -27,13 +40,14 @@ fn build_map_lines(game: &Game, area: Rect) -> Vec<Line<'static>> {
     let mut lines = Vec::with_capacity(view_h);
 
     for vy in 0..view_h {
-        let map_y = map_index_for_view(vy, view_h, MAP_H, game.player.y);
+        let map_y = map_index_for_view(vy, view_h, 1, MAP_H, game.player.y);
         let mut spans = Vec::with_capacity(view_w);
-        for vx in 0..view_w {
-            let map_x = map_index_for_view(vx, view_w, MAP_W, game.player.x);
+        for vx in (0..view_w).step_by(cell) {
+            let map_x = map_index_for_view(vx, view_w, cell, MAP_W, game.player.x);
             match (map_x, map_y) {
                 (Some(x), Some(y)) => spans.push(tile_span(game, x, y)),
-                _ => spans.push(margin_span(vx, vy)),
+                // A leftover column too narrow for a whole tile stays blank.
+                _ => spans.push(margin_span(theme, cell.min(view_w - vx), vx / cell, vy)),
             }
         }
         lines.push(Line::from(spans));
@@ -42,12 +56,43 @@ fn build_map_lines(game: &Game, area: Rect) -> Vec<Line<'static>> {
     lines
 }
 
+/// The map tile drawn at a screen cell while exploring, with the cells it
+/// covers.
+pub(crate) fn tile_at(game: &Game, area: Rect, x: u16, y: u16) -> Option<(Position, Rect)> {
+    let inner = area.inner(Margin {
+        horizontal: 1,
+        vertical: 1,
+    });
+    if game.mode != GameMode::Exploration || !rect_contains(inner, x, y) {
+        return None;
+    }
+    let cell = glyphs::of(game).cell_width;
+    let view_x = (x - inner.x) as usize;
+    let view_y = (y - inner.y) as usize;
+    let map_x = map_index_for_view(view_x, inner.width as usize, cell, MAP_W, game.player.x)?;
+    let map_y = map_index_for_view(view_y, inner.height as usize, 1, MAP_H, game.player.y)?;
+    let start = x - (view_x % cell) as u16;
+    Some((
+        Position { x: map_x, y: map_y },
+        Rect::new(start, y, cell as u16, 1),
+    ))
+}
+
+/// The map index shown at a screen cell, for tiles `cell` cells wide. The
+/// view is centred on a map smaller than it and otherwise scrolls to keep
+/// `focus` in the middle.
 fn map_index_for_view(
     view_index: usize,
     view_len: usize,
+    cell: usize,
     map_len: usize,
     focus: usize,
 ) -> Option<usize> {
+    let view_index = view_index / cell;
+    let view_len = view_len / cell;
+    if view_index >= view_len {
+        return None;
+    }
     if view_len >= map_len {
         let offset = (view_len - map_len) / 2;
         let in_map = view_index.checked_sub(offset)?;
@@ -60,43 +105,153 @@ fn map_index_for_view(
     }
 }
 
-fn margin_span(x: usize, y: usize) -> Span<'static> {
-    let bg = if (x + y).is_multiple_of(2) {
-        Color::Rgb(20, 24, 36)
+fn margin_span(theme: &Theme, width: usize, x: usize, y: usize) -> Span<'static> {
+    let paint = if (x + y).is_multiple_of(2) {
+        theme.map.margin
     } else {
-        Color::Rgb(17, 20, 30)
+        theme.map.margin_alt
+    };
+    Span::styled(" ".repeat(width), paint)
+}
+
+/// Shifts tile colors toward the light of the current time of day.
+fn tint(span: Span<'static>, time: TimeOfDay) -> Span<'static> {
+    let shade = |color: Option<Color>| match (color, time) {
+        (Some(Color::Rgb(r, g, b)), TimeOfDay::Dawn) => Some(Color::Rgb(
+            r.saturating_add(12),
+            g.saturating_add(4),
+            b.saturating_sub(8),
+        )),
+        (Some(Color::Rgb(r, g, b)), TimeOfDay::Dusk) => Some(Color::Rgb(
+            r,
+            (g as u16 * 4 / 5) as u8,
+            (b as u16 * 2 / 3) as u8,
+        )),
+        (Some(Color::Rgb(r, g, b)), TimeOfDay::Night) => Some(Color::Rgb(
+            (r as u16 / 2) as u8,
+            (g as u16 * 3 / 5) as u8,
+            (b as u16 * 4 / 5 + 24).min(255) as u8,
+        )),
+        (Some(color), TimeOfDay::Night) => Some(darker(color)),
+        (color, _) => color,
+    };
+    let mut style = Style {
+        fg: shade(span.style.fg),
+        bg: shade(span.style.bg),
+        ..span.style
     };
-    Span::styled(" ", Style::default().bg(bg))
+    // Palettes without true colors cannot shift their hue, so night falls
+    // on them as dimmed text instead.
+    let true_color = [span.style.fg, span.style.bg]
+        .iter()
+        .any(|color| matches!(color, Some(Color::Rgb(..))));
+    if time == TimeOfDay::Night && !true_color {
+        style = style.add_modifier(Modifier::DIM);
+    }
+    span.style(style)
+}
+
+/// The darker neighbour of a named or indexed terminal color.
+fn darker(color: Color) -> Color {
+    match color {
+        Color::White => Color::Gray,
+        Color::Gray => Color::DarkGray,
+        Color::LightRed => Color::Red,
+        Color::LightGreen => Color::Green,
+        Color::LightYellow => Color::Yellow,
+        Color::LightBlue => Color::Blue,
+        Color::LightMagenta => Color::Magenta,
+        Color::LightCyan => Color::Cyan,
+        Color::Indexed(index @ 8..=15) => Color::Indexed(index - 8),
+        color => color,
+    }
 }
 
-fn tile_span(game: &Game, x: usize, y: usize) -> Span<'static> {
+pub(crate) fn tile_span(game: &Game, x: usize, y: usize) -> Span<'static> {
+    let theme = theme::of(game);
+    let glyphs = glyphs::of(game);
     if x == game.player.x && y == game.player.y {
-        return Span::styled(
-            "@",
-            Style::default()
-                .fg(Color::Rgb(18, 20, 30))
-                .bg(Color::Rgb(124, 196, 255))
-                .add_modifier(Modifier::BOLD),
-        );
+        return Span::styled(glyphs.player.as_str(), theme.map.player);
+    }
+
+    if !game.world.is_explored(x, y) {
+        return Span::styled(glyphs.unexplored.as_str(), theme.map.unexplored);
     }
+    tint(explored_span(game, theme, glyphs, x, y), game.time_of_day())
+}
 
+fn explored_span(
+    game: &Game,
+    theme: &Theme,
+    glyphs: &'static Glyphs,
+    x: usize,
+    y: usize,
+) -> Span<'static> {
+    let map = &theme.map;
+    if game.encounter_mode == EncounterMode::Roaming && game.world.monster_at(x, y).is_some() {
+        return Span::styled(glyphs.monster.as_str(), map.monster);
+    }
     if game.world.has_unopened_chest(x, y) {
-        return Span::styled("C", Style::default().fg(Color::Rgb(255, 213, 124)));
+        return Span::styled(glyphs.chest.as_str(), map.chest);
+    }
+    if let Some(node) = game.world.resource_node_at(x, y) {
+        return match node.material {
+            Material::Herb => Span::styled(glyphs.herb.as_str(), map.herb),
+            Material::Ore | Material::Hide => Span::styled(glyphs.ore.as_str(), map.ore),
+        };
     }
-    if game.world.has_active_npc(x, y) {
-        return Span::styled("N", Style::default().fg(Color::Rgb(226, 157, 255)));
+    if game.world.npc_present(x, y, game.time_of_day()) {
+        return Span::styled(glyphs.npc.as_str(), map.npc);
+    }
+    if game.world.boss_site_at(x, y).is_some() {
+        return Span::styled(glyphs.boss.as_str(), map.boss);
     }
 
     match game.map[y][x] {
-        Tile::Floor if game.world.tile_is_cleared(x, y) => Span::styled(
-            ",",
-            Style::default()
-                .fg(Color::Rgb(132, 196, 136))
-                .add_modifier(Modifier::DIM),
-        ),
-        Tile::Floor => Span::styled(".", Style::default().fg(MUTED).add_modifier(Modifier::DIM)),
-        Tile::Wall => Span::styled("#", Style::default().fg(Color::Rgb(112, 120, 142))),
-        Tile::Town => Span::styled("H", Style::default().fg(Color::Rgb(113, 222, 230))),
-        Tile::Lair => Span::styled("X", Style::default().fg(Color::Rgb(255, 118, 118))),
+        Tile::Floor if game.world.tile_is_cleared(x, y) => {
+            Span::styled(glyphs.cleared.as_str(), map.cleared)
+        }
+        Tile::Floor => Span::styled(glyphs.floor.as_str(), map.floor),
+        Tile::Wall => Span::styled(glyphs.wall.as_str(), map.wall),
+        Tile::Town => Span::styled(glyphs.town.as_str(), map.town),
+        Tile::Lair if game.world.lair_sealed() => {
+            Span::styled(glyphs.lair_sealed.as_str(), map.lair_sealed)
+        }
+        Tile::Lair => Span::styled(glyphs.lair.as_str(), map.lair),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::{Color, Modifier, Style};
+    use ratatui::text::Span;
+
+    use super::tint;
+    use crate::game::model::TimeOfDay;
+
+    #[test]
+    fn night_darkens_every_kind_of_palette() {
+        let named = tint(
+            Span::styled("#", Style::new().fg(Color::LightRed)),
+            TimeOfDay::Night,
+        );
+        assert_eq!(named.style.fg, Some(Color::Red));
+        assert!(named.style.add_modifier.contains(Modifier::DIM));
+
+        let plain = tint(Span::styled("#", Style::new().bold()), TimeOfDay::Night);
+        assert!(plain.style.add_modifier.contains(Modifier::DIM));
+
+        let rgb = tint(
+            Span::styled("#", Style::new().fg(Color::Rgb(200, 200, 200))),
+            TimeOfDay::Night,
+        );
+        assert_eq!(rgb.style.fg, Some(Color::Rgb(100, 120, 184)));
+        assert!(!rgb.style.add_modifier.contains(Modifier::DIM));
+
+        let day = tint(
+            Span::styled("#", Style::new().fg(Color::LightRed)),
+            TimeOfDay::Day,
+        );
+        assert_eq!(day.style.fg, Some(Color::LightRed));
     }
 }
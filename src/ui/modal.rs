@@ -0,0 +1,43 @@
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::text::Line;
+use ratatui::widgets::{Clear, Paragraph, Wrap};
+
+use crate::ui::panel_block;
+use crate::ui::theme::{Paint, Theme};
+
+const MODAL_WIDTH: u16 = 56;
+
+/// Draws a dialog centred over whatever is already on screen.
+pub fn render(
+    frame: &mut Frame,
+    theme: &Theme,
+    title: String,
+    body: Vec<Line<'static>>,
+    accent: Paint,
+) {
+    let width = MODAL_WIDTH.min(frame.area().width);
+    let inner_width = width.saturating_sub(2).max(1) as usize;
+    let rows: usize = body
+        .iter()
+        .map(|line| line.width().max(1).div_ceil(inner_width))
+        .sum();
+    let area = centered(frame.area(), width, rows as u16 + 2);
+    let dialog = Paragraph::new(body)
+        .style(theme.text)
+        .block(panel_block(theme, title, accent))
+        .wrap(Wrap { trim: true });
+    frame.render_widget(Clear, area);
+    frame.render_widget(dialog, area);
+}
+
+fn centered(outer: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(outer.width);
+    let height = height.min(outer.height);
+    Rect {
+        x: outer.x + (outer.width - width) / 2,
+        y: outer.y + (outer.height - height) / 2,
+        width,
+        height,
+    }
+}
@@ -1,43 +1,56 @@
 use ratatui::Frame;
 use ratatui::layout::Rect;
-use ratatui::style::{Color, Modifier, Style};
+use ratatui::style::{Style, Stylize};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Paragraph, Wrap};
 use rust_i18n::t;
 
 use crate::game::Game;
-use crate::game::model::GameMode;
-use crate::ui::{MUTED, TEXT, bar, mode_accent, panel_block};
+use crate::game::model::{Command, GameMode, PanelTab};
+use crate::ui::theme::{self, Paint, Theme};
+use crate::ui::{bar, glyphs, panel_block};
 
-pub fn render_stats(frame: &mut Frame, game: &Game, area: Rect, requested_scroll: usize) {
-    let accent = mode_accent(game.mode);
-    let (quest_status, quest_color) = if !game.quest.accepted {
-        (t!("ui.quest.none").to_string(), MUTED)
+/// The hunting quest's state as shown in the hero panel and the quest log.
+pub(crate) fn quest_status(game: &Game) -> (String, Paint) {
+    let theme = theme::of(game);
+    if !game.quest.accepted {
+        (t!("ui.quest.none").to_string(), theme.muted)
     } else if game.quest.completed && !game.quest.rewarded {
         (
             t!("ui.quest.ready", reward = game.quest.reward_gold).to_string(),
-            Color::Rgb(255, 206, 122),
+            theme.reward,
         )
     } else if game.quest.rewarded {
-        (t!("ui.quest.done").to_string(), Color::Rgb(135, 210, 145))
+        (t!("ui.quest.done").to_string(), theme.success)
     } else {
         (
             t!("ui.quest.progress", progress = game.quest.progress_text()).to_string(),
-            Color::Rgb(140, 200, 255),
+            theme.quest,
         )
-    };
+    }
+}
 
-    let lines = vec![
+pub fn render_stats(frame: &mut Frame, game: &Game, area: Rect, requested_scroll: usize) {
+    let theme = theme::of(game);
+    let accent = theme.accent(game.mode);
+    let (quest_status, quest_paint) = quest_status(game);
+
+    let mut lines = vec![
         kv_line(
+            theme,
             t!("ui.stats.level").to_string(),
-            game.player.level.to_string(),
+            format!(
+                "{}  {}",
+                game.player.level,
+                t!(game.player.class.label_key())
+            ),
             accent,
         ),
         Line::from(vec![
-            label_span(t!("ui.stats.gold").to_string()),
-            value_span(game.player.gold.to_string(), Color::Rgb(255, 213, 124)),
+            label_span(theme, t!("ui.stats.gold").to_string()),
+            value_span(game.player.gold.to_string(), theme.gold),
             Span::raw("  "),
-            label_span(t!("ui.stats.bag").to_string()),
+            label_span(theme, t!("ui.stats.bag").to_string()),
             value_span(
                 format!(
                     "{}:{}  {}:{}",
@@ -46,85 +59,186 @@ pub fn render_stats(frame: &mut Frame, game: &Game, area: Rect, requested_scroll
                     t!("ui.stats.ether_short"),
                     game.player.bag.ether
                 ),
-                TEXT,
+                theme.text,
             ),
         ]),
+        kv_line(
+            theme,
+            t!("ui.stats.materials").to_string(),
+            format!(
+                "{}:{}  {}:{}  {}:{}",
+                t!("ui.stats.herb_short"),
+                game.player.bag.materials.herb,
+                t!("ui.stats.ore_short"),
+                game.player.bag.materials.ore,
+                t!("ui.stats.hide_short"),
+                game.player.bag.materials.hide
+            ),
+            theme.materials,
+        ),
         meter_line(
+            theme,
             t!("ui.stats.hp").to_string(),
             game.player.hp,
             game.player.max_hp,
             12,
-            Color::Rgb(255, 132, 132),
+            theme.hp,
         ),
         meter_line(
+            theme,
             t!("ui.stats.mp").to_string(),
             game.player.mp,
             game.player.max_mp,
             12,
-            Color::Rgb(122, 178, 255),
+            theme.mp,
         ),
         Line::from(vec![
-            label_span(t!("ui.stats.atk").to_string()),
+            label_span(theme, t!("ui.stats.atk").to_string()),
+            value_span(game.player.total_atk().to_string(), theme.attack),
+            Span::raw("  "),
+            label_span(theme, t!("ui.stats.def").to_string()),
+            value_span(game.player.total_def().to_string(), theme.defense),
+        ]),
+        Line::from(vec![
+            label_span(theme, t!("ui.stats.weapon").to_string()),
             value_span(
-                game.player.total_atk().to_string(),
-                Color::Rgb(255, 170, 110),
+                t!(game.player.equipment.weapon.i18n_key()).to_string(),
+                theme.attack,
             ),
             Span::raw("  "),
-            label_span(t!("ui.stats.def").to_string()),
+            label_span(theme, t!("ui.stats.armor").to_string()),
             value_span(
-                game.player.total_def().to_string(),
-                Color::Rgb(139, 215, 161),
+                t!(game.player.equipment.armor.i18n_key()).to_string(),
+                theme.defense,
             ),
         ]),
         Line::from(vec![
-            label_span(t!("ui.stats.weapon").to_string()),
+            label_span(theme, t!("ui.stats.time").to_string()),
             value_span(
-                t!(game.player.equipment.weapon.i18n_key()).to_string(),
-                Color::Rgb(255, 170, 110),
+                t!(
+                    "ui.time.stamp",
+                    day = game.clock.day(),
+                    clock = format!("{:02}:{:02}", game.clock.hour(), game.clock.minute())
+                )
+                .to_string(),
+                theme.text,
             ),
             Span::raw("  "),
-            label_span(t!("ui.stats.armor").to_string()),
             value_span(
-                t!(game.player.equipment.armor.i18n_key()).to_string(),
-                Color::Rgb(139, 215, 161),
+                t!(game.time_of_day().label_key()).to_string(),
+                theme.time_of_day(game.time_of_day()),
             ),
         ]),
         kv_line(
+            theme,
             t!("ui.stats.difficulty").to_string(),
-            t!(game.difficulty.label_key()).to_string(),
-            Color::Rgb(240, 189, 95),
+            if game.ng_tier() > 0 {
+                format!(
+                    "{}  {}",
+                    t!(game.difficulty.label_key()),
+                    t!("ui.stats.ng_plus", tier = game.ng_tier())
+                )
+            } else {
+                t!(game.difficulty.label_key()).to_string()
+            },
+            theme.heading,
         ),
+    ];
+    if game.campaign.roguelite {
+        lines.push(kv_line(
+            theme,
+            t!("ui.stats.run").to_string(),
+            t!("ui.legacy.roguelite_run").to_string(),
+            theme.roguelite,
+        ));
+    }
+    if let Some(day) = game.campaign.daily {
+        lines.push(kv_line(
+            theme,
+            t!("ui.stats.daily").to_string(),
+            game.date_label(day),
+            theme.challenge,
+        ));
+    }
+    if !game.campaign.modifiers.is_empty() {
+        lines.push(kv_line(
+            theme,
+            t!("ui.stats.rules").to_string(),
+            game.campaign
+                .modifiers
+                .iter()
+                .map(|modifier| t!(modifier.name_key()).to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+            theme.challenge,
+        ));
+    }
+    lines.extend([
         Line::from(vec![
-            label_span(t!("ui.stats.quest").to_string()),
-            value_span(quest_status, quest_color),
+            label_span(theme, t!("ui.stats.quest").to_string()),
+            value_span(quest_status, quest_paint),
         ]),
+        kv_line(
+            theme,
+            t!("ui.stats.seals").to_string(),
+            format!(
+                "{}/{}",
+                game.world.seals_broken(),
+                game.world.boss_sites.len()
+            ),
+            theme.seals,
+        ),
         meter_line(
+            theme,
             t!("ui.stats.exp").to_string(),
             game.player.exp,
             game.player.next_exp,
             12,
-            Color::Rgb(178, 160, 255),
+            theme.exp,
         ),
-    ];
+    ]);
+    if game.player.stat_points > 0 {
+        lines.push(kv_line(
+            theme,
+            t!("ui.stats.points").to_string(),
+            game.player.stat_points.to_string(),
+            theme.exp,
+        ));
+    }
+    for member in &game.party {
+        lines.push(meter_line(
+            theme,
+            format!("{} Lv{}", t!(member.kind.name_key()), member.level),
+            member.hp,
+            member.max_hp,
+            12,
+            if member.is_down() {
+                theme.muted
+            } else {
+                theme.ally
+            },
+        ));
+    }
     let stats = Paragraph::new(lines.clone())
         .scroll((clamp_scroll(requested_scroll, area, lines.len()), 0))
-        .style(Style::default().fg(TEXT))
-        .block(panel_block(t!("ui.panel.hero"), accent));
+        .style(theme.text)
+        .block(panel_block(theme, t!("ui.panel.hero"), accent));
     frame.render_widget(stats, area);
 }
 
 pub fn render_log(frame: &mut Frame, game: &Game, area: Rect, requested_scroll: usize) {
+    let theme = theme::of(game);
     let log_lines: Vec<Line> = if game.log.is_empty() {
         vec![Line::from(Span::styled(
             t!("ui.log.no_events").to_string(),
-            Style::default().fg(MUTED).add_modifier(Modifier::ITALIC),
+            theme.muted.italic(),
         ))]
     } else {
         game.log
             .iter()
             .map(|line| {
                 Line::from(vec![
-                    Span::styled("> ", Style::default().fg(Color::Rgb(126, 149, 255))),
+                    Span::styled("> ", theme.bullet),
                     Span::raw(line.to_string()),
                 ])
             })
@@ -132,93 +246,381 @@ pub fn render_log(frame: &mut Frame, game: &Game, area: Rect, requested_scroll:
     };
     let total_rows = wrapped_rows(&log_lines, area);
     let logs = Paragraph::new(log_lines)
-        .style(Style::default().fg(TEXT))
-        .block(panel_block(
-            t!("ui.panel.battle_log"),
-            Color::Rgb(126, 149, 255),
-        ))
+        .style(theme.text)
+        .block(panel_block(theme, t!("ui.panel.battle_log"), theme.bullet))
         .scroll((clamp_scroll(requested_scroll, area, total_rows), 0))
         .wrap(Wrap { trim: true });
     frame.render_widget(logs, area);
 }
 
-pub fn render_controls(frame: &mut Frame, mode: GameMode, area: Rect, requested_scroll: usize) {
-    let accent = mode_accent(mode);
-    let lines = control_lines(mode);
+fn tab_label(tab: PanelTab) -> String {
+    format!(" {} ", t!(tab.label_key()))
+}
+
+/// The clickable label of each tab on the compact layout's tab strip.
+pub(crate) fn tab_regions(area: Rect) -> Vec<(PanelTab, Rect)> {
+    let mut x = area.x;
+    PanelTab::ALL
+        .into_iter()
+        .map(|tab| {
+            let width = Span::raw(tab_label(tab)).width() as u16;
+            let region = Rect::new(x, area.y, width, 1).intersection(area);
+            x = x.saturating_add(width + 1);
+            (tab, region)
+        })
+        .collect()
+}
+
+pub fn render_tabs(frame: &mut Frame, game: &Game, area: Rect) {
+    let theme = theme::of(game);
+    let accent = theme.accent(game.mode);
+    let mut spans = Vec::new();
+    for tab in PanelTab::ALL {
+        let style = if tab == game.panel_tab {
+            accent.reversed().bold()
+        } else {
+            Style::from(theme.muted)
+        };
+        spans.push(Span::styled(tab_label(tab), style));
+        spans.push(Span::raw(" "));
+    }
+    spans.push(Span::styled(
+        t!("ui.tabs.hint", key = game.key_label(Command::NextPanel)).to_string(),
+        theme.muted.italic(),
+    ));
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+pub fn render_controls(frame: &mut Frame, game: &Game, area: Rect, requested_scroll: usize) {
+    let theme = theme::of(game);
+    let accent = theme.accent(game.mode);
+    let lines = control_lines(game);
     let controls = Paragraph::new(lines.clone())
         .scroll((clamp_scroll(requested_scroll, area, lines.len()), 0))
-        .style(Style::default().fg(TEXT))
-        .block(panel_block(t!("ui.panel.controls"), accent));
+        .style(theme.text)
+        .block(panel_block(theme, t!("ui.panel.controls"), accent));
     frame.render_widget(controls, area);
 }
 
-fn control_lines(mode: GameMode) -> Vec<Line<'static>> {
-    let items: Vec<String> = match mode {
+/// Control hints name whatever keys the active keymap binds.
+fn control_lines(game: &Game) -> Vec<Line<'static>> {
+    let theme = theme::of(game);
+    let key = |command| game.key_label(command);
+    let quit = t!("ui.controls.quit", key = key(Command::Quit)).to_string();
+    let save_load = t!(
+        "ui.controls.save_load",
+        save = key(Command::Save),
+        load = key(Command::Load)
+    )
+    .to_string();
+    let menu_select = t!(
+        "ui.controls.menu_select",
+        up = key(Command::MenuUp),
+        down = key(Command::MenuDown),
+        confirm = key(Command::Confirm)
+    )
+    .to_string();
+    let party_open = t!("ui.controls.party.open", key = key(Command::Party)).to_string();
+    let level_up_open = t!("ui.controls.level_up.open", key = key(Command::LevelUp)).to_string();
+    let records_open = t!("ui.controls.records.open", key = key(Command::Records)).to_string();
+    let open_settings = t!("ui.controls.open_settings", key = key(Command::Settings)).to_string();
+    let back = key(Command::Back);
+    let restart = t!("ui.controls.result.restart", key = key(Command::Restart)).to_string();
+
+    let items: Vec<String> = match game.mode {
         GameMode::Exploration => vec![
-            t!("ui.controls.exploration.move").to_string(),
-            t!("ui.controls.exploration.town").to_string(),
-            t!("ui.controls.open_settings").to_string(),
-            t!("ui.controls.save_load").to_string(),
-            t!("ui.controls.quit").to_string(),
+            t!(
+                "ui.controls.exploration.move",
+                up = key(Command::MoveUp),
+                left = key(Command::MoveLeft),
+                down = key(Command::MoveDown),
+                right = key(Command::MoveRight)
+            )
+            .to_string(),
+            t!(
+                "ui.controls.exploration.town",
+                key = key(Command::Interact),
+                glyph = glyphs::of(game).town
+            )
+            .to_string(),
+            t!(
+                "ui.controls.exploration.talk",
+                key = key(Command::Interact),
+                glyph = glyphs::of(game).npc
+            )
+            .to_string(),
+            t!(
+                "ui.controls.exploration.world_map",
+                key = key(Command::WorldMap)
+            )
+            .to_string(),
+            party_open,
+            level_up_open,
+            records_open,
+            open_settings,
+            save_load,
+            quit,
+            t!("ui.controls.mouse").to_string(),
+        ],
+        GameMode::Town if game.peddler => vec![
+            t!("ui.controls.peddler.buy").to_string(),
+            t!("ui.controls.peddler.leave", back = back).to_string(),
+            menu_select,
         ],
         GameMode::Town => vec![
             t!("ui.controls.town.buy").to_string(),
             t!("ui.controls.town.service").to_string(),
             t!("ui.controls.town.leave").to_string(),
-            t!("ui.controls.menu_select").to_string(),
-            t!("ui.controls.open_settings").to_string(),
-            t!("ui.controls.save_load").to_string(),
+            t!("ui.controls.town.workshop", back = back).to_string(),
+            party_open,
+            level_up_open,
+            menu_select,
+            open_settings,
+            save_load,
         ],
         GameMode::Settings => vec![
-            t!("ui.controls.settings.line_1").to_string(),
-            t!("ui.controls.settings.line_2").to_string(),
-            t!("ui.controls.settings.line_3").to_string(),
-            format!("6..8: {}", t!("ui.stats.difficulty")),
-            t!("ui.controls.save_load").to_string(),
+            t!(
+                "ui.controls.settings.line_1",
+                up = key(Command::MenuUp),
+                down = key(Command::MenuDown)
+            )
+            .to_string(),
+            t!(
+                "ui.controls.settings.line_2",
+                confirm = key(Command::Confirm)
+            )
+            .to_string(),
+            t!(
+                "ui.controls.settings.line_3",
+                back = back,
+                quit = key(Command::Quit)
+            )
+            .to_string(),
+            format!("6..9: {}", t!("ui.stats.difficulty")),
+            format!("0: {}", t!("ui.settings.encounters")),
+            t!(
+                "ui.controls.settings.sliders",
+                left = key(Command::MenuLeft),
+                right = key(Command::MenuRight),
+                reset = key(Command::ResetDifficulty)
+            )
+            .to_string(),
+            t!(
+                "ui.controls.settings.theme",
+                left = key(Command::MenuLeft),
+                right = key(Command::MenuRight)
+            )
+            .to_string(),
+            t!(
+                "ui.controls.settings.autosave",
+                confirm = key(Command::Confirm),
+                left = key(Command::MenuLeft),
+                right = key(Command::MenuRight)
+            )
+            .to_string(),
+            t!(
+                "ui.controls.settings.confirm",
+                key = key(Command::ResetPrompts)
+            )
+            .to_string(),
+            save_load,
+        ],
+        GameMode::Keybinds => vec![
+            t!(
+                "ui.controls.keybinds.select",
+                up = key(Command::MenuUp),
+                down = key(Command::MenuDown)
+            )
+            .to_string(),
+            t!(
+                "ui.controls.keybinds.rebind",
+                confirm = key(Command::Confirm)
+            )
+            .to_string(),
+            t!(
+                "ui.controls.keybinds.preset",
+                left = key(Command::MenuLeft),
+                right = key(Command::MenuRight)
+            )
+            .to_string(),
+            t!(
+                "ui.controls.keybinds.reset",
+                key = key(Command::ResetBinding)
+            )
+            .to_string(),
+            t!("ui.controls.keybinds.close", back = back).to_string(),
         ],
         GameMode::Battle => vec![
-            t!("ui.controls.battle.line_1").to_string(),
-            t!("ui.controls.battle.line_2").to_string(),
-            t!("ui.controls.battle.line_3").to_string(),
-            t!("ui.controls.menu_select").to_string(),
-            t!("ui.controls.save_load").to_string(),
+            t!(
+                "ui.controls.battle.line_1",
+                attack = key(Command::Attack),
+                skill = key(Command::Skill)
+            )
+            .to_string(),
+            t!(
+                "ui.controls.battle.line_2",
+                defend = key(Command::Defend),
+                potion = key(Command::Potion),
+                ether = key(Command::Ether)
+            )
+            .to_string(),
+            t!(
+                "ui.controls.battle.line_3",
+                run = key(Command::Run),
+                quit = key(Command::Quit)
+            )
+            .to_string(),
+            t!("ui.controls.battle.orders", back = back).to_string(),
+            menu_select,
+            save_load,
+        ],
+        GameMode::Dialogue => vec![
+            menu_select,
+            t!("ui.controls.dialogue.choose").to_string(),
+            t!("ui.controls.dialogue.leave", back = back).to_string(),
+            save_load,
+        ],
+        GameMode::Event => vec![
+            menu_select,
+            t!("ui.controls.event.choose").to_string(),
+            save_load,
+        ],
+        GameMode::Party => vec![
+            t!(
+                "ui.controls.party.select",
+                up = key(Command::MenuUp),
+                down = key(Command::MenuDown)
+            )
+            .to_string(),
+            t!(
+                "ui.controls.party.move",
+                up = key(Command::MoveMemberUp),
+                down = key(Command::MoveMemberDown)
+            )
+            .to_string(),
+            t!(
+                "ui.controls.party.gear",
+                weapon = key(Command::UpgradeMemberWeapon),
+                armor = key(Command::UpgradeMemberArmor)
+            )
+            .to_string(),
+            t!(
+                "ui.controls.party.dismiss",
+                key = key(Command::DismissMember)
+            )
+            .to_string(),
+            t!(
+                "ui.controls.party.close",
+                back = back,
+                key = key(Command::Party)
+            )
+            .to_string(),
+        ],
+        GameMode::ClassSelect => vec![
+            menu_select,
+            t!("ui.controls.class_select.choose").to_string(),
+            t!(
+                "ui.controls.class_select.legacy",
+                key = key(Command::OpenLegacy)
+            )
+            .to_string(),
+            t!(
+                "ui.controls.class_select.challenge",
+                key = key(Command::OpenChallenges)
+            )
+            .to_string(),
+            quit,
         ],
-        GameMode::Victory | GameMode::GameOver => vec![
-            t!("ui.controls.result.restart").to_string(),
-            t!("ui.controls.save_load").to_string(),
-            t!("ui.controls.quit").to_string(),
+        GameMode::Challenge => vec![
+            menu_select,
+            t!("ui.controls.challenge.toggle").to_string(),
+            t!(
+                "ui.controls.challenge.daily",
+                key = key(Command::ToggleDaily)
+            )
+            .to_string(),
+            t!(
+                "ui.controls.challenge.close",
+                back = back,
+                key = key(Command::OpenChallenges)
+            )
+            .to_string(),
         ],
+        GameMode::Legacy => vec![
+            menu_select,
+            t!("ui.controls.legacy.choose").to_string(),
+            t!(
+                "ui.controls.legacy.roguelite",
+                key = key(Command::ToggleRoguelite)
+            )
+            .to_string(),
+            t!(
+                "ui.controls.legacy.close",
+                back = back,
+                key = key(Command::OpenLegacy)
+            )
+            .to_string(),
+        ],
+        GameMode::LevelUp => vec![
+            menu_select,
+            t!("ui.controls.level_up.spend").to_string(),
+            t!(
+                "ui.controls.level_up.close",
+                back = back,
+                key = key(Command::LevelUp)
+            )
+            .to_string(),
+        ],
+        GameMode::Records => vec![
+            t!(
+                "ui.controls.records.close",
+                back = back,
+                key = key(Command::Records)
+            )
+            .to_string(),
+        ],
+        GameMode::Victory => vec![
+            restart,
+            t!(
+                "ui.controls.result.ng_plus",
+                key = key(Command::NewGamePlus)
+            )
+            .to_string(),
+            records_open,
+            save_load,
+            quit,
+        ],
+        GameMode::GameOver => vec![restart, records_open, save_load, quit],
     };
 
     items
         .into_iter()
-        .map(|item| {
-            Line::from(vec![
-                Span::styled("- ", Style::default().fg(Color::Rgb(126, 149, 255))),
-                Span::raw(item),
-            ])
-        })
+        .map(|item| Line::from(vec![Span::styled("- ", theme.bullet), Span::raw(item)]))
         .collect()
 }
 
-fn label_span(text: String) -> Span<'static> {
-    Span::styled(
-        format!("{text} "),
-        Style::default().fg(MUTED).add_modifier(Modifier::BOLD),
-    )
+fn label_span(theme: &Theme, text: String) -> Span<'static> {
+    Span::styled(format!("{text} "), theme.muted.bold())
 }
 
-fn value_span(text: String, color: Color) -> Span<'static> {
-    Span::styled(text, Style::default().fg(color))
+fn value_span(text: String, paint: Paint) -> Span<'static> {
+    Span::styled(text, paint)
 }
 
-fn kv_line(label: String, value: String, color: Color) -> Line<'static> {
-    Line::from(vec![label_span(label), value_span(value, color)])
+fn kv_line(theme: &Theme, label: String, value: String, paint: Paint) -> Line<'static> {
+    Line::from(vec![label_span(theme, label), value_span(value, paint)])
 }
 
-fn meter_line(label: String, cur: i32, max: i32, width: usize, color: Color) -> Line<'static> {
+fn meter_line(
+    theme: &Theme,
+    label: String,
+    cur: i32,
+    max: i32,
+    width: usize,
+    paint: Paint,
+) -> Line<'static> {
     let value = format!("{}/{} {}", cur, max, bar(cur, max, width));
-    kv_line(label, value, color)
+    kv_line(theme, label, value, paint)
 }
 
 fn clamp_scroll(requested_scroll: usize, area: Rect, total_rows: usize) -> u16 {
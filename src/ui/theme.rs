@@ -0,0 +1,356 @@
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use ratatui::style::{Color, Modifier, Style, Styled};
+use serde::Deserialize;
+
+use crate::game::Game;
+use crate::game::model::{GameMode, ThemeId, TimeOfDay};
+
+const THEME_DATA: &str = include_str!("../../config/themes.toml");
+/// The theme every other theme in the file starts from.
+const BASE_THEME: ThemeId = ThemeId::Default;
+
+/// One style slot of a theme, parsed from a string such as
+/// `"bold #12141e on #7cc4ff"`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Paint(Style);
+
+impl TryFrom<String> for Paint {
+    type Error = String;
+
+    fn try_from(spec: String) -> Result<Self, String> {
+        let mut style = Style::default();
+        let mut words = spec.split_whitespace();
+        while let Some(word) = words.next() {
+            let modifier = match word {
+                "bold" => Modifier::BOLD,
+                "dim" => Modifier::DIM,
+                "italic" => Modifier::ITALIC,
+                "underlined" => Modifier::UNDERLINED,
+                "reversed" => Modifier::REVERSED,
+                "crossed_out" => Modifier::CROSSED_OUT,
+                "on" => {
+                    let color = words
+                        .next()
+                        .ok_or_else(|| format!("`{spec}`: `on` needs a color"))?;
+                    style = style.bg(parse_color(&spec, color)?);
+                    continue;
+                }
+                color => {
+                    style = style.fg(parse_color(&spec, color)?);
+                    continue;
+                }
+            };
+            style = style.add_modifier(modifier);
+        }
+        Ok(Self(style))
+    }
+}
+
+fn parse_color(spec: &str, word: &str) -> Result<Color, String> {
+    word.parse()
+        .map_err(|_| format!("`{spec}`: unknown color or modifier `{word}`"))
+}
+
+impl From<Paint> for Style {
+    fn from(paint: Paint) -> Self {
+        paint.0
+    }
+}
+
+/// Lets a slot take `Stylize` calls directly, as in `theme.text.bold()`.
+impl Styled for Paint {
+    type Item = Style;
+
+    fn style(&self) -> Style {
+        self.0
+    }
+
+    fn set_style<S: Into<Style>>(self, style: S) -> Style {
+        self.0.patch(style)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Theme {
+    pub text: Paint,
+    pub muted: Paint,
+    /// Panel background.
+    pub surface: Paint,
+    /// Laid over whatever the mouse pointer rests on.
+    pub hover: Paint,
+    /// Section headings and the selected setting.
+    pub heading: Paint,
+    /// Current values on the Settings screen.
+    pub value: Paint,
+    pub bullet: Paint,
+    pub confirm: Paint,
+    pub gold: Paint,
+    pub reward: Paint,
+    pub success: Paint,
+    pub victory: Paint,
+    pub defeat: Paint,
+    pub enemy: Paint,
+    pub enemy_add: Paint,
+    pub ally: Paint,
+    pub hp: Paint,
+    pub mp: Paint,
+    pub exp: Paint,
+    pub attack: Paint,
+    pub defense: Paint,
+    pub materials: Paint,
+    pub quest: Paint,
+    pub roguelite: Paint,
+    pub challenge: Paint,
+    pub seals: Paint,
+    pub time: TimePaints,
+    pub accents: ModeAccents,
+    pub map: MapPaints,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TimePaints {
+    pub dawn: Paint,
+    pub day: Paint,
+    pub dusk: Paint,
+    pub night: Paint,
+}
+
+/// Panel borders and highlights, one per screen.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModeAccents {
+    pub exploration: Paint,
+    pub town: Paint,
+    pub settings: Paint,
+    pub battle: Paint,
+    pub dialogue: Paint,
+    pub event: Paint,
+    pub party: Paint,
+    pub class_select: Paint,
+    pub level_up: Paint,
+    pub records: Paint,
+    pub legacy: Paint,
+    pub challenge: Paint,
+    pub keybinds: Paint,
+    pub victory: Paint,
+    pub game_over: Paint,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MapPaints {
+    pub player: Paint,
+    pub unexplored: Paint,
+    /// Off-map cells alternate between these two in a checkerboard.
+    pub margin: Paint,
+    pub margin_alt: Paint,
+    pub monster: Paint,
+    pub chest: Paint,
+    pub herb: Paint,
+    pub ore: Paint,
+    pub npc: Paint,
+    pub boss: Paint,
+    pub cleared: Paint,
+    pub floor: Paint,
+    pub wall: Paint,
+    pub town: Paint,
+    pub lair_sealed: Paint,
+    pub lair: Paint,
+}
+
+impl Theme {
+    pub fn accent(&self, mode: GameMode) -> Paint {
+        let accents = &self.accents;
+        match mode {
+            GameMode::Exploration => accents.exploration,
+            GameMode::Town => accents.town,
+            GameMode::Settings => accents.settings,
+            GameMode::Battle => accents.battle,
+            GameMode::Dialogue => accents.dialogue,
+            GameMode::Event => accents.event,
+            GameMode::Party => accents.party,
+            GameMode::ClassSelect => accents.class_select,
+            GameMode::LevelUp => accents.level_up,
+            GameMode::Records => accents.records,
+            GameMode::Legacy => accents.legacy,
+            GameMode::Challenge => accents.challenge,
+            GameMode::Keybinds => accents.keybinds,
+            GameMode::Victory => accents.victory,
+            GameMode::GameOver => accents.game_over,
+        }
+    }
+
+    pub fn time_of_day(&self, time: TimeOfDay) -> Paint {
+        match time {
+            TimeOfDay::Dawn => self.time.dawn,
+            TimeOfDay::Day => self.time.day,
+            TimeOfDay::Dusk => self.time.dusk,
+            TimeOfDay::Night => self.time.night,
+        }
+    }
+}
+
+/// Lays `top` over `base`, merging nested tables key by key.
+fn overlay(base: &mut toml::Table, top: &toml::Table) {
+    for (key, value) in top {
+        match (base.get_mut(key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(top)) => overlay(base, top),
+            _ => {
+                base.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+fn parse_themes(content: &str) -> Result<BTreeMap<ThemeId, Theme>, String> {
+    let tables: BTreeMap<ThemeId, toml::Table> =
+        toml::from_str(content).map_err(|err| err.message().to_string())?;
+    let base = tables
+        .get(&BASE_THEME)
+        .ok_or_else(|| format!("missing the `{BASE_THEME:?}` theme"))?;
+    tables
+        .iter()
+        .map(|(id, table)| {
+            let mut merged = base.clone();
+            overlay(&mut merged, table);
+            toml::Value::Table(merged)
+                .try_into()
+                .map(|theme| (*id, theme))
+                .map_err(|err: toml::de::Error| format!("{id:?}: {}", err.message()))
+        })
+        .collect()
+}
+
+fn themes() -> &'static BTreeMap<ThemeId, Theme> {
+    static THEMES: OnceLock<BTreeMap<ThemeId, Theme>> = OnceLock::new();
+    THEMES.get_or_init(|| parse_themes(THEME_DATA).expect("embedded theme data is valid"))
+}
+
+/// `NO_COLOR` asks for no color at all; otherwise truecolor terminals
+/// announce themselves through `COLORTERM`, and the rest get the ANSI colors.
+fn detect(colorterm: Option<&str>, no_color: bool) -> ThemeId {
+    if no_color {
+        ThemeId::Monochrome
+    } else if matches!(colorterm, Some("truecolor" | "24bit")) {
+        ThemeId::Default
+    } else {
+        ThemeId::Ansi16
+    }
+}
+
+fn detected() -> ThemeId {
+    static DETECTED: OnceLock<ThemeId> = OnceLock::new();
+    *DETECTED.get_or_init(|| {
+        let colorterm = std::env::var("COLORTERM").ok();
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        detect(colorterm.as_deref(), no_color)
+    })
+}
+
+pub fn get(id: ThemeId) -> &'static Theme {
+    let id = if id == ThemeId::Auto { detected() } else { id };
+    &themes()[&id]
+}
+
+/// The theme the player picked in Settings.
+pub fn of(game: &Game) -> &'static Theme {
+    get(game.prefs.theme)
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::{Color, Modifier, Style};
+
+    use super::{BASE_THEME, Paint, THEME_DATA, Theme, detect, get, overlay, parse_themes, themes};
+    use crate::game::model::{GameMode, ThemeId};
+
+    /// Every slot of a theme, found by re-reading its merged table.
+    fn slots(id: ThemeId) -> Vec<(String, Style)> {
+        fn walk(prefix: &str, table: &toml::Table, out: &mut Vec<(String, Style)>) {
+            for (key, value) in table {
+                let name = format!("{prefix}{key}");
+                match value {
+                    toml::Value::Table(table) => walk(&format!("{name}."), table, out),
+                    value => {
+                        let paint = Paint::try_from(value.as_str().unwrap().to_string()).unwrap();
+                        out.push((name, paint.into()));
+                    }
+                }
+            }
+        }
+        let tables: toml::Table = toml::from_str(THEME_DATA).unwrap();
+        let table = |id| tables[name_of(id).as_str()].as_table().unwrap();
+        let mut merged = table(BASE_THEME).clone();
+        overlay(&mut merged, table(id));
+        let mut out = Vec::new();
+        walk("", &merged, &mut out);
+        out
+    }
+
+    fn name_of(id: ThemeId) -> String {
+        toml::Value::try_from(id)
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn paints_parse_colors_backgrounds_and_modifiers() {
+        let paint = Paint::try_from("bold #12141e on lightcyan".to_string()).unwrap();
+        assert_eq!(
+            Style::from(paint),
+            Style::default()
+                .fg(Color::Rgb(18, 20, 30))
+                .bg(Color::LightCyan)
+                .add_modifier(Modifier::BOLD)
+        );
+        assert_eq!(
+            Style::from(Paint::try_from(String::new()).unwrap()),
+            Style::default()
+        );
+        let error = Paint::try_from("blinking red".to_string()).unwrap_err();
+        assert!(error.contains("blinking"), "{error}");
+        assert!(Paint::try_from("red on".to_string()).is_err());
+    }
+
+    #[test]
+    fn every_built_in_theme_loads() {
+        for id in ThemeId::ALL {
+            let theme: &Theme = get(id);
+            assert_eq!(theme.accent(GameMode::Battle), theme.accents.battle);
+        }
+        assert_eq!(themes().len(), ThemeId::ALL.len() - 1);
+
+        let typo = "[default]\ntxet = \"red\"\n";
+        assert!(parse_themes(typo).is_err());
+    }
+
+    #[test]
+    fn limited_themes_stay_within_their_palette() {
+        for (slot, style) in slots(ThemeId::Ansi16) {
+            for color in [style.fg, style.bg].into_iter().flatten() {
+                assert!(
+                    !matches!(color, Color::Rgb(..) | Color::Indexed(_)),
+                    "ansi16 {slot}: {color:?}"
+                );
+            }
+        }
+        for (slot, style) in slots(ThemeId::Monochrome) {
+            assert_eq!((style.fg, style.bg), (None, None), "monochrome {slot}");
+        }
+    }
+
+    #[test]
+    fn auto_theme_follows_the_terminal() {
+        assert_eq!(detect(Some("truecolor"), false), ThemeId::Default);
+        assert_eq!(detect(Some("24bit"), false), ThemeId::Default);
+        assert_eq!(detect(None, false), ThemeId::Ansi16);
+        assert_eq!(detect(Some("truecolor"), true), ThemeId::Monochrome);
+    }
+}
@@ -0,0 +1,291 @@
+//! The whole world at once: the half-block minimap and the full-screen map
+//! overlay with its legend.
+
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Clear, Paragraph};
+use rust_i18n::t;
+
+use crate::game::Game;
+use crate::game::model::{Command, MAP_H, MAP_W, Tile};
+use crate::ui::glyphs::{self, Glyphs};
+use crate::ui::theme::{self, Paint, Theme};
+use crate::ui::{map, panel_block};
+
+/// Columns each legend entry gets in the overlay.
+const LEGEND_COLUMN_WIDTH: usize = 20;
+
+/// The tile `cell` stands for when `cells` cells cover `tiles` tiles; a cell
+/// covering the hero always picks the hero, so the hero never drops out.
+fn sample(cell: usize, cells: usize, tiles: usize, hero: Option<usize>) -> usize {
+    let start = cell * tiles / cells;
+    let end = ((cell + 1) * tiles / cells).max(start + 1);
+    hero.filter(|hero| (start..end).contains(hero))
+        .unwrap_or(start)
+}
+
+/// The color a tile shows as on the half-block map, or `None` while it is
+/// unexplored. Themes without colors fall back to the terminal's own.
+fn tile_color(game: &Game, theme: &Theme, x: usize, y: usize) -> Option<Color> {
+    let map = &theme.map;
+    let paint = if (x, y) == (game.player.x, game.player.y) {
+        let style = Style::from(map.player);
+        return Some(style.bg.or(style.fg).unwrap_or(Color::Reset));
+    } else if !game.world.is_explored(x, y) {
+        return None;
+    } else if game.world.has_unopened_chest(x, y) {
+        map.chest
+    } else {
+        match game.map[y][x] {
+            Tile::Floor if game.world.tile_is_cleared(x, y) => map.cleared,
+            Tile::Floor => map.floor,
+            Tile::Wall => map.wall,
+            Tile::Town => map.town,
+            Tile::Lair if game.world.lair_sealed() => map.lair_sealed,
+            Tile::Lair => map.lair,
+        }
+    };
+    Some(Style::from(paint).fg.unwrap_or(Color::Reset))
+}
+
+/// One cell holding two tiles stacked on top of each other.
+fn half_block(top: Option<Color>, bottom: Option<Color>) -> Span<'static> {
+    match (top, bottom) {
+        (None, None) => Span::raw(" "),
+        (Some(top), None) => Span::styled("▀", Style::default().fg(top)),
+        (None, Some(bottom)) => Span::styled("▄", Style::default().fg(bottom)),
+        (Some(top), Some(bottom)) if top == bottom => Span::styled("█", Style::default().fg(top)),
+        (Some(top), Some(bottom)) => Span::styled("▀", Style::default().fg(top).bg(bottom)),
+    }
+}
+
+/// The world in at most `width` by `height` cells, two tiles to a cell
+/// vertically and at most one horizontally; larger worlds are sampled.
+pub(crate) fn half_block_lines(game: &Game, width: usize, height: usize) -> Vec<Line<'static>> {
+    let theme = theme::of(game);
+    let width = width.min(MAP_W);
+    let rows = (height * 2).min(MAP_H);
+    let hero = (game.player.x, game.player.y);
+    (0..rows.div_ceil(2))
+        .map(|cy| {
+            let top = sample(cy * 2, rows, MAP_H, Some(hero.1));
+            let bottom = (cy * 2 + 1 < rows).then(|| sample(cy * 2 + 1, rows, MAP_H, Some(hero.1)));
+            let hero_x = (top == hero.1 || bottom == Some(hero.1)).then_some(hero.0);
+            Line::from(
+                (0..width)
+                    .map(|cx| {
+                        let x = sample(cx, width, MAP_W, hero_x);
+                        half_block(
+                            tile_color(game, theme, x, top),
+                            bottom.and_then(|y| tile_color(game, theme, x, y)),
+                        )
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect()
+}
+
+fn legend_entries(theme: &Theme, glyphs: &'static Glyphs) -> Vec<(&'static str, Paint, String)> {
+    let map = &theme.map;
+    vec![
+        (
+            glyphs.player.as_str(),
+            map.player,
+            t!("ui.world_map.you").to_string(),
+        ),
+        (
+            glyphs.town.as_str(),
+            map.town,
+            t!("ui.world_map.town").to_string(),
+        ),
+        (
+            glyphs.lair.as_str(),
+            map.lair,
+            t!("ui.world_map.lair").to_string(),
+        ),
+        (
+            glyphs.boss.as_str(),
+            map.boss,
+            t!("ui.world_map.boss").to_string(),
+        ),
+        (
+            glyphs.chest.as_str(),
+            map.chest,
+            t!("ui.world_map.chest").to_string(),
+        ),
+        (
+            glyphs.npc.as_str(),
+            map.npc,
+            t!("ui.world_map.npc").to_string(),
+        ),
+        (
+            glyphs.monster.as_str(),
+            map.monster,
+            t!("ui.world_map.monster").to_string(),
+        ),
+        (
+            glyphs.herb.as_str(),
+            map.herb,
+            t!("ui.world_map.herb").to_string(),
+        ),
+        (
+            glyphs.ore.as_str(),
+            map.ore,
+            t!("ui.world_map.ore").to_string(),
+        ),
+        (
+            glyphs.wall.as_str(),
+            map.wall,
+            t!("ui.world_map.wall").to_string(),
+        ),
+        (
+            glyphs.floor.as_str(),
+            map.floor,
+            t!("ui.world_map.floor").to_string(),
+        ),
+        (
+            glyphs.cleared.as_str(),
+            map.cleared,
+            t!("ui.world_map.cleared").to_string(),
+        ),
+    ]
+}
+
+/// The legend laid out in as many columns as fit the width.
+fn legend_lines(game: &Game, width: usize) -> Vec<Line<'static>> {
+    let theme = theme::of(game);
+    let entries = legend_entries(theme, glyphs::of(game));
+    let columns = (width / LEGEND_COLUMN_WIDTH).max(1);
+    entries
+        .chunks(columns)
+        .map(|row| {
+            Line::from(
+                row.iter()
+                    .flat_map(|(glyph, paint, label)| {
+                        let label = format!(" {label}");
+                        let pad = LEGEND_COLUMN_WIDTH
+                            .saturating_sub(Span::raw(*glyph).width() + Span::raw(&label).width());
+                        [
+                            Span::styled(*glyph, *paint),
+                            Span::styled(format!("{label}{}", " ".repeat(pad)), theme.muted),
+                        ]
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect()
+}
+
+/// Every tile at full size, or the half-block map when the screen is too
+/// small for that.
+fn world_lines(game: &Game, width: usize, height: usize) -> Vec<Line<'static>> {
+    let cell = glyphs::of(game).cell_width;
+    if width < MAP_W * cell || height < MAP_H {
+        return half_block_lines(game, width, height);
+    }
+    (0..MAP_H)
+        .map(|y| {
+            Line::from(
+                (0..MAP_W)
+                    .map(|x| map::tile_span(game, x, y))
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect()
+}
+
+/// Covers the whole screen with the world map, centred over its legend.
+pub fn render_overlay(frame: &mut Frame, game: &Game) {
+    let theme = theme::of(game);
+    let area = frame.area();
+    let block = panel_block(theme, t!("ui.panel.world_map"), theme.accent(game.mode));
+    let inner = block.inner(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+
+    let hint = Line::from(Span::styled(
+        t!(
+            "ui.world_map.close",
+            key = game.key_label(Command::WorldMap)
+        )
+        .to_string(),
+        theme.muted,
+    ));
+    let legend = legend_lines(game, inner.width as usize);
+    let [map_area, legend_area, hint_area] = Layout::vertical([
+        Constraint::Min(1),
+        Constraint::Length(legend.len() as u16 + 1),
+        Constraint::Length(1),
+    ])
+    .areas(inner);
+    let lines = world_lines(game, map_area.width as usize, map_area.height as usize);
+    let map_width = lines.iter().map(Line::width).max().unwrap_or(0) as u16;
+    let map_height = lines.len() as u16;
+    let centred = Rect {
+        x: map_area.x + map_area.width.saturating_sub(map_width) / 2,
+        y: map_area.y + map_area.height.saturating_sub(map_height) / 2,
+        width: map_width.min(map_area.width),
+        height: map_height.min(map_area.height),
+    };
+    frame.render_widget(Paragraph::new(lines), centred);
+    frame.render_widget(
+        Paragraph::new(legend),
+        Rect {
+            y: legend_area.y + 1,
+            height: legend_area.height.saturating_sub(1),
+            ..legend_area
+        },
+    );
+    frame.render_widget(Paragraph::new(hint), hint_area);
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::{Color, Style};
+
+    use super::{half_block, half_block_lines, sample};
+    use crate::game::Game;
+    use crate::game::model::{MAP_H, MAP_W, ThemeId};
+    use crate::ui::theme;
+
+    #[test]
+    fn half_blocks_stack_two_tiles_in_one_cell() {
+        let (red, blue) = (Color::Red, Color::Blue);
+        assert_eq!(half_block(None, None).content, " ");
+        assert_eq!(half_block(Some(red), None).content, "▀");
+        assert_eq!(half_block(None, Some(blue)).style.fg, Some(blue));
+        assert_eq!(half_block(Some(red), Some(red)).content, "█");
+        let both = half_block(Some(red), Some(blue));
+        assert_eq!((both.style.fg, both.style.bg), (Some(red), Some(blue)));
+    }
+
+    #[test]
+    fn the_half_block_map_fits_the_world_and_keeps_the_hero() {
+        let game = Game::new_with_seed(2026);
+        let lines = half_block_lines(&game, 80, 40);
+        assert_eq!(lines.len(), MAP_H / 2);
+        assert!(lines.iter().all(|line| line.width() == MAP_W));
+
+        for cells in 1..=MAP_W {
+            assert!(
+                (0..cells)
+                    .any(|cell| sample(cell, cells, MAP_W, Some(game.player.x)) == game.player.x)
+            );
+        }
+        let mut game = game;
+        game.prefs.theme = ThemeId::Default;
+        let hero = Style::from(theme::get(ThemeId::Default).map.player).bg;
+        let small = half_block_lines(&game, 10, 3);
+        assert_eq!(small.len(), 3);
+        assert!(small.iter().all(|line| line.width() == 10));
+        assert!(
+            small
+                .iter()
+                .flat_map(|line| &line.spans)
+                .any(|span| span.style.fg == hero || span.style.bg == hero)
+        );
+    }
+}
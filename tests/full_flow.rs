@@ -1,7 +1,7 @@
 use crossterm::event::KeyCode;
 
 use rpg_tui::game::Game;
-use rpg_tui::game::model::{Battle, Enemy, EnemyStyle, GameMode, MAP_H, MAP_W};
+use rpg_tui::game::model::{AiKind, Battle, Enemy, EnemyStyle, GameMode, MAP_H, MAP_W};
 
 #[test]
 fn seeded_flow_town_battle_level_up_boss_victory() {
@@ -15,35 +15,44 @@ fn seeded_flow_town_battle_level_up_boss_victory() {
     game.player.gold = 100;
     game.handle_key(KeyCode::Char('1'));
     game.handle_key(KeyCode::Char('8'));
+    assert_eq!(game.party.len(), 1);
+    game.handle_key(KeyCode::Char('0'));
     assert_eq!(game.mode, GameMode::Exploration);
     assert!(game.player.bag.potion >= 1);
 
     game.player.exp = game.player.next_exp - 1;
     game.player.base_atk = 999;
     game.mode = GameMode::Battle;
-    game.battle = Some(Battle {
-        enemy: Enemy {
-            name: "Dummy".to_string(),
-            hp: 4,
-            max_hp: 4,
-            atk: 1,
-            def: 0,
-            exp_reward: 2,
-            gold_reward: 1,
-            is_boss: false,
-            style: EnemyStyle::Skirmisher,
-        },
-        defending: false,
-    });
+    game.battle = Some(Battle::new(Enemy {
+        name: "Dummy".to_string(),
+        name_key: String::new(),
+        hp: 4,
+        max_hp: 4,
+        atk: 1,
+        def: 0,
+        exp_reward: 2,
+        gold_reward: 1,
+        is_boss: false,
+        style: EnemyStyle::Skirmisher,
+        ai: AiKind::Aggressive,
+        loot: None,
+        art: String::new(),
+    }));
+    game.handle_key(KeyCode::Char('1'));
+    assert_eq!(game.choosing_member(), Some(0));
     game.handle_key(KeyCode::Char('1'));
     assert!(game.player.level >= 2);
     assert_eq!(game.mode, GameMode::Exploration);
 
+    for site in &mut game.world.boss_sites {
+        site.defeated = true;
+    }
     game.player.base_atk = 999;
     game.player.x = MAP_W - 3;
     game.player.y = MAP_H - 2;
     game.handle_key(KeyCode::Right);
     assert_eq!(game.mode, GameMode::Battle);
     game.handle_key(KeyCode::Char('1'));
+    game.handle_key(KeyCode::Char('1'));
     assert_eq!(game.mode, GameMode::Victory);
 }
@@ -0,0 +1,122 @@
+//! Renders a seeded game at each layout breakpoint, with the world map open
+//! and mid-battle, and compares the screen text against `tests/snapshots/`. Run with
+//! `UPDATE_SNAPSHOTS=1` to rewrite the snapshots after an intended change.
+
+use std::fs;
+use std::path::PathBuf;
+
+use ratatui::Terminal;
+use ratatui::backend::TestBackend;
+use ratatui::buffer::Buffer;
+use ratatui::text::Span;
+
+use rpg_tui::game::Game;
+use rpg_tui::game::model::{
+    AiKind, Battle, BattleEffect, BattleSide, Enemy, EnemyStyle, GameMode, PanelTab,
+};
+use rpg_tui::ui::render;
+
+fn screen_text(buffer: &Buffer) -> String {
+    let area = buffer.area;
+    let mut text = String::new();
+    for y in 0..area.height {
+        let mut line = String::new();
+        let mut skip = 0;
+        for x in 0..area.width {
+            // The cell after a double-width glyph holds nothing of its own.
+            if skip > 0 {
+                skip -= 1;
+                continue;
+            }
+            let symbol = buffer[(x, y)].symbol();
+            skip = Span::raw(symbol).width().saturating_sub(1);
+            line.push_str(symbol);
+        }
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+fn seeded_game(tab: PanelTab) -> Game {
+    rust_i18n::set_locale("en");
+    let mut game = Game::new_with_seed(2026);
+    game.panel_tab = tab;
+    game
+}
+
+fn assert_snapshot(name: &str, width: u16, height: u16, game: &Game) {
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    terminal.draw(|frame| render(frame, game)).unwrap();
+    let actual = screen_text(terminal.backend().buffer());
+
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "snapshots", name]
+        .iter()
+        .collect();
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, &actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("missing snapshot {name}; run with UPDATE_SNAPSHOTS=1"));
+    assert_eq!(actual, expected, "screen at {width}x{height} changed");
+}
+
+#[test]
+fn too_small_terminals_only_show_a_notice() {
+    assert_snapshot("too_small.txt", 40, 12, &seeded_game(PanelTab::Hero));
+}
+
+#[test]
+fn compact_layout_stacks_one_tabbed_panel() {
+    assert_snapshot("compact_hero.txt", 80, 30, &seeded_game(PanelTab::Hero));
+    assert_snapshot("compact_log.txt", 80, 30, &seeded_game(PanelTab::Log));
+}
+
+#[test]
+fn standard_layout_keeps_the_sidebar() {
+    assert_snapshot("standard.txt", 120, 40, &seeded_game(PanelTab::Hero));
+}
+
+#[test]
+fn wide_layout_adds_minimap_quests_and_bestiary() {
+    assert_snapshot("wide.txt", 200, 50, &seeded_game(PanelTab::Hero));
+}
+
+#[test]
+fn world_map_overlay_shows_the_whole_world_and_a_legend() {
+    let mut game = seeded_game(PanelTab::Hero);
+    game.world_map_open = true;
+    assert_snapshot("world_map.txt", 120, 40, &game);
+    assert_snapshot("world_map_small.txt", 60, 20, &game);
+}
+
+#[test]
+fn battle_scene_replaces_the_map_during_a_fight() {
+    let mut game = seeded_game(PanelTab::Hero);
+    game.mode = GameMode::Battle;
+    game.battle = Some(Battle::new(Enemy {
+        name: "Wolf".to_string(),
+        name_key: "enemy.wolf".to_string(),
+        hp: 9,
+        max_hp: 16,
+        atk: 5,
+        def: 1,
+        exp_reward: 6,
+        gold_reward: 4,
+        is_boss: false,
+        style: EnemyStyle::Predator,
+        ai: AiKind::Aggressive,
+        loot: None,
+        art: "wolf".to_string(),
+    }));
+    assert_snapshot("battle.txt", 120, 40, &game);
+
+    game.battle_effects.push(BattleEffect {
+        side: BattleSide::Foes,
+        amount: 7,
+        frame: 2,
+    });
+    assert_snapshot("battle_hit.txt", 120, 40, &game);
+}